@@ -0,0 +1,59 @@
+//! Timings of the `laicrypto::field` primitives
+//!
+//! Run with `cargo bench --bench field`. Each primitive is timed over the
+//! same pseudo-random operands at a small, a 64-bit and a 127-bit prime.
+
+use laicrypto::field::{add_mod, inv_mod, jacobi, mul_mod, pow_mod, sqrt_mod, sub_mod};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const PRIMES: [(&str, u128); 3] = [
+    ("p = 1009", 1009),
+    ("p = 2^64 - 59", u64::MAX as u128 - 58),
+    ("p = 2^127 - 1", (1 << 127) - 1),
+];
+const OPERANDS: usize = 1024;
+const BUDGET: Duration = Duration::from_millis(200);
+
+fn operands(p: u128) -> Vec<u128> {
+    // SplitMix-style sequence, reproducible across runs
+    let mut state = 0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c834u128;
+    (0..OPERANDS)
+        .map(|_| {
+            state = state.wrapping_mul(0xda94_2042_e4dd_58b5).wrapping_add(1);
+            (state ^ (state >> 67)) % p
+        })
+        .collect()
+}
+
+/// Mean time per call of `f` over all operands, repeated until `BUDGET`
+fn time(xs: &[u128], mut f: impl FnMut(u128, u128) -> u128) -> Duration {
+    let start = Instant::now();
+    let mut calls = 0u32;
+    while start.elapsed() < BUDGET {
+        for pair in xs.windows(2) {
+            black_box(f(black_box(pair[0]), black_box(pair[1])));
+        }
+        calls += xs.len() as u32 - 1;
+    }
+    start.elapsed() / calls
+}
+
+fn main() {
+    for (name, p) in PRIMES {
+        let xs = operands(p);
+        println!("{}", name);
+        let results = [
+            ("add_mod", time(&xs, |a, b| add_mod(a, b, p))),
+            ("sub_mod", time(&xs, |a, b| sub_mod(a, b, p))),
+            ("mul_mod", time(&xs, |a, b| mul_mod(a, b, p))),
+            ("pow_mod", time(&xs, |a, b| pow_mod(a, b, p))),
+            ("inv_mod", time(&xs, |a, _| inv_mod(a, p).unwrap_or(0))),
+            ("jacobi", time(&xs, |a, _| jacobi(a, p) as u128)),
+            ("sqrt_mod", time(&xs, |a, _| sqrt_mod(a, p).unwrap_or(0))),
+        ];
+        for (op, per_call) in results {
+            println!("  {:<9} {:>10.1?}", op, per_call);
+        }
+    }
+}
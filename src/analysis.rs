@@ -0,0 +1,983 @@
+//! Experimental analysis of T-transform orbits
+//!
+//! With a fixed seed `s`, the map P ↦ T(P, s) is a deterministic function on
+//! F_p², so every orbit is eventually periodic. Short cycles in this map would
+//! make keys and ephemeral scalars collapse onto few points, so these tools
+//! measure tail and cycle lengths for a given parameter set.
+
+use crate::{field, LaiCryptoEngine, LaiCryptoError, Point};
+#[cfg(feature = "trace")]
+use crate::T_SEED_RETRIES;
+#[cfg(feature = "graphs")]
+use crate::{ColorMode, CryptoGraph, GraphStyle};
+use rand::{rngs::OsRng, Rng};
+use std::collections::HashMap;
+
+/// Largest modulus accepted by [`exhaustive`]
+pub const EXHAUSTIVE_MAX_P: u128 = 1 << 24;
+
+/// Cycle detection algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleAlgorithm {
+    /// Floyd's tortoise and hare
+    Floyd,
+    /// Brent's power-of-two search, usually fewer evaluations than Floyd
+    Brent,
+}
+
+/// Orbit structure found by cycle detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleInfo {
+    /// Steps before the orbit enters its cycle (μ)
+    pub tail_length: u64,
+    /// Length of the cycle (λ)
+    pub cycle_length: u64,
+    /// First point on the cycle
+    pub cycle_start: Point,
+    /// T-transform evaluations spent
+    pub evaluations: u64,
+}
+
+/// Fixed-seed transform with an evaluation budget
+struct Stepper<'a> {
+    engine: &'a mut LaiCryptoEngine,
+    s: u128,
+    evaluations: u64,
+    max_evaluations: u64,
+}
+
+impl Stepper<'_> {
+    /// Apply T once; `Ok(None)` once the budget is exhausted
+    fn step(&mut self, point: Point) -> Result<Option<Point>, LaiCryptoError> {
+        if self.evaluations >= self.max_evaluations {
+            return Ok(None);
+        }
+        self.evaluations += 1;
+        self.engine.t(point, self.s).map(Some)
+    }
+}
+
+/// Detect the cycle reached from `start` under P ↦ T(P, s)
+///
+/// Returns `Ok(None)` if no cycle is found within `max_evaluations`
+/// transform evaluations, and the transform error if the orbit hits a
+/// point where T fails. Engine tracing is suspended during the search.
+pub fn detect_cycle(
+    engine: &mut LaiCryptoEngine,
+    start: Point,
+    s: u128,
+    algorithm: CycleAlgorithm,
+    max_evaluations: u64,
+) -> Result<Option<CycleInfo>, LaiCryptoError> {
+    untraced(engine, |engine| {
+        let mut stepper = Stepper {
+            engine,
+            s,
+            evaluations: 0,
+            max_evaluations,
+        };
+        match algorithm {
+            CycleAlgorithm::Floyd => run_floyd(&mut stepper, start),
+            CycleAlgorithm::Brent => run_brent(&mut stepper, start),
+        }
+    })
+}
+
+/// Run `f` with engine tracing suspended, restoring the previous setting
+pub(crate) fn untraced<T>(engine: &mut LaiCryptoEngine, f: impl FnOnce(&mut LaiCryptoEngine) -> T) -> T {
+    let tracing = engine.tracing;
+    engine.tracing = false;
+    let result = f(engine);
+    engine.tracing = tracing;
+    result
+}
+
+/// Floyd cycle detection, see [`detect_cycle`]
+pub fn floyd(
+    engine: &mut LaiCryptoEngine,
+    start: Point,
+    s: u128,
+    max_evaluations: u64,
+) -> Result<Option<CycleInfo>, LaiCryptoError> {
+    detect_cycle(engine, start, s, CycleAlgorithm::Floyd, max_evaluations)
+}
+
+/// Brent cycle detection, see [`detect_cycle`]
+pub fn brent(
+    engine: &mut LaiCryptoEngine,
+    start: Point,
+    s: u128,
+    max_evaluations: u64,
+) -> Result<Option<CycleInfo>, LaiCryptoError> {
+    detect_cycle(engine, start, s, CycleAlgorithm::Brent, max_evaluations)
+}
+
+/// Unwrap a budgeted step, returning `Ok(None)` from the caller when exhausted
+macro_rules! step {
+    ($stepper:expr, $point:expr) => {
+        match $stepper.step($point)? {
+            Some(next) => next,
+            None => return Ok(None),
+        }
+    };
+}
+
+fn run_floyd(st: &mut Stepper, start: Point) -> Result<Option<CycleInfo>, LaiCryptoError> {
+    // Find a meeting point inside the cycle
+    let mut tortoise = step!(st, start);
+    let mut hare = step!(st, start);
+    hare = step!(st, hare);
+    while tortoise != hare {
+        tortoise = step!(st, tortoise);
+        hare = step!(st, hare);
+        hare = step!(st, hare);
+    }
+
+    // Tail length: restart the tortoise and move both at the same speed
+    let mut tail_length = 0;
+    tortoise = start;
+    while tortoise != hare {
+        tortoise = step!(st, tortoise);
+        hare = step!(st, hare);
+        tail_length += 1;
+    }
+
+    // Cycle length
+    let cycle_start = tortoise;
+    let mut cycle_length = 1;
+    hare = step!(st, tortoise);
+    while hare != cycle_start {
+        hare = step!(st, hare);
+        cycle_length += 1;
+    }
+
+    Ok(Some(CycleInfo {
+        tail_length,
+        cycle_length,
+        cycle_start,
+        evaluations: st.evaluations,
+    }))
+}
+
+fn run_brent(st: &mut Stepper, start: Point) -> Result<Option<CycleInfo>, LaiCryptoError> {
+    // Cycle length: hare runs ahead, tortoise teleports at powers of two
+    let mut power = 1u64;
+    let mut cycle_length = 1u64;
+    let mut tortoise = start;
+    let mut hare = step!(st, start);
+    while tortoise != hare {
+        if power == cycle_length {
+            tortoise = hare;
+            power *= 2;
+            cycle_length = 0;
+        }
+        hare = step!(st, hare);
+        cycle_length += 1;
+    }
+
+    // Tail length: hare starts one cycle length ahead
+    tortoise = start;
+    hare = start;
+    for _ in 0..cycle_length {
+        hare = step!(st, hare);
+    }
+    let mut tail_length = 0;
+    while tortoise != hare {
+        tortoise = step!(st, tortoise);
+        hare = step!(st, hare);
+        tail_length += 1;
+    }
+
+    Ok(Some(CycleInfo {
+        tail_length,
+        cycle_length,
+        cycle_start: tortoise,
+        evaluations: st.evaluations,
+    }))
+}
+
+/// Connected component of the functional graph of P ↦ T(P, s)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// Nodes in the component, including tails
+    pub size: u64,
+    /// Length of the terminal cycle, or 0 if every path ends where T fails
+    pub cycle_length: u64,
+    /// Curve points among the component's nodes
+    pub curve_points: u64,
+}
+
+/// Exhaustive functional-graph statistics for a small parameter set
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExhaustiveReport {
+    pub p: u128,
+    pub a: u128,
+    pub s: u128,
+    /// Number of affine points on y² = x³ + ax
+    pub curve_point_count: u64,
+    /// Nodes reached from the curve points (T leaves the curve, so this exceeds the count above)
+    pub node_count: u64,
+    /// Nodes at which T fails to find a square root
+    pub failed_transforms: u64,
+    /// Components sorted by descending size
+    pub components: Vec<Component>,
+    /// Points with T(P, s) = P
+    pub fixed_points: Vec<Point>,
+    /// Mean cycle length over components that end in a cycle
+    pub average_cycle_length: f64,
+}
+
+impl ExhaustiveReport {
+    /// Histogram of component sizes, largest first
+    #[cfg(feature = "graphs")]
+    pub fn component_graph(&self) -> CryptoGraph {
+        CryptoGraph {
+            title: format!("T Functional Graph Components (p={}, s={})", self.p, self.s),
+            data: self
+                .components
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i as f64, c.size as f64))
+                .collect(),
+            labels: [
+                ("x".to_string(), "Component".to_string()),
+                ("y".to_string(), "Nodes".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Histogram,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+}
+
+/// Enumerate every affine point on the engine's curve
+///
+/// Roots of each x³ + ax come from [`field::sqrt_mod`], which returns the
+/// same root as the engine's `sqrt_mod`.
+pub fn curve_points(engine: &LaiCryptoEngine) -> Vec<Point> {
+    let curve = engine.curve();
+    let mut points = Vec::new();
+    for x in 0..curve.p {
+        let Some(y) = field::sqrt_mod(curve.rhs(x), curve.p) else {
+            continue;
+        };
+        points.push(Point::new_unchecked(x, y));
+        if y != 0 {
+            points.push(Point::new_unchecked(x, curve.p - y));
+        }
+    }
+    points
+}
+
+/// Build the full functional graph of P ↦ T(P, s) reachable from all curve points
+///
+/// Only moduli below [`EXHAUSTIVE_MAX_P`] are accepted, and exploration
+/// fails with a `ValidationError` once more than `max_nodes` nodes are seen.
+pub fn exhaustive(
+    engine: &mut LaiCryptoEngine,
+    s: u128,
+    max_nodes: u64,
+) -> Result<ExhaustiveReport, LaiCryptoError> {
+    if engine.p >= EXHAUSTIVE_MAX_P {
+        return Err(LaiCryptoError::InvalidParameter {
+            param: "p".to_string(),
+            value: engine.p.to_string(),
+            reason: "Modulus too large for exhaustive enumeration".to_string(),
+            valid_range: format!("p < {}", EXHAUSTIVE_MAX_P),
+        });
+    }
+
+    untraced(engine, |engine| {
+        let starts = curve_points(engine);
+        let curve = engine.curve();
+
+        // Node index and component id per node
+        let mut index: HashMap<Point, usize> = HashMap::new();
+        let mut nodes: Vec<Point> = Vec::new();
+        let mut component: Vec<usize> = Vec::new();
+        let mut components: Vec<Component> = Vec::new();
+        let mut fixed_points = Vec::new();
+        let mut failed_transforms = 0;
+        const UNASSIGNED: usize = usize::MAX;
+
+        let mut intern = |point: Point, nodes: &mut Vec<Point>, component: &mut Vec<usize>| {
+            *index.entry(point).or_insert_with(|| {
+                nodes.push(point);
+                component.push(UNASSIGNED);
+                nodes.len() - 1
+            })
+        };
+
+        for start in starts.iter().copied() {
+            let mut id = intern(start, &mut nodes, &mut component);
+            if component[id] != UNASSIGNED {
+                continue;
+            }
+
+            // Walk until reaching an assigned node, a node on this path, or a failure
+            let mut path: Vec<usize> = Vec::new();
+            let mut on_path: HashMap<usize, usize> = HashMap::new();
+            let comp_id = loop {
+                if component[id] != UNASSIGNED {
+                    break component[id];
+                }
+                if let Some(&pos) = on_path.get(&id) {
+                    components.push(Component {
+                        size: 0,
+                        cycle_length: (path.len() - pos) as u64,
+                        curve_points: 0,
+                    });
+                    break components.len() - 1;
+                }
+                if nodes.len() as u64 > max_nodes {
+                    return Err(LaiCryptoError::ValidationError {
+                        operation: "exhaustive".to_string(),
+                        expected: format!("at most {} nodes", max_nodes),
+                        actual: format!("more than {} nodes", nodes.len() - 1),
+                    });
+                }
+
+                on_path.insert(id, path.len());
+                path.push(id);
+                match engine.t(nodes[id], s) {
+                    Ok(out) => {
+                        if out == nodes[id] {
+                            fixed_points.push(out);
+                        }
+                        id = intern(out, &mut nodes, &mut component);
+                    }
+                    Err(LaiCryptoError::TransformFailure { .. }) => {
+                        failed_transforms += 1;
+                        components.push(Component {
+                            size: 0,
+                            cycle_length: 0,
+                            curve_points: 0,
+                        });
+                        break components.len() - 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+
+            for &node in &path {
+                component[node] = comp_id;
+                components[comp_id].size += 1;
+                if curve.contains(nodes[node].x(), nodes[node].y()) {
+                    components[comp_id].curve_points += 1;
+                }
+            }
+        }
+
+        // Drop components emptied by merges and order largest first
+        components.retain(|c| c.size > 0);
+        components.sort_by_key(|c| std::cmp::Reverse(c.size));
+
+        let cycles: Vec<u64> = components
+            .iter()
+            .map(|c| c.cycle_length)
+            .filter(|&len| len > 0)
+            .collect();
+        let average_cycle_length = if cycles.is_empty() {
+            0.0
+        } else {
+            cycles.iter().sum::<u64>() as f64 / cycles.len() as f64
+        };
+
+        Ok(ExhaustiveReport {
+            p: curve.p,
+            a: curve.a,
+            s,
+            curve_point_count: starts.len() as u64,
+            node_count: nodes.len() as u64,
+            failed_transforms,
+            components,
+            fixed_points,
+            average_cycle_length,
+        })
+    })
+}
+
+/// Empirical square-root failure statistics of the T-transform
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureProfile {
+    /// Random (point, seed) pairs transformed
+    pub samples: u64,
+    /// Individual seed attempts made across all samples
+    pub steps: u64,
+    /// Probability that a single step's y2 has no square root
+    pub step_failure_rate: f64,
+    /// Mean retries before success, over successful samples
+    pub mean_retries: f64,
+    /// Retries predicted by a geometric model with the observed step rate
+    pub expected_retries: f64,
+    /// Fraction of samples that exhausted all seed retries
+    pub exhaustion_rate: f64,
+    /// Sample counts by retries used; the last bucket counts exhausted samples
+    pub retry_histogram: Vec<u64>,
+}
+
+impl FailureProfile {
+    /// Histogram of retries per transform, with exhaustion as the final bar
+    #[cfg(feature = "graphs")]
+    pub fn histogram(&self) -> CryptoGraph {
+        CryptoGraph {
+            title: format!(
+                "T Retry Distribution (step failure {:.1}%)",
+                self.step_failure_rate * 100.0
+            ),
+            data: self
+                .retry_histogram
+                .iter()
+                .enumerate()
+                .map(|(i, &n)| (i as f64, n as f64))
+                .collect(),
+            labels: [
+                ("x".to_string(), "Retries".to_string()),
+                ("y".to_string(), "Samples".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Histogram,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+}
+
+/// Transform `samples` random points with random seeds and profile sqrt failures
+///
+/// Each transform runs with tracing enabled so every seed attempt is
+/// observed; the added trace steps and history entries are removed again.
+#[cfg(feature = "trace")]
+pub fn profile_failures(
+    engine: &mut LaiCryptoEngine,
+    samples: u64,
+) -> Result<FailureProfile, LaiCryptoError> {
+    let p = engine.p;
+    let tracing = engine.tracing;
+    let trace_len = engine.trace.len();
+    let history_len = engine.metrics.operation_history.len();
+    engine.tracing = true;
+
+    let mut retry_histogram = vec![0u64; T_SEED_RETRIES as usize + 1];
+    let (mut steps, mut step_failures) = (0u64, 0u64);
+    let mut result = Ok(());
+    for _ in 0..samples {
+        let point = Point::new_unchecked(OsRng.gen_range(0..p), OsRng.gen_range(0..p));
+        let s = OsRng.gen_range(0..p);
+        let outcome = engine.t(point, s);
+
+        let attempts = &engine.trace[trace_len..];
+        let used = attempts.len();
+        steps += used as u64;
+        step_failures += attempts.iter().filter(|step| step.y1.is_none()).count() as u64;
+        engine.trace.truncate(trace_len);
+
+        match outcome {
+            Ok(_) => retry_histogram[used - 1] += 1,
+            Err(LaiCryptoError::TransformFailure { .. }) => {
+                retry_histogram[T_SEED_RETRIES as usize] += 1
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    engine.tracing = tracing;
+    engine.metrics.operation_history.truncate(history_len);
+    engine.metrics.operation_ids.truncate(history_len);
+    result?;
+
+    let rate = |n: u64, d: u64| if d == 0 { 0.0 } else { n as f64 / d as f64 };
+    let step_failure_rate = rate(step_failures, steps);
+    let exhausted = retry_histogram[T_SEED_RETRIES as usize];
+    let successes = samples - exhausted;
+    let retries: u64 = retry_histogram[..T_SEED_RETRIES as usize]
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| i as u64 * n)
+        .sum();
+    let expected_retries = if step_failure_rate < 1.0 {
+        step_failure_rate / (1.0 - step_failure_rate)
+    } else {
+        f64::INFINITY
+    };
+
+    Ok(FailureProfile {
+        samples,
+        steps,
+        step_failure_rate,
+        mean_retries: rate(retries, successes),
+        expected_retries,
+        exhaustion_rate: rate(exhausted, samples),
+        retry_histogram,
+    })
+}
+
+/// Buckets used for the uniformity test in [`hash_quality`]
+pub const HASH_QUALITY_BUCKETS: u128 = 64;
+
+/// Random-oracle sanity statistics for the KDF h(x, y, s)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashQuality {
+    pub samples: u64,
+    /// Output bits compared for avalanche (bit length of p − 1)
+    pub output_bits: u32,
+    /// Mean fraction of output bits flipped by a one-bit input change (ideal 0.5)
+    pub avalanche_mean: f64,
+    /// Standard deviation of the flipped fraction
+    pub avalanche_std_dev: f64,
+    /// Output counts per equal-width bucket of [0, p)
+    pub buckets: Vec<u64>,
+    /// Pearson chi-square statistic of `buckets` against uniform
+    pub chi_square: f64,
+    /// Degrees of freedom for `chi_square`
+    pub degrees_of_freedom: u64,
+    /// Repeated outputs among all samples
+    pub collisions: u64,
+    /// Collisions expected from the birthday bound for `samples` and p
+    pub expected_collisions: f64,
+}
+
+impl HashQuality {
+    /// Histogram of output buckets
+    #[cfg(feature = "graphs")]
+    pub fn histogram(&self) -> CryptoGraph {
+        CryptoGraph {
+            title: format!("h() Output Distribution (χ²={:.1})", self.chi_square),
+            data: self
+                .buckets
+                .iter()
+                .enumerate()
+                .map(|(i, &n)| (i as f64, n as f64))
+                .collect(),
+            labels: [
+                ("x".to_string(), "Bucket".to_string()),
+                ("y".to_string(), "Outputs".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Histogram,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+}
+
+/// Measure avalanche, uniformity modulo p and collisions of `engine.h`
+pub fn hash_quality(engine: &LaiCryptoEngine, samples: u64) -> HashQuality {
+    let p = engine.p;
+    let output_bits = (128 - (p - 1).leading_zeros()).max(1);
+    let input_bits = 128 - p.leading_zeros();
+    let bucket_count = HASH_QUALITY_BUCKETS.min(p);
+
+    let mut buckets = vec![0u64; bucket_count as usize];
+    let mut seen = std::collections::HashSet::new();
+    let mut collisions = 0;
+    let mut flips = Vec::with_capacity(samples as usize);
+
+    for _ in 0..samples {
+        let (x, y, s) = (OsRng.gen_range(0..p), OsRng.gen_range(0..p), OsRng.gen::<u128>());
+        let out = engine.h(x, y, s);
+
+        // Flip one input bit in x, y or s
+        let (mut fx, mut fy, mut fs) = (x, y, s);
+        match OsRng.gen_range(0..3) {
+            0 => fx ^= 1 << OsRng.gen_range(0..input_bits),
+            1 => fy ^= 1 << OsRng.gen_range(0..input_bits),
+            _ => fs ^= 1 << OsRng.gen_range(0..128),
+        }
+        let flipped = engine.h(fx, fy, fs);
+        flips.push((out ^ flipped).count_ones() as f64 / output_bits as f64);
+
+        let bucket = ((out as f64 / p as f64) * bucket_count as f64) as usize;
+        buckets[bucket.min(bucket_count as usize - 1)] += 1;
+        if !seen.insert(out) {
+            collisions += 1;
+        }
+    }
+
+    let n = samples.max(1) as f64;
+    let avalanche_mean = flips.iter().sum::<f64>() / n;
+    let avalanche_std_dev =
+        (flips.iter().map(|f| (f - avalanche_mean).powi(2)).sum::<f64>() / n).sqrt();
+    let expected = samples as f64 / bucket_count as f64;
+    let chi_square = if expected > 0.0 {
+        buckets
+            .iter()
+            .map(|&b| (b as f64 - expected).powi(2) / expected)
+            .sum()
+    } else {
+        0.0
+    };
+
+    HashQuality {
+        samples,
+        output_bits,
+        avalanche_mean,
+        avalanche_std_dev,
+        buckets,
+        chi_square,
+        degrees_of_freedom: bucket_count as u64 - 1,
+        collisions,
+        expected_collisions: samples as f64 * (samples as f64 - 1.0) / (2.0 * p as f64),
+    }
+}
+
+/// Significance level for [`randomness_tests`], as recommended by SP 800-22
+pub const RANDOMNESS_ALPHA: f64 = 0.01;
+/// Shortest input, in bits, accepted by [`randomness_tests`]
+pub const RANDOMNESS_MIN_BITS: usize = 100;
+
+/// Outcome of one statistical test
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomnessTest {
+    pub name: &'static str,
+    pub statistic: f64,
+    pub p_value: f64,
+    /// `p_value >= RANDOMNESS_ALPHA`
+    pub passed: bool,
+}
+
+impl RandomnessTest {
+    fn new(name: &'static str, statistic: f64, p_value: f64) -> Self {
+        RandomnessTest {
+            name,
+            statistic,
+            p_value,
+            passed: p_value >= RANDOMNESS_ALPHA,
+        }
+    }
+}
+
+/// Results of the SP 800-22 subset run by [`randomness_tests`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomnessReport {
+    pub bits: usize,
+    pub tests: Vec<RandomnessTest>,
+}
+
+impl RandomnessReport {
+    pub fn all_passed(&self) -> bool {
+        self.tests.iter().all(|t| t.passed)
+    }
+}
+
+/// Run the frequency, block frequency, runs and approximate entropy tests
+///
+/// Intended for ciphertext and shared-secret bytes; bits are read most
+/// significant first. Block length and entropy pattern length are chosen
+/// from the input length following the SP 800-22 recommendations.
+pub fn randomness_tests(data: &[u8]) -> Result<RandomnessReport, LaiCryptoError> {
+    let bits: Vec<u8> = data
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .collect();
+    let n = bits.len();
+    if n < RANDOMNESS_MIN_BITS {
+        return Err(LaiCryptoError::InvalidParameter {
+            param: "data".to_string(),
+            value: format!("{} bits", n),
+            reason: "Too short for the statistical tests".to_string(),
+            valid_range: format!("at least {} bits", RANDOMNESS_MIN_BITS),
+        });
+    }
+
+    let block_len = 20.max(n.div_ceil(99));
+    let pattern_len = (n.ilog2() as usize).saturating_sub(6).clamp(1, 10);
+    Ok(RandomnessReport {
+        bits: n,
+        tests: vec![
+            frequency_test(&bits),
+            block_frequency_test(&bits, block_len),
+            runs_test(&bits),
+            approximate_entropy_test(&bits, pattern_len),
+        ],
+    })
+}
+
+fn frequency_test(bits: &[u8]) -> RandomnessTest {
+    let n = bits.len() as f64;
+    let sum: f64 = bits.iter().map(|&b| 2.0 * b as f64 - 1.0).sum();
+    let s_obs = sum.abs() / n.sqrt();
+    RandomnessTest::new("frequency", s_obs, erfc(s_obs / std::f64::consts::SQRT_2))
+}
+
+fn block_frequency_test(bits: &[u8], block_len: usize) -> RandomnessTest {
+    let blocks = bits.len() / block_len;
+    let chi_square = 4.0
+        * block_len as f64
+        * bits
+            .chunks_exact(block_len)
+            .map(|block| {
+                let pi = block.iter().map(|&b| b as f64).sum::<f64>() / block_len as f64;
+                (pi - 0.5).powi(2)
+            })
+            .sum::<f64>();
+    RandomnessTest::new(
+        "block frequency",
+        chi_square,
+        igamc(blocks as f64 / 2.0, chi_square / 2.0),
+    )
+}
+
+fn runs_test(bits: &[u8]) -> RandomnessTest {
+    let n = bits.len() as f64;
+    let pi = bits.iter().map(|&b| b as f64).sum::<f64>() / n;
+    // Prerequisite frequency check; the runs test is not applicable otherwise
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return RandomnessTest::new("runs", 0.0, 0.0);
+    }
+    let runs = 1.0 + bits.windows(2).filter(|w| w[0] != w[1]).count() as f64;
+    let spread = pi * (1.0 - pi);
+    let p_value = erfc((runs - 2.0 * n * spread).abs() / (2.0 * (2.0 * n).sqrt() * spread));
+    RandomnessTest::new("runs", runs, p_value)
+}
+
+fn approximate_entropy_test(bits: &[u8], m: usize) -> RandomnessTest {
+    let n = bits.len();
+    // φ(m) over overlapping, wrapped m-bit patterns
+    let phi = |m: usize| -> f64 {
+        if m == 0 {
+            return 0.0;
+        }
+        let mut counts = vec![0u64; 1 << m];
+        for i in 0..n {
+            let pattern = (0..m).fold(0, |acc, j| (acc << 1) | bits[(i + j) % n] as usize);
+            counts[pattern] += 1;
+        }
+        counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let freq = c as f64 / n as f64;
+                freq * freq.ln()
+            })
+            .sum()
+    };
+    let ap_en = phi(m) - phi(m + 1);
+    let chi_square = 2.0 * n as f64 * (std::f64::consts::LN_2 - ap_en);
+    RandomnessTest::new(
+        "approximate entropy",
+        chi_square,
+        igamc((1u64 << (m - 1)) as f64, chi_square / 2.0),
+    )
+}
+
+/// Complementary error function, via erfc(x) = Q(1/2, x²)
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        2.0 - erfc(-x)
+    } else {
+        igamc(0.5, x * x)
+    }
+}
+
+/// Regularized upper incomplete gamma function Q(a, x)
+fn igamc(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefix = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // Series for P(a, x)
+        let (mut term, mut sum, mut ap) = (1.0 / a, 1.0 / a, a);
+        while term.abs() > sum.abs() * 1e-15 {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+        }
+        1.0 - sum * prefix
+    } else {
+        // Lentz continued fraction for Q(a, x)
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        prefix * h
+    }
+}
+
+/// ln Γ(x) by the Lanczos approximation (g = 7, n = 9)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFS[0], |acc, (i, &c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// First seed whose orbit from the base point cycles without a transform failure
+    fn cycling_seed(engine: &mut LaiCryptoEngine) -> (u128, CycleInfo) {
+        (1..50)
+            .find_map(|s| {
+                let p0 = engine.p0;
+                brent(engine, p0, s, 1_000_000).ok().flatten().map(|info| (s, info))
+            })
+            .expect("some seed should cycle")
+    }
+
+    #[test]
+    fn test_floyd_and_brent_agree() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let (s, brent_info) = cycling_seed(&mut engine);
+        let p0 = engine.p0;
+        let floyd_info = floyd(&mut engine, p0, s, 1_000_000).unwrap().unwrap();
+
+        assert_eq!(floyd_info.tail_length, brent_info.tail_length);
+        assert_eq!(floyd_info.cycle_length, brent_info.cycle_length);
+        assert_eq!(floyd_info.cycle_start, brent_info.cycle_start);
+        assert!(engine.trace.is_empty());
+        assert!(engine.tracing);
+
+        // Cross-check against an explicit walk
+        let mut seen = HashMap::new();
+        let mut point = p0;
+        let mut i = 0u64;
+        while !seen.contains_key(&point) {
+            seen.insert(point, i);
+            point = engine.t(point, s).unwrap();
+            i += 1;
+        }
+        assert_eq!(seen[&point], brent_info.tail_length);
+        assert_eq!(i - seen[&point], brent_info.cycle_length);
+    }
+
+    #[test]
+    fn test_cycle_budget() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let (s, _) = cycling_seed(&mut engine);
+        let p0 = engine.p0;
+        assert_eq!(brent(&mut engine, p0, s, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_exhaustive_small_prime() {
+        let mut engine = LaiCryptoEngine::new(103, 1, (0, 0)).unwrap();
+        let points = curve_points(&engine);
+        let curve = engine.curve();
+        assert!(points.iter().all(|pt| pt.is_on_curve(&curve)));
+        assert!(points.contains(&Point::IDENTITY));
+
+        let report = exhaustive(&mut engine, 1, 1_000_000).unwrap();
+        assert_eq!(report.curve_point_count, points.len() as u64);
+        assert_eq!(
+            report.components.iter().map(|c| c.size).sum::<u64>(),
+            report.node_count
+        );
+        assert_eq!(
+            report.components.iter().map(|c| c.curve_points).sum::<u64>(),
+            report.curve_point_count
+        );
+        assert!(report.components.windows(2).all(|w| w[0].size >= w[1].size));
+        #[cfg(feature = "graphs")]
+        assert_eq!(report.component_graph().data.len(), report.components.len());
+
+        assert!(exhaustive(&mut engine, 1, 1).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "trace")]
+    fn test_profile_failures() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let profile = profile_failures(&mut engine, 400).unwrap();
+        assert_eq!(profile.retry_histogram.iter().sum::<u64>(), 400);
+        assert!(profile.steps >= 400);
+        // Half of F_p is a non-residue, so y2 fails about half the time
+        assert!(profile.step_failure_rate > 0.3 && profile.step_failure_rate < 0.7);
+        assert!(engine.trace.is_empty());
+        assert!(engine.metrics.operation_history.is_empty());
+        #[cfg(feature = "graphs")]
+        assert_eq!(profile.histogram().data.len(), T_SEED_RETRIES as usize + 1);
+    }
+
+    #[test]
+    fn test_hash_quality() {
+        let engine = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        let quality = hash_quality(&engine, 2000);
+        assert_eq!(quality.buckets.iter().sum::<u64>(), 2000);
+        assert!((quality.avalanche_mean - 0.5).abs() < 0.1);
+        // 63 degrees of freedom: χ² beyond 120 has probability well below 1e-6
+        assert!(quality.chi_square < 120.0);
+        assert!(quality.collisions as f64 <= quality.expected_collisions * 3.0 + 10.0);
+        #[cfg(feature = "graphs")]
+        assert_eq!(quality.histogram().data.len(), 64);
+    }
+
+    #[test]
+    fn test_randomness_reference_vectors() {
+        // Worked examples from NIST SP 800-22 rev. 1a, sections 2.1–2.3 and 2.12
+        let eps: Vec<u8> = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000"
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        assert!((frequency_test(&eps).p_value - 0.109599).abs() < 1e-5);
+        assert!((block_frequency_test(&eps, 10).p_value - 0.706438).abs() < 1e-5);
+        assert!((runs_test(&eps).p_value - 0.500798).abs() < 1e-5);
+        assert!((approximate_entropy_test(&eps, 2).p_value - 0.235301).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_randomness_tests() {
+        let mut random = vec![0u8; 4096];
+        OsRng.fill(&mut random[..]);
+        let report = randomness_tests(&random).unwrap();
+        assert_eq!(report.tests.len(), 4);
+        assert_eq!(report.bits, 4096 * 8);
+
+        let constant = randomness_tests(&[0xAA; 512]).unwrap();
+        assert!(!constant.all_passed());
+        assert!(randomness_tests(&[0; 12]).is_err());
+    }
+}
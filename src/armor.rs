@@ -0,0 +1,346 @@
+//! ASCII armor for carrying binary output through text channels
+//!
+//! Armored data is base64 between BEGIN and END lines, followed by a
+//! CRC-24 checksum line as in OpenPGP:
+//!
+//! ```text
+//! -----BEGIN LAI MESSAGE-----
+//! TEFJVgEAAQ...
+//! =xxxx
+//! -----END LAI MESSAGE-----
+//! ```
+//!
+//! [`ArmorWriter`] and [`ArmorReader`] work incrementally, so large
+//! payloads never need to be held as a single string. A reader returns
+//! decoded bytes before it reaches the checksum; only a read that returns
+//! end of file has verified the whole stream.
+
+use crate::{Ciphertext, LaiCryptoEngine, LaiCryptoError, PublicKey};
+use std::io::{self, BufRead, Read, Write};
+
+/// Label for ciphertexts
+pub const MESSAGE: &str = "LAI MESSAGE";
+/// Label for [`crate::signcrypt`] output
+pub const SIGNCRYPTED_MESSAGE: &str = "LAI SIGNCRYPTED MESSAGE";
+/// Label for public keys
+pub const PUBLIC_KEY: &str = "LAI PUBLIC KEY";
+/// Base64 characters per body line
+pub const LINE_LEN: usize = 64;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const CRC24_INIT: u32 = 0xb7_04ce;
+const CRC24_POLY: u32 = 0x186_4cfb;
+
+fn crc24(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= u32::from(byte) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0xff_ffff
+}
+
+/// Encode one to three bytes as four base64 characters
+fn encode_group(group: &[u8]) -> [u8; 4] {
+    let n = group
+        .iter()
+        .enumerate()
+        .fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+    let mut out = [b'='; 4];
+    for (i, c) in out.iter_mut().enumerate().take(group.len() + 1) {
+        *c = ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize];
+    }
+    out
+}
+
+fn decode_char(c: u8) -> Option<u32> {
+    ALPHABET.iter().position(|&a| a == c).map(|i| i as u32)
+}
+
+fn invalid(reason: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.into())
+}
+
+/// Decode a body line, returning whether it ended in padding
+fn decode_line(line: &[u8], out: &mut Vec<u8>) -> io::Result<bool> {
+    if !line.len().is_multiple_of(4) {
+        return Err(invalid("Base64 line length is not a multiple of 4"));
+    }
+    let groups = line.len() / 4;
+    for (index, group) in line.chunks(4).enumerate() {
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && index + 1 != groups) {
+            return Err(invalid("Misplaced base64 padding"));
+        }
+        let mut n = 0u32;
+        for (i, &c) in group[..4 - padding].iter().enumerate() {
+            n |= decode_char(c)
+                .ok_or_else(|| invalid(format!("Invalid base64 character {:?}", c as char)))?
+                << (18 - 6 * i);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(line.ends_with(b"="))
+}
+
+fn begin_line(label: &str) -> String {
+    format!("-----BEGIN {}-----", label)
+}
+
+fn end_line(label: &str) -> String {
+    format!("-----END {}-----", label)
+}
+
+/// Streaming armor encoder; call [`ArmorWriter::finish`] to write the trailer
+pub struct ArmorWriter<W: Write> {
+    inner: W,
+    label: String,
+    pending: Vec<u8>,
+    column: usize,
+    crc: u32,
+}
+
+impl<W: Write> ArmorWriter<W> {
+    /// Write the BEGIN line for `label` and start encoding
+    pub fn new(mut inner: W, label: &str) -> io::Result<Self> {
+        writeln!(inner, "{}", begin_line(label))?;
+        Ok(ArmorWriter {
+            inner,
+            label: label.to_string(),
+            pending: Vec::with_capacity(3),
+            column: 0,
+            crc: CRC24_INIT,
+        })
+    }
+
+    fn emit(&mut self, groups: &[u8]) -> io::Result<()> {
+        let mut out = Vec::with_capacity(groups.len() / 3 * 4 + groups.len() / LINE_LEN + 5);
+        for group in groups.chunks(3) {
+            out.extend_from_slice(&encode_group(group));
+            self.column += 4;
+            if self.column == LINE_LEN {
+                out.push(b'\n');
+                self.column = 0;
+            }
+        }
+        self.inner.write_all(&out)
+    }
+
+    /// Flush the final group, checksum and END line, returning the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let pending = std::mem::take(&mut self.pending);
+        self.emit(&pending)?;
+        if self.column > 0 {
+            self.inner.write_all(b"\n")?;
+        }
+        let checksum = encode_group(&self.crc.to_be_bytes()[1..]);
+        writeln!(self.inner, "={}", String::from_utf8_lossy(&checksum))?;
+        writeln!(self.inner, "{}", end_line(&self.label))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ArmorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.crc = crc24(self.crc, buf);
+        self.pending.extend_from_slice(buf);
+        let whole = self.pending.len() / 3 * 3;
+        let groups: Vec<u8> = self.pending.drain(..whole).collect();
+        self.emit(&groups)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Streaming armor decoder
+pub struct ArmorReader<R: BufRead> {
+    inner: R,
+    label: String,
+    buffer: Vec<u8>,
+    pos: usize,
+    crc: u32,
+    padded: bool,
+    done: bool,
+}
+
+impl<R: BufRead> ArmorReader<R> {
+    /// Skip leading blank lines and read the BEGIN line
+    pub fn new(inner: R) -> io::Result<Self> {
+        let mut reader = ArmorReader {
+            inner,
+            label: String::new(),
+            buffer: Vec::new(),
+            pos: 0,
+            crc: CRC24_INIT,
+            padded: false,
+            done: false,
+        };
+        let first = loop {
+            match reader.next_line()? {
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Err(invalid("Missing BEGIN line")),
+            }
+        };
+        reader.label = first
+            .strip_prefix("-----BEGIN ")
+            .and_then(|rest| rest.strip_suffix("-----"))
+            .ok_or_else(|| invalid("Missing BEGIN line"))?
+            .to_string();
+        Ok(reader)
+    }
+
+    /// Label from the BEGIN line, such as [`MESSAGE`]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.inner.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+
+    /// Decode lines until some data is buffered or the trailer is verified
+    fn fill(&mut self) -> io::Result<()> {
+        while self.buffer.is_empty() && !self.done {
+            let line = self
+                .next_line()?
+                .ok_or_else(|| invalid("Missing checksum and END line"))?;
+            if let Some(checksum) = line.strip_prefix('=') {
+                let mut expected = Vec::with_capacity(3);
+                if checksum.len() != 4 || decode_line(checksum.as_bytes(), &mut expected)? {
+                    return Err(invalid("Malformed checksum line"));
+                }
+                if expected[..] != self.crc.to_be_bytes()[1..] {
+                    return Err(invalid("Armor checksum mismatch"));
+                }
+                if self.next_line()?.as_deref() != Some(end_line(&self.label).as_str()) {
+                    return Err(invalid(format!("Missing END {} line", self.label)));
+                }
+                self.done = true;
+            } else if line.starts_with("-----") {
+                return Err(invalid("Missing armor checksum"));
+            } else if self.padded {
+                return Err(invalid("Data after base64 padding"));
+            } else {
+                self.padded = decode_line(line.as_bytes(), &mut self.buffer)?;
+                self.crc = crc24(self.crc, &self.buffer);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ArmorReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buffer.len() {
+            self.buffer.clear();
+            self.pos = 0;
+            self.fill()?;
+        }
+        let n = buf.len().min(self.buffer.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Armor `data` under `label`
+pub fn armor(label: &str, data: &[u8]) -> String {
+    let mut writer = ArmorWriter::new(Vec::new(), label).expect("writing to a Vec cannot fail");
+    writer
+        .write_all(data)
+        .expect("writing to a Vec cannot fail");
+    let out = writer.finish().expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("armor is ASCII")
+}
+
+/// Decode armored text, returning its label and payload
+pub fn dearmor(text: &[u8]) -> Result<(String, Vec<u8>), LaiCryptoError> {
+    let wrap = |e| LaiCryptoError::serialization("armor", e);
+    let mut reader = ArmorReader::new(text).map_err(wrap)?;
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(wrap)?;
+    Ok((reader.label, data))
+}
+
+/// Decode armored text, requiring the given label
+pub fn dearmor_labeled(text: &[u8], label: &str) -> Result<Vec<u8>, LaiCryptoError> {
+    let (found, data) = dearmor(text)?;
+    if found != label {
+        return Err(LaiCryptoError::ValidationError {
+            operation: "dearmor".to_string(),
+            expected: label.to_string(),
+            actual: found,
+        });
+    }
+    Ok(data)
+}
+
+impl PublicKey {
+    /// Armored envelope of this key
+    pub fn to_armor(&self, engine: &LaiCryptoEngine) -> String {
+        armor(PUBLIC_KEY, &self.to_envelope(engine))
+    }
+}
+
+impl Ciphertext {
+    /// Armored envelope of this ciphertext
+    pub fn to_armor(&self, engine: &LaiCryptoEngine) -> String {
+        armor(MESSAGE, &self.to_envelope(engine))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_armor_roundtrip() {
+        for len in [0, 1, 2, 3, 47, 48, 49, 200] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 % 256) as u8).collect();
+            let text = armor(MESSAGE, &data);
+            assert!(text.starts_with("-----BEGIN LAI MESSAGE-----\n"));
+            assert!(text.lines().all(|l| l.len() <= LINE_LEN));
+            assert_eq!(
+                dearmor(text.as_bytes()).unwrap(),
+                (MESSAGE.to_string(), data.clone())
+            );
+
+            // Streaming in small pieces gives the same text
+            let mut writer = ArmorWriter::new(Vec::new(), MESSAGE).unwrap();
+            for piece in data.chunks(5) {
+                writer.write_all(piece).unwrap();
+            }
+            assert_eq!(String::from_utf8(writer.finish().unwrap()).unwrap(), text);
+        }
+
+        // RFC 4648 test vector and a CRLF transport
+        let text = armor(MESSAGE, b"foobar").replace('\n', "\r\n");
+        assert!(text.contains("Zm9vYmFy"));
+        assert_eq!(
+            dearmor_labeled(text.as_bytes(), MESSAGE).unwrap(),
+            b"foobar"
+        );
+        assert!(dearmor_labeled(text.as_bytes(), PUBLIC_KEY).is_err());
+
+        let tampered = armor(MESSAGE, b"foobar").replace("Zm9v", "Zm9w");
+        assert!(matches!(
+            dearmor(tampered.as_bytes()),
+            Err(LaiCryptoError::Serialization { .. })
+        ));
+        let unterminated = armor(MESSAGE, b"foobar").replace("-----END LAI MESSAGE-----\n", "");
+        assert!(dearmor(unterminated.as_bytes()).is_err());
+    }
+}
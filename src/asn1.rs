@@ -0,0 +1,248 @@
+//! DER encodings of parameter sets, keys, ciphertexts and signcrypted messages
+//!
+//! The structures follow this ASN.1 module; integers are non-negative and
+//! encoded in their minimal form, so equal values give identical DER:
+//!
+//! ```text
+//! LaiPoint ::= SEQUENCE { x INTEGER, y INTEGER }
+//! LaiParameters ::= SEQUENCE { p INTEGER, a INTEGER, p0 LaiPoint }
+//! LaiPublicKey ::= SEQUENCE { parameters LaiParameters, q LaiPoint }
+//! LaiCiphertext ::= SEQUENCE {
+//!     version INTEGER, c1 LaiPoint, c2 LaiPoint, tag OCTET STRING (SIZE (32)) }
+//! LaiSigncrypted ::= SEQUENCE {
+//!     version INTEGER, ephemeral LaiPoint, sender LaiPoint,
+//!     body OCTET STRING, mac OCTET STRING (SIZE (64)) }
+//! ```
+//!
+//! The crate has no signature primitive; signcrypted messages from
+//! [`crate::signcrypt`] are its sender-authenticated output and convert
+//! losslessly to and from their native byte form.
+
+use crate::hybrid::MAC_LEN;
+use crate::{signcrypt, Ciphertext, CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point, PublicKey, TAG_LEN};
+use der::asn1::OctetString;
+use der::{Decode, Encode, Sequence};
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+pub struct DerPoint {
+    pub x: u128,
+    pub y: u128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+pub struct DerParameters {
+    pub p: u128,
+    pub a: u128,
+    pub p0: DerPoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+pub struct DerPublicKey {
+    pub parameters: DerParameters,
+    pub q: DerPoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Sequence)]
+pub struct DerCiphertext {
+    pub version: u8,
+    pub c1: DerPoint,
+    pub c2: DerPoint,
+    pub tag: OctetString,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Sequence)]
+pub struct DerSigncrypted {
+    pub version: u8,
+    pub ephemeral: DerPoint,
+    pub sender: DerPoint,
+    pub body: OctetString,
+    pub mac: OctetString,
+}
+
+impl From<Point> for DerPoint {
+    fn from(p: Point) -> Self {
+        DerPoint { x: p.x(), y: p.y() }
+    }
+}
+
+impl DerPoint {
+    /// Convert to a point, requiring both coordinates below `p`
+    pub fn to_point(self, p: u128) -> Result<Point, LaiCryptoError> {
+        if self.x >= p || self.y >= p {
+            return Err(invalid("Coordinate not reduced modulo p"));
+        }
+        Ok(Point::new_unchecked(self.x, self.y))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let coord = |b: &[u8]| u128::from_be_bytes(b.try_into().expect("16-byte coordinate"));
+        DerPoint {
+            x: coord(&bytes[..16]),
+            y: coord(&bytes[16..Point::ENCODED_LEN]),
+        }
+    }
+}
+
+impl DerParameters {
+    pub fn from_engine(engine: &LaiCryptoEngine) -> Self {
+        DerParameters {
+            p: engine.p,
+            a: engine.a,
+            p0: engine.p0.into(),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> LaiCryptoError {
+    LaiCryptoError::serialization("DER", io::Error::new(io::ErrorKind::InvalidData, reason.to_string()))
+}
+
+fn decode<'a, T: Decode<'a>>(bytes: &'a [u8]) -> Result<T, LaiCryptoError> {
+    T::from_der(bytes).map_err(|e| LaiCryptoError::serialization("DER", e))
+}
+
+fn encode(value: &impl Encode) -> Vec<u8> {
+    value.to_der().expect("structure lengths fit DER")
+}
+
+fn octets(bytes: &[u8]) -> OctetString {
+    OctetString::new(bytes).expect("octet string length fits DER")
+}
+
+/// Encode `engine`'s parameter set
+pub fn params_to_der(engine: &LaiCryptoEngine) -> Vec<u8> {
+    encode(&DerParameters::from_engine(engine))
+}
+
+/// Build an engine from a DER parameter set, validating it as [`LaiCryptoEngine::new`] does
+pub fn params_from_der(bytes: &[u8]) -> Result<LaiCryptoEngine, LaiCryptoError> {
+    let params: DerParameters = decode(bytes)?;
+    LaiCryptoEngine::new(params.p, params.a, (params.p0.x, params.p0.y))
+}
+
+pub fn public_key_to_der(key: &PublicKey, engine: &LaiCryptoEngine) -> Vec<u8> {
+    encode(&DerPublicKey {
+        parameters: DerParameters::from_engine(engine),
+        q: key.point().into(),
+    })
+}
+
+/// Decode a key, requiring it to belong to `engine`'s parameters
+pub fn public_key_from_der(bytes: &[u8], engine: &LaiCryptoEngine) -> Result<PublicKey, LaiCryptoError> {
+    let key: DerPublicKey = decode(bytes)?;
+    let expected = DerParameters::from_engine(engine);
+    if key.parameters != expected {
+        let describe = |d: &DerParameters| format!("p={}, a={}, P0=({}, {})", d.p, d.a, d.p0.x, d.p0.y);
+        return Err(LaiCryptoError::ValidationError {
+            operation: "DER public key".to_string(),
+            expected: describe(&expected),
+            actual: describe(&key.parameters),
+        });
+    }
+    Ok(PublicKey::new(key.q.to_point(engine.p)?))
+}
+
+pub fn ciphertext_to_der(ct: &Ciphertext) -> Vec<u8> {
+    encode(&DerCiphertext {
+        version: ct.version.to_byte(),
+        c1: ct.c1.into(),
+        c2: ct.c2.into(),
+        tag: octets(&ct.tag),
+    })
+}
+
+pub fn ciphertext_from_der(bytes: &[u8], p: u128) -> Result<Ciphertext, LaiCryptoError> {
+    let ct: DerCiphertext = decode(bytes)?;
+    Ok(Ciphertext {
+        c1: ct.c1.to_point(p)?,
+        c2: ct.c2.to_point(p)?,
+        tag: ct
+            .tag
+            .as_bytes()
+            .try_into()
+            .map_err(|_| invalid(&format!("Tag must be {} bytes", TAG_LEN)))?,
+        version: CiphertextVersion::from_byte(ct.version).ok_or_else(|| invalid("Unknown ciphertext version"))?,
+    })
+}
+
+/// Re-encode a native signcrypted message as DER
+///
+/// Only the framing is checked; authenticity is left to
+/// [`signcrypt::unsigncrypt`] on the native form.
+pub fn signcrypted_to_der(message: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+    let magic = signcrypt::MAGIC.len();
+    if message.len() < signcrypt::HEADER_LEN + MAC_LEN || message[..magic] != signcrypt::MAGIC {
+        return Err(invalid("Not a signcrypted message"));
+    }
+    let points = &message[magic + 1..signcrypt::HEADER_LEN];
+    let (body, mac) = message[signcrypt::HEADER_LEN..].split_at(message.len() - signcrypt::HEADER_LEN - MAC_LEN);
+    Ok(encode(&DerSigncrypted {
+        version: message[magic],
+        ephemeral: DerPoint::from_bytes(&points[..Point::ENCODED_LEN]),
+        sender: DerPoint::from_bytes(&points[Point::ENCODED_LEN..]),
+        body: octets(body),
+        mac: octets(mac),
+    }))
+}
+
+/// Recover the native signcrypted bytes from their DER form
+pub fn signcrypted_from_der(bytes: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+    let message: DerSigncrypted = decode(bytes)?;
+    if message.mac.as_bytes().len() != MAC_LEN {
+        return Err(invalid(&format!("MAC must be {} bytes", MAC_LEN)));
+    }
+    let mut out = Vec::with_capacity(signcrypt::HEADER_LEN + message.body.as_bytes().len() + MAC_LEN);
+    out.extend_from_slice(&signcrypt::MAGIC);
+    out.push(message.version);
+    for point in [message.ephemeral, message.sender] {
+        out.extend_from_slice(&point.x.to_be_bytes());
+        out.extend_from_slice(&point.y.to_be_bytes());
+    }
+    out.extend_from_slice(message.body.as_bytes());
+    out.extend_from_slice(message.mac.as_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_der_roundtrip() {
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let restored = params_from_der(&params_to_der(&engine)).unwrap();
+        assert_eq!((restored.p, restored.a, restored.p0), (engine.p, engine.a, engine.p0));
+
+        let key = PublicKey::new(engine.p0);
+        let der = public_key_to_der(&key, &engine);
+        assert_eq!(der[0], 0x30);
+        assert_eq!(public_key_from_der(&der, &engine).unwrap().point(), key.point());
+        let other = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        assert!(matches!(
+            public_key_from_der(&der, &other),
+            Err(LaiCryptoError::ValidationError { .. })
+        ));
+
+        let ct = Ciphertext {
+            c1: engine.p0,
+            c2: Point::new_unchecked(1008, 0),
+            tag: [0xab; TAG_LEN],
+            version: CiphertextVersion::V2,
+        };
+        assert_eq!(ciphertext_from_der(&ciphertext_to_der(&ct), engine.p).unwrap(), ct);
+        assert!(ciphertext_from_der(&ciphertext_to_der(&ct), 1000).is_err());
+        let mut trailing = ciphertext_to_der(&ct);
+        trailing.push(0);
+        assert!(matches!(
+            ciphertext_from_der(&trailing, engine.p),
+            Err(LaiCryptoError::Serialization { .. })
+        ));
+
+        let mut message = signcrypt::MAGIC.to_vec();
+        message.push(signcrypt::VERSION);
+        message.extend((0..2 * Point::ENCODED_LEN as u8 + 5).chain([7; MAC_LEN]));
+        let der = signcrypted_to_der(&message).unwrap();
+        assert_eq!(signcrypted_from_der(&der).unwrap(), message);
+    }
+}
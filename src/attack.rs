@@ -0,0 +1,116 @@
+//! Generic key-recovery attack simulators
+//!
+//! These run real attacks with a bounded work budget so users can check
+//! empirically that a parameter set does not fall to them.
+
+use crate::{CurveParams, LaiCryptoEngine, LaiCryptoError, Point, CHAIN_SEED};
+use std::time::Duration;
+
+/// Outcome of a budgeted key-recovery attempt
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackReport {
+    /// Recovered private key, if any
+    pub recovered: Option<u128>,
+    /// T-transform evaluations performed
+    pub operations: u64,
+    /// Evaluation budget the attack was given
+    pub budget: u64,
+    /// Wall-clock time spent
+    pub elapsed: Duration,
+    /// Whether the walk ended early because T failed
+    pub chain_broken: bool,
+    /// Evaluations needed to cover the whole key space 1..p
+    pub full_cost: u128,
+}
+
+impl AttackReport {
+    /// Fraction of the key space covered by the attack
+    pub fn coverage(&self) -> f64 {
+        self.operations as f64 / self.full_cost as f64
+    }
+
+    /// Extrapolated time to exhaust the key space at the observed rate
+    pub fn projected_full_time(&self) -> Option<Duration> {
+        if self.operations == 0 {
+            return None;
+        }
+        let per_op = self.elapsed.as_secs_f64() / self.operations as f64;
+        Some(Duration::from_secs_f64(per_op * self.full_cost as f64))
+    }
+}
+
+/// Chain walk recovering k from Q = f^k(P0)
+///
+/// f is the fixed-seed map of [`crate::CHAIN_SEED`]. It has no group law,
+/// so a rho-style collision in its orbit reveals the cycle structure but
+/// not k; the attack walks the orbit from `p0` and compares each point
+/// against `public_point`. Any k that reaches `public_point` is reported,
+/// which is as good as the original key for decryption. The walk runs on
+/// its own untraced engine over `params` with the default hash.
+pub fn chain_walk(
+    params: &CurveParams,
+    p0: Point,
+    public_point: Point,
+    budget: u64,
+) -> Result<AttackReport, LaiCryptoError> {
+    let mut engine = LaiCryptoEngine::new_unchecked(params.p, params.a, p0);
+    engine.tracing = false;
+    let start = engine.clock.now();
+    let full_cost = params.p - 1;
+
+    let mut current = p0;
+    let mut operations = 0;
+    let mut recovered = None;
+    let mut chain_broken = false;
+
+    while operations < budget && u128::from(operations) < full_cost {
+        let k = u128::from(operations) + 1;
+        operations += 1;
+        match engine.t(current, CHAIN_SEED) {
+            Ok(next) => current = next,
+            Err(LaiCryptoError::TransformFailure { .. }) => {
+                // the orbit cannot reach any larger k either
+                chain_broken = true;
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+        if current == public_point {
+            recovered = Some(k);
+            break;
+        }
+    }
+
+    Ok(AttackReport {
+        recovered,
+        operations,
+        budget,
+        elapsed: engine.clock.since(start),
+        chain_broken,
+        full_cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_walk_recovers_small_key() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 25;
+        let public = engine.pow_t(engine.p0, k).unwrap();
+        let (params, p0) = (engine.curve(), engine.p0);
+
+        let report = chain_walk(&params, p0, public, 1_000).unwrap();
+        let found = report.recovered.expect("key within budget");
+        assert!(found <= k);
+        assert_eq!(engine.pow_t(engine.p0, found).unwrap(), public);
+        assert_eq!(report.operations as u128, found);
+
+        let starved = chain_walk(&params, p0, public, found as u64 - 1).unwrap();
+        assert_eq!(starved.recovered, None);
+        assert_eq!(starved.operations, found as u64 - 1);
+        assert!(starved.coverage() < 1.0);
+    }
+}
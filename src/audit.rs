@@ -0,0 +1,304 @@
+//! Weak-parameter audit with machine-readable findings
+//!
+//! The [`log`] submodule keeps the separate hash-chained record of key
+//! operations.
+
+use crate::{add_mod, analysis, is_prime, simd, CurveParams, LaiCryptoEngine, CHAIN_SEED};
+use std::fmt;
+
+pub mod log;
+
+pub use log::{verify_chain, AuditLog, AuditRecord, KeyOperation};
+
+/// Moduli with fewer bits than this are reported as tiny
+pub const MIN_MODULUS_BITS: u32 = 64;
+/// Trial-division bound used for the p − 1 smoothness check
+pub const SMOOTHNESS_BOUND: u128 = 1 << 16;
+/// Transform evaluations spent looking for a short base-point cycle
+pub const SHORT_CYCLE_BUDGET: u64 = 1 << 16;
+/// Points sampled when estimating the per-step square-root failure rate
+pub const SQRT_FAILURE_SAMPLES: u128 = 256;
+/// Per-step failure rate above which a finding is raised (random is ~0.5)
+pub const MAX_SQRT_FAILURE_RATE: f64 = 0.6;
+
+/// How serious a finding is, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        })
+    }
+}
+
+/// Category of an audit finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FindingKind {
+    /// Modulus is not prime
+    CompositeModulus,
+    /// Modulus too small for any meaningful security
+    TinyModulus,
+    /// a ≡ 0 (mod p)
+    ZeroCoefficient,
+    /// p − 1 has only small prime factors
+    SmoothOrder,
+    /// The base point's fixed-seed orbit enters a short cycle
+    ShortBaseCycle,
+    /// T needs a square root of a non-residue too often
+    HighSqrtFailureRate,
+    /// A single T-transform ran out of seed retries
+    TransformExhausted,
+    /// Key generation ran out of attempts
+    KeygenExhausted,
+    /// A public key coordinate is not reduced modulo p
+    KeyOutOfRange,
+    /// A public key is a degenerate point or lies early on the base chain
+    WeakKeyPoint,
+}
+
+/// One diagnostic with severity and remediation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub message: String,
+    pub remediation: String,
+}
+
+impl Finding {
+    pub fn new(
+        kind: FindingKind,
+        severity: Severity,
+        message: impl Into<String>,
+        remediation: impl Into<String>,
+    ) -> Self {
+        Finding {
+            kind,
+            severity,
+            message: message.into(),
+            remediation: remediation.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {} ({})", self.severity, self.message, self.remediation)
+    }
+}
+
+/// Join findings for error messages
+pub(crate) fn describe(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Checks that need no factoring or transform evaluations
+pub(crate) fn quick_findings(params: &CurveParams) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let bits = 128 - params.p.leading_zeros();
+    if bits < MIN_MODULUS_BITS {
+        findings.push(Finding::new(
+            FindingKind::TinyModulus,
+            if bits < 32 { Severity::Critical } else { Severity::High },
+            format!("Modulus has only {} bits", bits),
+            format!("Use a modulus of at least {} bits", MIN_MODULUS_BITS),
+        ));
+    }
+    if params.a.is_multiple_of(params.p) {
+        findings.push(Finding::new(
+            FindingKind::ZeroCoefficient,
+            Severity::High,
+            "a ≡ 0 gives the special curve y² = x³",
+            "Choose a non-zero curve coefficient a",
+        ));
+    }
+    findings
+}
+
+impl CurveParams {
+    /// Audit the curve parameters, most severe findings first
+    pub fn audit(&self) -> Vec<Finding> {
+        let mut findings = quick_findings(self);
+        if self.p <= u64::MAX as u128 && !is_prime(self.p) {
+            findings.push(Finding::new(
+                FindingKind::CompositeModulus,
+                Severity::Critical,
+                format!("Modulus {} is not prime", self.p),
+                "Use a prime modulus",
+            ));
+        } else if self.p > 2 {
+            let largest = largest_factor_below(self.p - 1, SMOOTHNESS_BOUND);
+            if largest.is_some() {
+                findings.push(Finding::new(
+                    FindingKind::SmoothOrder,
+                    Severity::Medium,
+                    format!("p − 1 is {}-smooth", SMOOTHNESS_BOUND),
+                    "Prefer a safe prime p = 2q + 1 with q prime",
+                ));
+            }
+        }
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    }
+}
+
+/// Largest prime factor of n if all factors are below `bound`
+fn largest_factor_below(mut n: u128, bound: u128) -> Option<u128> {
+    let mut largest = 1;
+    let mut d = 2;
+    while d < bound && d * d <= n {
+        while n.is_multiple_of(d) {
+            n /= d;
+            largest = d;
+        }
+        d += 1;
+    }
+    if n > 1 {
+        if n >= bound {
+            return None;
+        }
+        largest = largest.max(n);
+    }
+    Some(largest)
+}
+
+impl LaiCryptoEngine {
+    /// Audit curve parameters, base point and transform behaviour
+    ///
+    /// Transform-based checks are bounded by [`SHORT_CYCLE_BUDGET`]
+    /// evaluations of the fixed-seed chain and [`SQRT_FAILURE_SAMPLES`]
+    /// samples, whatever the modulus. Engine tracing is suspended throughout.
+    pub fn audit(&mut self) -> Vec<Finding> {
+        let curve = self.curve();
+        let mut findings = curve.audit();
+
+        if let Ok(Some(info)) = analysis::brent(self, self.p0, CHAIN_SEED, SHORT_CYCLE_BUDGET) {
+            if u128::from(info.cycle_length).pow(2) < curve.p {
+                findings.push(Finding::new(
+                    FindingKind::ShortBaseCycle,
+                    Severity::High,
+                    format!(
+                        "Base point {} enters a cycle of length {} after {} steps",
+                        self.p0, info.cycle_length, info.tail_length
+                    ),
+                    "Choose a different base point",
+                ));
+            }
+        }
+
+        let rate = self.sqrt_failure_rate();
+        if rate > MAX_SQRT_FAILURE_RATE {
+            findings.push(Finding::new(
+                FindingKind::HighSqrtFailureRate,
+                Severity::High,
+                format!("{:.0}% of T steps hit a non-residue", rate * 100.0),
+                "Check that p is prime and a is non-zero",
+            ));
+        }
+
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    }
+
+    /// Fraction of deterministic sample points whose y2 has no square root
+    ///
+    /// Every sample is tested at once with Euler's criterion: y2 is a
+    /// non-residue exactly when y2^((p−1)/2) ≡ −1. Any other value above 1
+    /// only occurs for composite p and is counted as a failure too.
+    fn sqrt_failure_rate(&mut self) -> f64 {
+        let p = self.p;
+        let samples = SQRT_FAILURE_SAMPLES.min(p);
+        let xs: Vec<u128> = (0..samples).map(|i| i % p).collect();
+        let ys: Vec<u128> = (0..samples).map(|i| (i * 7919) % p).collect();
+        let mut y2 = vec![0; xs.len()];
+        simd::mul_mod_batch(&xs, &ys, p, &mut y2);
+        for (i, v) in y2.iter_mut().enumerate() {
+            let hh = self.h(xs[i], ys[i], i as u128 + 1);
+            *v = add_mod(*v, hh, p);
+        }
+        let mut euler = vec![0; y2.len()];
+        simd::pow_mod_batch(&y2, (p - 1) / 2, p, &mut euler);
+        let failures = euler.iter().filter(|&&e| e > 1).count();
+        failures as f64 / samples as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_audit_findings() {
+        let tiny = CurveParams { p: 1009, a: 0 };
+        let findings = tiny.audit();
+        let kinds: Vec<_> = findings.iter().map(|f| f.kind).collect();
+        assert!(kinds.contains(&FindingKind::TinyModulus));
+        assert!(kinds.contains(&FindingKind::ZeroCoefficient));
+        assert!(kinds.contains(&FindingKind::SmoothOrder));
+        assert!(findings.windows(2).all(|w| w[0].severity >= w[1].severity));
+
+        let composite = CurveParams { p: 1001, a: 3 }.audit();
+        assert!(composite
+            .iter()
+            .any(|f| f.kind == FindingKind::CompositeModulus && f.severity == Severity::Critical));
+
+        // 2^61 − 1 − 1 = 2·3²·5²·7·11·13·31·41·61·151·331·1321
+        let mersenne = CurveParams { p: (1 << 61) - 1, a: 3 }.audit();
+        assert_eq!(mersenne.len(), 2);
+        assert!(mersenne.iter().all(|f| f.kind != FindingKind::CompositeModulus));
+    }
+
+    #[test]
+    fn test_engine_audit() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let findings = engine.audit();
+        assert!(findings.iter().all(|f| f.kind != FindingKind::HighSqrtFailureRate));
+        assert!(engine.trace.is_empty());
+        assert!(findings[0].to_string().starts_with("[CRITICAL]"));
+
+        // The base chain under CHAIN_SEED cycles after 237 steps with period 179
+        let p0 = engine.p0;
+        let info = analysis::brent(&mut engine, p0, CHAIN_SEED, SHORT_CYCLE_BUDGET)
+            .unwrap()
+            .unwrap();
+        assert_eq!((info.tail_length, info.cycle_length), (237, 179));
+        assert!(findings.iter().all(|f| f.kind != FindingKind::ShortBaseCycle));
+
+        // Moduli above 2^64 are no longer skipped
+        let p = (1 << 89) - 1;
+        let mut wide = (1..)
+            .find_map(|x| LaiCryptoEngine::new(p, 3, (x, 0)).ok())
+            .unwrap();
+        wide.audit();
+        assert!(wide.metrics.t_transform_count > 0);
+
+        // Euler's criterion agrees with actually taking the square roots
+        let failures = (0..SQRT_FAILURE_SAMPLES)
+            .filter(|&i| {
+                let (x, y) = (i, (i * 7919) % 1009);
+                let y2 = add_mod(crate::mul_mod(x, y, 1009), engine.h(x, y, i + 1), 1009);
+                engine.sqrt_mod(y2).is_none()
+            })
+            .count();
+        assert_eq!(
+            engine.sqrt_failure_rate(),
+            failures as f64 / SQRT_FAILURE_SAMPLES as f64
+        );
+    }
+}
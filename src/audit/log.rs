@@ -0,0 +1,246 @@
+//! Tamper-evident log of key operations
+//!
+//! Each record stores the hash of its predecessor, and its own hash covers
+//! every field:
+//!
+//! ```text
+//! SHA-256("LAI-audit" | previous hash | seq (8) | timestamp (8) | operation
+//!         | key flag | fingerprint (32, if flagged) | params hash (16) | success)
+//! ```
+//!
+//! Editing, inserting, removing or reordering a record breaks the chain
+//! from that point on, which [`verify_chain`] reports. Dropping records
+//! from the end leaves a valid chain, so keep [`AuditLog::head`] somewhere
+//! the log's writer cannot change if truncation matters.
+
+use crate::envelope::PARAMS_HASH_LEN;
+use crate::keyring::Fingerprint;
+use crate::{LaiCryptoEngine, LaiCryptoError, Point, PublicKey};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Previous-hash value of the first record
+pub const GENESIS: [u8; 32] = [0; 32];
+
+/// Kind of key operation recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub enum KeyOperation {
+    Keygen,
+    Encrypt,
+    Decrypt,
+}
+
+impl KeyOperation {
+    fn to_byte(self) -> u8 {
+        match self {
+            KeyOperation::Keygen => 1,
+            KeyOperation::Encrypt => 2,
+            KeyOperation::Decrypt => 3,
+        }
+    }
+}
+
+impl fmt::Display for KeyOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            KeyOperation::Keygen => "keygen",
+            KeyOperation::Encrypt => "encrypt",
+            KeyOperation::Decrypt => "decrypt",
+        })
+    }
+}
+
+/// One chained entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct AuditRecord {
+    /// Position in the log, from 0
+    pub seq: u64,
+    /// Unix seconds
+    pub timestamp: u64,
+    pub operation: KeyOperation,
+    /// Public key involved; decryption knows only the secret scalar
+    pub key: Option<Fingerprint>,
+    pub params_hash: [u8; PARAMS_HASH_LEN],
+    pub success: bool,
+    pub prev_hash: [u8; 32],
+    pub hash: [u8; 32],
+}
+
+impl AuditRecord {
+    /// Hash over every field but `hash` itself
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"LAI-audit");
+        hasher.update(self.prev_hash);
+        hasher.update(self.seq.to_be_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        hasher.update([self.operation.to_byte()]);
+        match &self.key {
+            Some(fingerprint) => {
+                hasher.update([1]);
+                hasher.update(fingerprint.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        hasher.update(self.params_hash);
+        hasher.update([u8::from(self.success)]);
+        hasher.finalize().into()
+    }
+}
+
+/// Append-only chain of [`AuditRecord`]s
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct AuditLog {
+    records: Vec<AuditRecord>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain a record for `operation` onto the log
+    pub fn append(
+        &mut self,
+        operation: KeyOperation,
+        key: Option<Fingerprint>,
+        params_hash: [u8; PARAMS_HASH_LEN],
+        success: bool,
+        timestamp: u64,
+    ) -> &AuditRecord {
+        let mut record = AuditRecord {
+            seq: self.records.len() as u64,
+            timestamp,
+            operation,
+            key,
+            params_hash,
+            success,
+            prev_hash: self.head(),
+            hash: [0; 32],
+        };
+        record.hash = record.compute_hash();
+        self.records.push(record);
+        self.records.last().expect("just pushed")
+    }
+
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Hash of the newest record, or [`GENESIS`] when empty
+    pub fn head(&self) -> [u8; 32] {
+        self.records.last().map_or(GENESIS, |r| r.hash)
+    }
+
+    pub fn verify(&self) -> Result<(), LaiCryptoError> {
+        verify_chain(&self.records)
+    }
+}
+
+/// Check that `records` form an unbroken chain from [`GENESIS`]
+///
+/// Returns a `ValidationError` naming the first record whose sequence
+/// number, link or hash is wrong.
+pub fn verify_chain(records: &[AuditRecord]) -> Result<(), LaiCryptoError> {
+    let mut prev = GENESIS;
+    for (i, record) in records.iter().enumerate() {
+        let broken = |expected: String, actual: String| LaiCryptoError::ValidationError {
+            operation: format!("audit chain record {}", i),
+            expected,
+            actual,
+        };
+        if record.seq != i as u64 {
+            return Err(broken(format!("seq {}", i), format!("seq {}", record.seq)));
+        }
+        if record.prev_hash != prev {
+            return Err(broken(
+                format!("previous hash {}", crate::envelope::hex(&prev)),
+                format!("previous hash {}", crate::envelope::hex(&record.prev_hash)),
+            ));
+        }
+        let hash = record.compute_hash();
+        if record.hash != hash {
+            return Err(broken(
+                format!("hash {}", crate::envelope::hex(&hash)),
+                format!("hash {}", crate::envelope::hex(&record.hash)),
+            ));
+        }
+        prev = record.hash;
+    }
+    Ok(())
+}
+
+impl LaiCryptoEngine {
+    /// Append to `audit_log`, if enabled
+    pub(crate) fn log_key_operation(
+        &mut self,
+        operation: KeyOperation,
+        key: Option<Point>,
+        success: bool,
+    ) {
+        if self.audit_log.is_none() {
+            return;
+        }
+        let fingerprint = key.map(|q| PublicKey::new(q).fingerprint(self));
+        let params_hash = self.params_hash();
+        let now = crate::metadata::unix_now();
+        if let Some(log) = &mut self.audit_log {
+            log.append(operation, fingerprint, params_hash, success, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_chain() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 7;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        engine.audit_log = Some(AuditLog::new());
+        let ct = engine.encrypt_with_ephemeral(42, q, k).unwrap();
+        engine.encrypt(5, q).ok();
+        engine.decrypt(&ct, k).unwrap();
+        assert!(engine.decrypt(&ct, k + 1).is_err());
+
+        let log = engine.audit_log.take().unwrap();
+        let ops: Vec<_> = log
+            .records()
+            .iter()
+            .map(|r| (r.operation, r.success))
+            .collect();
+        assert_eq!(
+            ops[ops.len() - 2..],
+            [
+                (KeyOperation::Decrypt, true),
+                (KeyOperation::Decrypt, false)
+            ]
+        );
+        assert_eq!(
+            log.records()[0].key,
+            Some(PublicKey::new(q).fingerprint(&engine))
+        );
+        log.verify().unwrap();
+
+        let mut records = log.records().to_vec();
+        records[1].success = !records[1].success;
+        assert!(verify_chain(&records).is_err());
+        let mut records = log.records().to_vec();
+        records.remove(1);
+        assert!(verify_chain(&records).is_err());
+        assert!(verify_chain(&log.records()[..2]).is_ok());
+    }
+}
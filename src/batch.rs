@@ -0,0 +1,320 @@
+//! Many operations at once, spread across threads
+//!
+//! [`LaiCryptoEngine::decrypt_batch`] opens a mailbox of ciphertexts with a
+//! single secret key, and [`LaiCryptoEngine::keygen_n`] provisions many
+//! key pairs. Shared tables are computed once; the work is then split
+//! across worker engines on scoped threads, each copying this engine's
+//! parameters, hash and limits with tracing off. Per-item results come
+//! back in input order, and the calling engine records one audit entry per
+//! item and the transform counts of all workers, but no per-step trace.
+//!
+//! ```
+//! use laicrypto::{LaiCryptoEngine, SecretKey};
+//!
+//! let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+//! let k = 7;
+//! let q = engine.pow_t(engine.p0, k).unwrap();
+//! let cts: Vec<_> = (0..4)
+//!     .map(|m| engine.encrypt(m, q).unwrap().0)
+//!     .collect();
+//! let opened = engine.decrypt_batch(&cts, &SecretKey::new(k));
+//! assert_eq!(opened.into_iter().collect::<Result<Vec<_>, _>>().unwrap(), [0, 1, 2, 3]);
+//! ```
+
+use crate::audit::KeyOperation;
+use crate::field::sample_below;
+use crate::precompute::PrecomputeProfile;
+use crate::{
+    Ciphertext, ExposeSecret, KeyPair, KeygenReport, LaiCryptoEngine, LaiCryptoError,
+    PublicKey, SecretKey,
+};
+use rand::RngCore;
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+impl LaiCryptoEngine {
+    /// Decrypt every ciphertext in `cts` with `key`, in parallel
+    ///
+    /// Each result equals what [`LaiCryptoEngine::decrypt`] returns for that
+    /// ciphertext; one failure does not affect the others.
+    pub fn decrypt_batch(
+        &mut self,
+        cts: &[Ciphertext],
+        key: &SecretKey,
+    ) -> Vec<Result<u128, LaiCryptoError>> {
+        self.in_operation(|engine| engine.decrypt_batch_inner(cts, *key.expose_secret()))
+    }
+
+    fn decrypt_batch_inner(
+        &mut self,
+        cts: &[Ciphertext],
+        k: u128,
+    ) -> Vec<Result<u128, LaiCryptoError>> {
+        let start = self.clock.now();
+        let mut template = self.worker();
+        if template.precomputed().is_none() {
+            template.precompute(PrecomputeProfile::Field);
+        }
+
+        let mut workers = template.workers(cts.len());
+        let results = par_map(&mut workers, cts, |worker, ct| worker.open(ct, k));
+
+        for worker in &workers {
+            self.metrics.t_transform_count += worker.metrics.t_transform_count;
+            self.metrics.sqrt_attempts += worker.metrics.sqrt_attempts;
+        }
+        for result in &results {
+            self.log_key_operation(KeyOperation::Decrypt, None, result.is_ok());
+        }
+        let duration = self.clock.since(start);
+        self.record_operation("decrypt_batch", duration);
+        results
+    }
+
+    /// Decryption without logging or metrics, as run by a batch worker
+    fn open(&mut self, ct: &Ciphertext, k: u128) -> Result<u128, LaiCryptoError> {
+        ct.validate(&self.curve())
+            .and_then(|()| self.pow_t_secret(ct.c1, k))
+            .and_then(|shared| self.open_with_shared(ct, shared, "decrypt"))
+    }
+
+    /// Generate `count` key pairs, attempts for all of them running in parallel
+    ///
+    /// Each pair is drawn and checked as by [`LaiCryptoEngine::keygen`],
+    /// with up to `max_attempts` attempts apiece. Attempts run in rounds: a
+    /// round fills one buffer from the engine's RNG with scalars for every
+    /// pair still missing, then evaluates their chains across the workers,
+    /// which share a [`PrecomputeProfile::Standard`] table of the base
+    /// point's chain. An attempt landing on a public point already issued
+    /// in this batch counts as failed, so the pairs are distinct. A pair
+    /// still missing after the last round is `KeygenFailed`.
+    pub fn keygen_n(&mut self, count: usize) -> Vec<Result<KeyPair, LaiCryptoError>> {
+        self.in_operation(|engine| engine.keygen_n_inner(count))
+    }
+
+    fn keygen_n_inner(&mut self, count: usize) -> Vec<Result<KeyPair, LaiCryptoError>> {
+        let start = self.clock.now();
+        let mut template = self.worker();
+        if matches!(
+            template.precomputed(),
+            None | Some(PrecomputeProfile::Field)
+        ) {
+            template.precompute(PrecomputeProfile::Standard);
+        }
+        let mut workers = template.workers(count);
+
+        let mut slots: Vec<Slot> = (0..count).map(|_| Slot::default()).collect();
+        let mut issued = HashSet::new();
+        for attempt in 1..=self.max_attempts {
+            let pending: Vec<usize> = (0..count).filter(|&i| slots[i].pair.is_none()).collect();
+            if pending.is_empty() {
+                break;
+            }
+            let scalars = self.scalar_batch(pending.len());
+            let outcomes = par_map(&mut workers, &scalars, |worker, &k| {
+                let (t_count, started) = (worker.metrics.t_transform_count, worker.clock.now());
+                let q = worker.keygen_attempt(k);
+                let t_steps = worker.metrics.t_transform_count.wrapping_sub(t_count);
+                (q, t_steps, worker.clock.since(started))
+            });
+            for ((&i, &k), (q, t_steps, duration)) in pending.iter().zip(&scalars).zip(outcomes) {
+                let q = q.and_then(|q| {
+                    if issued.insert(q) {
+                        Ok(q)
+                    } else {
+                        Err(LaiCryptoError::ValidationError {
+                            operation: "keygen_n".to_string(),
+                            expected: "a public point not yet issued in this batch".to_string(),
+                            actual: q.to_string(),
+                        })
+                    }
+                });
+                let slot = &mut slots[i];
+                slot.t_steps = slot.t_steps.wrapping_add(t_steps);
+                slot.duration += duration;
+                match q {
+                    Ok(q) => slot.pair = Some((attempt, k, q)),
+                    Err(e) => slot.failures.push(e),
+                }
+            }
+        }
+
+        for worker in &workers {
+            self.metrics.t_transform_count += worker.metrics.t_transform_count;
+            self.metrics.sqrt_attempts += worker.metrics.sqrt_attempts;
+        }
+        let results: Vec<_> = slots
+            .into_iter()
+            .map(|slot| match slot.pair {
+                Some((attempts, k, q)) => {
+                    self.log_key_operation(KeyOperation::Keygen, Some(q), true);
+                    Ok(KeyPair {
+                        public: PublicKey::new(q),
+                        secret: SecretKey::new(k),
+                        report: KeygenReport {
+                            attempts,
+                            t_steps: slot.t_steps,
+                            duration: slot.duration,
+                        },
+                    })
+                }
+                None => {
+                    self.log_key_operation(KeyOperation::Keygen, None, false);
+                    Err(self.keygen_exhausted(slot.failures))
+                }
+            })
+            .collect();
+        let duration = self.clock.since(start);
+        self.metrics.keygen_time = duration;
+        self.record_operation("keygen_n", duration);
+        results
+    }
+
+    /// `n` uniform scalars in 1..p from a single RNG fill
+    ///
+    /// The buffer holds two candidates per scalar, enough for rejection
+    /// sampling almost always; in the rare case it runs dry the rest is
+    /// read from the RNG directly.
+    fn scalar_batch(&mut self, n: usize) -> Vec<u128> {
+        let len = (128 - (self.p - 1).leading_zeros()).div_ceil(8) as usize;
+        let mut pool = vec![0u8; 2 * n * len];
+        self.rng.fill_bytes(&mut pool);
+        let mut offset = 0;
+        (0..n)
+            .map(|_| {
+                sample_below(self.p - 1, |buf| {
+                    match pool.get(offset..offset + buf.len()) {
+                        Some(bytes) => {
+                            buf.copy_from_slice(bytes);
+                            offset += buf.len();
+                        }
+                        None => self.rng.fill_bytes(buf),
+                    }
+                }) + 1
+            })
+            .collect()
+    }
+
+    /// One untraced worker per available thread, at most one per item
+    fn workers(&self, items: usize) -> Vec<Self> {
+        let threads = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(items)
+            .max(1);
+        (0..threads).map(|_| self.worker()).collect()
+    }
+
+    /// Untraced engine with this one's parameters, limits and cached tables
+    pub(crate) fn worker(&self) -> Self {
+        let mut worker = Self::new_unchecked(self.p, self.a, self.p0);
+        worker.hash = self.hash;
+        worker.domain_separation = self.domain_separation;
+        worker.blinding = self.blinding;
+        worker.max_attempts = self.max_attempts;
+        worker.max_duration = self.max_duration;
+        worker.clock = self.clock.clone();
+        worker.tracing = false;
+        worker.precomputed = self.precomputed.clone();
+        worker
+    }
+}
+
+/// Progress of one key pair in [`LaiCryptoEngine::keygen_n`]
+#[derive(Default)]
+struct Slot {
+    /// Attempt number, secret and public point once found
+    pair: Option<(u32, u128, crate::Point)>,
+    t_steps: u32,
+    duration: Duration,
+    failures: Vec<LaiCryptoError>,
+}
+
+/// `f` over `items` in order, chunks of them running on one thread per worker
+fn par_map<T: Sync, R: Send>(
+    workers: &mut [LaiCryptoEngine],
+    items: &[T],
+    f: impl Fn(&mut LaiCryptoEngine, &T) -> R + Sync,
+) -> Vec<R> {
+    let chunk = items.len().div_ceil(workers.len()).max(1);
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = workers
+            .iter_mut()
+            .zip(items.chunks(chunk))
+            .map(|(worker, chunk)| {
+                scope.spawn(move || chunk.iter().map(|item| f(worker, item)).collect::<Vec<_>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("batch worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorKind, Point};
+
+    #[test]
+    fn test_decrypt_batch_matches_decrypt() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 9;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        let mut cts: Vec<Ciphertext> = (0..20)
+            .map(|m| engine.encrypt(m * 50, q).unwrap().0)
+            .collect();
+        cts[3].c1 = Point::new_unchecked(2000, 0);
+        cts[5].tag[0] ^= 1;
+
+        let key = SecretKey::new(k);
+        let count = engine.metrics.t_transform_count;
+        let batch = engine.decrypt_batch(&cts, &key);
+        assert!(engine.metrics.t_transform_count > count);
+        assert_eq!(batch.len(), cts.len());
+        for (ct, result) in cts.iter().zip(&batch) {
+            let single = engine.decrypt(ct, k);
+            assert_eq!(
+                result.as_ref().map_err(|e| e.kind()),
+                single.as_ref().map_err(|e| e.kind())
+            );
+        }
+        assert_eq!(
+            batch[3].as_ref().unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            batch[5].as_ref().unwrap_err().kind(),
+            ErrorKind::Authentication
+        );
+        assert_eq!(*batch[19].as_ref().unwrap(), 950);
+        assert!(engine.decrypt_batch(&[], &key).is_empty());
+    }
+
+    #[test]
+    fn test_keygen_n() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.max_attempts = 200;
+        let pairs = engine.keygen_n(6);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.iter().all(Result::is_ok));
+        let pairs: Vec<_> = pairs.into_iter().map(Result::unwrap).collect();
+        for pair in &pairs {
+            let (k, q) = (*pair.secret.expose_secret(), pair.public.point());
+            assert_eq!(engine.pow_t(engine.p0, k).unwrap(), q);
+            assert!(pair.report.attempts >= 1);
+        }
+        let secrets: HashSet<u128> = pairs.iter().map(|pair| *pair.secret.expose_secret()).collect();
+        let publics: HashSet<_> = pairs.iter().map(|pair| pair.public.point()).collect();
+        assert_eq!((secrets.len(), publics.len()), (6, 6));
+
+        engine.max_attempts = 0;
+        let none = engine.keygen_n(2);
+        assert!(none
+            .iter()
+            .all(|r| matches!(r, Err(LaiCryptoError::KeygenFailed { attempts: 0, .. }))));
+        assert!(engine.keygen_n(0).is_empty());
+    }
+}
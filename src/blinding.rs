@@ -0,0 +1,88 @@
+//! Decoy-transform blinding of the secret chain length
+//!
+//! T is not homomorphic in the exponent, so k cannot be re-randomized
+//! algebraically as in elliptic-curve scalar blinding. Instead, with
+//! [`LaiCryptoEngine::blinding`] set, every [`LaiCryptoEngine::pow_t`] and
+//! [`LaiCryptoEngine::pow_t_range`] call splits the real chain into runs at random positions and fills the
+//! gaps with a random number of decoy transforms on a random point. The
+//! split and decoy count are drawn afresh per call, so repeated
+//! operations with the same key show a different number and rhythm of
+//! transforms each time. The result is unchanged, and decoys are left out
+//! of the trace and the transform and square-root counters, so metrics
+//! report only the real work.
+
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use rand::Rng;
+
+impl LaiCryptoEngine {
+    /// `exp` real transforms of `point` interleaved with up to `blinding` decoys
+    ///
+    /// The i-th real transform, counting from 0, runs at seed `seed(i)`.
+    pub(crate) fn blinded_chain(
+        &mut self,
+        mut point: Point,
+        exp: u128,
+        seed: impl Fn(u128) -> u128,
+    ) -> Result<Point, LaiCryptoError> {
+        let mut decoys = u128::from(self.rng.gen_range(0..=self.blinding));
+        let mut decoy = self.random_point();
+        let mut i = 0;
+        while i < exp || decoys > 0 {
+            // Each remaining transform is equally likely to come next
+            let remaining = (exp - i) + decoys;
+            if self.rng.gen_range(0..remaining) < decoys {
+                decoys -= 1;
+                let tracing = std::mem::replace(&mut self.tracing, false);
+                let counts = (self.metrics.t_transform_count, self.metrics.sqrt_attempts);
+                let seed = self.rng.gen_range(1..self.p);
+                decoy = self.t(decoy, seed).unwrap_or_else(|_| self.random_point());
+                (self.metrics.t_transform_count, self.metrics.sqrt_attempts) = counts;
+                self.tracing = tracing;
+            } else {
+                point = self.t(point, seed(i))?;
+                i += 1;
+            }
+        }
+        Ok(point)
+    }
+
+    fn random_point(&mut self) -> Point {
+        let p = self.p;
+        Point::new_unchecked(self.rng.gen_range(0..p), self.rng.gen_range(0..p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blinding_preserves_result() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let expected = engine.pow_t_range(p0, 1, 12).unwrap();
+        engine.blinding = 16;
+        engine.tracing = true;
+        engine.trace.clear();
+        for _ in 0..5 {
+            assert_eq!(engine.pow_t_range(p0, 1, 12).unwrap(), expected);
+        }
+        // Decoys leave no trace entries
+        assert!(engine
+            .trace
+            .iter()
+            .all(|step| (1..=12 + 9).contains(&step.s)));
+
+        engine.blinding = 0;
+        let count = engine.metrics.t_transform_count;
+        let expected = engine.pow_t(p0, 12).unwrap();
+        let real = engine.metrics.t_transform_count - count;
+        engine.blinding = 16;
+        for _ in 0..5 {
+            let count = engine.metrics.t_transform_count;
+            assert_eq!(engine.pow_t(p0, 12).unwrap(), expected);
+            // Decoys are not counted as transforms either
+            assert_eq!(engine.metrics.t_transform_count - count, real);
+        }
+    }
+}
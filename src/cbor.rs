@@ -0,0 +1,226 @@
+//! Compact CBOR encoding of keys, ciphertexts and traces
+//!
+//! Objects are CBOR maps with small integer keys in ascending order, and
+//! every integer uses its shortest form: a plain unsigned integer up to
+//! 2^64 − 1, else a tag-2 bignum with leading zero bytes removed. Equal
+//! values therefore always encode to identical bytes, as required when an
+//! encoding is hashed or signed. Points are two-element arrays [x, y].
+
+use crate::operation::OperationId;
+use crate::{Ciphertext, CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point, PublicKey, TraceStep, TAG_LEN};
+use ciborium::Value;
+use std::{io, time::Duration};
+
+fn invalid(reason: &str) -> LaiCryptoError {
+    LaiCryptoError::serialization("CBOR", io::Error::new(io::ErrorKind::InvalidData, reason.to_string()))
+}
+
+fn int(value: u128) -> Value {
+    match u64::try_from(value) {
+        Ok(small) => Value::Integer(small.into()),
+        Err(_) => {
+            let bytes = value.to_be_bytes();
+            let start = bytes.iter().position(|&b| b != 0).unwrap_or(15);
+            Value::Tag(2, Box::new(Value::Bytes(bytes[start..].to_vec())))
+        }
+    }
+}
+
+fn as_int(value: &Value) -> Result<u128, LaiCryptoError> {
+    match value {
+        Value::Integer(i) => u128::try_from(*i).map_err(|_| invalid("Negative integer")),
+        Value::Tag(2, inner) => match inner.as_ref() {
+            Value::Bytes(b) if b.len() <= 16 => Ok(b.iter().fold(0u128, |acc, &x| acc << 8 | u128::from(x))),
+            _ => Err(invalid("Bignum wider than 128 bits")),
+        },
+        _ => Err(invalid("Expected an integer")),
+    }
+}
+
+fn point(p: Point) -> Value {
+    Value::Array(vec![int(p.x()), int(p.y())])
+}
+
+fn as_point(value: &Value) -> Result<Point, LaiCryptoError> {
+    match value {
+        Value::Array(items) if items.len() == 2 => Ok(Point::new_unchecked(as_int(&items[0])?, as_int(&items[1])?)),
+        _ => Err(invalid("Expected a point [x, y]")),
+    }
+}
+
+fn map(fields: Vec<(u8, Value)>) -> Value {
+    Value::Map(fields.into_iter().map(|(k, v)| (Value::Integer(k.into()), v)).collect())
+}
+
+/// Look up integer key `key` in a map
+fn field(value: &Value, key: u8) -> Result<&Value, LaiCryptoError> {
+    let Value::Map(entries) = value else {
+        return Err(invalid("Expected a map"));
+    };
+    entries
+        .iter()
+        .find(|(k, _)| matches!(k, Value::Integer(i) if i128::from(*i) == i128::from(key)))
+        .map(|(_, v)| v)
+        .ok_or_else(|| invalid(&format!("Missing key {}", key)))
+}
+
+fn to_vec(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    ciborium::into_writer(value, &mut out).expect("writing to a Vec cannot fail");
+    out
+}
+
+fn optional(value: &Value) -> Option<&Value> {
+    (!value.is_null()).then_some(value)
+}
+
+fn from_slice(bytes: &[u8]) -> Result<Value, LaiCryptoError> {
+    ciborium::from_reader(bytes).map_err(|e| LaiCryptoError::serialization("CBOR", e))
+}
+
+fn reduced(p: Point, modulus: u128) -> Result<Point, LaiCryptoError> {
+    if p.x() >= modulus || p.y() >= modulus {
+        return Err(invalid("Coordinate not reduced modulo p"));
+    }
+    Ok(p)
+}
+
+/// `{1: p, 2: a, 3: P0, 4: Q}`
+pub fn public_key_to_cbor(key: &PublicKey, engine: &LaiCryptoEngine) -> Vec<u8> {
+    to_vec(&map(vec![
+        (1, int(engine.p)),
+        (2, int(engine.a)),
+        (3, point(engine.p0)),
+        (4, point(key.point())),
+    ]))
+}
+
+/// Decode a key, requiring it to belong to `engine`'s parameters
+pub fn public_key_from_cbor(bytes: &[u8], engine: &LaiCryptoEngine) -> Result<PublicKey, LaiCryptoError> {
+    let value = from_slice(bytes)?;
+    let (p, a, p0) = (as_int(field(&value, 1)?)?, as_int(field(&value, 2)?)?, as_point(field(&value, 3)?)?);
+    if (p, a, p0) != (engine.p, engine.a, engine.p0) {
+        return Err(LaiCryptoError::ValidationError {
+            operation: "CBOR public key".to_string(),
+            expected: format!("p={}, a={}, P0={}", engine.p, engine.a, engine.p0),
+            actual: format!("p={}, a={}, P0={}", p, a, p0),
+        });
+    }
+    Ok(PublicKey::new(reduced(as_point(field(&value, 4)?)?, p)?))
+}
+
+/// `{1: c1, 2: c2, 3: tag, 4: version}`; a missing version reads as 1
+pub fn ciphertext_to_cbor(ct: &Ciphertext) -> Vec<u8> {
+    to_vec(&map(vec![
+        (1, point(ct.c1)),
+        (2, point(ct.c2)),
+        (3, Value::Bytes(ct.tag.to_vec())),
+        (4, int(u128::from(ct.version.to_byte()))),
+    ]))
+}
+
+pub fn ciphertext_from_cbor(bytes: &[u8], p: u128) -> Result<Ciphertext, LaiCryptoError> {
+    let value = from_slice(bytes)?;
+    let tag = match field(&value, 3)? {
+        Value::Bytes(b) => b.as_slice().try_into().map_err(|_| invalid(&format!("Tag must be {} bytes", TAG_LEN)))?,
+        _ => return Err(invalid("Expected tag bytes")),
+    };
+    let version = match field(&value, 4) {
+        Ok(v) => u8::try_from(as_int(v)?)
+            .ok()
+            .and_then(CiphertextVersion::from_byte)
+            .ok_or_else(|| invalid("Unknown ciphertext version"))?,
+        Err(_) => CiphertextVersion::V1,
+    };
+    Ok(Ciphertext {
+        c1: reduced(as_point(field(&value, 1)?)?, p)?,
+        c2: reduced(as_point(field(&value, 2)?)?, p)?,
+        tag,
+        version,
+    })
+}
+
+/// Array of `{1: step, 2: input, 3: s, 4: h, 5: x1, 6: y2, 7: y1, 8: output, 9: duration_ns, 10: operation}`,
+/// with null for a missing y1 or output; key 10 is omitted outside an operation
+pub fn trace_to_cbor(trace: &[TraceStep]) -> Vec<u8> {
+    let steps = trace
+        .iter()
+        .map(|st| {
+            let mut entries = vec![
+                (1, int(u128::from(st.step))),
+                (2, point(st.input)),
+                (3, int(st.s)),
+                (4, int(st.h)),
+                (5, int(st.x1)),
+                (6, int(st.y2)),
+                (7, st.y1.map_or(Value::Null, int)),
+                (8, st.output.map_or(Value::Null, point)),
+                (9, int(st.duration.as_nanos())),
+            ];
+            if let Some(id) = st.operation {
+                entries.push((10, int(u128::from(id.0))));
+            }
+            map(entries)
+        })
+        .collect();
+    to_vec(&Value::Array(steps))
+}
+
+pub fn trace_from_cbor(bytes: &[u8]) -> Result<Vec<TraceStep>, LaiCryptoError> {
+    let Value::Array(steps) = from_slice(bytes)? else {
+        return Err(invalid("Expected an array of steps"));
+    };
+    steps
+        .iter()
+        .map(|st| {
+            Ok(TraceStep {
+                step: u32::try_from(as_int(field(st, 1)?)?).map_err(|_| invalid("Step out of range"))?,
+                input: as_point(field(st, 2)?)?,
+                s: as_int(field(st, 3)?)?,
+                h: as_int(field(st, 4)?)?,
+                x1: as_int(field(st, 5)?)?,
+                y2: as_int(field(st, 6)?)?,
+                y1: optional(field(st, 7)?).map(as_int).transpose()?,
+                output: optional(field(st, 8)?).map(as_point).transpose()?,
+                duration: Duration::from_nanos(
+                    u64::try_from(as_int(field(st, 9)?)?).map_err(|_| invalid("Duration out of range"))?,
+                ),
+                operation: field(st, 10)
+                    .ok()
+                    .map(|id| u64::try_from(as_int(id)?).map(OperationId).map_err(|_| invalid("Operation out of range")))
+                    .transpose()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_roundtrip_is_deterministic() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, 8).unwrap();
+
+        let bytes = ciphertext_to_cbor(&ct);
+        assert_eq!(ciphertext_from_cbor(&bytes, 1009).unwrap(), ct);
+        assert_eq!(bytes, ciphertext_to_cbor(&ciphertext_from_cbor(&bytes, 1009).unwrap()));
+        let key = PublicKey::new(q);
+        assert_eq!(public_key_from_cbor(&public_key_to_cbor(&key, &engine), &engine).unwrap(), key);
+        let other = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        assert!(public_key_from_cbor(&public_key_to_cbor(&key, &engine), &other).is_err());
+
+        let trace = engine.trace.clone();
+        assert!(!trace.is_empty());
+        assert_eq!(trace_from_cbor(&trace_to_cbor(&trace)).unwrap(), trace);
+
+        // Shortest forms: small integers inline, large ones as trimmed bignums
+        assert_eq!(to_vec(&int(23)), [0x17]);
+        assert_eq!(to_vec(&int(1 << 64)), [0xc2, 0x49, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(as_int(&from_slice(&to_vec(&int(u128::MAX))).unwrap()).unwrap(), u128::MAX);
+        assert!(ciphertext_from_cbor(&[0xa0], 1009).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Chunked encryption for large payloads with random-access decryption
+//!
+//! The header encapsulates a content scalar as in [`crate::hybrid`]; the
+//! body is split into fixed-size chunks, each sealed under its own key
+//! derived from the chunk index and a final-chunk flag:
+//!
+//! ```text
+//! magic "LAIC" | version | chunk size (4) | c1 (32) | c2 (32) | chunk 0 | chunk 1 | ... | final chunk
+//! chunk = body | HMAC-SHA-512 (64)
+//! ```
+//!
+//! Every chunk except the last carries exactly `chunk size` body bytes, so
+//! chunk `i` sits at a computable offset and decrypts on its own. Binding
+//! the index and flag into the key stops chunks from being reordered,
+//! dropped from the end or appended after the final one.
+
+use crate::hybrid::{raw_point, SessionKeys, MAC_LEN};
+use crate::{CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use hmac::Mac;
+use rand::{rngs::OsRng, Rng};
+use sha2::Sha512;
+use std::ops::Range;
+
+/// Magic bytes opening every chunked ciphertext
+pub const MAGIC: [u8; 4] = *b"LAIC";
+/// Current wire format version; version 1 masked the content scalar as V1
+pub const VERSION: u8 = 2;
+/// Bytes before the first chunk: magic, version, chunk size, c1 and c2
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + 2 * Point::ENCODED_LEN;
+/// Chunk body size used by [`LaiCryptoEngine::encrypt_chunked`]
+pub const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+
+/// Key material for the chunks behind one header
+#[derive(Clone)]
+pub struct ChunkKeys {
+    master: [u8; 32],
+    chunk_size: u32,
+}
+
+impl ChunkKeys {
+    fn derive(shared: Point, content: u128, header: &[u8]) -> Self {
+        let mut ikm = shared.to_bytes().to_vec();
+        ikm.extend_from_slice(&content.to_be_bytes());
+        let mut master = [0u8; 32];
+        Hkdf::<Sha512>::new(None, &ikm)
+            .expand_multi_info(&[b"LAI-chunked", header], &mut master)
+            .expect("32 bytes is a valid HKDF-SHA-512 length");
+        let chunk_size = u32::from_be_bytes(header[MAGIC.len() + 1..MAGIC.len() + 5].try_into().expect("header length"));
+        ChunkKeys { master, chunk_size }
+    }
+
+    fn keys(&self, index: u64, last: bool) -> SessionKeys {
+        let mut okm = [0u8; 64];
+        Hkdf::<Sha512>::new(None, &self.master)
+            .expand_multi_info(&[b"LAI-chunk", &index.to_be_bytes(), &[u8::from(last)]], &mut okm)
+            .expect("64 bytes is a valid HKDF-SHA-512 length");
+        SessionKeys::from_digest(&okm)
+    }
+
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// Seal chunk `index`; only the final chunk may be shorter than the chunk size
+    pub fn seal_chunk(&self, index: u64, plaintext: &[u8], last: bool) -> Vec<u8> {
+        debug_assert!(plaintext.len() <= self.chunk_size as usize);
+        let keys = self.keys(index, last);
+        let mut out = plaintext.to_vec();
+        keys.apply_keystream(&mut out);
+        let mut mac = keys.mac();
+        mac.update(&out);
+        out.extend_from_slice(&mac.finalize().into_bytes());
+        out
+    }
+
+    /// Verify and decrypt sealed chunk `index`
+    pub fn open_chunk(&self, index: u64, sealed: &[u8], last: bool) -> Result<Vec<u8>, LaiCryptoError> {
+        let auth_failed = || LaiCryptoError::AuthenticationFailed {
+            operation: format!("open_chunk {}", index),
+        };
+        let body_len = sealed.len().checked_sub(MAC_LEN).ok_or_else(auth_failed)?;
+        let (body, tag) = sealed.split_at(body_len);
+        let keys = self.keys(index, last);
+        let mut mac = keys.mac();
+        mac.update(body);
+        mac.verify_slice(tag).map_err(|_| auth_failed())?;
+        let mut out = body.to_vec();
+        keys.apply_keystream(&mut out);
+        Ok(out)
+    }
+
+    /// Byte range of sealed chunk `index` within a ciphertext of `total_len`
+    /// bytes, with whether it is the final chunk; `None` past the end
+    pub fn chunk_range(&self, total_len: usize, index: u64) -> Option<(Range<usize>, bool)> {
+        let sealed = self.chunk_size as usize + MAC_LEN;
+        let body_len = total_len.checked_sub(HEADER_LEN)?;
+        let count = body_len.div_ceil(sealed).max(1) as u64;
+        if index >= count {
+            return None;
+        }
+        let start = HEADER_LEN + index as usize * sealed;
+        let end = (start + sealed).min(total_len);
+        Some((start..end, index + 1 == count))
+    }
+
+    /// Decrypt chunk `index` of a complete ciphertext without touching the others
+    pub fn decrypt_chunk(&self, ciphertext: &[u8], index: u64) -> Result<Vec<u8>, LaiCryptoError> {
+        let (range, last) = self.chunk_range(ciphertext.len(), index).ok_or_else(|| LaiCryptoError::InvalidParameter {
+            param: "chunk index".to_string(),
+            value: index.to_string(),
+            reason: "Chunk past the end of the ciphertext".to_string(),
+            valid_range: format!("below {}", self.chunk_count(ciphertext.len())),
+        })?;
+        self.open_chunk(index, &ciphertext[range], last)
+    }
+
+    /// Number of chunks in a ciphertext of `total_len` bytes
+    pub fn chunk_count(&self, total_len: usize) -> u64 {
+        let body_len = total_len.saturating_sub(HEADER_LEN);
+        body_len.div_ceil(self.chunk_size as usize + MAC_LEN).max(1) as u64
+    }
+}
+
+fn malformed(reason: &str, len: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "chunked header".to_string(),
+        value: format!("{} bytes", len),
+        reason: reason.to_string(),
+        valid_range: format!("at least {} bytes starting with \"LAIC\" v{}", HEADER_LEN, VERSION),
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Encrypt `plaintext` to public point `q` as a chunked ciphertext
+    pub fn encrypt_chunked(&mut self, plaintext: &[u8], q: Point, chunk_size: u32) -> Result<Vec<u8>, LaiCryptoError> {
+        let (mut out, keys) = self.chunked_header(q, chunk_size)?;
+        let chunks: Vec<&[u8]> = if plaintext.is_empty() {
+            vec![&[]]
+        } else {
+            plaintext.chunks(keys.chunk_size as usize).collect()
+        };
+        let last = chunks.len() - 1;
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            out.extend(keys.seal_chunk(index as u64, chunk, index == last));
+        }
+        Ok(out)
+    }
+
+    /// Header and chunk keys for writing a chunked ciphertext incrementally
+    pub fn chunked_header(&mut self, q: Point, chunk_size: u32) -> Result<(Vec<u8>, ChunkKeys), LaiCryptoError> {
+        if chunk_size == 0 {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "chunk_size".to_string(),
+                value: "0".to_string(),
+                reason: "Chunks must carry data".to_string(),
+                valid_range: "1 ≤ chunk_size ≤ 2^32-1".to_string(),
+            });
+        }
+        self.retry_ephemeral(|engine, r| {
+            let content = OsRng.gen_range(0..engine.p);
+            engine.chunked_header_with(q, r, content, chunk_size)
+        })
+    }
+
+    pub(crate) fn chunked_header_with(
+        &mut self,
+        q: Point,
+        r: u128,
+        content: u128,
+        chunk_size: u32,
+    ) -> Result<(Vec<u8>, ChunkKeys), LaiCryptoError> {
+        let ct = self.encrypt_versioned(content, q, r, CiphertextVersion::V2)?;
+        let shared = self.pow_t(q, r)?;
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.push(VERSION);
+        header.extend_from_slice(&chunk_size.to_be_bytes());
+        header.extend_from_slice(&ct.c1.to_bytes());
+        header.extend_from_slice(&ct.c2.to_bytes());
+        let keys = ChunkKeys::derive(shared, content, &header);
+        Ok((header, keys))
+    }
+
+    /// Recover the chunk keys from the header of a chunked ciphertext
+    ///
+    /// Only the first [`HEADER_LEN`] bytes are read. A wrong key or a
+    /// modified header is detected when the first chunk fails to open.
+    pub fn open_chunked(&mut self, ciphertext: &[u8], k: u128) -> Result<ChunkKeys, LaiCryptoError> {
+        let len = ciphertext.len();
+        if len < HEADER_LEN {
+            return Err(malformed("Header too short", len));
+        }
+        if ciphertext[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LAIC magic", len));
+        }
+        if ciphertext[MAGIC.len()] != VERSION {
+            return Err(malformed("Unsupported version", len));
+        }
+        let points = &ciphertext[MAGIC.len() + 5..HEADER_LEN];
+        let (c1, c2) = points.split_at(Point::ENCODED_LEN);
+        let (c1, c2) = match (raw_point(c1, self.p), raw_point(c2, self.p)) {
+            (Some(c1), Some(c2)) => (c1, c2),
+            _ => return Err(malformed("Coordinate not reduced modulo p", len)),
+        };
+        if ciphertext[MAGIC.len() + 1..MAGIC.len() + 5] == [0; 4] {
+            return Err(malformed("Zero chunk size", len));
+        }
+        let (content, shared) = self.unmask(c1, c2, k, CiphertextVersion::V2)?;
+        Ok(ChunkKeys::derive(shared, content, &ciphertext[..HEADER_LEN]))
+    }
+
+    /// Verify and decrypt every chunk of a chunked ciphertext
+    pub fn decrypt_chunked(&mut self, ciphertext: &[u8], k: u128) -> Result<Vec<u8>, LaiCryptoError> {
+        let keys = self.open_chunked(ciphertext, k)?;
+        let mut out = Vec::with_capacity(ciphertext.len());
+        for index in 0..keys.chunk_count(ciphertext.len()) {
+            out.extend(keys.decrypt_chunk(ciphertext, index)?);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_random_access() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 8;
+        let q = engine.pow_t(p0, k).unwrap();
+        let plaintext: Vec<u8> = (0..100u8).collect();
+
+        let (mut sealed, keys) = engine.chunked_header(q, 16).unwrap();
+        for (i, chunk) in plaintext.chunks(16).enumerate() {
+            sealed.extend(keys.seal_chunk(i as u64, chunk, i == 6));
+        }
+        assert_eq!(engine.decrypt_chunked(&sealed, k).unwrap(), plaintext);
+
+        let opened = engine.open_chunked(&sealed, k).unwrap();
+        assert_eq!(opened.chunk_count(sealed.len()), 7);
+        assert_eq!(opened.decrypt_chunk(&sealed, 3).unwrap(), plaintext[48..64]);
+        assert_eq!(opened.decrypt_chunk(&sealed, 6).unwrap(), plaintext[96..]);
+        assert!(opened.decrypt_chunk(&sealed, 7).is_err());
+
+        // Truncating at a chunk boundary loses the final flag
+        let (range, _) = opened.chunk_range(sealed.len(), 5).unwrap();
+        assert!(engine.decrypt_chunked(&sealed[..range.end], k).is_err());
+        let mut swapped = sealed.clone();
+        swapped.copy_within(HEADER_LEN..HEADER_LEN + 80, HEADER_LEN + 80);
+        assert!(engine.decrypt_chunked(&swapped, k).is_err());
+        assert!(engine.encrypt_chunked(b"", q, 0).is_err());
+
+        let sealed = engine.encrypt_chunked(&plaintext, q, 32).unwrap();
+        assert_eq!(engine.decrypt_chunked(&sealed, k).unwrap(), plaintext);
+        assert!(engine.decrypt_chunked(&sealed, k + 1).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! Ciphertext format and its key-confirmation tags
+
+use crate::{hash, reduce_wide, CurveParams, LaiCryptoError, Point};
+use sha2::{Digest, Sha512};
+
+/// Length of the [`Ciphertext`] key-confirmation tag
+pub const TAG_LEN: usize = 32;
+
+/// How a [`Ciphertext`] derives its mask and tag from the shared point S
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CiphertextVersion {
+    /// c2 = (m + x(S), y(S)), as in the reference implementation, untagged
+    ///
+    /// The raw coordinate is structured and biased as a mask and y(S) is
+    /// sent in the clear, so a guess of m fixes S and any tag over S and m
+    /// would confirm the guess. The tag is all zeros and decryption does
+    /// not authenticate. Kept for known-answer tests and for the additive
+    /// homomorphism of [`crate::homomorphic`].
+    V1,
+    /// c2 = (m + mask, 0), the mask and tag key coming from HKDF-SHA-512
+    /// over both coordinates of S, c1 and the parameter hash
+    #[default]
+    V2,
+}
+
+impl CiphertextVersion {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            CiphertextVersion::V1 => 1,
+            CiphertextVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(CiphertextVersion::V1),
+            2 => Some(CiphertextVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// Encrypted message with a key-confirmation tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ciphertext {
+    /// Ephemeral point f^r(P0), see [`crate::CHAIN_SEED`]
+    pub c1: Point,
+    /// Masked message
+    pub c2: Point,
+    /// Truncated SHA-512 binding the shared secret to the message, all
+    /// zeros in V1
+    pub tag: [u8; TAG_LEN],
+    /// Mask and tag derivation used
+    pub version: CiphertextVersion,
+}
+
+impl Ciphertext {
+    /// Check the structure of a received ciphertext before any T chain runs
+    ///
+    /// Every coordinate must be reduced modulo p, and a V2 ciphertext must
+    /// carry c2 = (m + mask, 0). c1 is a T-transform output, which does
+    /// not lie on the curve in general, so only its range is checked.
+    pub fn validate(&self, params: &CurveParams) -> Result<(), LaiCryptoError> {
+        for (name, point) in [("ciphertext.c1", self.c1), ("ciphertext.c2", self.c2)] {
+            if point.x() >= params.p || point.y() >= params.p {
+                return Err(LaiCryptoError::InvalidParameter {
+                    param: name.to_string(),
+                    value: point.to_string(),
+                    reason: "Coordinate not reduced modulo p".to_string(),
+                    valid_range: format!("0 ≤ x, y < {}", params.p),
+                });
+            }
+        }
+        if self.version == CiphertextVersion::V2 && self.c2.y() != 0 {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "ciphertext.c2".to_string(),
+                value: self.c2.to_string(),
+                reason: "V2 ciphertexts carry y = 0 in c2".to_string(),
+                valid_range: "(m + mask, 0)".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// V2 mask and tag key from HKDF-SHA-512 over S, with c1 and the parameters as context
+pub(crate) fn kdf_mask(
+    hash: &hash::HashFunction,
+    shared: Point,
+    c1: Point,
+    params_hash: &[u8],
+    p: u128,
+) -> (u128, [u8; 32]) {
+    let mut okm = [0u8; 64];
+    let expanded = hash.hkdf(
+        params_hash,
+        &shared.to_bytes(),
+        &[b"LAI-mask-v2", &c1.to_bytes()],
+        &mut okm,
+    );
+    assert!(
+        expanded,
+        "64 bytes is a valid HKDF length for 16-byte outputs and up"
+    );
+    let mask = reduce_wide(&okm[..32], p);
+    let mut tag_key = [0u8; 32];
+    tag_key.copy_from_slice(&okm[32..]);
+    (mask, tag_key)
+}
+
+/// V2 key-confirmation tag binding the derived tag key to the message
+pub(crate) fn confirmation_tag_v2(tag_key: &[u8; 32], m: u128) -> [u8; TAG_LEN] {
+    let mut hasher = Sha512::new();
+    hasher.update(b"LAI-confirm-v2");
+    hasher.update(tag_key);
+    hasher.update(m.to_be_bytes());
+    let mut tag = [0u8; TAG_LEN];
+    tag.copy_from_slice(&hasher.finalize()[..TAG_LEN]);
+    tag
+}
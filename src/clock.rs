@@ -0,0 +1,223 @@
+//! Time sources for metrics and timeouts
+//!
+//! The engine reads time only through its [`Clock`], so tests can swap in a
+//! [`MockClock`] and check timeout and metric logic without sleeping. A
+//! clock reports the time elapsed since its own arbitrary origin; only
+//! differences between readings are meaningful.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Monotonic source of elapsed time
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Time since this clock's origin; never decreases
+    fn now(&self) -> Duration;
+}
+
+/// Wall-independent monotonic clock backed by [`Instant`]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Cheaper monotonic clock with tick resolution, typically 1–4 ms
+///
+/// Reads `CLOCK_MONOTONIC_COARSE` on Linux, which avoids the hardware
+/// counter, and rounds [`SystemClock`] down to whole milliseconds
+/// elsewhere. Suited to timeouts and long-running metrics, not to timing
+/// single transforms.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoarseClock {
+    #[cfg(not(target_os = "linux"))]
+    inner: SystemClock,
+}
+
+impl Clock for CoarseClock {
+    #[cfg(target_os = "linux")]
+    fn now(&self) -> Duration {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: ts is a valid, writable timespec and the clock id is a
+        // constant Linux always provides
+        let rc = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC_COARSE, &mut ts) };
+        debug_assert_eq!(rc, 0);
+        Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn now(&self) -> Duration {
+        let now = self.inner.now();
+        Duration::from_millis(now.as_millis() as u64)
+    }
+}
+
+/// Manually driven clock for deterministic tests
+///
+/// Time moves only through [`MockClock::advance`] and [`MockClock::set`],
+/// plus an optional fixed `tick` added after every reading so that each
+/// timed operation appears to take exactly that long. Clones share state.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    nanos: Arc<AtomicU64>,
+    tick: u64,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clock that advances by `tick` after every reading
+    pub fn ticking(tick: Duration) -> Self {
+        Self {
+            nanos: Arc::default(),
+            tick: tick.as_nanos() as u64,
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Jump to `to`; ignored if it would move time backwards
+    pub fn set(&self, to: Duration) {
+        self.nanos.fetch_max(to.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.fetch_add(self.tick, Ordering::SeqCst))
+    }
+}
+
+/// Shared handle to the clock an engine reads
+///
+/// Snapshots do not carry the clock: it encodes to nothing and decodes as
+/// a fresh [`SystemClock`].
+#[derive(Clone)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    pub fn now(&self) -> Duration {
+        self.0.now()
+    }
+
+    /// Time elapsed since an earlier reading `start`
+    pub fn since(&self, start: Duration) -> Duration {
+        self.now().saturating_sub(start)
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock::default())
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for SharedClock {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C> bincode::Decode<C> for SharedClock {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(SharedClock);
+
+impl crate::LaiCryptoEngine {
+    /// Read time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+
+    #[test]
+    fn test_clocks_monotonic() {
+        let system = SystemClock::default();
+        let coarse = CoarseClock::default();
+        let (s, c) = (system.now(), coarse.now());
+        assert!(system.now() >= s);
+        assert!(coarse.now() >= c);
+
+        let mock = MockClock::new();
+        mock.advance(Duration::from_millis(5));
+        mock.set(Duration::from_millis(2));
+        assert_eq!(mock.now(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_mock_clock_drives_metrics_and_timeouts() {
+        let tick = Duration::from_millis(3);
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0))
+            .unwrap()
+            .with_clock(MockClock::ticking(tick));
+        let k = 7;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        // A random ephemeral chain can fail at this toy modulus
+        while engine.encrypt(42, q).is_err() {}
+        assert!(engine.metrics.encrypt_time >= tick);
+        assert_eq!(engine.metrics.encrypt_time.as_nanos() % tick.as_nanos(), 0);
+
+        // Each reading costs a full minute, so any failing transform times out
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0))
+            .unwrap()
+            .with_clock(MockClock::ticking(Duration::from_secs(60)));
+        engine.tracing = false;
+        let p = engine.p;
+        let failing = (0..p)
+            .flat_map(|x| (0..p).map(move |y| (x, y)))
+            .take(100_000)
+            .map(|(x, y)| Point::new_unchecked(x, y))
+            .find_map(|pt| engine.t(pt, 1).err());
+        if let Some(e) = failing {
+            assert!(matches!(e, LaiCryptoError::Timeout { .. }), "{:?}", e);
+        }
+    }
+}
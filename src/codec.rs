@@ -0,0 +1,102 @@
+//! Byte strings to field elements and back
+//!
+//! Bytes are split into blocks of [`block_len`] bytes, each read as a
+//! big-endian integer. A block of that length is always below p, so every
+//! byte string maps to valid elements without rejection sampling or bias.
+//! The stream is prefixed with its length as 8 big-endian bytes and the
+//! final block is zero-filled:
+//!
+//! ```text
+//! length (8) | data | zero fill  →  [e0, e1, ...], each block_len bytes
+//! ```
+
+pub use crate::padding::block_len;
+
+use crate::LaiCryptoError;
+
+const LENGTH_PREFIX: usize = 8;
+
+fn usable_block_len(p: u128) -> Result<usize, LaiCryptoError> {
+    match block_len(p) {
+        0 => Err(LaiCryptoError::InvalidParameter {
+            param: "p".to_string(),
+            value: p.to_string(),
+            reason: "Modulus too small to hold a byte per element".to_string(),
+            valid_range: "p ≥ 256".to_string(),
+        }),
+        len => Ok(len),
+    }
+}
+
+/// Encode `bytes` as field elements below `p`
+pub fn encode_to_field(bytes: &[u8], p: u128) -> Result<Vec<u128>, LaiCryptoError> {
+    let block = usable_block_len(p)?;
+    let mut stream = Vec::with_capacity(LENGTH_PREFIX + bytes.len() + block);
+    stream.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+    stream.extend_from_slice(bytes);
+    stream.resize(stream.len().div_ceil(block) * block, 0);
+    Ok(stream
+        .chunks(block)
+        .map(|chunk| chunk.iter().fold(0u128, |acc, &b| acc << 8 | u128::from(b)))
+        .collect())
+}
+
+/// Decode elements produced by [`encode_to_field`] with the same `p`
+///
+/// Rejects elements wider than a block, a length prefix that disagrees with
+/// the element count, and nonzero fill bytes, so each byte string has
+/// exactly one encoding.
+pub fn decode_from_field(elements: &[u128], p: u128) -> Result<Vec<u8>, LaiCryptoError> {
+    let block = usable_block_len(p)?;
+    let invalid = |reason: &str| LaiCryptoError::InvalidParameter {
+        param: "elements".to_string(),
+        value: format!("{} elements", elements.len()),
+        reason: reason.to_string(),
+        valid_range: format!("length-prefixed {}-byte blocks", block),
+    };
+    let mut stream = Vec::with_capacity(elements.len() * block);
+    for &element in elements {
+        if block < 16 && element >> (8 * block) != 0 {
+            return Err(invalid("Element wider than a block"));
+        }
+        stream.extend_from_slice(&element.to_be_bytes()[16 - block..]);
+    }
+    if stream.len() < LENGTH_PREFIX {
+        return Err(invalid("Missing length prefix"));
+    }
+    let (prefix, rest) = stream.split_at(LENGTH_PREFIX);
+    let len = u64::from_be_bytes(prefix.try_into().expect("8-byte prefix"));
+    let len = usize::try_from(len).ok().filter(|&len| len <= rest.len()).ok_or_else(|| invalid("Length prefix exceeds data"))?;
+    if (LENGTH_PREFIX + len).div_ceil(block) != elements.len() {
+        return Err(invalid("Trailing elements after data"));
+    }
+    if rest[len..].iter().any(|&b| b != 0) {
+        return Err(invalid("Nonzero fill bytes"));
+    }
+    Ok(rest[..len].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_codec_roundtrip() {
+        for p in [1009u128, 65537, (1u128 << 127) - 1, u128::MAX - 158] {
+            for len in [0usize, 1, 15, 16, 17, 100] {
+                let data: Vec<u8> = (0..len).map(|i| (i * 37) as u8 | 0x80).collect();
+                let elements = encode_to_field(&data, p).unwrap();
+                assert!(elements.iter().all(|&e| e < p));
+                assert_eq!(decode_from_field(&elements, p).unwrap(), data);
+            }
+        }
+
+        let mut elements = encode_to_field(b"abc", 65537).unwrap();
+        assert_eq!(elements.len(), 6);
+        elements.push(0);
+        assert!(decode_from_field(&elements, 65537).is_err());
+        assert!(decode_from_field(&[0, 0, 0, 3, 0x6162, 0x63ff], 65537).is_err());
+        assert!(decode_from_field(&[0x10000], 65537).is_err());
+        assert!(encode_to_field(b"x", 251).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! Hybrid key exchange combining LAI with X25519 or ML-KEM-768
+//!
+//! Each party holds an [`ExchangeKeyPair`] next to a classical X25519 key
+//! pair or, with the `ml-kem` feature, an ML-KEM-768 key pair. The two
+//! secrets are combined with HKDF-SHA-512, whose extract step is
+//! HMAC(LAI secret, other secret); HMAC is a dual PRF, so the result stays
+//! secret as long as either input does. The expand step binds the public
+//! keys and, for the KEM, the composite ciphertext.
+
+use crate::exchange::{ExchangeKeyPair, SharedSecret};
+use crate::hybrid::raw_point;
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use secrecy::ExposeSecret;
+use sha2::Sha512;
+use x25519_dalek::StaticSecret;
+#[cfg(feature = "ml-kem")]
+use ml_kem::{
+    kem::{Decapsulate, Encapsulate},
+    Ciphertext, Encoded, EncodedSizeUser, KemCore, MlKem768,
+};
+use crate::redact::Redacted;
+use std::fmt;
+
+/// Encoded length of a [`HybridPublicKey`]
+pub const PUBLIC_KEY_LEN: usize = Point::ENCODED_LEN + 32;
+
+/// LAI and X25519 public keys sent as one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HybridPublicKey {
+    pub lai: Point,
+    pub x25519: [u8; 32],
+}
+
+impl HybridPublicKey {
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LEN] {
+        let mut out = [0u8; PUBLIC_KEY_LEN];
+        out[..Point::ENCODED_LEN].copy_from_slice(&self.lai.to_bytes());
+        out[Point::ENCODED_LEN..].copy_from_slice(&self.x25519);
+        out
+    }
+
+    /// Decode a key, checking the LAI point is reduced modulo `p`
+    pub fn from_bytes(bytes: &[u8], p: u128) -> Result<Self, LaiCryptoError> {
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "hybrid_public_key".to_string(),
+            value: format!("{} bytes", bytes.len()),
+            reason: reason.to_string(),
+            valid_range: format!("{} bytes, LAI coordinates below p", PUBLIC_KEY_LEN),
+        };
+        if bytes.len() != PUBLIC_KEY_LEN {
+            return Err(malformed("Wrong key length"));
+        }
+        let (lai, x25519) = bytes.split_at(Point::ENCODED_LEN);
+        Ok(HybridPublicKey {
+            lai: raw_point(lai, p).ok_or_else(|| malformed("Coordinate not reduced modulo p"))?,
+            x25519: x25519.try_into().expect("length checked"),
+        })
+    }
+}
+
+/// LAI and X25519 key pairs used together
+#[derive(Clone)]
+pub struct HybridKeyPair {
+    lai: ExchangeKeyPair,
+    x25519: StaticSecret,
+}
+
+impl fmt::Debug for HybridKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HybridKeyPair")
+            .field("lai", &self.lai)
+            .field("x25519", &Redacted(&self.x25519.to_bytes()))
+            .finish()
+    }
+}
+
+impl HybridKeyPair {
+    pub fn generate(engine: &mut LaiCryptoEngine) -> Result<Self, LaiCryptoError> {
+        Ok(HybridKeyPair {
+            lai: ExchangeKeyPair::generate(engine)?,
+            x25519: StaticSecret::random_from_rng(OsRng),
+        })
+    }
+
+    pub fn public(&self) -> HybridPublicKey {
+        HybridPublicKey {
+            lai: self.lai.public(),
+            x25519: x25519_dalek::PublicKey::from(&self.x25519).to_bytes(),
+        }
+    }
+
+    /// Combined secret, identical on both sides
+    ///
+    /// Fails if either agreement fails, including an X25519 peer key of
+    /// small order that would force a known output.
+    pub fn shared_secret(
+        &self,
+        engine: &mut LaiCryptoEngine,
+        peer: &HybridPublicKey,
+    ) -> Result<SharedSecret, LaiCryptoError> {
+        let lai = self.lai.shared_secret(engine, peer.lai)?;
+        let classical = self.x25519.diffie_hellman(&x25519_dalek::PublicKey::from(peer.x25519));
+        if !classical.was_contributory() {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "x25519_agreement".to_string(),
+                expected: "contributory peer key".to_string(),
+                actual: "small-order point".to_string(),
+            });
+        }
+
+        let (mut first, mut second) = (self.public().to_bytes(), peer.to_bytes());
+        if first > second {
+            std::mem::swap(&mut first, &mut second);
+        }
+        let mut okm = [0u8; 32];
+        Hkdf::<Sha512>::new(Some(lai.expose_secret()), classical.as_bytes())
+            .expand_multi_info(&[b"LAI-X25519", &first, &second], &mut okm)
+            .expect("32 bytes is a valid HKDF-SHA-512 length");
+        Ok(SharedSecret::new(okm))
+    }
+}
+
+#[cfg(feature = "ml-kem")]
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+#[cfg(feature = "ml-kem")]
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+
+/// ML-KEM-768 encapsulation key length
+#[cfg(feature = "ml-kem")]
+pub const ML_KEM_PUBLIC_KEY_LEN: usize = 1184;
+/// ML-KEM-768 ciphertext length
+#[cfg(feature = "ml-kem")]
+pub const ML_KEM_CIPHERTEXT_LEN: usize = 1088;
+/// Composite ciphertext: LAI ephemeral point then ML-KEM-768 ciphertext
+#[cfg(feature = "ml-kem")]
+pub const COMPOSITE_CIPHERTEXT_LEN: usize = Point::ENCODED_LEN + ML_KEM_CIPHERTEXT_LEN;
+
+/// LAI public point and ML-KEM-768 encapsulation key
+#[cfg(feature = "ml-kem")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositePublicKey {
+    pub lai: Point,
+    ml_kem: EncapsulationKey,
+}
+
+#[cfg(feature = "ml-kem")]
+impl CompositePublicKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.lai.to_bytes().to_vec();
+        out.extend_from_slice(&self.ml_kem.as_bytes());
+        out
+    }
+
+    /// Decode a key, checking the LAI point is reduced modulo `p`
+    pub fn from_bytes(bytes: &[u8], p: u128) -> Result<Self, LaiCryptoError> {
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "composite_public_key".to_string(),
+            value: format!("{} bytes", bytes.len()),
+            reason: reason.to_string(),
+            valid_range: format!(
+                "{} bytes, LAI coordinates below p",
+                Point::ENCODED_LEN + ML_KEM_PUBLIC_KEY_LEN
+            ),
+        };
+        if bytes.len() != Point::ENCODED_LEN + ML_KEM_PUBLIC_KEY_LEN {
+            return Err(malformed("Wrong key length"));
+        }
+        let (lai, ml_kem) = bytes.split_at(Point::ENCODED_LEN);
+        let encoded = Encoded::<EncapsulationKey>::try_from(ml_kem).expect("length checked");
+        Ok(CompositePublicKey {
+            lai: raw_point(lai, p).ok_or_else(|| malformed("Coordinate not reduced modulo p"))?,
+            ml_kem: EncapsulationKey::from_bytes(&encoded),
+        })
+    }
+
+    /// Encapsulate a fresh composite secret to this key
+    ///
+    /// A new LAI ephemeral is drawn whenever its agreement chain fails.
+    pub fn encapsulate(
+        &self,
+        engine: &mut LaiCryptoEngine,
+    ) -> Result<([u8; COMPOSITE_CIPHERTEXT_LEN], SharedSecret), LaiCryptoError> {
+        let (ephemeral, lai) = engine.retry_ephemeral(|engine, secret| {
+            let ephemeral = ExchangeKeyPair::from_secret(engine, secret)?;
+            let lai = ephemeral.shared_secret(engine, self.lai)?;
+            Ok((ephemeral.public(), lai))
+        })?;
+        let (ml_kem_ct, ml_kem_secret) = self
+            .ml_kem
+            .encapsulate(&mut OsRng)
+            .expect("ML-KEM encapsulation is infallible");
+
+        let mut ciphertext = [0u8; COMPOSITE_CIPHERTEXT_LEN];
+        ciphertext[..Point::ENCODED_LEN].copy_from_slice(&ephemeral.to_bytes());
+        ciphertext[Point::ENCODED_LEN..].copy_from_slice(&ml_kem_ct);
+        let secret = combine_kem(&lai, &ml_kem_secret, &ciphertext, self);
+        Ok((ciphertext, secret))
+    }
+}
+
+/// LAI exchange key pair with an ML-KEM-768 key pair
+#[cfg(feature = "ml-kem")]
+pub struct CompositeKeyPair {
+    lai: ExchangeKeyPair,
+    decapsulation: DecapsulationKey,
+    encapsulation: EncapsulationKey,
+}
+
+#[cfg(feature = "ml-kem")]
+impl fmt::Debug for CompositeKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompositeKeyPair")
+            .field("lai", &self.lai)
+            .field("decapsulation", &Redacted(&self.decapsulation.as_bytes()))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "ml-kem")]
+impl CompositeKeyPair {
+    pub fn generate(engine: &mut LaiCryptoEngine) -> Result<Self, LaiCryptoError> {
+        let (decapsulation, encapsulation) = MlKem768::generate(&mut OsRng);
+        Ok(CompositeKeyPair {
+            lai: ExchangeKeyPair::generate(engine)?,
+            decapsulation,
+            encapsulation,
+        })
+    }
+
+    pub fn public(&self) -> CompositePublicKey {
+        CompositePublicKey {
+            lai: self.lai.public(),
+            ml_kem: self.encapsulation.clone(),
+        }
+    }
+
+    /// Recover the composite secret from a ciphertext
+    ///
+    /// ML-KEM rejects implicitly, so a modified ciphertext yields an
+    /// unrelated secret rather than an error; pair this with
+    /// [`SharedSecret::confirmation_tag`] to detect it.
+    pub fn decapsulate(
+        &self,
+        engine: &mut LaiCryptoEngine,
+        ciphertext: &[u8],
+    ) -> Result<SharedSecret, LaiCryptoError> {
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "composite_ciphertext".to_string(),
+            value: format!("{} bytes", ciphertext.len()),
+            reason: reason.to_string(),
+            valid_range: format!("{} bytes, LAI coordinates below p", COMPOSITE_CIPHERTEXT_LEN),
+        };
+        if ciphertext.len() != COMPOSITE_CIPHERTEXT_LEN {
+            return Err(malformed("Wrong ciphertext length"));
+        }
+        let (ephemeral, ml_kem_ct) = ciphertext.split_at(Point::ENCODED_LEN);
+        let ephemeral = raw_point(ephemeral, engine.p).ok_or_else(|| malformed("Coordinate not reduced modulo p"))?;
+        let lai = self.lai.shared_secret(engine, ephemeral)?;
+        let ml_kem_ct = Ciphertext::<MlKem768>::try_from(ml_kem_ct).expect("length checked");
+        let ml_kem_secret = self
+            .decapsulation
+            .decapsulate(&ml_kem_ct)
+            .expect("ML-KEM decapsulation rejects implicitly");
+        Ok(combine_kem(&lai, &ml_kem_secret, ciphertext, &self.public()))
+    }
+}
+
+#[cfg(feature = "ml-kem")]
+fn combine_kem(lai: &SharedSecret, ml_kem: &[u8], ciphertext: &[u8], public: &CompositePublicKey) -> SharedSecret {
+    let mut okm = [0u8; 32];
+    Hkdf::<Sha512>::new(Some(lai.expose_secret()), ml_kem)
+        .expand_multi_info(&[b"LAI-MLKEM768", ciphertext, &public.to_bytes()], &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA-512 length");
+    SharedSecret::new(okm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_agreement() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = HybridKeyPair::generate(&mut engine).unwrap();
+        let bob = HybridKeyPair::generate(&mut engine).unwrap();
+        let encoded = bob.public().to_bytes();
+        assert_eq!(HybridPublicKey::from_bytes(&encoded, 1009).unwrap(), bob.public());
+
+        if let (Ok(a), Ok(b)) = (
+            alice.shared_secret(&mut engine, &bob.public()),
+            bob.shared_secret(&mut engine, &alice.public()),
+        ) {
+            assert!(a == b);
+            let low_order = HybridPublicKey { x25519: [0; 32], ..bob.public() };
+            assert!(alice.shared_secret(&mut engine, &low_order).is_err());
+        }
+    }
+
+    #[cfg(feature = "ml-kem")]
+    #[test]
+    fn test_composite_kem() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let bob = CompositeKeyPair::generate(&mut engine).unwrap();
+        let public = CompositePublicKey::from_bytes(&bob.public().to_bytes(), 1009).unwrap();
+        assert!(public == bob.public());
+
+        let (ciphertext, sent) = public.encapsulate(&mut engine).unwrap();
+        let received = bob.decapsulate(&mut engine, &ciphertext).unwrap();
+        assert!(sent == received);
+
+        let mut tampered = ciphertext;
+        tampered[Point::ENCODED_LEN + 7] ^= 1;
+        assert!(bob.decapsulate(&mut engine, &tampered).unwrap() != sent);
+        assert!(bob.decapsulate(&mut engine, &ciphertext[1..]).is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! Differential testing against reference-implementation transcripts
+//!
+//! A transcript is the JSON document written by the Python (`lai.py`) or
+//! Julia reference implementation, e.g. `script.min.json`:
+//!
+//! ```text
+//! {
+//!   "p": 10007, "a": 5, "P0": [1, 0],
+//!   "hashes":     [{ "x": 1, "y": 0, "s": 1, "h": 4242 }],
+//!   "transforms": [{ "point": [1, 0], "s": 1, "output": [17, 3] }],
+//!   "k": 543, "Q": [5236, 945],
+//!   "blocks":     [{ "m": 12, "r": 164, "C1": [5557, 5010], "C2": [98, 3267] }]
+//! }
+//! ```
+//!
+//! Only `p`, `a` and `P0` are required. Records are replayed in the order
+//! above, from the smallest building block to whole ciphertexts, so the
+//! first mismatch points at the earliest diverging computation. Block
+//! `C2` and decryption are only checked when the block carries `m`.
+
+use crate::field::sub_mod;
+use crate::{add_mod, LaiCryptoEngine, LaiCryptoError, Point};
+use std::{fmt, fs, path::Path};
+
+/// First field whose Rust value differs from the transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Path of the field in the transcript, e.g. `blocks[3].C1`
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: expected {}, got {}", self.field, self.expected, self.actual)
+    }
+}
+
+/// Outcome of replaying a transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceReport {
+    /// Fields that matched before the first mismatch
+    pub checks: usize,
+    pub first_mismatch: Option<Mismatch>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.first_mismatch.is_none()
+    }
+}
+
+/// Read and replay a transcript file
+pub fn replay_file(path: impl AsRef<Path>) -> Result<ConformanceReport, LaiCryptoError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .map_err(|e| LaiCryptoError::io(format!("reading {}", path.display()), e))?;
+    replay(&text)
+}
+
+/// Replay a transcript through a fresh engine built from its parameters
+///
+/// Malformed transcripts are an error; computations that fail in Rust but
+/// succeeded in the reference are reported as mismatches.
+pub fn replay(transcript: &str) -> Result<ConformanceReport, LaiCryptoError> {
+    let doc = Json::parse(transcript)?;
+    let p = doc.field("", "p")?.uint("p")?;
+    let a = doc.field("", "a")?.uint("a")?;
+    let p0 = doc.field("", "P0")?.point("P0")?;
+    let mut engine = LaiCryptoEngine::new(p, a, p0)?;
+    // The reference implementations hash without domain labels
+    engine.domain_separation = false;
+
+    let mut checks = 0;
+    match run_checks(&doc, &mut engine, &mut checks) {
+        Ok(()) => Ok(ConformanceReport {
+            checks,
+            first_mismatch: None,
+        }),
+        Err(Stop::Mismatch(mismatch)) => Ok(ConformanceReport {
+            checks,
+            first_mismatch: Some(mismatch),
+        }),
+        Err(Stop::Malformed(e)) => Err(e),
+    }
+}
+
+/// Why replay stopped early
+enum Stop {
+    Mismatch(Mismatch),
+    Malformed(LaiCryptoError),
+}
+
+impl From<LaiCryptoError> for Stop {
+    fn from(e: LaiCryptoError) -> Self {
+        Stop::Malformed(e)
+    }
+}
+
+/// Compare one computed value against the transcript
+fn check<T: PartialEq + fmt::Display>(
+    checks: &mut usize,
+    field: String,
+    expected: T,
+    actual: Result<T, LaiCryptoError>,
+) -> Result<(), Stop> {
+    let actual = match actual {
+        Ok(value) if value == expected => {
+            *checks += 1;
+            return Ok(());
+        }
+        Ok(value) => value.to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+    Err(Stop::Mismatch(Mismatch {
+        field,
+        expected: expected.to_string(),
+        actual,
+    }))
+}
+
+fn run_checks(doc: &Json, engine: &mut LaiCryptoEngine, checks: &mut usize) -> Result<(), Stop> {
+    let p0 = engine.p0;
+
+    for (i, rec) in doc.list("hashes")?.iter().enumerate() {
+        let path = format!("hashes[{}]", i);
+        let x = rec.field(&path, "x")?.uint(&path)?;
+        let y = rec.field(&path, "y")?.uint(&path)?;
+        let s = rec.field(&path, "s")?.uint(&path)?;
+        let h = rec.field(&path, "h")?.uint(&path)?;
+        check(checks, format!("{}.h", path), h, Ok(engine.h(x, y, s)))?;
+    }
+
+    for (i, rec) in doc.list("transforms")?.iter().enumerate() {
+        let path = format!("transforms[{}]", i);
+        let point = rec.field(&path, "point")?.point(&path)?;
+        let s = rec.field(&path, "s")?.uint(&path)?;
+        let output = rec.field(&path, "output")?.point(&path)?;
+        check(checks, format!("{}.output", path), output, engine.t(point, s))?;
+    }
+
+    let key = match (doc.get("k"), doc.get("Q")) {
+        (Some(k), Some(q)) => {
+            let (k, q) = (k.uint("k")?, q.point("Q")?);
+            check(checks, "Q".to_string(), q, engine.pow_t_range(p0, 1, k))?;
+            Some((k, q))
+        }
+        _ => None,
+    };
+
+    for (i, rec) in doc.list("blocks")?.iter().enumerate() {
+        let path = format!("blocks[{}]", i);
+        let r = rec.field(&path, "r")?.uint(&path)?;
+        let c1 = rec.field(&path, "C1")?.point(&path)?;
+        let c2 = rec.field(&path, "C2")?.point(&path)?;
+        check(checks, format!("{}.C1", path), c1, engine.pow_t_range(p0, 1, r))?;
+
+        if let (Some(m), Some((k, q))) = (rec.get("m"), key) {
+            let m = m.uint(&path)?;
+            // The reference masks with T_r ∘ … ∘ T_1(Q) and unmasks with
+            // T_k ∘ … ∘ T_1(C1), not with the engine's fixed-seed map
+            let p = engine.p;
+            let actual = engine
+                .pow_t_range(q, 1, r)
+                .map(|s| Point::new_unchecked(add_mod(m % p, s.x(), p), s.y()));
+            check(checks, format!("{}.C2", path), c2, actual)?;
+            let decrypted = engine
+                .pow_t_range(c1, 1, k)
+                .map(|s| sub_mod(c2.x() % p, s.x(), p));
+            check(checks, format!("{}.m", path), m, decrypted)?;
+        }
+    }
+    Ok(())
+}
+
+fn malformed(reason: impl Into<String>) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "transcript".to_string(),
+        value: String::new(),
+        reason: reason.into(),
+        valid_range: "reference transcript JSON".to_string(),
+    }
+}
+
+/// Minimal JSON value; numbers keep their text so values up to u128 survive
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(text: &str) -> Result<Json, LaiCryptoError> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_ws();
+        if parser.pos != parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn field(&self, path: &str, key: &str) -> Result<&Json, LaiCryptoError> {
+        self.get(key)
+            .ok_or_else(|| malformed(format!("missing field {}.{}", path, key)))
+    }
+
+    /// Optional array field, empty when absent
+    fn list(&self, key: &str) -> Result<&[Json], LaiCryptoError> {
+        match self.get(key) {
+            None => Ok(&[]),
+            Some(Json::Array(items)) => Ok(items),
+            Some(_) => Err(malformed(format!("{} is not an array", key))),
+        }
+    }
+
+    fn uint(&self, path: &str) -> Result<u128, LaiCryptoError> {
+        match self {
+            Json::Number(text) => text
+                .parse()
+                .map_err(|_| malformed(format!("{}: {} is not an unsigned 128-bit integer", path, text))),
+            _ => Err(malformed(format!("{}: expected a number", path))),
+        }
+    }
+
+    fn point(&self, path: &str) -> Result<Point, LaiCryptoError> {
+        match self {
+            Json::Array(items) if items.len() == 2 => {
+                Ok(Point::new_unchecked(items[0].uint(path)?, items[1].uint(path)?))
+            }
+            _ => Err(malformed(format!("{}: expected [x, y]", path))),
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> LaiCryptoError {
+        malformed(format!("{} at byte {}", what, self.pos))
+    }
+
+    fn skip_ws(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, LaiCryptoError> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected token"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, LaiCryptoError> {
+        self.skip_ws();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_ws();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return Err(self.error("expected ':'"));
+                        }
+                        fields.push((key, self.value()?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return Err(self.error("expected ',' or '}'"));
+                        }
+                    }
+                }
+                Ok(Json::Object(fields))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return Err(self.error("expected ',' or ']'"));
+                        }
+                    }
+                }
+                Ok(Json::Array(items))
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while self
+                    .bytes
+                    .get(self.pos)
+                    .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.pos += 1;
+                }
+                let text = std::str::from_utf8(&self.bytes[start..self.pos])
+                    .map_err(|_| self.error("invalid number"))?;
+                Ok(Json::Number(text.to_string()))
+            }
+            _ => Err(self.error("unexpected token")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, LaiCryptoError> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(self.error("expected string"));
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.bytes.get(self.pos) {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    let escaped = match self.bytes.get(self.pos + 1) {
+                        Some(b'n') => b'\n',
+                        Some(b't') => b'\t',
+                        Some(b'r') => b'\r',
+                        Some(&c @ (b'"' | b'\\' | b'/')) => c,
+                        _ => return Err(self.error("unsupported escape")),
+                    };
+                    out.push(escaped);
+                    self.pos += 2;
+                }
+                Some(&c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_self_transcript() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.domain_separation = false;
+        let p0 = engine.p0;
+        let out = engine.t(p0, 1).unwrap();
+        let q = engine.pow_t_range(p0, 1, 7).unwrap();
+        let c1 = engine.pow_t_range(p0, 1, 5).unwrap();
+        let transcript = format!(
+            r#"{{ "p": 1009, "a": 3, "P0": [0, 0],
+                "hashes": [{{ "x": 4, "y": 5, "s": 6, "h": {} }}],
+                "transforms": [{{ "point": [0, 0], "s": 1, "output": [{}, {}] }}],
+                "k": 7, "Q": [{}, {}],
+                "blocks": [{{ "r": 5, "C1": [{}, {}], "C2": [1, 2] }}] }}"#,
+            engine.h(4, 5, 6),
+            out.x(), out.y(), q.x(), q.y(), c1.x(), c1.y()
+        );
+        let report = replay(&transcript).unwrap();
+        assert!(report.passed(), "{:?}", report.first_mismatch);
+        assert_eq!(report.checks, 4);
+    }
+
+    #[test]
+    fn test_replay_reports_first_mismatch() {
+        // Head of script.min.json as written by lai.py
+        let transcript = r#"{ "p": 10007, "a": 5, "P0": [1, 0],
+            "transforms": [], "k": 543, "Q": [5236, 945],
+            "blocks": [{ "C1": [5557, 5010], "C2": [98, 3267], "r": 164 }] }"#;
+        let report = replay(transcript).unwrap();
+        let mismatch = report.first_mismatch.unwrap();
+        assert_eq!(mismatch.field, "Q");
+        assert_eq!(mismatch.expected, "(5236, 945)");
+        assert_eq!(report.checks, 0);
+
+        assert!(replay(r#"{ "p": 10007, "a": 5 }"#).is_err());
+        assert!(replay(r#"{ "p": 10007, "a": 5, "P0": [1, 0] "#).is_err());
+    }
+}
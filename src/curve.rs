@@ -0,0 +1,127 @@
+//! Curve parameters and points
+
+use crate::{add_mod, mul_mod, LaiCryptoError};
+use std::fmt;
+
+/// Curve parameters for y² = x³ + ax over F_p
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CurveParams {
+    pub p: u128,
+    pub a: u128,
+}
+
+impl CurveParams {
+    /// Right-hand side x³ + ax (mod p) of the curve equation
+    pub const fn rhs(&self, x: u128) -> u128 {
+        let x = x % self.p;
+        let x3 = mul_mod(mul_mod(x, x, self.p), x, self.p);
+        add_mod(x3, mul_mod(self.a % self.p, x, self.p), self.p)
+    }
+
+    /// Whether (x, y) is a reduced point satisfying the curve equation
+    pub fn contains(&self, x: u128, y: u128) -> bool {
+        x < self.p && y < self.p && mul_mod(y, y, self.p) == self.rhs(x)
+    }
+}
+
+/// Affine point with coordinates reduced modulo p
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct Point {
+    x: u128,
+    y: u128,
+}
+
+impl Point {
+    /// Length of the [`Point::to_bytes`] encoding
+    pub const ENCODED_LEN: usize = 32;
+    /// The degenerate point (0, 0), used as the identity encoding
+    pub const IDENTITY: Point = Point { x: 0, y: 0 };
+
+    /// Create a point, checking coordinate ranges and the curve equation
+    pub fn new(x: u128, y: u128, params: &CurveParams) -> Result<Self, LaiCryptoError> {
+        if x >= params.p || y >= params.p {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "point".to_string(),
+                value: format!("({}, {})", x, y),
+                reason: "Coordinate not reduced modulo p".to_string(),
+                valid_range: format!("0 ≤ x, y < {}", params.p),
+            });
+        }
+
+        if !params.contains(x, y) {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "point".to_string(),
+                value: format!("({}, {})", x, y),
+                reason: "Point not on curve y² = x³ + ax".to_string(),
+                valid_range: "Valid curve points".to_string(),
+            });
+        }
+
+        Ok(Self { x, y })
+    }
+
+    /// Create a point without validation
+    ///
+    /// T-transform outputs are field elements but do not stay on the curve,
+    /// so the engine builds its intermediate points this way.
+    pub const fn new_unchecked(x: u128, y: u128) -> Self {
+        Self { x, y }
+    }
+
+    pub const fn x(&self) -> u128 {
+        self.x
+    }
+
+    pub const fn y(&self) -> u128 {
+        self.y
+    }
+
+    /// Whether this is the identity encoding (0, 0)
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Whether this point satisfies the curve equation for `params`
+    pub fn is_on_curve(&self, params: &CurveParams) -> bool {
+        params.contains(self.x, self.y)
+    }
+
+    /// Encode as big-endian x followed by big-endian y
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut out = [0u8; Self::ENCODED_LEN];
+        out[..16].copy_from_slice(&self.x.to_be_bytes());
+        out[16..].copy_from_slice(&self.y.to_be_bytes());
+        out
+    }
+
+    /// Decode a [`Point::to_bytes`] encoding with full validation
+    pub fn from_bytes(bytes: &[u8], params: &CurveParams) -> Result<Self, LaiCryptoError> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "bytes".to_string(),
+                value: format!("{} bytes", bytes.len()),
+                reason: "Wrong encoded point length".to_string(),
+                valid_range: format!("exactly {} bytes", Self::ENCODED_LEN),
+            });
+        }
+
+        let mut x = [0u8; 16];
+        let mut y = [0u8; 16];
+        x.copy_from_slice(&bytes[..16]);
+        y.copy_from_slice(&bytes[16..]);
+        Self::new(u128::from_be_bytes(x), u128::from_be_bytes(y), params)
+    }
+}
+
+impl From<Point> for (u128, u128) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
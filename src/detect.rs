@@ -0,0 +1,166 @@
+//! Encoding detection for keys and ciphertexts
+//!
+//! `parse_auto` sniffs the leading bytes of its input, tries each encoding
+//! whose signature matches, and returns the first successful decode. A
+//! single matching encoding that fails reports its own error; otherwise the
+//! error is [`LaiCryptoError::UnrecognizedFormat`] naming every encoding
+//! attempted and why it was rejected.
+
+use crate::{armor, envelope};
+use crate::{Ciphertext, LaiCryptoEngine, LaiCryptoError, PublicKey};
+
+/// Encodings recognised by `parse_auto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// ASCII armor from [`crate::armor`] around any other format
+    Armor,
+    /// Native `LAIV` envelope
+    Envelope,
+    /// DER `SEQUENCE` from [`crate::asn1`]
+    #[cfg(feature = "der")]
+    Der,
+    /// Deterministic CBOR map
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Format {
+    /// Every format, in the order detection tries them
+    pub const ALL: &'static [Format] = &[
+        Format::Armor,
+        Format::Envelope,
+        #[cfg(feature = "der")]
+        Format::Der,
+        #[cfg(feature = "cbor")]
+        Format::Cbor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Armor => "armor",
+            Format::Envelope => "envelope",
+            #[cfg(feature = "der")]
+            Format::Der => "DER",
+            #[cfg(feature = "cbor")]
+            Format::Cbor => "CBOR",
+        }
+    }
+
+    /// Whether `bytes` carry this format's signature
+    pub fn matches(self, bytes: &[u8]) -> bool {
+        match self {
+            Format::Armor => bytes.trim_ascii_start().starts_with(b"-----BEGIN "),
+            Format::Envelope => bytes.starts_with(&envelope::MAGIC),
+            #[cfg(feature = "der")]
+            Format::Der => bytes.first() == Some(&0x30),
+            // Both objects are definite-length maps (major type 5)
+            #[cfg(feature = "cbor")]
+            Format::Cbor => bytes.first().is_some_and(|&b| b >> 5 == 5),
+        }
+    }
+}
+
+/// Formats whose signature `bytes` carry
+pub fn detect(bytes: &[u8]) -> Vec<Format> {
+    Format::ALL.iter().copied().filter(|f| f.matches(bytes)).collect()
+}
+
+fn parse_with<T>(
+    object: &str,
+    bytes: &[u8],
+    mut decode: impl FnMut(Format) -> Result<T, LaiCryptoError>,
+) -> Result<T, LaiCryptoError> {
+    let candidates = detect(bytes);
+    if candidates.is_empty() {
+        return Err(LaiCryptoError::UnrecognizedFormat {
+            object: object.to_string(),
+            attempted: Format::ALL.iter().map(|f| format!("{}: signature not found", f.name())).collect(),
+        });
+    }
+
+    let mut failures = Vec::with_capacity(candidates.len());
+    for format in &candidates {
+        match decode(*format) {
+            Ok(value) => return Ok(value),
+            Err(e) => failures.push((*format, e)),
+        }
+    }
+    if failures.len() == 1 {
+        return Err(failures.pop().expect("one failure").1);
+    }
+    Err(LaiCryptoError::UnrecognizedFormat {
+        object: object.to_string(),
+        attempted: failures.iter().map(|(f, e)| format!("{}: {}", f.name(), e)).collect(),
+    })
+}
+
+impl PublicKey {
+    /// Decode a key in any supported encoding for `engine`'s parameters
+    pub fn parse_auto(engine: &LaiCryptoEngine, bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        parse_with("public key", bytes, |format| match format {
+            Format::Armor => PublicKey::parse_auto(engine, &armor::dearmor_labeled(bytes, armor::PUBLIC_KEY)?),
+            Format::Envelope => PublicKey::from_envelope(engine, bytes),
+            #[cfg(feature = "der")]
+            Format::Der => crate::asn1::public_key_from_der(bytes, engine),
+            #[cfg(feature = "cbor")]
+            Format::Cbor => crate::cbor::public_key_from_cbor(bytes, engine),
+        })
+    }
+}
+
+impl Ciphertext {
+    /// Decode a ciphertext in any supported encoding for `engine`'s parameters
+    pub fn parse_auto(engine: &LaiCryptoEngine, bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        parse_with("ciphertext", bytes, |format| match format {
+            Format::Armor => Ciphertext::parse_auto(engine, &armor::dearmor_labeled(bytes, armor::MESSAGE)?),
+            Format::Envelope => Ciphertext::from_envelope(engine, bytes),
+            #[cfg(feature = "der")]
+            Format::Der => crate::asn1::ciphertext_from_der(bytes, engine.p),
+            #[cfg(feature = "cbor")]
+            Format::Cbor => crate::cbor::ciphertext_from_cbor(bytes, engine.p),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_auto() {
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let key = PublicKey::new(engine.p0);
+
+        let parsed = PublicKey::parse_auto(&engine, &key.to_envelope(&engine)).unwrap();
+        assert_eq!(parsed.point(), key.point());
+        let armored = key.to_armor(&engine);
+        assert_eq!(detect(armored.as_bytes()), vec![Format::Armor]);
+        assert_eq!(PublicKey::parse_auto(&engine, armored.as_bytes()).unwrap().point(), key.point());
+        #[cfg(feature = "der")]
+        {
+            let der = crate::asn1::public_key_to_der(&key, &engine);
+            assert_eq!(detect(&der), vec![Format::Der]);
+            assert_eq!(PublicKey::parse_auto(&engine, &der).unwrap().point(), key.point());
+        }
+        #[cfg(feature = "cbor")]
+        {
+            let cbor = crate::cbor::public_key_to_cbor(&key, &engine);
+            assert_eq!(detect(&cbor), vec![Format::Cbor]);
+            assert_eq!(PublicKey::parse_auto(&engine, &cbor).unwrap().point(), key.point());
+        }
+
+        match PublicKey::parse_auto(&engine, b"not a key") {
+            Err(LaiCryptoError::UnrecognizedFormat { attempted, .. }) => {
+                assert_eq!(attempted.len(), Format::ALL.len());
+                assert!(attempted[1].starts_with("envelope"));
+            }
+            other => panic!("expected UnrecognizedFormat, got {:?}", other.map(|k| k.point())),
+        }
+        let truncated = &key.to_envelope(&engine)[..10];
+        assert!(matches!(
+            Ciphertext::parse_auto(&engine, truncated),
+            Err(LaiCryptoError::InvalidParameter { .. })
+        ));
+    }
+}
@@ -0,0 +1,847 @@
+//! The [`LaiCryptoEngine`] and its T-transform
+
+use crate::audit::{self, Finding, FindingKind, Severity};
+use crate::{
+    add_mod, clock, field::sub_mod, confirmation_tag_v2, domain, envelope, has_sqrt, hash,
+    is_prime, jacobi, kdf_mask, mul_mod, operation, precompute, random_scalar, reuse, rng, sqrt, Ciphertext,
+    CiphertextVersion, CurveParams, ErrorKind, KeyPair, KeygenReport, LaiCryptoError, PerfMetrics,
+    Point, PublicKey, SecretKey, TraceStep,
+};
+#[cfg(feature = "metrics")]
+use crate::{registry, telemetry};
+use crate::sqrt::SqrtMethod;
+use std::{fmt, time::Duration};
+use subtle::ConstantTimeEq;
+
+/// Seeds tried by a single T-transform before it fails
+pub const T_SEED_RETRIES: u32 = 10;
+
+/// Seed of the map f(P) = T(P, CHAIN_SEED) that keys and ciphertexts iterate
+///
+/// Iterates of a single map commute, f^r(f^k(P)) = f^(r+k)(P) = f^k(f^r(P)),
+/// so the receiver reaches the sender's shared point from c1.
+pub const CHAIN_SEED: u128 = 1 << 127;
+
+/// Exponent bits consumed per table lookup in [`LaiCryptoEngine::mod_pow`]
+const POW_WINDOW: u32 = 4;
+
+/// LAI cryptographic engine with enhanced capabilities
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct LaiCryptoEngine {
+    pub p: u128,
+    pub a: u128,
+    pub p0: Point,
+    pub trace: Vec<TraceStep>,
+    pub metrics: PerfMetrics,
+    pub max_attempts: u32,
+    pub max_duration: Duration,
+    /// Record per-step `trace` entries and per-transform history; disable for long analysis runs
+    pub tracing: bool,
+    /// Recently emitted c1 values; encryptions repeating one fail with `RandomnessReuse`
+    pub reuse_guard: Option<reuse::ReuseGuard>,
+    /// Prefix every h input with its [`domain::Domain`] label; off only to match the reference implementations
+    pub domain_separation: bool,
+    /// Hash behind h() and the V2 mask KDF; SHA-512 unless changed
+    pub hash: hash::HashFunction,
+    /// Most decoy transforms mixed into each chain, see [`crate::blinding`]; 0 disables
+    pub blinding: u32,
+    /// Hash-chained record of keygen, encrypt and decrypt calls; off when `None`
+    pub audit_log: Option<audit::AuditLog>,
+    /// Source of every duration in `metrics`, `trace` and timeouts; see [`LaiCryptoEngine::with_clock`]
+    pub clock: clock::SharedClock,
+    pub(crate) rng: rng::EngineRng,
+    #[cfg(feature = "metrics")]
+    pub(crate) registry: registry::Registration,
+    pub(crate) operations: operation::Operations,
+    #[cfg(feature = "metrics")]
+    pub(crate) telemetry: telemetry::TelemetrySink,
+    pub(crate) precomputed: precompute::Tables,
+    pub(crate) secret_input: sqrt::SecretInput,
+}
+
+/// Parameters and configuration; the trace and metrics are summarized
+impl fmt::Debug for LaiCryptoEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("LaiCryptoEngine");
+        s.field("p", &self.p)
+            .field("a", &self.a)
+            .field("p0", &self.p0)
+            .field("params_hash", &envelope::hex(&self.params_hash()))
+            .field("hash", &self.hash)
+            .field("domain_separation", &self.domain_separation)
+            .field("blinding", &self.blinding)
+            .field(
+                "audit_records",
+                &self.audit_log.as_ref().map(|log| log.len()),
+            )
+            .field("max_attempts", &self.max_attempts)
+            .field("max_duration", &self.max_duration)
+            .field("tracing", &self.tracing)
+            .field("trace_steps", &self.trace.len())
+            .field("reuse_guard", &self.reuse_guard.is_some())
+            .field("clock", &self.clock)
+            .field("rng", &self.rng)
+            .field("precomputed", &self.precomputed());
+        #[cfg(feature = "metrics")]
+        s.field("registered", &self.registry)
+            .field("telemetry", &self.telemetry);
+        s.finish()
+    }
+}
+
+impl fmt::Display for LaiCryptoEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LAI(p = {}, a = {}, P0 = {}, {})",
+            self.p,
+            self.a,
+            self.p0,
+            self.hash.name()
+        )
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Create new engine with parameter validation
+    ///
+    /// `p0` is a coordinate pair or a [`Point`]; either way its coordinates
+    /// must be reduced modulo p and x³ + ax must be a square.
+    pub fn new(p: u128, a: u128, p0: impl Into<(u128, u128)>) -> Result<Self, LaiCryptoError> {
+        let (x0, y0) = p0.into();
+        // Validate parameters
+        if p < 100 {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "p".to_string(),
+                value: p.to_string(),
+                reason: "Modulus too small (min 100)".to_string(),
+                valid_range: "100 ≤ p ≤ 2^128-1".to_string(),
+            });
+        }
+
+        if !is_prime(p) {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "p".to_string(),
+                value: p.to_string(),
+                reason: "Modulus must be prime".to_string(),
+                valid_range: "Prime numbers only".to_string(),
+            });
+        }
+
+        if a >= p {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "a".to_string(),
+                value: a.to_string(),
+                reason: "Parameter a must be less than modulus".to_string(),
+                valid_range: format!("0 ≤ a < {}", p),
+            });
+        }
+
+        // Verify base point
+        if x0 >= p || y0 >= p {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "p0".to_string(),
+                value: format!("({}, {})", x0, y0),
+                reason: "Coordinate not reduced modulo p".to_string(),
+                valid_range: format!("0 ≤ x, y < {}", p),
+            });
+        }
+
+        let p0 = Point::new_unchecked(x0, y0);
+        let y_sq = CurveParams { p, a }.rhs(x0);
+        if !has_sqrt(y_sq, p) {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "p0".to_string(),
+                value: p0.to_string(),
+                reason: "Base point not on curve".to_string(),
+                valid_range: "Valid curve points".to_string(),
+            });
+        }
+
+        Ok(Self::new_unchecked(p, a, p0))
+    }
+
+    /// Engine over parameters already known to be valid
+    pub(crate) fn new_unchecked(p: u128, a: u128, p0: Point) -> Self {
+        Self {
+            p,
+            a,
+            p0,
+            trace: Vec::new(),
+            metrics: PerfMetrics {
+                keygen_time: Duration::default(),
+                encrypt_time: Duration::default(),
+                decrypt_time: Duration::default(),
+                t_transform_count: 0,
+                sqrt_attempts: 0,
+                sqrt_method: None,
+                operation_history: Vec::new(),
+                operation_ids: Vec::new(),
+            },
+            max_attempts: 100,
+            max_duration: Duration::from_secs(5),
+            tracing: true,
+            reuse_guard: None,
+            domain_separation: true,
+            hash: hash::HashFunction::default(),
+            blinding: 0,
+            audit_log: None,
+            clock: clock::SharedClock::default(),
+            rng: rng::EngineRng::default(),
+            #[cfg(feature = "metrics")]
+            registry: registry::Registration::default(),
+            operations: operation::Operations::default(),
+            #[cfg(feature = "metrics")]
+            telemetry: telemetry::TelemetrySink::default(),
+            precomputed: precompute::Tables::default(),
+            secret_input: sqrt::SecretInput::default(),
+        }
+    }
+
+    /// Curve parameters of this engine
+    pub fn curve(&self) -> CurveParams {
+        CurveParams {
+            p: self.p,
+            a: self.a,
+        }
+    }
+
+    /// Record operation metrics
+    #[cfg(feature = "metrics")]
+    pub(crate) fn record_operation(&mut self, op: &str, duration: Duration) {
+        self.metrics
+            .operation_history
+            .push((op.to_string(), duration));
+        self.metrics.operation_ids.push(self.operations.current());
+        self.telemetry
+            .operation(op, duration, self.operations.current());
+        self.registry.record(op, duration);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    pub(crate) fn record_operation(&mut self, _op: &str, _duration: Duration) {}
+
+    /// Modular exponentiation by 4-bit fixed windows
+    ///
+    /// Exponents are consumed in ⌈b/4⌉ windows, b being the bit length of
+    /// `p` (or of `exp`, should it be longer), with a squaring per bit and
+    /// a multiplication per window. Each window reads its factor by
+    /// scanning the whole precomputed table, so for any exponent below
+    /// 2^b the sequence of multiplications and table reads is the same.
+    /// Products go through [`mul_mod`], whose running time, like that of
+    /// u128 division, depends on the operands: this is not constant time.
+    pub fn mod_pow(&self, base: u128, exp: u128) -> u128 {
+        let m = self.p;
+        let mut table = [1u128; 1 << POW_WINDOW];
+        table[1] = base % m;
+        for i in 2..table.len() {
+            table[i] = mul_mod(table[i - 1], table[1], m);
+        }
+
+        let bits = u128::BITS - (m | exp).leading_zeros();
+        let mut result = 1u128;
+        for w in (0..bits.div_ceil(POW_WINDOW)).rev() {
+            for _ in 0..POW_WINDOW {
+                result = mul_mod(result, result, m);
+            }
+            let digit = (exp >> (w * POW_WINDOW)) & ((1 << POW_WINDOW) - 1);
+            let mut factor = 0u128;
+            for (i, &entry) in (0u128..).zip(&table) {
+                let hit = u128::from(i.ct_eq(&digit).unwrap_u8());
+                factor |= entry & hit.wrapping_neg();
+            }
+            result = mul_mod(result, factor, m);
+        }
+        result
+    }
+
+    /// Modular square root with detailed error handling
+    ///
+    /// Inside key generation and decryption, where the input derives from
+    /// the secret scalar, this is [`LaiCryptoEngine::sqrt_mod_ct`].
+    pub fn sqrt_mod(&mut self, a: u128) -> Option<u128> {
+        if self.secret_input() {
+            return self.sqrt_mod_ct(a);
+        }
+        let method = SqrtMethod::for_prime(self.p);
+        self.metrics.sqrt_method = Some(method);
+        let a = a % self.p;
+        if a == 0 {
+            return Some(0);
+        }
+        if method == SqrtMethod::FiveMod8 {
+            let (x, c) = self.sqrt_5_mod_8(a);
+            let x2 = mul_mod(x, x, self.p);
+            return if x2 == a {
+                Some(x)
+            } else if x2 == self.p - a {
+                Some(mul_mod(x, c, self.p))
+            } else {
+                None
+            };
+        }
+        if jacobi(a, self.p) == -1 {
+            return None;
+        }
+
+        let mut attempts = 0;
+        let result = match method {
+            SqrtMethod::ThreeMod4 => Some(self.mod_pow(a, (self.p + 1) / 4)),
+            _ => {
+                let (q, s, c) = self.sqrt_constants();
+
+                let mut m = s;
+                let mut c = c;
+                let mut t = self.mod_pow(a, q);
+                let mut r = self.mod_pow(a, q.div_ceil(2));
+
+                while t != 1 {
+                    let mut i = 1;
+                    let mut t2i = self.mod_pow(t, 2);
+                    while t2i != 1 && i < m {
+                        t2i = self.mod_pow(t2i, 2);
+                        i += 1;
+                        attempts += 1;
+                    }
+                    if i == m {
+                        return None;
+                    }
+
+                    let b = self.mod_pow(c, 1 << (m - i - 1));
+                    m = i;
+                    c = mul_mod(b, b, self.p);
+                    t = mul_mod(t, c, self.p);
+                    r = mul_mod(r, b, self.p);
+                }
+                Some(r)
+            }
+        };
+
+        self.metrics.sqrt_attempts += attempts;
+        result
+    }
+
+    /// Tonelli-Shanks q, s and c = z^q for p - 1 = q · 2^s, cached or computed
+    pub(crate) fn sqrt_constants(&mut self) -> (u128, u32, u128) {
+        if let Some(k) = self.cached_sqrt_constants() {
+            return (k.q, k.s, k.c);
+        }
+        let mut q = self.p - 1;
+        let mut s = 0;
+        while q.is_multiple_of(2) {
+            q /= 2;
+            s += 1;
+        }
+
+        let mut z = 2;
+        while jacobi(z, self.p) != -1 {
+            z += 1;
+            self.metrics.sqrt_attempts += 1;
+        }
+        (q, s, self.mod_pow(z, q))
+    }
+
+    /// Enhanced hash function for T-transform
+    pub fn h(&self, x: u128, y: u128, s: u128) -> u128 {
+        self.h_in(domain::Domain::Transform, x, y, s)
+    }
+
+    /// Hash of the length-prefixed domain label and inputs, sampled below p
+    pub(crate) fn hash_to_field(&self, label: &[u8], x: u128, y: u128, s: u128) -> u128 {
+        self.hash.to_field(
+            &[
+                &[label.len() as u8],
+                label,
+                &x.to_be_bytes(),
+                &y.to_be_bytes(),
+                &s.to_be_bytes(),
+                &self.p.to_be_bytes(),
+            ],
+            self.p,
+        )
+    }
+
+    /// The reference implementations' h: first 128 digest bits modulo p
+    pub(crate) fn legacy_hash_to_field(&self, x: u128, y: u128, s: u128) -> u128 {
+        let digest = self.hash.digest(&[
+            &x.to_be_bytes(),
+            &y.to_be_bytes(),
+            &s.to_be_bytes(),
+            &self.p.to_be_bytes(),
+        ]);
+        u128::from_be_bytes(
+            digest[..16]
+                .try_into()
+                .expect("digests are at least 32 bytes"),
+        ) % self.p
+    }
+
+    /// Single T-transform with detailed tracing
+    pub fn t(&mut self, point: Point, s: u128) -> Result<Point, LaiCryptoError> {
+        let start = self.clock.now();
+        let (x, y) = point.into();
+        let inv2 = self.inv2();
+        let mut steps = Vec::new();
+
+        for i in 0..T_SEED_RETRIES {
+            let s_cur = s + i as u128;
+            let step_start = self.clock.now();
+            let hh = self.h(x, y, s_cur);
+            let x1 = mul_mod(add_mod(add_mod(x % self.p, self.a, self.p), hh, self.p), inv2, self.p);
+            let y2 = add_mod(mul_mod(x, y, self.p), hh, self.p);
+            let y1 = self.sqrt_mod(y2);
+            let step_duration = self.clock.since(step_start);
+
+            let output = y1.map(|y| Point::new_unchecked(x1, y));
+            let step = TraceStep {
+                step: i,
+                input: point,
+                s: s_cur,
+                h: hh,
+                x1,
+                y2,
+                y1,
+                output,
+                duration: step_duration,
+                operation: self.operations.current(),
+            };
+            #[cfg(feature = "trace")]
+            if self.tracing {
+                #[cfg(feature = "metrics")]
+                self.telemetry.trace(&step);
+                self.trace.push(step.clone());
+            }
+            steps.push(step);
+            self.metrics.t_transform_count += 1;
+
+            if let Some(out) = output {
+                let duration = self.clock.since(start);
+                if self.tracing {
+                    self.record_operation("t", duration);
+                }
+                return Ok(out);
+            }
+        }
+
+        let duration = self.clock.since(start);
+        if duration > self.max_duration {
+            return Err(LaiCryptoError::Timeout {
+                operation: "t".to_string(),
+                duration,
+                max_duration: self.max_duration,
+            });
+        }
+
+        if self.tracing {
+            self.record_operation("t", duration);
+        }
+        Err(LaiCryptoError::TransformFailure {
+            point,
+            s,
+            steps,
+            findings: {
+                let mut findings = audit::quick_findings(&self.curve());
+                findings.push(Finding::new(
+                    FindingKind::TransformExhausted,
+                    Severity::Medium,
+                    format!(
+                        "No square root for seeds {}..{}",
+                        s,
+                        s + T_SEED_RETRIES as u128
+                    ),
+                    "Try a different starting s",
+                ));
+                findings
+            },
+        })
+    }
+
+    /// Apply T-transform multiple times
+    pub fn pow_t_range(
+        &mut self,
+        mut point: Point,
+        start_s: u128,
+        exp: u128,
+    ) -> Result<Point, LaiCryptoError> {
+        let start = self.clock.now();
+        if self.blinding > 0 {
+            point = self.blinded_chain(point, exp, |i| start_s + i)?;
+        } else {
+            for s in start_s..start_s + exp {
+                point = self.t(point, s)?;
+            }
+        }
+        let duration = self.clock.since(start);
+        self.record_operation("pow_t_range", duration);
+        Ok(point)
+    }
+
+    /// f^n(point) for the fixed-seed map f(P) = T(P, [`CHAIN_SEED`])
+    ///
+    /// Keys are Q = f^k(P0) and ciphertexts carry c1 = f^r(P0), so the
+    /// sender's f^r(Q) and the receiver's f^k(c1) are the same point.
+    pub fn pow_t(&mut self, mut point: Point, n: u128) -> Result<Point, LaiCryptoError> {
+        let start = self.clock.now();
+        if self.blinding > 0 {
+            point = self.blinded_chain(point, n, |_| CHAIN_SEED)?;
+        } else {
+            let mut done = 0;
+            if let Some((stored, skipped)) = self.chain_start(point, n) {
+                (point, done) = (stored, skipped);
+            }
+            for _ in done..n {
+                point = self.t(point, CHAIN_SEED)?;
+            }
+        }
+        let duration = self.clock.since(start);
+        self.record_operation("pow_t", duration);
+        Ok(point)
+    }
+
+    /// Lazily iterate successive T-transforms of `start`, seeds from `start_s` upward
+    ///
+    /// `orbit(p, 1).nth(k - 1)` yields the same point as `pow_t_range(p, 1, k)`.
+    /// The iterator ends after yielding the first error.
+    pub fn orbit(&mut self, start: Point, start_s: u128) -> Orbit<'_> {
+        Orbit {
+            engine: self,
+            point: start,
+            s: start_s,
+            done: false,
+        }
+    }
+
+    /// Key generation with validation
+    ///
+    /// Each attempt draws a fresh k; attempts whose chain fails or whose
+    /// public point fails validation are collected in `KeygenFailed`. On
+    /// success the [`KeygenReport`] counts the attempts and transforms the
+    /// key took.
+    pub fn keygen(&mut self) -> Result<KeyPair, LaiCryptoError> {
+        self.in_operation(Self::keygen_inner)
+    }
+
+    fn keygen_inner(&mut self) -> Result<KeyPair, LaiCryptoError> {
+        let start = self.clock.now();
+        let t_count = self.metrics.t_transform_count;
+        let mut failures = Vec::new();
+        for attempt in 1..=self.max_attempts {
+            let k = random_scalar(&mut self.rng, self.p);
+            let q = match self.keygen_attempt(k) {
+                Ok(q) => q,
+                Err(e) => {
+                    failures.push(e);
+                    continue;
+                }
+            };
+
+            let duration = self.clock.since(start);
+            self.metrics.keygen_time = duration;
+            self.record_operation("keygen", duration);
+            self.log_key_operation(audit::KeyOperation::Keygen, Some(q), true);
+            return Ok(KeyPair {
+                public: PublicKey::new(q),
+                secret: SecretKey::new(k),
+                report: KeygenReport {
+                    attempts: attempt,
+                    t_steps: self.metrics.t_transform_count.wrapping_sub(t_count),
+                    duration,
+                },
+            });
+        }
+
+        self.log_key_operation(audit::KeyOperation::Keygen, None, false);
+        Err(self.keygen_exhausted(failures))
+    }
+
+    /// Public point f^k(P0) for secret `k`, if its chain succeeds
+    ///
+    /// A failed chain returns the transform error, and a point with an
+    /// unreduced coordinate, with y = 0 or equal to P0 a `ValidationError`,
+    /// matching what [`PublicKey::validate`] rejects. Q is not checked
+    /// against the curve equation, which T's outputs do not satisfy.
+    pub(crate) fn keygen_attempt(&mut self, k: u128) -> Result<Point, LaiCryptoError> {
+        let q = self.pow_t_secret(self.p0, k)?;
+        if q.x() >= self.p || q.y() >= self.p || q.y() == 0 || q == self.p0 {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "keygen".to_string(),
+                expected: format!("coordinates < {}, y ≠ 0 and Q ≠ P0", self.p),
+                actual: q.to_string(),
+            });
+        }
+        Ok(q)
+    }
+
+    /// `KeygenFailed` after `max_attempts` attempts failed for `failures`
+    pub(crate) fn keygen_exhausted(&self, failures: Vec<LaiCryptoError>) -> LaiCryptoError {
+        let mut findings = self.curve().audit();
+        findings.push(Finding::new(
+            FindingKind::KeygenExhausted,
+            Severity::High,
+            format!(
+                "All {} attempts from base point {} failed",
+                self.max_attempts, self.p0
+            ),
+            "Verify the base point or raise max_attempts",
+        ));
+        LaiCryptoError::KeygenFailed {
+            attempts: self.max_attempts,
+            modulus: self.p,
+            base_point: self.p0,
+            findings,
+            failures,
+        }
+    }
+
+    /// Encryption with a key-confirmation tag
+    pub fn encrypt(&mut self, m: u128, q: Point) -> Result<(Ciphertext, u128), LaiCryptoError> {
+        self.in_operation(|engine| engine.encrypt_inner(m, q))
+    }
+
+    fn encrypt_inner(&mut self, m: u128, q: Point) -> Result<(Ciphertext, u128), LaiCryptoError> {
+        let start = self.clock.now();
+        let ct = self.retry_ephemeral(|engine, r| {
+            engine
+                .encrypt_versioned(m, q, r, CiphertextVersion::default())
+                .map(|ct| (ct, r))
+        });
+        self.log_key_operation(audit::KeyOperation::Encrypt, Some(q), ct.is_ok());
+        let (ct, r) = ct?;
+
+        let duration = self.clock.since(start);
+        self.metrics.encrypt_time = duration;
+        self.record_operation("encrypt", duration);
+        Ok((ct, r))
+    }
+
+    /// Run `f` with fresh ephemeral scalars until its T chains succeed
+    ///
+    /// Like the reference implementation, a new scalar is drawn whenever a
+    /// chain fails, up to `max_attempts` times; other errors are returned.
+    pub(crate) fn retry_ephemeral<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Self, u128) -> Result<T, LaiCryptoError>,
+    ) -> Result<T, LaiCryptoError> {
+        let mut last = None;
+        for _ in 0..self.max_attempts.max(1) {
+            let r = random_scalar(&mut self.rng, self.p);
+            match f(self, r) {
+                Err(e) if e.kind() == ErrorKind::Transform => last = Some(e),
+                result => return result,
+            }
+        }
+        Err(last.expect("at least one attempt"))
+    }
+
+    /// Deterministic encryption with a caller-chosen ephemeral scalar `r`
+    ///
+    /// Only for known-answer tests and transcript replay; reusing `r`
+    /// across messages reveals their difference.
+    pub fn encrypt_with_ephemeral(
+        &mut self,
+        m: u128,
+        q: Point,
+        r: u128,
+    ) -> Result<Ciphertext, LaiCryptoError> {
+        self.in_operation(|engine| engine.encrypt_versioned(m, q, r, CiphertextVersion::default()))
+    }
+
+    /// [`LaiCryptoEngine::encrypt_with_ephemeral`] in a chosen ciphertext format
+    pub fn encrypt_versioned(
+        &mut self,
+        m: u128,
+        q: Point,
+        r: u128,
+        version: CiphertextVersion,
+    ) -> Result<Ciphertext, LaiCryptoError> {
+        let c1 = self.pow_t(self.p0, r)?;
+        if self
+            .reuse_guard
+            .as_ref()
+            .is_some_and(|guard| guard.contains(c1))
+        {
+            return Err(LaiCryptoError::RandomnessReuse {
+                operation: "encrypt".to_string(),
+                c1,
+            });
+        }
+        let sr = self.pow_t(q, r)?;
+        if let Some(guard) = &mut self.reuse_guard {
+            guard.insert(c1);
+        }
+        let m = m % self.p;
+        let (c2, tag) = match version {
+            CiphertextVersion::V1 => (
+                Point::new_unchecked(add_mod(m, sr.x(), self.p), sr.y()),
+                [0; crate::TAG_LEN],
+            ),
+            CiphertextVersion::V2 => {
+                let (mask, tag_key) = kdf_mask(&self.hash, sr, c1, &self.params_hash(), self.p);
+                (
+                    Point::new_unchecked(add_mod(m, mask, self.p), 0),
+                    confirmation_tag_v2(&tag_key, m),
+                )
+            }
+        };
+        Ok(Ciphertext {
+            c1,
+            c2,
+            tag,
+            version,
+        })
+    }
+
+    /// Unmask c2 with the receiver's shared point, without checking the tag
+    pub(crate) fn unmask(
+        &mut self,
+        c1: Point,
+        c2: Point,
+        k: u128,
+        version: CiphertextVersion,
+    ) -> Result<(u128, Point), LaiCryptoError> {
+        let s_val = self.pow_t_secret(c1, k)?;
+        let mask = match version {
+            CiphertextVersion::V1 => s_val.x(),
+            CiphertextVersion::V2 => kdf_mask(&self.hash, s_val, c1, &self.params_hash(), self.p).0,
+        };
+        Ok((sub_mod(c2.x() % self.p, mask, self.p), s_val))
+    }
+
+    /// Recover the message of `ct` given its shared point, checking the V2 tag
+    pub(crate) fn open_with_shared(
+        &self,
+        ct: &Ciphertext,
+        shared: Point,
+        operation: &str,
+    ) -> Result<u128, LaiCryptoError> {
+        let c2x = ct.c2.x() % self.p;
+        match ct.version {
+            // V1 carries no tag, see CiphertextVersion::V1
+            CiphertextVersion::V1 => Ok(sub_mod(c2x, shared.x() % self.p, self.p)),
+            CiphertextVersion::V2 => {
+                let (mask, tag_key) =
+                    kdf_mask(&self.hash, shared, ct.c1, &self.params_hash(), self.p);
+                let m = sub_mod(c2x, mask, self.p);
+                if !bool::from(confirmation_tag_v2(&tag_key, m).ct_eq(&ct.tag)) {
+                    return Err(LaiCryptoError::AuthenticationFailed {
+                        operation: operation.to_string(),
+                    });
+                }
+                Ok(m)
+            }
+        }
+    }
+
+    /// Decryption, rejecting ciphertexts whose confirmation tag does not match
+    ///
+    /// Malformed ciphertexts fail [`Ciphertext::validate`] before any
+    /// transform runs. A wrong private key or a modified `c2` yields a
+    /// different shared point or message, so the recomputed tag differs and
+    /// `AuthenticationFailed` is returned instead of a wrong plaintext.
+    /// V1 ciphertexts carry no tag and are decrypted unauthenticated.
+    pub fn decrypt(&mut self, ct: &Ciphertext, k: u128) -> Result<u128, LaiCryptoError> {
+        self.in_operation(|engine| engine.decrypt_inner(ct, k))
+    }
+
+    fn decrypt_inner(&mut self, ct: &Ciphertext, k: u128) -> Result<u128, LaiCryptoError> {
+        let start = self.clock.now();
+        let m = ct
+            .validate(&self.curve())
+            .and_then(|()| self.pow_t_secret(ct.c1, k))
+            .and_then(|s_val| self.open_with_shared(ct, s_val, "decrypt"));
+        self.log_key_operation(audit::KeyOperation::Decrypt, None, m.is_ok());
+        let m = m?;
+
+        let duration = self.clock.since(start);
+        self.metrics.decrypt_time = duration;
+        self.record_operation("decrypt", duration);
+        Ok(m)
+    }
+
+    /// Print detailed trace with diagnostics
+    pub fn print_trace(&self) {
+        println!("=== LAI Cryptographic Trace ===");
+        println!("Modulus: {}, Parameter a: {}", self.p, self.a);
+        println!("Base Point: {}", self.p0);
+        println!("Operations: {}", self.metrics.operation_history.len());
+        println!("T-transforms: {}", self.metrics.t_transform_count);
+        println!("Sqrt attempts: {}", self.metrics.sqrt_attempts);
+        if let Some(method) = self.metrics.sqrt_method {
+            println!("Sqrt method: {}", method);
+        }
+        println!("\nDetailed Trace:");
+
+        for step in &self.trace {
+            println!("[Step {}] s={} | Input: {}", step.step, step.s, step.input);
+            println!("  Hash h={} | x'={}, y²={}", step.h, step.x1, step.y2);
+            print!("  Status: ");
+            match step.y1 {
+                Some(y) => println!("Success -> Output: ({}, {})", step.x1, y),
+                None => println!("Failure: No modular square root found"),
+            }
+            println!(
+                "  Duration: {:.3}µs",
+                step.duration.as_secs_f64() * 1_000_000.0
+            );
+            println!("{}", "-".repeat(60));
+        }
+
+        println!("\nPerformance Metrics:");
+        println!(
+            "Key Generation: {:.3}ms",
+            self.metrics.keygen_time.as_secs_f64() * 1000.0
+        );
+        println!(
+            "Encryption: {:.3}ms",
+            self.metrics.encrypt_time.as_secs_f64() * 1000.0
+        );
+        println!(
+            "Decryption: {:.3}ms",
+            self.metrics.decrypt_time.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// Iterator over repeated T-transforms, created by [`LaiCryptoEngine::orbit`]
+pub struct Orbit<'a> {
+    engine: &'a mut LaiCryptoEngine,
+    point: Point,
+    s: u128,
+    done: bool,
+}
+
+impl Orbit<'_> {
+    /// Most recently yielded point (the start point before the first step)
+    pub fn current(&self) -> Point {
+        self.point
+    }
+
+    /// Seed index the next step will use
+    pub fn next_s(&self) -> u128 {
+        self.s
+    }
+}
+
+impl Iterator for Orbit<'_> {
+    type Item = Result<Point, LaiCryptoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.engine.t(self.point, self.s) {
+            Ok(point) => {
+                self.point = point;
+                self.s += 1;
+                Some(Ok(point))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Orbit<'_> {}
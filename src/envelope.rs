@@ -0,0 +1,332 @@
+//! Versioned envelope shared by serialized keys and ciphertexts
+//!
+//! ```text
+//! magic "LAIV" | version | suite (2) | kind | params hash (16) | payload length (4) | payload
+//! ```
+//!
+//! The suite names the algorithm combination and the params hash pins the
+//! modulus, coefficient and base point, so an object made under other
+//! parameters or by a future algorithm is rejected with a specific error
+//! instead of being decrypted into garbage.
+
+use crate::{Ciphertext, CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point, PublicKey, TAG_LEN};
+use sha2::{Digest, Sha512};
+
+/// Magic bytes opening every envelope
+pub const MAGIC: [u8; 4] = *b"LAIV";
+/// Current envelope version
+pub const VERSION: u8 = 1;
+/// Length of the parameter fingerprint
+pub const PARAMS_HASH_LEN: usize = 16;
+/// Bytes before the payload
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 2 + 1 + PARAMS_HASH_LEN + 4;
+
+/// Algorithm combination a payload belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suite {
+    /// T-chain encryption and exchange with SHA-512 derivations
+    Lai,
+    /// LAI combined with X25519
+    LaiX25519,
+    /// LAI combined with ML-KEM-768
+    LaiMlKem768,
+    /// T-chain encryption with SHAKE256 derivations
+    LaiShake256,
+    /// T-chain encryption with BLAKE3 derivations
+    LaiBlake3,
+}
+
+impl Suite {
+    pub fn id(self) -> u16 {
+        match self {
+            Suite::Lai => 1,
+            Suite::LaiX25519 => 2,
+            Suite::LaiMlKem768 => 3,
+            Suite::LaiShake256 => 4,
+            Suite::LaiBlake3 => 5,
+        }
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        match id {
+            1 => Some(Suite::Lai),
+            2 => Some(Suite::LaiX25519),
+            3 => Some(Suite::LaiMlKem768),
+            4 => Some(Suite::LaiShake256),
+            5 => Some(Suite::LaiBlake3),
+            _ => None,
+        }
+    }
+}
+
+/// What an envelope carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadKind {
+    PublicKey,
+    Ciphertext,
+    HybridCiphertext,
+    ChunkedCiphertext,
+    Signcrypted,
+    RatchetState,
+    PublicKeyWithMetadata,
+}
+
+impl PayloadKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            PayloadKind::PublicKey => 1,
+            PayloadKind::Ciphertext => 2,
+            PayloadKind::HybridCiphertext => 3,
+            PayloadKind::ChunkedCiphertext => 4,
+            PayloadKind::Signcrypted => 5,
+            PayloadKind::RatchetState => 6,
+            PayloadKind::PublicKeyWithMetadata => 7,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(PayloadKind::PublicKey),
+            2 => Some(PayloadKind::Ciphertext),
+            3 => Some(PayloadKind::HybridCiphertext),
+            4 => Some(PayloadKind::ChunkedCiphertext),
+            5 => Some(PayloadKind::Signcrypted),
+            6 => Some(PayloadKind::RatchetState),
+            7 => Some(PayloadKind::PublicKeyWithMetadata),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub suite: Suite,
+    pub kind: PayloadKind,
+    pub params_hash: [u8; PARAMS_HASH_LEN],
+    pub payload: Vec<u8>,
+}
+
+fn malformed(reason: &str, len: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "envelope".to_string(),
+        value: format!("{} bytes", len),
+        reason: reason.to_string(),
+        valid_range: format!("\"LAIV\" v{} with a known suite and payload kind", VERSION),
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl LaiCryptoEngine {
+    /// Fingerprint of (p, a, P0) recorded in every envelope
+    pub fn params_hash(&self) -> [u8; PARAMS_HASH_LEN] {
+        let digest = Sha512::new()
+            .chain_update(b"LAI-params")
+            .chain_update(self.p.to_be_bytes())
+            .chain_update(self.a.to_be_bytes())
+            .chain_update(self.p0.to_bytes())
+            .finalize();
+        digest[..PARAMS_HASH_LEN].try_into().expect("digest is longer")
+    }
+
+    /// Suite recorded in envelopes this engine writes
+    pub fn suite(&self) -> Suite {
+        #[cfg(feature = "shake")]
+        if self.hash == crate::hash::HashFunction::SHAKE256 {
+            return Suite::LaiShake256;
+        }
+        #[cfg(feature = "blake3")]
+        if self.hash == crate::hash::HashFunction::BLAKE3 {
+            return Suite::LaiBlake3;
+        }
+        Suite::Lai
+    }
+}
+
+impl Envelope {
+    /// Envelope for `payload` under this engine's parameters
+    pub fn new(engine: &LaiCryptoEngine, suite: Suite, kind: PayloadKind, payload: Vec<u8>) -> Self {
+        Envelope {
+            suite,
+            kind,
+            params_hash: engine.params_hash(),
+            payload,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.suite.id().to_be_bytes());
+        out.push(self.kind.to_byte());
+        out.extend_from_slice(&self.params_hash);
+        out.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    /// Parse the envelope structure without checking parameters
+    pub fn parse(bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let len = bytes.len();
+        if len < HEADER_LEN {
+            return Err(malformed("Envelope too short", len));
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LAIV magic", len));
+        }
+        if bytes[4] != VERSION {
+            return Err(malformed(&format!("Unsupported version {}", bytes[4]), len));
+        }
+        let suite_id = u16::from_be_bytes([bytes[5], bytes[6]]);
+        let suite = Suite::from_id(suite_id).ok_or_else(|| malformed(&format!("Unknown suite {}", suite_id), len))?;
+        let kind = PayloadKind::from_byte(bytes[7]).ok_or_else(|| malformed(&format!("Unknown payload kind {}", bytes[7]), len))?;
+        let params_hash = bytes[8..8 + PARAMS_HASH_LEN].try_into().expect("length checked");
+        let payload_len = u32::from_be_bytes(bytes[HEADER_LEN - 4..HEADER_LEN].try_into().expect("length checked"));
+        if payload_len as usize != len - HEADER_LEN {
+            return Err(malformed("Payload length does not match envelope", len));
+        }
+        Ok(Envelope {
+            suite,
+            kind,
+            params_hash,
+            payload: bytes[HEADER_LEN..].to_vec(),
+        })
+    }
+
+    /// Parse and check the payload kind and this engine's parameters
+    pub fn open(engine: &LaiCryptoEngine, bytes: &[u8], kind: PayloadKind) -> Result<Self, LaiCryptoError> {
+        let envelope = Self::parse(bytes)?;
+        if envelope.kind != kind {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "envelope".to_string(),
+                expected: format!("{:?} payload", kind),
+                actual: format!("{:?} payload", envelope.kind),
+            });
+        }
+        let plain = matches!(
+            envelope.suite,
+            Suite::Lai | Suite::LaiShake256 | Suite::LaiBlake3
+        );
+        if plain && envelope.suite != engine.suite() {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "envelope".to_string(),
+                expected: format!("{:?} suite", engine.suite()),
+                actual: format!("{:?} suite", envelope.suite),
+            });
+        }
+        let params_hash = engine.params_hash();
+        if envelope.params_hash != params_hash {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "envelope".to_string(),
+                expected: format!("parameters {}", hex(&params_hash)),
+                actual: format!("parameters {}", hex(&envelope.params_hash)),
+            });
+        }
+        Ok(envelope)
+    }
+}
+
+/// Decode a reduced point, reporting failures against `param`
+pub(crate) fn point_at(bytes: &[u8], p: u128, param: &str) -> Result<Point, LaiCryptoError> {
+    crate::hybrid::raw_point(bytes, p).ok_or_else(|| LaiCryptoError::InvalidParameter {
+        param: param.to_string(),
+        value: hex(bytes),
+        reason: "Coordinate not reduced modulo p".to_string(),
+        valid_range: "coordinates below p".to_string(),
+    })
+}
+
+pub(crate) fn wrong_length(param: &str, len: usize, expected: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: param.to_string(),
+        value: format!("{} bytes", len),
+        reason: "Wrong payload length".to_string(),
+        valid_range: format!("exactly {} bytes", expected),
+    }
+}
+
+impl PublicKey {
+    pub fn to_envelope(&self, engine: &LaiCryptoEngine) -> Vec<u8> {
+        Envelope::new(engine, engine.suite(), PayloadKind::PublicKey, self.point().to_bytes().to_vec()).to_bytes()
+    }
+
+    pub fn from_envelope(engine: &LaiCryptoEngine, bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let payload = Envelope::open(engine, bytes, PayloadKind::PublicKey)?.payload;
+        if payload.len() != Point::ENCODED_LEN {
+            return Err(wrong_length("public key", payload.len(), Point::ENCODED_LEN));
+        }
+        Ok(PublicKey::new(point_at(&payload, engine.p, "public key")?))
+    }
+}
+
+impl Ciphertext {
+    /// Encoded length: version, c1, c2 and tag
+    pub const ENCODED_LEN: usize = 1 + 2 * Point::ENCODED_LEN + TAG_LEN;
+
+    pub fn to_envelope(&self, engine: &LaiCryptoEngine) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::ENCODED_LEN);
+        payload.push(self.version.to_byte());
+        payload.extend_from_slice(&self.c1.to_bytes());
+        payload.extend_from_slice(&self.c2.to_bytes());
+        payload.extend_from_slice(&self.tag);
+        Envelope::new(engine, engine.suite(), PayloadKind::Ciphertext, payload).to_bytes()
+    }
+
+    pub fn from_envelope(engine: &LaiCryptoEngine, bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let payload = Envelope::open(engine, bytes, PayloadKind::Ciphertext)?.payload;
+        if payload.len() != Self::ENCODED_LEN {
+            return Err(wrong_length("ciphertext", payload.len(), Self::ENCODED_LEN));
+        }
+        let version = CiphertextVersion::from_byte(payload[0]).ok_or_else(|| LaiCryptoError::InvalidParameter {
+            param: "ciphertext version".to_string(),
+            value: payload[0].to_string(),
+            reason: "Unknown ciphertext version".to_string(),
+            valid_range: "1 or 2".to_string(),
+        })?;
+        let (points, tag) = payload[1..].split_at(2 * Point::ENCODED_LEN);
+        let (c1, c2) = points.split_at(Point::ENCODED_LEN);
+        Ok(Ciphertext {
+            c1: point_at(c1, engine.p, "c1")?,
+            c2: point_at(c2, engine.p, "c2")?,
+            tag: tag.try_into().expect("length checked"),
+            version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_roundtrip_and_rejection() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, 8).unwrap();
+
+        let sealed = ct.to_envelope(&engine);
+        assert_eq!(sealed.len(), HEADER_LEN + Ciphertext::ENCODED_LEN);
+        assert_eq!(Ciphertext::from_envelope(&engine, &sealed).unwrap(), ct);
+        let pk = PublicKey::new(q);
+        assert_eq!(PublicKey::from_envelope(&engine, &pk.to_envelope(&engine)).unwrap(), pk);
+
+        // Other parameters, kinds, suites and versions are refused up front
+        let other = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        assert!(matches!(
+            Ciphertext::from_envelope(&other, &sealed),
+            Err(LaiCryptoError::ValidationError { .. })
+        ));
+        assert!(PublicKey::from_envelope(&engine, &sealed).is_err());
+        let mut future = sealed.clone();
+        future[6] = 99;
+        assert!(Envelope::parse(&future).is_err());
+        future[4] = VERSION + 1;
+        assert!(Envelope::parse(&future).is_err());
+        assert!(Envelope::parse(&sealed[..sealed.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,560 @@
+//! Error types shared by every module
+//!
+//! [`LaiCryptoError`] carries enough context to explain a failure and, where
+//! possible, how to fix it; [`ErrorKind`] is its coarse category.
+
+use crate::audit::{self, Finding};
+use crate::{Point, TraceStep};
+use std::{fmt, io, sync::Arc, time::Duration};
+
+/// Comprehensive error types with structured diagnostics
+///
+/// New variants may be added; branch on [`LaiCryptoError::kind`] rather
+/// than matching exhaustively.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum LaiCryptoError {
+    /// Modular square root failure (Tonelli-Shanks)
+    SqrtFailure {
+        input: u128,
+        modulus: u128,
+        attempts: u32,
+        findings: Vec<Finding>,
+    },
+    /// T-transform failure with context
+    TransformFailure {
+        point: Point,
+        s: u128,
+        steps: Vec<TraceStep>,
+        findings: Vec<Finding>,
+    },
+    /// Key generation failure
+    KeygenFailed {
+        attempts: u32,
+        modulus: u128,
+        base_point: Point,
+        findings: Vec<Finding>,
+        /// Why each attempt failed, in order
+        failures: Vec<LaiCryptoError>,
+    },
+    /// Parameter validation failure
+    InvalidParameter {
+        param: String,
+        value: String,
+        reason: String,
+        valid_range: String,
+    },
+    /// Operation timeout
+    Timeout {
+        operation: String,
+        duration: Duration,
+        max_duration: Duration,
+    },
+    /// Cryptographic validation failure
+    ValidationError {
+        operation: String,
+        expected: String,
+        actual: String,
+    },
+    /// Ciphertext tag did not match: wrong key or tampered ciphertext
+    AuthenticationFailed { operation: String },
+    /// An encryption would emit a c1 already seen by the reuse guard
+    RandomnessReuse { operation: String, c1: Point },
+    /// Key used after its not-after time; both times are Unix seconds
+    KeyExpired { not_after: u64, now: u64 },
+    /// Key metadata does not permit the attempted operation
+    UsageViolation { operation: String, allowed: String },
+    /// Graph rendering error
+    GraphError { context: String, cause: String },
+    /// Reading or writing files and streams failed
+    Io {
+        context: String,
+        source: ErrorSource,
+    },
+    /// Encoding or decoding a serialized format failed
+    Serialization { format: String, source: ErrorSource },
+    /// Input matched none of the supported encodings
+    UnrecognizedFormat {
+        object: String,
+        attempted: Vec<String>,
+    },
+}
+
+/// Underlying cause shared behind an `Arc` so errors stay `Clone`
+///
+/// Two sources compare equal when their messages match.
+#[derive(Debug, Clone)]
+pub struct ErrorSource(Arc<dyn std::error::Error + Send + Sync>);
+
+impl ErrorSource {
+    pub fn new(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        ErrorSource(Arc::new(error))
+    }
+}
+
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for LaiCryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SqrtFailure {
+                input,
+                modulus,
+                attempts,
+                findings,
+            } => write!(
+                f,
+                "Square root failure for {} mod {} after {} attempts. {}",
+                input,
+                modulus,
+                attempts,
+                audit::describe(findings)
+            ),
+            Self::TransformFailure {
+                point,
+                s,
+                steps,
+                findings,
+            } => write!(
+                f,
+                "T-transform failed at point {} with s={} after {} steps. {}",
+                point,
+                s,
+                steps.len(),
+                audit::describe(findings)
+            ),
+            Self::KeygenFailed {
+                attempts,
+                modulus,
+                base_point,
+                findings,
+                failures,
+            } => {
+                write!(
+                    f,
+                    "Key generation failed after {} attempts (modulus={}, base_point={}). {}",
+                    attempts,
+                    modulus,
+                    base_point,
+                    audit::describe(findings)
+                )?;
+                if let Some(last) = failures.last() {
+                    write!(f, " Last attempt: {}", last)?;
+                }
+                Ok(())
+            }
+            Self::InvalidParameter {
+                param,
+                value,
+                reason,
+                valid_range,
+            } => write!(
+                f,
+                "Invalid parameter {}: {} (value={}). Valid range: {}",
+                param, reason, value, valid_range
+            ),
+            Self::Timeout {
+                operation,
+                duration,
+                max_duration,
+            } => write!(
+                f,
+                "Operation '{}' timed out after {:?} (max allowed: {:?})",
+                operation, duration, max_duration
+            ),
+            Self::ValidationError {
+                operation,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Validation failed for {}: expected {}, got {}",
+                operation, expected, actual
+            ),
+            Self::GraphError { context, cause } => {
+                write!(f, "Graph error in {}: {}", context, cause)
+            }
+            Self::AuthenticationFailed { operation } => {
+                write!(
+                    f,
+                    "Authentication failed in {}: wrong key or modified ciphertext",
+                    operation
+                )
+            }
+            Self::RandomnessReuse { operation, c1 } => write!(
+                f,
+                "Randomness reuse in {}: ephemeral commitment {} was already emitted",
+                operation, c1
+            ),
+            Self::KeyExpired { not_after, now } => {
+                write!(
+                    f,
+                    "Key expired at {} (now {}), Unix seconds",
+                    not_after, now
+                )
+            }
+            Self::UsageViolation { operation, allowed } => {
+                write!(
+                    f,
+                    "Key usage violation: {} not permitted; key allows {}",
+                    operation, allowed
+                )
+            }
+            Self::Io { context, source } => write!(f, "I/O error while {}: {}", context, source),
+            Self::Serialization { format, source } => {
+                write!(f, "{} serialization error: {}", format, source)
+            }
+            Self::UnrecognizedFormat { object, attempted } => {
+                write!(
+                    f,
+                    "Unrecognized {} encoding; attempted {}",
+                    object,
+                    attempted.join("; ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LaiCryptoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } | Self::Serialization { source, .. } => Some(&*source.0),
+            _ => None,
+        }
+    }
+}
+
+/// Broad category of a [`LaiCryptoError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Square root did not exist or could not be found
+    Arithmetic,
+    /// T-transform exhausted its seed retries
+    Transform,
+    /// Key generation exhausted its attempts
+    Keygen,
+    /// Caller supplied an out-of-range or malformed value
+    InvalidInput,
+    /// Operation exceeded its time budget
+    Timeout,
+    /// Computed result failed a consistency check
+    Validation,
+    /// Ciphertext failed authentication
+    Authentication,
+    /// Graph could not be rendered
+    Rendering,
+    Io,
+    Serialization,
+}
+
+impl LaiCryptoError {
+    /// Category of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::SqrtFailure { .. } => ErrorKind::Arithmetic,
+            Self::TransformFailure { .. } => ErrorKind::Transform,
+            Self::KeygenFailed { .. } => ErrorKind::Keygen,
+            Self::InvalidParameter { .. } => ErrorKind::InvalidInput,
+            Self::Timeout { .. } => ErrorKind::Timeout,
+            Self::ValidationError { .. } => ErrorKind::Validation,
+            Self::AuthenticationFailed { .. } => ErrorKind::Authentication,
+            Self::RandomnessReuse { .. } => ErrorKind::Validation,
+            Self::KeyExpired { .. } | Self::UsageViolation { .. } => ErrorKind::Validation,
+            Self::GraphError { .. } => ErrorKind::Rendering,
+            Self::Io { .. } => ErrorKind::Io,
+            Self::Serialization { .. } | Self::UnrecognizedFormat { .. } => {
+                ErrorKind::Serialization
+            }
+        }
+    }
+
+    /// Whether repeating the operation may succeed
+    ///
+    /// Randomized steps (keygen, fresh ephemeral scalars, new seeds) and
+    /// transient I/O are retryable; bad input and failed checks are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::SqrtFailure { .. }
+            | Self::TransformFailure { .. }
+            | Self::KeygenFailed { .. }
+            | Self::Timeout { .. }
+            | Self::RandomnessReuse { .. } => true,
+            Self::Io { source, .. } => source.0.downcast_ref::<io::Error>().is_some_and(|e| {
+                matches!(
+                    e.kind(),
+                    io::ErrorKind::Interrupted
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                )
+            }),
+            _ => false,
+        }
+    }
+
+    /// Stable numeric code; the thousands digit groups codes by [`ErrorKind`]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::SqrtFailure { .. } => 1001,
+            Self::TransformFailure { .. } => 1002,
+            Self::KeygenFailed { .. } => 1003,
+            Self::InvalidParameter { .. } => 2001,
+            Self::Timeout { .. } => 3001,
+            Self::ValidationError { .. } => 4001,
+            Self::AuthenticationFailed { .. } => 4002,
+            Self::RandomnessReuse { .. } => 4003,
+            Self::KeyExpired { .. } => 4004,
+            Self::UsageViolation { .. } => 4005,
+            Self::GraphError { .. } => 5001,
+            Self::Io { .. } => 6001,
+            Self::Serialization { .. } => 6002,
+            Self::UnrecognizedFormat { .. } => 6003,
+        }
+    }
+
+    /// Stable string code matching [`LaiCryptoError::code`]
+    pub fn code_name(&self) -> &'static str {
+        match self {
+            Self::SqrtFailure { .. } => "LAI_SQRT_FAILURE",
+            Self::TransformFailure { .. } => "LAI_TRANSFORM_FAILURE",
+            Self::KeygenFailed { .. } => "LAI_KEYGEN_FAILED",
+            Self::InvalidParameter { .. } => "LAI_INVALID_PARAMETER",
+            Self::Timeout { .. } => "LAI_TIMEOUT",
+            Self::ValidationError { .. } => "LAI_VALIDATION_ERROR",
+            Self::AuthenticationFailed { .. } => "LAI_AUTHENTICATION_FAILED",
+            Self::RandomnessReuse { .. } => "LAI_RANDOMNESS_REUSE",
+            Self::KeyExpired { .. } => "LAI_KEY_EXPIRED",
+            Self::UsageViolation { .. } => "LAI_USAGE_VIOLATION",
+            Self::GraphError { .. } => "LAI_GRAPH_ERROR",
+            Self::Io { .. } => "LAI_IO",
+            Self::Serialization { .. } => "LAI_SERIALIZATION",
+            Self::UnrecognizedFormat { .. } => "LAI_UNRECOGNIZED_FORMAT",
+        }
+    }
+
+    /// Serialize code, category, message and every contextual field as JSON
+    ///
+    /// Field values are integers encoded as decimal strings, since u128
+    /// values exceed the range JSON consumers handle exactly.
+    pub fn to_json(&self) -> String {
+        let num = |v: u128| json_string(&v.to_string());
+        let point = |p: &Point| format!("[{},{}]", num(p.x()), num(p.y()));
+        let ms = |d: &Duration| format!("{}", d.as_secs_f64() * 1000.0);
+        let text = |t: &str| json_string(t);
+        let findings = |fs: &[Finding]| {
+            let items: Vec<String> = fs
+                .iter()
+                .map(|f| {
+                    json_object(&[
+                        ("kind", text(&format!("{:?}", f.kind))),
+                        ("severity", text(&f.severity.to_string())),
+                        ("message", text(&f.message)),
+                        ("remediation", text(&f.remediation)),
+                    ])
+                })
+                .collect();
+            format!("[{}]", items.join(","))
+        };
+
+        let context = match self {
+            Self::SqrtFailure {
+                input,
+                modulus,
+                attempts,
+                findings: fs,
+            } => json_object(&[
+                ("input", num(*input)),
+                ("modulus", num(*modulus)),
+                ("attempts", attempts.to_string()),
+                ("findings", findings(fs)),
+            ]),
+            Self::TransformFailure {
+                point: pt,
+                s,
+                steps,
+                findings: fs,
+            } => {
+                let steps: Vec<String> = steps.iter().map(TraceStep::to_json).collect();
+                json_object(&[
+                    ("point", point(pt)),
+                    ("s", num(*s)),
+                    ("steps", format!("[{}]", steps.join(","))),
+                    ("findings", findings(fs)),
+                ])
+            }
+            Self::KeygenFailed {
+                attempts,
+                modulus,
+                base_point,
+                findings: fs,
+                failures,
+            } => {
+                let failures: Vec<String> = failures.iter().map(|e| e.to_json()).collect();
+                json_object(&[
+                    ("attempts", attempts.to_string()),
+                    ("modulus", num(*modulus)),
+                    ("base_point", point(base_point)),
+                    ("findings", findings(fs)),
+                    ("failures", format!("[{}]", failures.join(","))),
+                ])
+            }
+            Self::InvalidParameter {
+                param,
+                value,
+                reason,
+                valid_range,
+            } => json_object(&[
+                ("param", text(param)),
+                ("value", text(value)),
+                ("reason", text(reason)),
+                ("valid_range", text(valid_range)),
+            ]),
+            Self::Timeout {
+                operation,
+                duration,
+                max_duration,
+            } => json_object(&[
+                ("operation", text(operation)),
+                ("duration_ms", ms(duration)),
+                ("max_duration_ms", ms(max_duration)),
+            ]),
+            Self::ValidationError {
+                operation,
+                expected,
+                actual,
+            } => json_object(&[
+                ("operation", text(operation)),
+                ("expected", text(expected)),
+                ("actual", text(actual)),
+            ]),
+            Self::GraphError { context, cause } => {
+                json_object(&[("context", text(context)), ("cause", text(cause))])
+            }
+            Self::AuthenticationFailed { operation } => {
+                json_object(&[("operation", text(operation))])
+            }
+            Self::RandomnessReuse { operation, c1 } => {
+                json_object(&[("operation", text(operation)), ("c1", point(c1))])
+            }
+            Self::KeyExpired { not_after, now } => json_object(&[
+                ("not_after", num(u128::from(*not_after))),
+                ("now", num(u128::from(*now))),
+            ]),
+            Self::UsageViolation { operation, allowed } => {
+                json_object(&[("operation", text(operation)), ("allowed", text(allowed))])
+            }
+            Self::Io { context, source } => json_object(&[
+                ("context", text(context)),
+                ("source", text(&source.to_string())),
+            ]),
+            Self::Serialization { format, source } => json_object(&[
+                ("format", text(format)),
+                ("source", text(&source.to_string())),
+            ]),
+            Self::UnrecognizedFormat { object, attempted } => json_object(&[
+                ("object", text(object)),
+                (
+                    "attempted",
+                    format!(
+                        "[{}]",
+                        attempted
+                            .iter()
+                            .map(|a| text(a))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    ),
+                ),
+            ]),
+        };
+
+        json_object(&[
+            ("code", self.code().to_string()),
+            ("name", text(self.code_name())),
+            ("kind", text(&format!("{:?}", self.kind()))),
+            ("retryable", self.is_retryable().to_string()),
+            ("message", text(&self.to_string())),
+            ("context", context),
+        ])
+    }
+
+    /// Wrap an I/O error with what was being attempted
+    pub fn io(context: impl Into<String>, error: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source: ErrorSource::new(error),
+        }
+    }
+
+    /// Wrap a decoding error from the named format
+    pub fn serialization(
+        format: impl Into<String>,
+        error: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        Self::Serialization {
+            format: format.into(),
+            source: ErrorSource::new(error),
+        }
+    }
+}
+
+impl From<io::Error> for LaiCryptoError {
+    fn from(error: io::Error) -> Self {
+        Self::io("performing I/O", error)
+    }
+}
+
+impl From<std::str::Utf8Error> for LaiCryptoError {
+    fn from(error: std::str::Utf8Error) -> Self {
+        Self::serialization("UTF-8", error)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for LaiCryptoError {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Self::serialization("UTF-8", error)
+    }
+}
+
+impl From<std::num::ParseIntError> for LaiCryptoError {
+    fn from(error: std::num::ParseIntError) -> Self {
+        Self::serialization("integer", error)
+    }
+}
+
+/// Encode text as a JSON string literal safe for inline `<script>` blocks
+pub(crate) fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '<' => out.push_str("\\u003c"),
+            '>' => out.push_str("\\u003e"),
+            '&' => out.push_str("\\u0026"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Join already-encoded JSON values into an object
+pub(crate) fn json_object(fields: &[(&str, String)]) -> String {
+    let body: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), value))
+        .collect();
+    format!("{{{}}}", body.join(","))
+}
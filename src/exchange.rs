@@ -0,0 +1,259 @@
+//! Commutative key agreement by iterating T with a fixed seed
+//!
+//! Iterating the single map f(P) = T(P, s) commutes:
+//! f^a(f^b(G)) = f^(a+b)(G) = f^b(f^a(G)), which gives a Diffie–Hellman
+//! style agreement. It is the map [`LaiCryptoEngine::pow_t`] iterates for
+//! encryption, so recovering a from f^a(G) costs the same orbit walk as
+//! recovering an encryption key.
+
+use crate::{mul_mod, LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use secrecy::{ExposeSecret, SecretBox};
+use subtle::{Choice, ConstantTimeEq};
+use crate::redact::Redacted;
+use std::fmt;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Length of a key-confirmation tag
+pub const CONFIRMATION_LEN: usize = 32;
+
+/// Seed of the exchange map, the [`crate::CHAIN_SEED`] keys and ciphertexts use
+pub const EXCHANGE_SEED: u128 = crate::CHAIN_SEED;
+
+/// f^n(point) for f(P) = T(P, EXCHANGE_SEED)
+pub fn iterate(engine: &mut LaiCryptoEngine, point: Point, n: u128) -> Result<Point, LaiCryptoError> {
+    engine.pow_t(point, n)
+}
+
+/// Replace y by the root T itself would produce, undoing a sign flip
+///
+/// Every T output has y = sqrt_mod(v) for some v, so sqrt_mod(y²) recovers
+/// it from either root. Lets protocols send y or p − y interchangeably.
+pub fn canonicalize(engine: &mut LaiCryptoEngine, point: Point) -> Point {
+    let p = engine.p;
+    let y = engine
+        .sqrt_mod(mul_mod(point.y(), point.y(), p))
+        .unwrap_or(point.y());
+    Point::new_unchecked(point.x(), y)
+}
+
+/// Which side of a protocol run a party plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Initiator,
+    Responder,
+}
+
+impl Role {
+    pub(crate) fn label(self) -> &'static [u8] {
+        match self {
+            Role::Initiator => b"initiator",
+            Role::Responder => b"responder",
+        }
+    }
+
+    pub(crate) fn peer(self) -> Role {
+        match self {
+            Role::Initiator => Role::Responder,
+            Role::Responder => Role::Initiator,
+        }
+    }
+}
+
+/// Exchange key pair (a, f^a(P0))
+#[derive(Clone)]
+pub struct ExchangeKeyPair {
+    secret: u128,
+    public: Point,
+}
+
+impl fmt::Debug for ExchangeKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExchangeKeyPair")
+            .field("public", &self.public)
+            .field("secret", &Redacted(&self.secret))
+            .finish()
+    }
+}
+
+impl ExchangeKeyPair {
+    /// Draw a fresh secret, redrawing while its orbit hits a failing T
+    pub fn generate(engine: &mut LaiCryptoEngine) -> Result<Self, LaiCryptoError> {
+        engine.retry_ephemeral(Self::from_secret)
+    }
+
+    /// Key pair for a known secret scalar
+    pub fn from_secret(engine: &mut LaiCryptoEngine, secret: u128) -> Result<Self, LaiCryptoError> {
+        let p0 = engine.p0;
+        Ok(ExchangeKeyPair {
+            secret,
+            public: engine.pow_t_secret(p0, secret)?,
+        })
+    }
+
+    /// Rebuild a key pair from stored parts without walking the orbit
+    pub(crate) fn from_parts(secret: u128, public: Point) -> Self {
+        ExchangeKeyPair { secret, public }
+    }
+
+    pub(crate) fn secret(&self) -> u128 {
+        self.secret
+    }
+
+    pub fn public(&self) -> Point {
+        self.public
+    }
+
+    /// Shared point f^a(peer) = f^(a+b)(P0)
+    pub fn agree(&self, engine: &mut LaiCryptoEngine, peer: Point) -> Result<Point, LaiCryptoError> {
+        engine.pow_t_secret(peer, self.secret)
+    }
+
+    /// Agreement hashed with both public points into a uniform secret
+    pub fn shared_secret(&self, engine: &mut LaiCryptoEngine, peer: Point) -> Result<SharedSecret, LaiCryptoError> {
+        let agreed = self.agree(engine, peer)?;
+        let (mut first, mut second) = (self.public.to_bytes(), peer.to_bytes());
+        if first > second {
+            std::mem::swap(&mut first, &mut second);
+        }
+        let mut okm = [0u8; 32];
+        Hkdf::<Sha512>::new(None, &agreed.to_bytes())
+            .expand_multi_info(&[b"LAI-shared", &first, &second], &mut okm)
+            .expect("32 bytes is a valid HKDF-SHA-512 length");
+        Ok(SharedSecret::new(okm))
+    }
+}
+
+/// Secret both parties derive from a key exchange
+///
+/// Zeroed on drop and readable only through
+/// [`ExposeSecret::expose_secret`]. Equality runs in constant time.
+pub struct SharedSecret(SecretBox<[u8; 32]>);
+
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SharedSecret")
+            .field(&Redacted(self.expose_secret()))
+            .finish()
+    }
+}
+
+impl ExposeSecret<[u8; 32]> for SharedSecret {
+    fn expose_secret(&self) -> &[u8; 32] {
+        self.0.expose_secret()
+    }
+}
+
+impl Clone for SharedSecret {
+    fn clone(&self) -> Self {
+        SharedSecret::new(*self.expose_secret())
+    }
+}
+
+impl ConstantTimeEq for SharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.expose_secret().ct_eq(other.expose_secret())
+    }
+}
+
+impl PartialEq for SharedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SharedSecret {}
+
+impl SharedSecret {
+    pub(crate) fn new(bytes: [u8; 32]) -> Self {
+        SharedSecret(SecretBox::new(Box::new(bytes)))
+    }
+
+    /// Tag proving to the peer that `role` derived this secret
+    ///
+    /// Each side sends the tag for its own role and checks the peer's with
+    /// [`SharedSecret::verify_confirmation`], so mismatched keys are caught
+    /// before any data is decrypted. Tags for the two roles differ, which
+    /// stops a tag from being reflected back to its sender.
+    pub fn confirmation_tag(&self, role: Role) -> [u8; CONFIRMATION_LEN] {
+        let mut tag = [0u8; CONFIRMATION_LEN];
+        tag.copy_from_slice(&self.confirmation_mac(role).finalize().into_bytes()[..CONFIRMATION_LEN]);
+        tag
+    }
+
+    /// Check, in constant time, the peer's tag for its `role`
+    pub fn verify_confirmation(&self, role: Role, tag: &[u8]) -> Result<(), LaiCryptoError> {
+        if tag.len() != CONFIRMATION_LEN {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "confirmation_tag".to_string(),
+                value: format!("{} bytes", tag.len()),
+                reason: "Wrong tag length".to_string(),
+                valid_range: format!("{} bytes", CONFIRMATION_LEN),
+            });
+        }
+        self.confirmation_mac(role)
+            .verify_truncated_left(tag)
+            .map_err(|_| LaiCryptoError::AuthenticationFailed {
+                operation: "key_confirmation".to_string(),
+            })
+    }
+
+    fn confirmation_mac(&self, role: Role) -> HmacSha512 {
+        let mut mac = HmacSha512::new_from_slice(self.expose_secret()).expect("HMAC accepts any key length");
+        mac.update(b"LAI-confirm");
+        mac.update(role.label());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agreement_commutes() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let bob = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let (ab, ba) = (
+            alice.agree(&mut engine, bob.public()),
+            bob.agree(&mut engine, alice.public()),
+        );
+        // Either walk may still hit a failing T; when both succeed they agree
+        if let (Ok(ab), Ok(ba)) = (ab, ba) {
+            assert_eq!(ab, ba);
+        }
+
+        let pk = alice.public();
+        let flipped = Point::new_unchecked(pk.x(), (1009 - pk.y()) % 1009);
+        assert_eq!(canonicalize(&mut engine, flipped), pk);
+    }
+
+    #[test]
+    fn test_key_confirmation() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let bob = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let (Ok(a), Ok(b)) = (
+            alice.shared_secret(&mut engine, bob.public()),
+            bob.shared_secret(&mut engine, alice.public()),
+        ) else {
+            return;
+        };
+        assert!(a == b);
+        assert!(bool::from(a.ct_eq(&b)));
+        assert!(a != SharedSecret::new([0; 32]));
+        let tag = a.confirmation_tag(Role::Initiator);
+        assert!(b.verify_confirmation(Role::Initiator, &tag).is_ok());
+        // Reflected tags and a mismatched secret are both rejected
+        assert!(b.verify_confirmation(Role::Responder, &tag).is_err());
+        assert!(matches!(
+            SharedSecret::new([0; 32]).verify_confirmation(Role::Initiator, &tag),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+        assert!(b.verify_confirmation(Role::Initiator, &tag[..16]).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+//! Arithmetic modulo a prime `p < 2^127`
+//!
+//! Free functions on `u128` residues, the same ones the engine and the
+//! [`crate::analysis`] tools are built on, for experiments that need field
+//! arithmetic without a [`crate::LaiCryptoEngine`]. None of them overflow
+//! for any modulus below 2^127, and most are `const fn`. Operands of
+//! [`add_mod`] and [`sub_mod`] must already be reduced; the others reduce
+//! their inputs.
+//!
+//! [`sqrt_mod`] returns the root [`crate::LaiCryptoEngine::sqrt_mod`] does,
+//! by the method [`crate::sqrt::SqrtMethod::for_prime`] names. Nothing
+//! here is constant time.
+//!
+//! ```
+//! use laicrypto::field::{inv_mod, mul_mod, sqrt_mod, sub_mod};
+//!
+//! let p = 1009;
+//! assert_eq!(mul_mod(inv_mod(7, p).unwrap(), 7, p), 1);
+//! assert_eq!(sub_mod(3, 5, p), 1007);
+//! let y = sqrt_mod(2, p).unwrap();
+//! assert_eq!(mul_mod(y, y, p), 2);
+//! ```
+
+use rand::RngCore;
+
+/// Miller-Rabin primality test for u128
+pub const fn is_prime(n: u128) -> bool {
+    // Handle small primes
+    match n {
+        2 | 3 => return true,
+        _ if n <= 1 || n.is_multiple_of(2) => return false,
+        _ => {}
+    }
+
+    let mut d = n - 1;
+    let mut s = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+
+    // Extended bases for 128-bit numbers
+    let bases: &[u128] = if n < 2_047 {
+        &[2]
+    } else if n < 1_373_653 {
+        &[2, 3]
+    } else if n < 9_080_191 {
+        &[31, 73]
+    } else if n < 25_326_001 {
+        &[2, 3, 5]
+    } else if n < 3_215_031_751 {
+        &[2, 3, 5, 7]
+    } else if n < 4_759_123_141 {
+        &[2, 7, 61]
+    } else if n < 1_122_004_669_633 {
+        &[2, 13, 23, 1_662_803]
+    } else if n < 2_152_302_898_747 {
+        &[2, 3, 5, 7, 11]
+    } else if n < 3_474_749_660_383 {
+        &[2, 3, 5, 7, 11, 13]
+    } else if n < 341_550_071_728_321 {
+        &[2, 3, 5, 7, 11, 13, 17]
+    } else {
+        // Deterministic for 2^64 and larger
+        &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]
+    };
+
+    let mut i = 0;
+    'base_loop: while i < bases.len() {
+        let a = bases[i];
+        i += 1;
+        if a >= n {
+            continue;
+        }
+
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+
+        let mut r = 1;
+        while r < s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'base_loop;
+            }
+            r += 1;
+        }
+        return false;
+    }
+    true
+}
+
+/// Overflow-safe modular exponentiation
+pub const fn pow_mod(mut base: u128, mut exp: u128, modulus: u128) -> u128 {
+    if modulus == 1 {
+        return 0;
+    }
+    let mut result = 1;
+    base %= modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        exp >>= 1;
+        base = mul_mod(base, base, modulus);
+    }
+    result
+}
+
+/// Overflow-safe modular addition for operands already reduced mod m
+pub const fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// Modular subtraction for operands already reduced mod m
+pub const fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        a + (m - b)
+    }
+}
+
+/// Read candidates of p's bit length from `read` until one falls below p
+pub(crate) fn sample_below(p: u128, mut read: impl FnMut(&mut [u8])) -> u128 {
+    let bits = 128 - p.leading_zeros();
+    let len = bits.div_ceil(8) as usize;
+    let mask = u128::MAX >> (128 - bits);
+    loop {
+        let mut buf = [0u8; 16];
+        read(&mut buf[16 - len..]);
+        let candidate = u128::from_be_bytes(buf) & mask;
+        if candidate < p {
+            return candidate;
+        }
+    }
+}
+
+/// Uniform scalar in 1..p
+///
+/// Rejection-samples candidates of p - 1's bit length, so no scalar is
+/// favoured; fewer than two draws are needed on average.
+pub fn random_scalar<R: RngCore + ?Sized>(rng: &mut R, p: u128) -> u128 {
+    sample_below(p - 1, |buf| rng.fill_bytes(buf)) + 1
+}
+
+/// Big-endian `bytes` reduced modulo m
+///
+/// With 256 or more input bits the result is within 2^-128 of uniform for
+/// any u128 modulus, unlike reducing a single 128-bit word.
+pub(crate) fn reduce_wide(bytes: &[u8], m: u128) -> u128 {
+    bytes.iter().fold(0u128, |acc, &b| {
+        add_mod(mul_mod(acc, 256, m), u128::from(b) % m, m)
+    })
+}
+
+/// Overflow-safe modular multiplication for any u128 modulus
+pub const fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (mut a, mut b) = (a % m, b % m);
+    if a <= u64::MAX as u128 && b <= u64::MAX as u128 {
+        return (a * b) % m;
+    }
+
+    // Double-and-add keeps every intermediate below m
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    result
+}
+
+/// Jacobi symbol (a/n) for odd n, by the binary algorithm
+///
+/// Only shifts, subtractions and comparisons: strip factors of two,
+/// flipping the sign when n ≡ ±3 (mod 8), and subtract the smaller odd
+/// operand from the larger, flipping on reciprocity when both are
+/// 3 (mod 4). For prime n it is the Legendre symbol.
+pub const fn jacobi(a: u128, n: u128) -> i8 {
+    let (mut a, mut n) = (a % n, n);
+    let mut sign = 1;
+    while a != 0 {
+        let twos = a.trailing_zeros();
+        a >>= twos;
+        if twos % 2 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            sign = -sign;
+        }
+        if a < n {
+            let t = a;
+            a = n;
+            n = t;
+            if a % 4 == 3 && n % 4 == 3 {
+                sign = -sign;
+            }
+        }
+        a -= n;
+    }
+    if n == 1 {
+        sign
+    } else {
+        0
+    }
+}
+
+/// Check if a has square root modulo p
+pub const fn has_sqrt(a: u128, p: u128) -> bool {
+    a == 0 || jacobi(a, p) == 1
+}
+
+/// Inverse of a modulo m, if gcd(a, m) = 1
+///
+/// Extended Euclid with the Bézout coefficient kept reduced mod m, so it
+/// works for composite moduli too.
+pub const fn inv_mod(a: u128, m: u128) -> Option<u128> {
+    let (mut r0, mut r1) = (m, a % m);
+    let (mut t0, mut t1) = (0, 1 % m);
+    while r1 != 0 {
+        let q = r0 / r1;
+        let r = r0 - q * r1;
+        r0 = r1;
+        r1 = r;
+        let t = sub_mod(t0, mul_mod(q, t1, m), m);
+        t0 = t1;
+        t1 = t;
+    }
+    if r0 == 1 {
+        Some(t0)
+    } else {
+        None
+    }
+}
+
+/// Square root of a modulo odd prime p, if a is a quadratic residue
+pub const fn sqrt_mod(a: u128, p: u128) -> Option<u128> {
+    let a = a % p;
+    if a == 0 {
+        return Some(0);
+    }
+    if jacobi(a, p) != 1 {
+        return None;
+    }
+    match p % 8 {
+        3 | 7 => Some(pow_mod(a, (p + 1) / 4, p)),
+        5 => {
+            // x² = ±a; 2 is a non-residue, so 2^((p-1)/4) is √-1
+            let x = pow_mod(a, (p + 3) / 8, p);
+            if mul_mod(x, x, p) == a {
+                Some(x)
+            } else {
+                Some(mul_mod(x, pow_mod(2, (p - 1) / 4, p), p))
+            }
+        }
+        _ => {
+            let s = (p - 1).trailing_zeros();
+            let q = (p - 1) >> s;
+            let mut z = 2;
+            while jacobi(z, p) != -1 {
+                z += 1;
+            }
+            let mut m = s;
+            let mut c = pow_mod(z, q, p);
+            let mut t = pow_mod(a, q, p);
+            let mut r = pow_mod(a, q.div_ceil(2), p);
+            while t != 1 {
+                let mut i = 1;
+                let mut t2i = mul_mod(t, t, p);
+                while t2i != 1 {
+                    t2i = mul_mod(t2i, t2i, p);
+                    i += 1;
+                }
+                let mut b = c;
+                let mut j = 0;
+                while j < m - i - 1 {
+                    b = mul_mod(b, b, p);
+                    j += 1;
+                }
+                m = i;
+                c = mul_mod(b, b, p);
+                t = mul_mod(t, c, p);
+                r = mul_mod(r, b, p);
+            }
+            Some(r)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LaiCryptoEngine;
+
+    #[test]
+    fn test_jacobi_matches_euler() {
+        for p in [3u128, 5, 1009, 1013, 7681, 10007] {
+            for a in 0..p.min(2000) {
+                let euler = match pow_mod(a, (p - 1) / 2, p) {
+                    0 => 0,
+                    1 => 1,
+                    _ => -1,
+                };
+                assert_eq!(jacobi(a, p), euler, "({}/{})", a, p);
+            }
+        }
+        // Composite n: (2/15) = (2/3)(2/5) = 1, though 2 is no square mod 15
+        assert_eq!(jacobi(2, 15), 1);
+        assert_eq!(jacobi(5, 15), 0);
+        let p = u128::MAX - 158;
+        assert_eq!(jacobi(p - 1, p), if p % 4 == 1 { 1 } else { -1 });
+        assert_eq!(jacobi(4, p), 1);
+    }
+
+    #[test]
+    fn test_field_ops() {
+        for p in [1019u128, 1013, 1009, 7681] {
+            let mut engine = LaiCryptoEngine::new_unchecked(p, 3, crate::Point::IDENTITY);
+            for a in 0..p {
+                assert_eq!(sqrt_mod(a, p), engine.sqrt_mod(a), "{} mod {}", a, p);
+                assert_eq!(add_mod(sub_mod(a, 7, p), 7, p), a);
+                match inv_mod(a, p) {
+                    Some(inv) => assert_eq!(mul_mod(a, inv, p), 1),
+                    None => assert_eq!(a, 0),
+                }
+            }
+        }
+        assert_eq!(inv_mod(6, 15), None);
+        assert_eq!(inv_mod(7, 15), Some(13));
+
+        // Near the top of the supported range
+        let p = (1u128 << 127) - 1;
+        let a = p - 12345;
+        assert_eq!(mul_mod(a, inv_mod(a, p).unwrap(), p), 1);
+        let y = sqrt_mod(mul_mod(a, a, p), p).unwrap();
+        assert!(y == a || y == p - a);
+        assert_eq!(sub_mod(0, 1, p), p - 1);
+        assert!(is_prime(p));
+    }
+}
@@ -0,0 +1,343 @@
+//! Batch T-transform orbits on the GPU
+//!
+//! Parameter searches and orbit statistics walk millions of independent
+//! T-transform chains. [`LaiCryptoEngine::batch_orbits`] hands each chain to
+//! one invocation of a wgpu compute shader and reads every point back. The
+//! shader mirrors [`LaiCryptoEngine::t`] for engines using the default
+//! SHA-512 hash with `p` below [`GPU_MAX_P`]; other engines, or calls
+//! without a [`GpuContext`], run the same chains on the CPU. A number of
+//! GPU orbits can be recomputed on the CPU and compared before anything
+//! is returned.
+//!
+//! Orbits evaluated here are not traced, and the only metric recorded is
+//! the `batch_orbits` operation itself.
+
+use crate::hash::HashFunction;
+use crate::{LaiCryptoEngine, LaiCryptoError, Point, T_SEED_RETRIES};
+use std::fmt;
+use wgpu::util::DeviceExt;
+
+/// Moduli accepted by the shader, whose products must fit in 32 bits
+pub const GPU_MAX_P: u128 = 1 << 15;
+
+/// Invocations per workgroup, as declared in the shader
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Where a batch was evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitBackend {
+    Gpu,
+    Cpu,
+}
+
+/// Points of each requested orbit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrbitBatch {
+    /// One entry per start, shorter than `steps` where a transform failed
+    pub orbits: Vec<Vec<Point>>,
+    pub backend: OrbitBackend,
+    /// GPU orbits recomputed on the CPU and found equal
+    pub cross_checked: usize,
+}
+
+/// Device, queue and compiled T-transform pipeline
+pub struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter: String,
+}
+
+impl fmt::Debug for GpuContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GpuContext")
+            .field("adapter", &self.adapter)
+            .finish()
+    }
+}
+
+fn gpu_error(actual: impl fmt::Display) -> LaiCryptoError {
+    LaiCryptoError::ValidationError {
+        operation: "gpu".to_string(),
+        expected: "a working compute adapter".to_string(),
+        actual: actual.to_string(),
+    }
+}
+
+impl GpuContext {
+    /// Open the default high-performance adapter and compile the shader
+    pub fn new() -> Result<Self, LaiCryptoError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(gpu_error)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("laicrypto"),
+            ..Default::default()
+        }))
+        .map_err(gpu_error)?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("t_transform"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("t_transform.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("t_transform"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            adapter: adapter.get_info().name,
+        })
+    }
+
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter
+    }
+
+    fn run(
+        &self,
+        params: [u32; 8],
+        starts: &[Point],
+        steps: u32,
+    ) -> Result<Vec<Vec<Point>>, LaiCryptoError> {
+        let words =
+            |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+        let start_words: Vec<u32> = starts
+            .iter()
+            .flat_map(|pt| [pt.x() as u32, pt.y() as u32])
+            .collect();
+        let points_len = 8 * starts.len() as u64 * u64::from(steps);
+        let lengths_len = 4 * starts.len() as u64;
+
+        let storage = |label, size, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let uniform = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: &words(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let input = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("starts"),
+                contents: &words(&start_words),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let output = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        let readback = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
+        let points = storage("points", points_len, output);
+        let lengths = storage("lengths", lengths_len, output);
+        let points_read = storage("points readback", points_len, readback);
+        let lengths_read = storage("lengths readback", lengths_len, readback);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("t_transform"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[&uniform, &input, &points, &lengths]
+                .iter()
+                .enumerate()
+                .map(|(i, buffer)| wgpu::BindGroupEntry {
+                    binding: i as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("t_transform"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((starts.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&points, 0, &points_read, 0, points_len);
+        encoder.copy_buffer_to_buffer(&lengths, 0, &lengths_read, 0, lengths_len);
+        self.queue.submit([encoder.finish()]);
+
+        let read = |buffer: &wgpu::Buffer| -> Result<Vec<u32>, LaiCryptoError> {
+            buffer.map_async(wgpu::MapMode::Read, .., |_| {});
+            self.device
+                .poll(wgpu::PollType::wait_indefinitely())
+                .map_err(gpu_error)?;
+            let data = buffer.get_mapped_range(..).map_err(gpu_error)?;
+            Ok(data
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes(b.try_into().expect("4-byte chunk")))
+                .collect())
+        };
+        let flat = read(&points_read)?;
+        let lengths = read(&lengths_read)?;
+        Ok(lengths
+            .iter()
+            .enumerate()
+            .map(|(orbit, &len)| {
+                let base = 2 * orbit * steps as usize;
+                (0..len as usize)
+                    .map(|k| {
+                        let i = base + 2 * k;
+                        Point::new_unchecked(flat[i].into(), flat[i + 1].into())
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Whether [`LaiCryptoEngine::batch_orbits`] can use the GPU for these seeds
+    pub fn gpu_supported(&self, start_s: u128, steps: u32) -> bool {
+        self.p < GPU_MAX_P
+            && self.hash == HashFunction::SHA512
+            && start_s + u128::from(steps) + u128::from(T_SEED_RETRIES) <= u128::from(u32::MAX)
+    }
+
+    /// `steps` T-transforms from each start, seeds from `start_s` upward
+    ///
+    /// Each orbit equals `self.orbit(start, start_s).take(steps)` up to its
+    /// first failure. With `gpu` given and [`LaiCryptoEngine::gpu_supported`],
+    /// the orbits run on the GPU and the first `cross_check` of them are
+    /// recomputed on the CPU; any difference is a `ValidationError`.
+    pub fn batch_orbits(
+        &mut self,
+        gpu: Option<&GpuContext>,
+        starts: &[Point],
+        start_s: u128,
+        steps: u32,
+        cross_check: usize,
+    ) -> Result<OrbitBatch, LaiCryptoError> {
+        let start = self.clock.now();
+        if let Some(bad) = starts
+            .iter()
+            .find(|pt| pt.x() >= self.p || pt.y() >= self.p)
+        {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "starts".to_string(),
+                value: bad.to_string(),
+                reason: "Coordinates must be reduced modulo p".to_string(),
+                valid_range: format!("0 ≤ x, y < {}", self.p),
+            });
+        }
+
+        let batch = match gpu {
+            Some(gpu) if self.gpu_supported(start_s, steps) && !starts.is_empty() => {
+                let params = [
+                    self.p as u32,
+                    self.a as u32,
+                    self.mod_pow(2, self.p - 2) as u32,
+                    steps,
+                    start_s as u32,
+                    self.domain_separation as u32,
+                    starts.len() as u32,
+                    T_SEED_RETRIES,
+                ];
+                let orbits = gpu.run(params, starts, steps)?;
+                let checked = cross_check.min(orbits.len());
+                for (i, orbit) in orbits.iter().take(checked).enumerate() {
+                    let expected = self.cpu_orbit(starts[i], start_s, steps);
+                    if &expected != orbit {
+                        return Err(LaiCryptoError::ValidationError {
+                            operation: "batch_orbits".to_string(),
+                            expected: format!("CPU orbit {:?}", expected),
+                            actual: format!("GPU orbit {:?}", orbit),
+                        });
+                    }
+                }
+                OrbitBatch {
+                    orbits,
+                    backend: OrbitBackend::Gpu,
+                    cross_checked: checked,
+                }
+            }
+            _ => OrbitBatch {
+                orbits: starts
+                    .iter()
+                    .map(|&pt| self.cpu_orbit(pt, start_s, steps))
+                    .collect(),
+                backend: OrbitBackend::Cpu,
+                cross_checked: 0,
+            },
+        };
+        let duration = self.clock.since(start);
+        self.record_operation("batch_orbits", duration);
+        Ok(batch)
+    }
+
+    fn cpu_orbit(&mut self, start: Point, start_s: u128, steps: u32) -> Vec<Point> {
+        let tracing = std::mem::replace(&mut self.tracing, false);
+        let orbit = self
+            .orbit(start, start_s)
+            .take(steps as usize)
+            .map_while(Result::ok)
+            .collect();
+        self.tracing = tracing;
+        orbit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shader_validates() {
+        use wgpu::naga::valid::{Capabilities, ValidationFlags, Validator};
+        let module = wgpu::naga::front::wgsl::parse_str(include_str!("t_transform.wgsl"))
+            .unwrap_or_else(|e| panic!("{}", e));
+        Validator::new(ValidationFlags::all(), Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_batch_orbits_match_engine() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let starts: Vec<Point> = (0..200u128)
+            .map(|i| Point::new_unchecked(i * 5 % 1009, i * 11 % 1009))
+            .collect();
+        let steps = 6;
+        let gpu = GpuContext::new().ok();
+        let batch = engine
+            .batch_orbits(gpu.as_ref(), &starts, 1, steps, starts.len())
+            .unwrap();
+        assert_eq!(batch.orbits.len(), starts.len());
+        assert!(engine.trace.is_empty());
+
+        for (start, orbit) in starts.iter().zip(&batch.orbits) {
+            let expected: Vec<Point> = engine
+                .orbit(*start, 1)
+                .take(steps as usize)
+                .map_while(Result::ok)
+                .collect();
+            assert_eq!(orbit, &expected);
+        }
+        if gpu.is_some() {
+            assert_eq!(batch.backend, OrbitBackend::Gpu);
+            assert_eq!(batch.cross_checked, starts.len());
+        }
+
+        engine.hash = HashFunction::SHA256;
+        assert!(!engine.gpu_supported(1, steps));
+        let cpu = engine.batch_orbits(gpu.as_ref(), &starts[..3], 1, steps, 0);
+        assert_eq!(cpu.unwrap().backend, OrbitBackend::Cpu);
+    }
+}
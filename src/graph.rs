@@ -0,0 +1,1863 @@
+//! Terminal, SVG, PNG and HTML plots of engine metrics
+//!
+//! Compiled only with the `graphs` feature.
+
+#[cfg(feature = "plot-html")]
+use crate::json_string;
+use crate::{LaiCryptoEngine, LaiCryptoError};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+
+/// Graphing module for cryptographic visualization
+///
+/// Build one with [`CryptoGraph::new`]; fields may be added in later
+/// releases, so struct literals are only accepted inside this crate.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CryptoGraph {
+    pub title: String,
+    pub data: Vec<(f64, f64)>,
+    pub labels: HashMap<String, String>,
+    pub style: GraphStyle,
+    pub color: ColorMode,
+    /// Reject zero-width axes instead of padding them
+    pub strict: bool,
+    /// Additional named series drawn alongside `data`
+    pub series: Vec<GraphSeries>,
+}
+
+/// Named data series for multi-series graphs
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSeries {
+    pub name: String,
+    pub data: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphStyle {
+    Line,
+    Scatter,
+    Histogram,
+    /// Unicode Braille cells, 2×4 sub-pixels per character
+    Braille,
+    /// Point density binned into shaded cells
+    Heatmap,
+}
+
+/// Smoothing filter applied to graph y values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Trailing mean over the given number of points
+    MovingAverage(usize),
+    /// Exponential moving average with smoothing factor alpha in (0, 1]
+    Ema(f64),
+}
+
+/// Strategy for reducing the number of plotted points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Downsampling {
+    /// Largest-Triangle-Three-Buckets, keeping the given number of points
+    Lttb(usize),
+    /// Minimum and maximum y of each of the given number of buckets
+    MinMax(usize),
+}
+
+/// Engine metric plotted by [`CryptoGraph::compare`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphMetric {
+    /// Duration of each entry in the operation history (ms)
+    OperationTime,
+    /// Duration of each recorded T-transform step (µs)
+    StepTime,
+    /// Last key generation time (ms)
+    KeygenTime,
+    /// Last encryption time (ms)
+    EncryptTime,
+    /// Last decryption time (ms)
+    DecryptTime,
+    /// Total T-transform invocations
+    TransformCount,
+    /// Total square root search iterations
+    SqrtAttempts,
+}
+
+/// ANSI color output setting for rendered graphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Color only when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Resolve whether ANSI escape codes should be emitted
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// ANSI colors assigned to data series in order
+pub const SERIES_COLORS: [&str; 6] = [
+    "\x1b[36m", "\x1b[33m", "\x1b[35m", "\x1b[34m", "\x1b[32m", "\x1b[37m",
+];
+/// Plot markers distinguishing series when more than one is drawn
+pub const SERIES_MARKERS: [char; 6] = ['●', '○', '◆', '◇', '▲', '△'];
+/// ANSI color for the minimum y value highlight
+pub const MIN_COLOR: &str = "\x1b[1;32m";
+/// ANSI color for the maximum y value highlight
+pub const MAX_COLOR: &str = "\x1b[1;31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Braille dot bits indexed by `[sub_row][sub_col]` within a cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+type ColorGrid = Vec<Vec<Option<&'static str>>>;
+
+/// Extent of the plotted data
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+}
+
+impl Bounds {
+    fn x_range(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    fn y_range(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Highlight color for extreme y values
+    fn highlight(&self, y: f64) -> Option<&'static str> {
+        if y == self.max_y {
+            Some(MAX_COLOR)
+        } else if y == self.min_y {
+            Some(MIN_COLOR)
+        } else {
+            None
+        }
+    }
+}
+
+impl CryptoGraph {
+    /// Graph of a single series with no axis labels, colored when the
+    /// terminal allows it and with zero-width axes padded
+    pub fn new(title: impl Into<String>, data: Vec<(f64, f64)>, style: GraphStyle) -> Self {
+        CryptoGraph {
+            title: title.into(),
+            data,
+            labels: HashMap::new(),
+            style,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+
+    /// Set the label of axis `"x"` or `"y"`
+    pub fn with_label(mut self, axis: &str, label: impl Into<String>) -> Self {
+        self.labels.insert(axis.to_string(), label.into());
+        self
+    }
+
+    /// Smooth y values of every series in place
+    pub fn smooth(&mut self, smoothing: Smoothing) -> Result<&mut Self, LaiCryptoError> {
+        match smoothing {
+            Smoothing::MovingAverage(window) if window == 0 => {
+                return Err(LaiCryptoError::InvalidParameter {
+                    param: "window".to_string(),
+                    value: window.to_string(),
+                    reason: "Moving average window must be non-empty".to_string(),
+                    valid_range: "window ≥ 1".to_string(),
+                });
+            }
+            Smoothing::Ema(alpha) if !(alpha > 0.0 && alpha <= 1.0) => {
+                return Err(LaiCryptoError::InvalidParameter {
+                    param: "alpha".to_string(),
+                    value: alpha.to_string(),
+                    reason: "EMA smoothing factor out of range".to_string(),
+                    valid_range: "0 < alpha ≤ 1".to_string(),
+                });
+            }
+            _ => {}
+        }
+
+        for data in self.series_data_mut() {
+            match smoothing {
+                Smoothing::MovingAverage(window) => {
+                    let ys: Vec<f64> = data.iter().map(|&(_, y)| y).collect();
+                    for (i, point) in data.iter_mut().enumerate() {
+                        let start = (i + 1).saturating_sub(window);
+                        point.1 = ys[start..=i].iter().sum::<f64>() / (i + 1 - start) as f64;
+                    }
+                }
+                Smoothing::Ema(alpha) => {
+                    let mut ema = None;
+                    for point in data.iter_mut() {
+                        let next = match ema {
+                            Some(prev) => alpha * point.1 + (1.0 - alpha) * prev,
+                            None => point.1,
+                        };
+                        point.1 = next;
+                        ema = Some(next);
+                    }
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Reduce the number of points of every series in place so long traces don't overplot
+    pub fn downsample(&mut self, downsampling: Downsampling) -> &mut Self {
+        for data in self.series_data_mut() {
+            *data = match downsampling {
+                Downsampling::Lttb(threshold) => lttb(data, threshold),
+                Downsampling::MinMax(buckets) => min_max_buckets(data, buckets),
+            };
+        }
+        self
+    }
+
+    /// Primary data (when non-empty) followed by the additional series, with display names
+    fn all_series(&self) -> Vec<(&str, &[(f64, f64)])> {
+        let mut all = Vec::with_capacity(self.series.len() + 1);
+        if !self.data.is_empty() {
+            let name = self
+                .labels
+                .get("series")
+                .map(String::as_str)
+                .unwrap_or("data");
+            all.push((name, self.data.as_slice()));
+        }
+        all.extend(
+            self.series
+                .iter()
+                .map(|s| (s.name.as_str(), s.data.as_slice())),
+        );
+        all
+    }
+
+    fn series_data_mut(&mut self) -> impl Iterator<Item = &mut Vec<(f64, f64)>> {
+        std::iter::once(&mut self.data).chain(self.series.iter_mut().map(|s| &mut s.data))
+    }
+
+    /// Largest point count over all series
+    #[cfg(feature = "plot-svg")]
+    fn max_series_len(&self) -> usize {
+        self.all_series()
+            .iter()
+            .map(|(_, data)| data.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Build a multi-series chart of one metric across several engines
+    ///
+    /// Time-series metrics produce one line per engine; scalar metrics
+    /// produce one bar per engine. Series are named by parameter set.
+    pub fn compare(engines: &[&LaiCryptoEngine], metric: GraphMetric) -> CryptoGraph {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let series = engines
+            .iter()
+            .enumerate()
+            .map(|(i, engine)| {
+                let m = &engine.metrics;
+                let scalar = |v: f64| vec![(i as f64, v)];
+                let data = match metric {
+                    GraphMetric::OperationTime => m
+                        .operation_history
+                        .iter()
+                        .enumerate()
+                        .map(|(j, (_, d))| (j as f64, ms(*d)))
+                        .collect(),
+                    GraphMetric::StepTime => engine
+                        .trace
+                        .iter()
+                        .enumerate()
+                        .map(|(j, step)| (j as f64, step.duration.as_secs_f64() * 1_000_000.0))
+                        .collect(),
+                    GraphMetric::KeygenTime => scalar(ms(m.keygen_time)),
+                    GraphMetric::EncryptTime => scalar(ms(m.encrypt_time)),
+                    GraphMetric::DecryptTime => scalar(ms(m.decrypt_time)),
+                    GraphMetric::TransformCount => scalar(m.t_transform_count as f64),
+                    GraphMetric::SqrtAttempts => scalar(m.sqrt_attempts as f64),
+                };
+                GraphSeries {
+                    name: format!("#{} p={} a={}", i + 1, engine.p, engine.a),
+                    data,
+                }
+            })
+            .collect();
+
+        let (title, x_label, y_label, style) = match metric {
+            GraphMetric::OperationTime => (
+                "Operation Timeline",
+                "Operation Sequence",
+                "Time (ms)",
+                GraphStyle::Line,
+            ),
+            GraphMetric::StepTime => (
+                "T-transform Step Complexity",
+                "Step Index",
+                "Duration (µs)",
+                GraphStyle::Line,
+            ),
+            GraphMetric::KeygenTime => (
+                "Key Generation Time",
+                "Engine",
+                "Time (ms)",
+                GraphStyle::Histogram,
+            ),
+            GraphMetric::EncryptTime => (
+                "Encryption Time",
+                "Engine",
+                "Time (ms)",
+                GraphStyle::Histogram,
+            ),
+            GraphMetric::DecryptTime => (
+                "Decryption Time",
+                "Engine",
+                "Time (ms)",
+                GraphStyle::Histogram,
+            ),
+            GraphMetric::TransformCount => (
+                "T-transform Count",
+                "Engine",
+                "Transforms",
+                GraphStyle::Histogram,
+            ),
+            GraphMetric::SqrtAttempts => {
+                ("Sqrt Attempts", "Engine", "Attempts", GraphStyle::Histogram)
+            }
+        };
+
+        CryptoGraph {
+            title: format!("{} Comparison", title),
+            data: Vec::new(),
+            labels: [
+                ("x".to_string(), x_label.to_string()),
+                ("y".to_string(), y_label.to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style,
+            color: ColorMode::Auto,
+            strict: false,
+            series,
+        }
+    }
+
+    /// Renders graph to ASCII art
+    pub fn render_ascii(&self, width: usize, height: usize) -> Result<String, LaiCryptoError> {
+        match self.style {
+            GraphStyle::Braille => return self.render_braille(width, height),
+            GraphStyle::Heatmap => return self.render_heatmap(width, height),
+            _ => {}
+        }
+        if width < 3 || height < 3 {
+            return Err(LaiCryptoError::GraphError {
+                context: "render_ascii".to_string(),
+                cause: format!("Canvas {}x{} too small (min 3x3)", width, height),
+            });
+        }
+
+        let bounds = self.data_bounds("render_ascii")?;
+        let mut grid = vec![vec![' '; width]; height];
+        let mut colors: ColorGrid = vec![vec![None; width]; height];
+
+        // Plot data points
+        let series = self.all_series();
+        for (i, (_, data)) in series.iter().enumerate() {
+            let marker = match self.style {
+                _ if series.len() > 1 => SERIES_MARKERS[i % SERIES_MARKERS.len()],
+                GraphStyle::Scatter => '●',
+                GraphStyle::Line => '•',
+                GraphStyle::Histogram => '█',
+                GraphStyle::Braille | GraphStyle::Heatmap => '⣿',
+            };
+
+            for &(x, y) in data.iter() {
+                // Map into the cells inside the border
+                let col = ((x - bounds.min_x) / bounds.x_range() * (width - 3) as f64) as usize + 1;
+                let row = height
+                    - 2
+                    - ((y - bounds.min_y) / bounds.y_range() * (height - 3) as f64) as usize;
+
+                if row < height && col < width {
+                    grid[row][col] = marker;
+                    colors[row][col] = bounds
+                        .highlight(y)
+                        .or(Some(SERIES_COLORS[i % SERIES_COLORS.len()]));
+                }
+            }
+        }
+
+        self.draw_frame(&mut grid);
+        Ok(self.grid_to_string(grid, &colors))
+    }
+
+    /// Renders graph to ASCII art sized to the current terminal
+    ///
+    /// Falls back to [`DEFAULT_TERMINAL_SIZE`] when the terminal size is
+    /// unknown. One row is left free so the shell prompt does not scroll
+    /// the title off screen.
+    pub fn render_ascii_auto(&self) -> Result<String, LaiCryptoError> {
+        let (cols, rows) = terminal_size().unwrap_or(DEFAULT_TERMINAL_SIZE);
+        self.render_ascii(cols.max(3), rows.saturating_sub(1).max(3))
+    }
+
+    /// Renders graph using Braille cells for 8× the ASCII resolution
+    pub fn render_braille(&self, width: usize, height: usize) -> Result<String, LaiCryptoError> {
+        if width < 3 || height < 3 {
+            return Err(LaiCryptoError::GraphError {
+                context: "render_braille".to_string(),
+                cause: format!("Canvas {}x{} too small (min 3x3)", width, height),
+            });
+        }
+
+        let bounds = self.data_bounds("render_braille")?;
+        let cols = width - 2;
+        let rows = height - 2;
+        let dot_w = cols * 2;
+        let dot_h = rows * 4;
+        let mut cells = vec![vec![0u32; cols]; rows];
+        let mut colors: ColorGrid = vec![vec![None; width]; height];
+
+        for (i, (_, data)) in self.all_series().iter().enumerate() {
+            for &(x, y) in data.iter() {
+                let dx =
+                    ((x - bounds.min_x) / bounds.x_range() * (dot_w - 1) as f64).round() as usize;
+                let dy = dot_h
+                    - 1
+                    - ((y - bounds.min_y) / bounds.y_range() * (dot_h - 1) as f64).round() as usize;
+                cells[dy / 4][dx / 2] |= BRAILLE_DOTS[dy % 4][dx % 2];
+
+                let cell_color = &mut colors[dy / 4 + 1][dx / 2 + 1];
+                *cell_color = bounds
+                    .highlight(y)
+                    .or(*cell_color)
+                    .or(Some(SERIES_COLORS[i % SERIES_COLORS.len()]));
+            }
+        }
+
+        let mut grid = vec![vec![' '; width]; height];
+        for (row, cell_row) in cells.iter().enumerate() {
+            for (col, &bits) in cell_row.iter().enumerate() {
+                if bits != 0 {
+                    grid[row + 1][col + 1] = char::from_u32(0x2800 + bits).unwrap_or(' ');
+                }
+            }
+        }
+
+        self.draw_frame(&mut grid);
+        Ok(self.grid_to_string(grid, &colors))
+    }
+
+    /// Renders point density as shaded cells, darker where more points fall
+    pub fn render_heatmap(&self, width: usize, height: usize) -> Result<String, LaiCryptoError> {
+        const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+        if width < 3 || height < 3 {
+            return Err(LaiCryptoError::GraphError {
+                context: "render_heatmap".to_string(),
+                cause: format!("Canvas {}x{} too small (min 3x3)", width, height),
+            });
+        }
+
+        let bounds = self.data_bounds("render_heatmap")?;
+        let cols = width - 2;
+        let rows = height - 2;
+        let mut bins = vec![vec![0usize; cols]; rows];
+
+        for &(x, y) in self.all_series().iter().flat_map(|(_, data)| data.iter()) {
+            let col = ((x - bounds.min_x) / bounds.x_range() * (cols - 1) as f64).round() as usize;
+            let row = rows
+                - 1
+                - ((y - bounds.min_y) / bounds.y_range() * (rows - 1) as f64).round() as usize;
+            bins[row][col] += 1;
+        }
+
+        let max_count = bins.iter().flatten().copied().max().unwrap_or(0);
+        let mut grid = vec![vec![' '; width]; height];
+        let mut colors: ColorGrid = vec![vec![None; width]; height];
+        for (row, bin_row) in bins.iter().enumerate() {
+            for (col, &count) in bin_row.iter().enumerate() {
+                if count > 0 {
+                    let level = (count * SHADES.len()).div_ceil(max_count) - 1;
+                    grid[row + 1][col + 1] = SHADES[level];
+                    colors[row + 1][col + 1] = Some(if count == max_count {
+                        MAX_COLOR
+                    } else {
+                        SERIES_COLORS[0]
+                    });
+                }
+            }
+        }
+
+        self.draw_frame(&mut grid);
+        Ok(self.grid_to_string(grid, &colors))
+    }
+
+    /// Renders the y values of the first series as a single line of block characters
+    ///
+    /// Series longer than `width` are averaged into `width` buckets; shorter
+    /// series use one character per point.
+    pub fn render_sparkline(&self, width: usize) -> Result<String, LaiCryptoError> {
+        const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let data = match self.all_series().first() {
+            Some(&(_, data)) if !data.is_empty() && width > 0 => data,
+            _ => {
+                return Err(LaiCryptoError::GraphError {
+                    context: "render_sparkline".to_string(),
+                    cause: "No data to plot".to_string(),
+                })
+            }
+        };
+
+        let buckets = width.min(data.len());
+        let values: Vec<f64> = (0..buckets)
+            .map(|i| {
+                let start = i * data.len() / buckets;
+                let end = (i + 1) * data.len() / buckets;
+                let chunk = &data[start..end];
+                chunk.iter().map(|&(_, y)| y).sum::<f64>() / chunk.len() as f64
+            })
+            .collect();
+
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        let range = max - min;
+
+        Ok(values
+            .iter()
+            .map(|&v| {
+                let level = if range > 0.0 {
+                    ((v - min) / range * (BLOCKS.len() - 1) as f64).round() as usize
+                } else {
+                    0
+                };
+                BLOCKS[level]
+            })
+            .collect())
+    }
+
+    /// Compute the plotted data extent
+    fn data_bounds(&self, context: &str) -> Result<Bounds, LaiCryptoError> {
+        let series = self.all_series();
+        if series.iter().all(|(_, data)| data.is_empty()) {
+            return Err(LaiCryptoError::GraphError {
+                context: context.to_string(),
+                cause: "No data to plot".to_string(),
+            });
+        }
+
+        let mut bounds = Bounds {
+            min_x: f64::MAX,
+            max_x: f64::MIN,
+            min_y: f64::MAX,
+            max_y: f64::MIN,
+        };
+
+        for &(x, y) in series.iter().flat_map(|(_, data)| data.iter()) {
+            bounds.min_x = bounds.min_x.min(x);
+            bounds.max_x = bounds.max_x.max(x);
+            bounds.min_y = bounds.min_y.min(y);
+            bounds.max_y = bounds.max_y.max(y);
+        }
+
+        let flat = bounds.x_range() <= 0.0 || bounds.y_range() <= 0.0;
+        if flat && self.strict {
+            return Err(LaiCryptoError::GraphError {
+                context: context.to_string(),
+                cause: "Invalid data range".to_string(),
+            });
+        }
+
+        // Pad zero-width axes symmetrically so constant data renders centered
+        let pad = |v: f64| if v == 0.0 { 1.0 } else { v.abs() * 0.5 };
+        if bounds.x_range() <= 0.0 {
+            let p = pad(bounds.min_x);
+            bounds.min_x -= p;
+            bounds.max_x += p;
+        }
+        if bounds.y_range() <= 0.0 {
+            let p = pad(bounds.min_y);
+            bounds.min_y -= p;
+            bounds.max_y += p;
+        }
+
+        Ok(bounds)
+    }
+
+    /// Draw border, title and axis labels onto the grid
+    fn draw_frame(&self, grid: &mut [Vec<char>]) {
+        let height = grid.len();
+        let width = grid[0].len();
+
+        // Add border
+        for row in grid.iter_mut() {
+            row[0] = '|';
+            row[width - 1] = '|';
+        }
+        grid[0].fill('-');
+        grid[height - 1].fill('-');
+        grid[0][0] = '+';
+        grid[0][width - 1] = '+';
+        grid[height - 1][0] = '+';
+        grid[height - 1][width - 1] = '+';
+
+        // Add title, truncated to fit between the corners
+        let title = fit_label(&self.title, width - 2);
+        let title_pos = 1 + (width - 2 - title.len()) / 2;
+        for (i, &c) in title.iter().enumerate() {
+            grid[0][title_pos + i] = c;
+        }
+
+        // Add axis labels
+        if let Some(x_label) = self.labels.get("x") {
+            let label = fit_label(x_label, width - 2);
+            let label_pos = 1 + (width - 2 - label.len()) / 2;
+            for (i, &c) in label.iter().enumerate() {
+                grid[height - 1][label_pos + i] = c;
+            }
+        }
+
+        // y label runs vertically down the left border
+        if let Some(y_label) = self.labels.get("y") {
+            let label = fit_label(y_label, height - 2);
+            let label_pos = 1 + (height - 2 - label.len()) / 2;
+            for (i, &c) in label.iter().enumerate() {
+                grid[label_pos + i][0] = c;
+            }
+        }
+    }
+
+    /// Build output string from a character grid, applying ANSI colors if enabled
+    fn grid_to_string(&self, grid: Vec<Vec<char>>, colors: &ColorGrid) -> String {
+        let use_color = self.color.enabled();
+        let mut result = String::new();
+        for (row, color_row) in grid.into_iter().zip(colors) {
+            for (c, color) in row.into_iter().zip(color_row) {
+                match color {
+                    Some(code) if use_color => {
+                        result.push_str(code);
+                        result.push(c);
+                        result.push_str(ANSI_RESET);
+                    }
+                    _ => result.push(c),
+                }
+            }
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Largest-Triangle-Three-Buckets downsampling
+fn lttb(data: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    if threshold >= data.len() || threshold < 3 {
+        return data.to_vec();
+    }
+
+    let mut sampled = Vec::with_capacity(threshold);
+    let bucket_size = (data.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut a = 0;
+    sampled.push(data[0]);
+
+    for i in 0..threshold - 2 {
+        // Average of the next bucket is the third triangle vertex
+        let next_start = ((i + 1) as f64 * bucket_size) as usize + 1;
+        let next_end = (((i + 2) as f64 * bucket_size) as usize + 1).min(data.len());
+        let next = &data[next_start..next_end];
+        let avg_x = next.iter().map(|p| p.0).sum::<f64>() / next.len() as f64;
+        let avg_y = next.iter().map(|p| p.1).sum::<f64>() / next.len() as f64;
+
+        let start = (i as f64 * bucket_size) as usize + 1;
+        let end = next_start;
+        let (ax, ay) = data[a];
+        let mut best = start;
+        let mut best_area = -1.0;
+        for (j, &(x, y)) in data.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs();
+            if area > best_area {
+                best_area = area;
+                best = j;
+            }
+        }
+        sampled.push(data[best]);
+        a = best;
+    }
+
+    sampled.push(data[data.len() - 1]);
+    sampled
+}
+
+/// Keep the minimum and maximum y point of each bucket, in x order
+fn min_max_buckets(data: &[(f64, f64)], buckets: usize) -> Vec<(f64, f64)> {
+    if buckets == 0 || buckets * 2 >= data.len() {
+        return data.to_vec();
+    }
+
+    let mut sampled = Vec::with_capacity(buckets * 2);
+    for i in 0..buckets {
+        let chunk = &data[i * data.len() / buckets..(i + 1) * data.len() / buckets];
+        let min = (0..chunk.len()).fold(0, |m, j| if chunk[j].1 < chunk[m].1 { j } else { m });
+        let max = (0..chunk.len()).fold(0, |m, j| if chunk[j].1 > chunk[m].1 { j } else { m });
+        sampled.push(chunk[min.min(max)]);
+        if min != max {
+            sampled.push(chunk[min.max(max)]);
+        }
+    }
+    sampled
+}
+
+/// Truncate a label to at most `max` characters, marking the cut with an ellipsis
+fn fit_label(text: &str, max: usize) -> Vec<char> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max {
+        chars
+    } else if max == 0 {
+        Vec::new()
+    } else {
+        let mut fitted = chars[..max - 1].to_vec();
+        fitted.push('…');
+        fitted
+    }
+}
+
+/// Fallback canvas size when the terminal cannot be queried
+pub const DEFAULT_TERMINAL_SIZE: (usize, usize) = (80, 24);
+
+/// Query the terminal dimensions as (columns, rows)
+///
+/// Uses the `TIOCGWINSZ` ioctl on stdout for Unix terminals, then the
+/// `COLUMNS`/`LINES` environment variables. Returns `None` if neither is
+/// available, e.g. when output is redirected to a file.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    #[cfg(unix)]
+    {
+        if io::stdout().is_terminal() {
+            let mut ws = libc::winsize {
+                ws_row: 0,
+                ws_col: 0,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+            // SAFETY: TIOCGWINSZ only writes into the provided winsize struct
+            let rc = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) };
+            if rc == 0 && ws.ws_col > 0 && ws.ws_row > 0 {
+                return Some((ws.ws_col as usize, ws.ws_row as usize));
+            }
+        }
+    }
+
+    let env_dim = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&v| v > 0)
+    };
+    Some((env_dim("COLUMNS")?, env_dim("LINES")?))
+}
+
+/// Stroke/fill colors assigned to data series in SVG output
+#[cfg(feature = "plot-svg")]
+pub const SVG_SERIES_COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+];
+
+/// Pixel layout shared by the vector and raster graph exporters
+#[cfg(feature = "plot-svg")]
+#[derive(Debug, Clone, Copy)]
+struct PlotLayout {
+    width: f64,
+    height: f64,
+    left: f64,
+    right: f64,
+    top: f64,
+    bottom: f64,
+    bounds: Bounds,
+}
+
+#[cfg(feature = "plot-svg")]
+impl PlotLayout {
+    const TICKS: usize = 5;
+
+    fn new(bounds: Bounds, width: usize, height: usize) -> Self {
+        let (width, height) = (width as f64, height as f64);
+        Self {
+            width,
+            height,
+            left: 70.0,
+            right: width - 20.0,
+            top: 40.0,
+            bottom: height - 50.0,
+            bounds,
+        }
+    }
+
+    /// Map a data point to pixel coordinates
+    fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        let px =
+            self.left + (x - self.bounds.min_x) / self.bounds.x_range() * (self.right - self.left);
+        let py = self.bottom
+            - (y - self.bounds.min_y) / self.bounds.y_range() * (self.bottom - self.top);
+        (px, py)
+    }
+
+    /// Evenly spaced tick values between `min` and `max`
+    fn ticks(min: f64, max: f64) -> Vec<f64> {
+        (0..Self::TICKS)
+            .map(|i| min + (max - min) * i as f64 / (Self::TICKS - 1) as f64)
+            .collect()
+    }
+
+    fn x_ticks(&self) -> Vec<f64> {
+        Self::ticks(self.bounds.min_x, self.bounds.max_x)
+    }
+
+    fn y_ticks(&self) -> Vec<f64> {
+        Self::ticks(self.bounds.min_y, self.bounds.max_y)
+    }
+}
+
+#[cfg(feature = "plot-svg")]
+impl CryptoGraph {
+    /// Renders graph to a standalone SVG document
+    pub fn render_svg(&self, width: usize, height: usize) -> Result<String, LaiCryptoError> {
+        if width < 200 || height < 150 {
+            return Err(LaiCryptoError::GraphError {
+                context: "render_svg".to_string(),
+                cause: format!("Canvas {}x{} too small (min 200x150)", width, height),
+            });
+        }
+
+        let layout = PlotLayout::new(self.data_bounds("render_svg")?, width, height);
+        let mut svg = String::new();
+
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+            w = width,
+            h = height
+        ));
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n");
+
+        // Title
+        if !self.title.is_empty() {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"24\" text-anchor=\"middle\" font-size=\"16\" font-weight=\"bold\">{}</text>\n",
+                layout.width / 2.0,
+                xml_escape(&self.title)
+            ));
+        }
+
+        // Axes and ticks
+        svg.push_str(&format!(
+            "<path d=\"M{l:.1},{t:.1} V{b:.1} H{r:.1}\" fill=\"none\" stroke=\"#333333\"/>\n",
+            l = layout.left,
+            t = layout.top,
+            b = layout.bottom,
+            r = layout.right
+        ));
+        for tick in layout.x_ticks() {
+            let (px, _) = layout.map(tick, layout.bounds.min_y);
+            svg.push_str(&format!(
+                "<line x1=\"{px:.1}\" y1=\"{b:.1}\" x2=\"{px:.1}\" y2=\"{b5:.1}\" stroke=\"#333333\"/>\n<text x=\"{px:.1}\" y=\"{b18:.1}\" text-anchor=\"middle\">{v}</text>\n",
+                px = px,
+                b = layout.bottom,
+                b5 = layout.bottom + 5.0,
+                b18 = layout.bottom + 18.0,
+                v = format_tick(tick)
+            ));
+        }
+        for tick in layout.y_ticks() {
+            let (_, py) = layout.map(layout.bounds.min_x, tick);
+            svg.push_str(&format!(
+                "<line x1=\"{l5:.1}\" y1=\"{py:.1}\" x2=\"{l:.1}\" y2=\"{py:.1}\" stroke=\"#333333\"/>\n<text x=\"{l8:.1}\" y=\"{py4:.1}\" text-anchor=\"end\">{v}</text>\n",
+                l5 = layout.left - 5.0,
+                l = layout.left,
+                l8 = layout.left - 8.0,
+                py = py,
+                py4 = py + 4.0,
+                v = format_tick(tick)
+            ));
+        }
+
+        // Axis labels
+        if let Some(x_label) = self.labels.get("x") {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+                (layout.left + layout.right) / 2.0,
+                layout.height - 12.0,
+                xml_escape(x_label)
+            ));
+        }
+        if let Some(y_label) = self.labels.get("y") {
+            let cy = (layout.top + layout.bottom) / 2.0;
+            svg.push_str(&format!(
+                "<text x=\"16\" y=\"{cy:.1}\" text-anchor=\"middle\" transform=\"rotate(-90 16 {cy:.1})\">{}</text>\n",
+                xml_escape(y_label),
+                cy = cy
+            ));
+        }
+
+        // Data
+        let series = self.all_series();
+        let group_width = (layout.right - layout.left) / self.max_series_len() as f64 * 0.8;
+        let bar_width = (group_width / series.len() as f64).max(1.0);
+        for (i, (_, data)) in series.iter().enumerate() {
+            let color = SVG_SERIES_COLORS[i % SVG_SERIES_COLORS.len()];
+            match self.style {
+                GraphStyle::Line => {
+                    let points: Vec<String> = data
+                        .iter()
+                        .map(|&(x, y)| {
+                            let (px, py) = layout.map(x, y);
+                            format!("{:.1},{:.1}", px, py)
+                        })
+                        .collect();
+                    svg.push_str(&format!(
+                        "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>\n",
+                        points.join(" "),
+                        color
+                    ));
+                }
+                GraphStyle::Histogram => {
+                    for &(x, y) in data.iter() {
+                        let (px, py) = layout.map(x, y);
+                        svg.push_str(&format!(
+                            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                            px - group_width / 2.0 + i as f64 * bar_width,
+                            py,
+                            bar_width,
+                            layout.bottom - py,
+                            color
+                        ));
+                    }
+                }
+                GraphStyle::Scatter | GraphStyle::Braille | GraphStyle::Heatmap => {
+                    for &(x, y) in data.iter() {
+                        let (px, py) = layout.map(x, y);
+                        svg.push_str(&format!(
+                            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2.5\" fill=\"{}\"/>\n",
+                            px, py, color
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Legend
+        for (i, (name, _)) in series.iter().enumerate() {
+            let y = layout.top - 2.0 + i as f64 * 16.0;
+            svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"10\" height=\"10\" fill=\"{}\"/>\n<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                layout.right - 110.0,
+                y,
+                SVG_SERIES_COLORS[i % SVG_SERIES_COLORS.len()],
+                layout.right - 95.0,
+                y + 9.0,
+                xml_escape(name)
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+/// Escape text for embedding in XML/HTML
+#[cfg(any(feature = "plot-svg", feature = "plot-html"))]
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format an axis tick value compactly
+#[cfg(feature = "plot-svg")]
+fn format_tick(value: f64) -> String {
+    if value.abs() >= 1000.0 || value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Colors used by the raster graph exporter
+#[cfg(feature = "plot-png")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphTheme {
+    pub background: [u8; 3],
+    pub foreground: [u8; 3],
+    pub series: [u8; 3],
+}
+
+#[cfg(feature = "plot-png")]
+impl GraphTheme {
+    /// Dark series on a white background
+    pub fn light() -> Self {
+        Self {
+            background: [255, 255, 255],
+            foreground: [51, 51, 51],
+            series: [31, 119, 180],
+        }
+    }
+
+    /// Light series on a near-black background
+    pub fn dark() -> Self {
+        Self {
+            background: [24, 24, 24],
+            foreground: [220, 220, 220],
+            series: [255, 159, 64],
+        }
+    }
+}
+
+#[cfg(feature = "plot-png")]
+impl GraphTheme {
+    /// Color of the `index`-th series; the first uses the theme's series color
+    pub fn series_color(&self, index: usize) -> [u8; 3] {
+        const EXTRA: [[u8; 3]; 5] = [
+            [255, 127, 14],
+            [44, 160, 44],
+            [214, 39, 40],
+            [148, 103, 189],
+            [140, 86, 75],
+        ];
+        match index {
+            0 => self.series,
+            i => EXTRA[(i - 1) % EXTRA.len()],
+        }
+    }
+}
+
+#[cfg(feature = "plot-png")]
+impl Default for GraphTheme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+/// 3×5 bitmap glyphs, one bit per pixel, rows top to bottom
+#[cfg(feature = "plot-png")]
+fn glyph(c: char) -> u16 {
+    match c.to_ascii_uppercase() {
+        '0' => 0b111_101_101_101_111,
+        '1' => 0b010_110_010_010_111,
+        '2' => 0b111_001_111_100_111,
+        '3' => 0b111_001_111_001_111,
+        '4' => 0b101_101_111_001_001,
+        '5' => 0b111_100_111_001_111,
+        '6' => 0b111_100_111_101_111,
+        '7' => 0b111_001_010_010_010,
+        '8' => 0b111_101_111_101_111,
+        '9' => 0b111_101_111_001_111,
+        'A' => 0b010_101_111_101_101,
+        'B' => 0b110_101_110_101_110,
+        'C' => 0b011_100_100_100_011,
+        'D' => 0b110_101_101_101_110,
+        'E' => 0b111_100_110_100_111,
+        'F' => 0b111_100_110_100_100,
+        'G' => 0b011_100_101_101_011,
+        'H' => 0b101_101_111_101_101,
+        'I' => 0b111_010_010_010_111,
+        'J' => 0b001_001_001_101_010,
+        'K' => 0b101_101_110_101_101,
+        'L' => 0b100_100_100_100_111,
+        'M' => 0b101_111_111_101_101,
+        'N' => 0b110_101_101_101_101,
+        'O' => 0b010_101_101_101_010,
+        'P' => 0b110_101_110_100_100,
+        'Q' => 0b010_101_101_110_011,
+        'R' => 0b110_101_110_101_101,
+        'S' => 0b011_100_010_001_110,
+        'T' => 0b111_010_010_010_010,
+        'U' => 0b101_101_101_101_111,
+        'V' => 0b101_101_101_101_010,
+        'W' => 0b101_101_111_111_101,
+        'X' => 0b101_101_010_101_101,
+        'Y' => 0b101_101_010_010_010,
+        'Z' => 0b111_001_010_100_111,
+        '.' => 0b000_000_000_000_010,
+        ',' => 0b000_000_000_010_100,
+        ':' => 0b000_010_000_010_000,
+        '-' => 0b000_000_111_000_000,
+        '_' => 0b000_000_000_000_111,
+        '/' => 0b001_001_010_100_100,
+        '(' => 0b010_100_100_100_010,
+        ')' => 0b010_001_001_001_010,
+        '%' => 0b101_001_010_100_101,
+        '<' => 0b001_010_100_010_001,
+        '>' => 0b100_010_001_010_100,
+        '=' => 0b000_111_000_111_000,
+        '+' => 0b000_010_111_010_000,
+        _ => 0,
+    }
+}
+
+/// RGB pixel buffer with basic drawing primitives
+#[cfg(feature = "plot-png")]
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+#[cfg(feature = "plot-png")]
+impl Raster {
+    fn new(width: usize, height: usize, background: [u8; 3]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = color;
+        }
+    }
+
+    /// Bresenham line between two pixel coordinates
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: [u8; 3]) {
+        let (mut x0, mut y0) = (from.0.round() as i64, from.1.round() as i64);
+        let (x1, y1) = (to.0.round() as i64, to.1.round() as i64);
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        loop {
+            self.set(x0, y0, color);
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: [u8; 3]) {
+        for py in y.round() as i64..(y + h).round() as i64 {
+            for px in x.round() as i64..(x + w).round() as i64 {
+                self.set(px, py, color);
+            }
+        }
+    }
+
+    fn fill_circle(&mut self, cx: f64, cy: f64, r: f64, color: [u8; 3]) {
+        let (cx, cy, r) = (cx.round() as i64, cy.round() as i64, r.round() as i64);
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if dx * dx + dy * dy <= r * r {
+                    self.set(cx + dx, cy + dy, color);
+                }
+            }
+        }
+    }
+
+    /// Draw text with the built-in 3×5 font, anchored at its top-left corner
+    fn text(&mut self, x: f64, y: f64, text: &str, scale: i64, color: [u8; 3]) {
+        let (x, y) = (x.round() as i64, y.round() as i64);
+        for (i, c) in text.chars().enumerate() {
+            let bits = glyph(c);
+            let ox = x + i as i64 * 4 * scale;
+            for row in 0..5 {
+                for col in 0..3 {
+                    if bits & (1 << (14 - (row * 3 + col))) != 0 {
+                        for sy in 0..scale {
+                            for sx in 0..scale {
+                                self.set(ox + col * scale + sx, y + row * scale + sy, color);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pixel width of `text` rendered at `scale`
+    fn text_width(text: &str, scale: i64) -> f64 {
+        (text.chars().count() as i64 * 4 * scale) as f64
+    }
+
+    /// Encode as an 8-bit RGB PNG with uncompressed deflate blocks
+    fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0); // filter: none
+            for px in row {
+                raw.extend_from_slice(px);
+            }
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xffff).peekable();
+        while let Some(block) = blocks.next() {
+            zlib.push(if blocks.peek().is_none() { 1 } else { 0 });
+            let len = block.len() as u16;
+            zlib.extend_from_slice(&len.to_le_bytes());
+            zlib.extend_from_slice(&(!len).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        png_chunk(&mut png, b"IHDR", &ihdr);
+        png_chunk(&mut png, b"IDAT", &zlib);
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+#[cfg(feature = "plot-png")]
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+#[cfg(feature = "plot-png")]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(feature = "plot-png")]
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(feature = "plot-png")]
+impl CryptoGraph {
+    /// Renders graph to PNG bytes using the SVG layout
+    pub fn render_png(
+        &self,
+        width: usize,
+        height: usize,
+        theme: &GraphTheme,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        Ok(self.rasterize("render_png", width, height, theme)?.to_png())
+    }
+
+    /// Draw the graph into a pixel buffer
+    fn rasterize(
+        &self,
+        context: &str,
+        width: usize,
+        height: usize,
+        theme: &GraphTheme,
+    ) -> Result<Raster, LaiCryptoError> {
+        if width < 200 || height < 150 {
+            return Err(LaiCryptoError::GraphError {
+                context: context.to_string(),
+                cause: format!("Canvas {}x{} too small (min 200x150)", width, height),
+            });
+        }
+
+        let layout = PlotLayout::new(self.data_bounds(context)?, width, height);
+        let mut raster = Raster::new(width, height, theme.background);
+        let fg = theme.foreground;
+
+        // Title
+        if !self.title.is_empty() {
+            let x = (layout.width - Raster::text_width(&self.title, 2)) / 2.0;
+            raster.text(x, 14.0, &self.title, 2, fg);
+        }
+
+        // Axes and ticks
+        raster.line((layout.left, layout.top), (layout.left, layout.bottom), fg);
+        raster.line(
+            (layout.left, layout.bottom),
+            (layout.right, layout.bottom),
+            fg,
+        );
+        for tick in layout.x_ticks() {
+            let (px, _) = layout.map(tick, layout.bounds.min_y);
+            let label = format_tick(tick);
+            raster.line((px, layout.bottom), (px, layout.bottom + 5.0), fg);
+            raster.text(
+                px - Raster::text_width(&label, 1) / 2.0,
+                layout.bottom + 9.0,
+                &label,
+                1,
+                fg,
+            );
+        }
+        for tick in layout.y_ticks() {
+            let (_, py) = layout.map(layout.bounds.min_x, tick);
+            let label = format_tick(tick);
+            raster.line((layout.left - 5.0, py), (layout.left, py), fg);
+            raster.text(
+                layout.left - 8.0 - Raster::text_width(&label, 1),
+                py - 2.0,
+                &label,
+                1,
+                fg,
+            );
+        }
+
+        // Axis labels
+        if let Some(x_label) = self.labels.get("x") {
+            let x = (layout.left + layout.right - Raster::text_width(x_label, 1)) / 2.0;
+            raster.text(x, layout.height - 18.0, x_label, 1, fg);
+        }
+        if let Some(y_label) = self.labels.get("y") {
+            raster.text(4.0, layout.top - 14.0, y_label, 1, fg);
+        }
+
+        // Data
+        let series = self.all_series();
+        let group_width = (layout.right - layout.left) / self.max_series_len() as f64 * 0.8;
+        let bar_width = (group_width / series.len() as f64).max(1.0);
+        for (i, (_, data)) in series.iter().enumerate() {
+            let color = theme.series_color(i);
+            match self.style {
+                GraphStyle::Line => {
+                    for pair in data.windows(2) {
+                        let from = layout.map(pair[0].0, pair[0].1);
+                        let to = layout.map(pair[1].0, pair[1].1);
+                        raster.line(from, to, color);
+                    }
+                }
+                GraphStyle::Histogram => {
+                    for &(x, y) in data.iter() {
+                        let (px, py) = layout.map(x, y);
+                        let left = px - group_width / 2.0 + i as f64 * bar_width;
+                        raster.fill_rect(left, py, bar_width, layout.bottom - py, color);
+                    }
+                }
+                GraphStyle::Scatter | GraphStyle::Braille | GraphStyle::Heatmap => {
+                    for &(x, y) in data.iter() {
+                        let (px, py) = layout.map(x, y);
+                        raster.fill_circle(px, py, 2.0, color);
+                    }
+                }
+            }
+        }
+
+        // Legend
+        for (i, (name, _)) in series.iter().enumerate() {
+            let y = layout.top - 2.0 + i as f64 * 16.0;
+            raster.fill_rect(layout.right - 110.0, y, 10.0, 10.0, theme.series_color(i));
+            raster.text(layout.right - 95.0, y + 2.0, name, 1, fg);
+        }
+
+        Ok(raster)
+    }
+}
+
+/// Pixel size assumed for one terminal character cell when drawing sixels
+#[cfg(feature = "plot-sixel")]
+pub const SIXEL_CELL_SIZE: (usize, usize) = (10, 20);
+
+/// Whether the current terminal advertises sixel graphics support
+///
+/// Detection is environment based: `LAI_SIXEL=1`/`0` forces the result,
+/// otherwise `TERM`/`TERM_PROGRAM` are matched against terminals known to
+/// ship sixel support. Output must also be a TTY.
+#[cfg(feature = "plot-sixel")]
+pub fn sixel_supported() -> bool {
+    if let Some(forced) = std::env::var_os("LAI_SIXEL") {
+        return forced == "1";
+    }
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+
+    let term = std::env::var("TERM").unwrap_or_default();
+    let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+    term.contains("sixel")
+        || ["mlterm", "yaft", "foot", "contour"]
+            .iter()
+            .any(|t| term.starts_with(t))
+        || ["WezTerm", "mintty", "iTerm.app"].contains(&program.as_str())
+}
+
+#[cfg(feature = "plot-sixel")]
+impl Raster {
+    /// Encode as a DEC sixel escape sequence
+    fn to_sixel(&self) -> String {
+        let mut palette: Vec<[u8; 3]> = Vec::new();
+        for px in &self.pixels {
+            if !palette.contains(px) {
+                palette.push(*px);
+            }
+        }
+
+        let mut out = format!("\x1bPq\"1;1;{};{}", self.width, self.height);
+        for (i, rgb) in palette.iter().enumerate() {
+            let pct = |c: u8| c as u32 * 100 / 255;
+            out.push_str(&format!(
+                "#{};2;{};{};{}",
+                i,
+                pct(rgb[0]),
+                pct(rgb[1]),
+                pct(rgb[2])
+            ));
+        }
+
+        for band in (0..self.height).step_by(6) {
+            for (i, rgb) in palette.iter().enumerate() {
+                let row: Vec<u8> = (0..self.width)
+                    .map(|x| {
+                        (0..6)
+                            .filter(|dy| band + dy < self.height)
+                            .filter(|dy| self.pixels[(band + dy) * self.width + x] == *rgb)
+                            .fold(0u8, |bits, dy| bits | (1 << dy))
+                    })
+                    .collect();
+                if row.iter().all(|&bits| bits == 0) {
+                    continue;
+                }
+
+                out.push_str(&format!("#{}", i));
+                let mut x = 0;
+                while x < row.len() {
+                    let run = row[x..].iter().take_while(|&&b| b == row[x]).count();
+                    let c = (b'?' + row[x]) as char;
+                    if run > 3 {
+                        out.push_str(&format!("!{}{}", run, c));
+                    } else {
+                        out.extend(std::iter::repeat_n(c, run));
+                    }
+                    x += run;
+                }
+                out.push('$');
+            }
+            out.push('-');
+        }
+
+        out.push_str("\x1b\\");
+        out
+    }
+}
+
+#[cfg(feature = "plot-sixel")]
+impl CryptoGraph {
+    /// Renders graph as a sixel image sized in pixels
+    pub fn render_sixel(
+        &self,
+        width: usize,
+        height: usize,
+        theme: &GraphTheme,
+    ) -> Result<String, LaiCryptoError> {
+        Ok(self
+            .rasterize("render_sixel", width, height, theme)?
+            .to_sixel())
+    }
+
+    /// Renders graph for inline terminal display, sized in character cells
+    ///
+    /// Emits sixel graphics when [`sixel_supported`] reports support and
+    /// falls back to [`CryptoGraph::render_ascii`] otherwise.
+    pub fn render_terminal(
+        &self,
+        cols: usize,
+        rows: usize,
+        theme: &GraphTheme,
+    ) -> Result<String, LaiCryptoError> {
+        if sixel_supported() {
+            let (cell_w, cell_h) = SIXEL_CELL_SIZE;
+            self.render_sixel(cols * cell_w, rows * cell_h, theme)
+        } else {
+            self.render_ascii(cols, rows)
+        }
+    }
+}
+
+/// Canvas drawing and zoom/pan/hover handling for the HTML export
+#[cfg(feature = "plot-html")]
+const HTML_CHART_SCRIPT: &str = r#"
+(function () {
+  const canvas = document.getElementById('chart');
+  const tip = document.getElementById('tip');
+  const ctx = canvas.getContext('2d');
+  const pad = { l: 70, r: 20, t: 20, b: 40 };
+  const COLORS = ['#1f77b4', '#ff7f0e', '#2ca02c', '#d62728', '#9467bd', '#8c564b'];
+  const DATA = [].concat(...SERIES.map(s => s.data));
+  const xs = DATA.map(p => p[0]);
+  const full = [Math.min(...xs), Math.max(...xs)];
+  let view = full.slice();
+  let drag = null;
+
+  function sx(x) { return pad.l + (x - view[0]) / (view[1] - view[0] || 1) * (canvas.width - pad.l - pad.r); }
+  function inView(data) { return data.filter(p => p[0] >= view[0] && p[0] <= view[1]); }
+  function visible() { return inView(DATA); }
+  function yRange(pts) {
+    const v = pts.map(p => p[1]);
+    const lo = Math.min(...v), hi = Math.max(...v);
+    return lo === hi ? [lo - 1, hi + 1] : [lo, hi];
+  }
+
+  function draw() {
+    const pts = visible();
+    const [lo, hi] = pts.length ? yRange(pts) : yRange(DATA);
+    const sy = y => canvas.height - pad.b - (y - lo) / (hi - lo) * (canvas.height - pad.t - pad.b);
+    ctx.clearRect(0, 0, canvas.width, canvas.height);
+    ctx.strokeStyle = '#333'; ctx.fillStyle = '#333'; ctx.font = '12px sans-serif';
+    ctx.beginPath();
+    ctx.moveTo(pad.l, pad.t); ctx.lineTo(pad.l, canvas.height - pad.b); ctx.lineTo(canvas.width - pad.r, canvas.height - pad.b);
+    ctx.stroke();
+    for (let i = 0; i <= 4; i++) {
+      const xv = view[0] + (view[1] - view[0]) * i / 4, yv = lo + (hi - lo) * i / 4;
+      ctx.textAlign = 'center'; ctx.fillText(+xv.toFixed(2), sx(xv), canvas.height - pad.b + 16);
+      ctx.textAlign = 'right'; ctx.fillText(+yv.toFixed(2), pad.l - 6, sy(yv) + 4);
+    }
+    ctx.textAlign = 'center'; ctx.fillText(LABELS.x, canvas.width / 2, canvas.height - 6);
+    ctx.save(); ctx.translate(14, canvas.height / 2); ctx.rotate(-Math.PI / 2); ctx.fillText(LABELS.y, 0, 0); ctx.restore();
+    SERIES.forEach((s, n) => {
+      const color = COLORS[n % COLORS.length];
+      const spts = inView(s.data);
+      ctx.strokeStyle = color; ctx.fillStyle = color;
+      if (STYLE === 'line') {
+        ctx.beginPath();
+        spts.forEach((p, i) => i ? ctx.lineTo(sx(p[0]), sy(p[1])) : ctx.moveTo(sx(p[0]), sy(p[1])));
+        ctx.stroke();
+      } else {
+        spts.forEach(p => ctx.fillRect(sx(p[0]) - 2, sy(p[1]) - 2, 4, 4));
+      }
+      ctx.fillRect(canvas.width - pad.r - 150, pad.t + n * 16, 10, 10);
+      ctx.fillStyle = '#333'; ctx.textAlign = 'left';
+      ctx.fillText(s.name, canvas.width - pad.r - 134, pad.t + n * 16 + 9);
+    });
+    canvas.sy = sy;
+  }
+
+  canvas.addEventListener('wheel', e => {
+    e.preventDefault();
+    const w = view[1] - view[0];
+    const at = view[0] + (e.offsetX - pad.l) / (canvas.width - pad.l - pad.r) * w;
+    const k = e.deltaY < 0 ? 0.8 : 1.25;
+    view = [at - (at - view[0]) * k, at + (view[1] - at) * k];
+    draw();
+  });
+  canvas.addEventListener('mousedown', e => { drag = { x: e.offsetX, view: view.slice() }; });
+  window.addEventListener('mouseup', () => { drag = null; });
+  canvas.addEventListener('dblclick', () => { view = full.slice(); draw(); });
+  canvas.addEventListener('mousemove', e => {
+    if (drag) {
+      const dx = (e.offsetX - drag.x) / (canvas.width - pad.l - pad.r) * (drag.view[1] - drag.view[0]);
+      view = [drag.view[0] - dx, drag.view[1] - dx];
+      draw();
+      return;
+    }
+    let best = null, bestD = 64;
+    SERIES.forEach(s => inView(s.data).forEach(p => {
+      const d = Math.abs(sx(p[0]) - e.offsetX) + Math.abs(canvas.sy(p[1]) - e.offsetY);
+      if (d < bestD) { best = { name: s.name, p: p }; bestD = d; }
+    }));
+    tip.textContent = best ? `${best.name} | ${LABELS.x}: ${best.p[0]}  ${LABELS.y}: ${best.p[1]}` : '';
+  });
+  draw();
+})();
+"#;
+
+#[cfg(feature = "plot-html")]
+impl CryptoGraph {
+    /// Renders graph to a standalone interactive HTML page
+    ///
+    /// The chart script is embedded inline, so the file works offline. Scroll
+    /// to zoom, drag to pan, double-click to reset and hover to inspect points.
+    pub fn render_html(&self) -> Result<String, LaiCryptoError> {
+        let mut total = 0;
+        let series: Vec<String> = self
+            .all_series()
+            .iter()
+            .map(|(name, data)| {
+                let points: Vec<String> = data
+                    .iter()
+                    .filter(|(x, y)| x.is_finite() && y.is_finite())
+                    .map(|(x, y)| format!("[{},{}]", x, y))
+                    .collect();
+                total += points.len();
+                format!(
+                    "{{ name: {}, data: [{}] }}",
+                    json_string(name),
+                    points.join(",")
+                )
+            })
+            .collect();
+        if total == 0 {
+            return Err(LaiCryptoError::GraphError {
+                context: "render_html".to_string(),
+                cause: "No data to plot".to_string(),
+            });
+        }
+
+        let label =
+            |axis: &str| json_string(self.labels.get(axis).map(String::as_str).unwrap_or(axis));
+        let style = match self.style {
+            GraphStyle::Line => "line",
+            _ => "points",
+        };
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>body{{font-family:sans-serif;margin:20px}}#tip{{height:1.2em;color:#555}}canvas{{border:1px solid #ccc;cursor:crosshair}}</style>\n\
+             </head>\n<body>\n<h2>{title}</h2>\n<canvas id=\"chart\" width=\"960\" height=\"480\"></canvas>\n<div id=\"tip\"></div>\n\
+             <script>\nconst SERIES = [{series}];\nconst LABELS = {{ x: {x}, y: {y} }};\nconst STYLE = \"{style}\";\n{script}</script>\n</body>\n</html>\n",
+            title = xml_escape(&self.title),
+            series = series.join(",\n"),
+            x = label("x"),
+            y = label("y"),
+            style = style,
+            script = HTML_CHART_SCRIPT,
+        ))
+    }
+}
+
+/// Pluggable rendering backend for [`CryptoGraph`]
+///
+/// Implement this to add output formats outside the crate; the built-in
+/// backends below wrap the `render_*` methods.
+pub trait GraphRenderer {
+    /// Rendered output, e.g. `String` for text formats or `Vec<u8>` for images
+    type Output;
+
+    /// Render the graph with this backend
+    fn render(&self, graph: &CryptoGraph) -> Result<Self::Output, LaiCryptoError>;
+}
+
+/// Character-cell renderer honouring the graph's own style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsciiRenderer {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GraphRenderer for AsciiRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_ascii(self.width, self.height)
+    }
+}
+
+/// Braille renderer regardless of the graph's style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrailleRenderer {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GraphRenderer for BrailleRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_braille(self.width, self.height)
+    }
+}
+
+/// Single-line sparkline renderer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparklineRenderer {
+    pub width: usize,
+}
+
+impl GraphRenderer for SparklineRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_sparkline(self.width)
+    }
+}
+
+/// SVG document renderer
+#[cfg(feature = "plot-svg")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvgRenderer {
+    pub width: usize,
+    pub height: usize,
+}
+
+#[cfg(feature = "plot-svg")]
+impl GraphRenderer for SvgRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_svg(self.width, self.height)
+    }
+}
+
+/// PNG image renderer
+#[cfg(feature = "plot-png")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PngRenderer {
+    pub width: usize,
+    pub height: usize,
+    pub theme: GraphTheme,
+}
+
+#[cfg(feature = "plot-png")]
+impl GraphRenderer for PngRenderer {
+    type Output = Vec<u8>;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<Vec<u8>, LaiCryptoError> {
+        graph.render_png(self.width, self.height, &self.theme)
+    }
+}
+
+/// Sixel escape-sequence renderer
+#[cfg(feature = "plot-sixel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixelRenderer {
+    pub width: usize,
+    pub height: usize,
+    pub theme: GraphTheme,
+}
+
+#[cfg(feature = "plot-sixel")]
+impl GraphRenderer for SixelRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_sixel(self.width, self.height, &self.theme)
+    }
+}
+
+/// Standalone interactive HTML renderer
+#[cfg(feature = "plot-html")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HtmlRenderer;
+
+#[cfg(feature = "plot-html")]
+impl GraphRenderer for HtmlRenderer {
+    type Output = String;
+
+    fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+        graph.render_html()
+    }
+}
+
+impl CryptoGraph {
+    /// Render with any [`GraphRenderer`] backend
+    pub fn render_with<R: GraphRenderer + ?Sized>(
+        &self,
+        renderer: &R,
+    ) -> Result<R::Output, LaiCryptoError> {
+        renderer.render(self)
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Generate performance graphs
+    pub fn generate_perf_graph(&self, style: GraphStyle) -> CryptoGraph {
+        let mut data = Vec::new();
+        for (i, (_, duration)) in self.metrics.operation_history.iter().enumerate() {
+            data.push((i as f64, duration.as_secs_f64() * 1000.0)); // ms
+        }
+
+        CryptoGraph {
+            title: "Operation Timeline".to_string(),
+            data,
+            labels: [
+                ("x".to_string(), "Operation Sequence".to_string()),
+                ("y".to_string(), "Time (ms)".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+
+    /// Generate complexity graph
+    pub fn generate_complexity_graph(&self) -> CryptoGraph {
+        let mut data = Vec::new();
+        for (i, step) in self.trace.iter().enumerate() {
+            data.push((i as f64, step.duration.as_secs_f64() * 1_000_000.0)); // µs
+        }
+
+        CryptoGraph {
+            title: "T-transform Step Complexity".to_string(),
+            data,
+            labels: [
+                ("x".to_string(), "Step Index".to_string()),
+                ("y".to_string(), "Duration (µs)".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Line,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+
+    /// Generate heatmap of transformed point distribution over the field
+    pub fn generate_point_distribution_graph(&self) -> CryptoGraph {
+        let data = self
+            .trace
+            .iter()
+            .filter_map(|step| step.output)
+            .map(|point| (point.x() as f64, point.y() as f64))
+            .collect();
+
+        CryptoGraph {
+            title: "T-transform Point Distribution".to_string(),
+            data,
+            labels: [
+                ("x".to_string(), "x".to_string()),
+                ("y".to_string(), "y".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Heatmap,
+            color: ColorMode::Auto,
+            strict: false,
+            series: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Selectable hash function behind h() and the mask KDF
+//!
+//! The engine defaults to SHA-512. Any [`Digest`] with a block size and at
+//! least 32 bytes of output can replace it:
+//!
+//! ```
+//! use laicrypto::{hash::HashFunction, LaiCryptoEngine};
+//!
+//! let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+//! engine.hash = HashFunction::of::<sha2::Sha384>("SHA-384");
+//! ```
+//!
+//! With the `shake` feature, [`HashFunction::SHAKE256`] selects the Keccak
+//! suite: h() squeezes SHAKE256 output and rejection-samples it below p,
+//! and the KDF squeezes SHAKE256 directly. Envelopes made by such an
+//! engine record [`crate::envelope::Suite::LaiShake256`]. The `blake3`
+//! feature adds [`HashFunction::BLAKE3`] for throughput-sensitive uses,
+//! sampled and recorded the same way under
+//! [`crate::envelope::Suite::LaiBlake3`]; [`HashFunction::time_digest`]
+//! compares it against the SHA-2 default on this machine.
+//!
+//! Both parties must select the same function. Snapshots record the name
+//! only, so [`LaiCryptoEngine::restore`] rebuilds the built-in functions
+//! and rejects any other.
+
+use crate::LaiCryptoError;
+use hkdf::SimpleHkdf;
+use sha2::digest::core_api::BlockSizeUser;
+use sha2::digest::typenum::Unsigned;
+use sha2::digest::{Digest, OutputSizeUser};
+use sha2::{Sha256, Sha512};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Fewest output bytes h() reduces into the field without noticeable bias
+pub const MIN_OUTPUT_LEN: usize = 32;
+
+/// HKDF extract-and-expand: salt, input key material, info parts, output
+type HkdfFn = fn(&[u8], &[u8], &[&[u8]], &mut [u8]) -> bool;
+/// Hash of the concatenated parts reduced below p
+type FieldFn = fn(&[&[u8]], u128) -> u128;
+
+/// Hash function with its HKDF instantiation
+#[derive(Clone, Copy)]
+pub struct HashFunction {
+    name: &'static str,
+    output_len: usize,
+    digest: fn(&[&[u8]]) -> Vec<u8>,
+    hkdf: HkdfFn,
+    to_field: FieldFn,
+}
+
+impl HashFunction {
+    pub const SHA256: HashFunction = HashFunction::of::<Sha256>("SHA-256");
+    pub const SHA512: HashFunction = HashFunction::of::<Sha512>("SHA-512");
+
+    /// SHAKE256 with rejection sampling into the field
+    #[cfg(feature = "shake")]
+    pub const SHAKE256: HashFunction = HashFunction {
+        name: "SHAKE256",
+        output_len: shake::OUTPUT_LEN,
+        digest: shake::digest,
+        hkdf: shake::kdf,
+        to_field: shake::to_field,
+    };
+
+    /// BLAKE3 with rejection sampling into the field
+    #[cfg(feature = "blake3")]
+    pub const BLAKE3: HashFunction = HashFunction {
+        name: "BLAKE3",
+        output_len: blake3_suite::OUTPUT_LEN,
+        digest: blake3_suite::digest,
+        hkdf: blake3_suite::kdf,
+        to_field: blake3_suite::to_field,
+    };
+
+    /// Built-in functions, as named in snapshots
+    pub fn builtin() -> Vec<HashFunction> {
+        #[allow(unused_mut)]
+        let mut all = vec![HashFunction::SHA256, HashFunction::SHA512];
+        #[cfg(feature = "shake")]
+        all.push(HashFunction::SHAKE256);
+        #[cfg(feature = "blake3")]
+        all.push(HashFunction::BLAKE3);
+        all
+    }
+
+    /// Wrap `D` under `name`
+    ///
+    /// Panics if `D` produces fewer than [`MIN_OUTPUT_LEN`] bytes.
+    pub const fn of<D: Digest + BlockSizeUser + Clone>(name: &'static str) -> Self {
+        let output_len = <D as OutputSizeUser>::OutputSize::USIZE;
+        assert!(
+            output_len >= MIN_OUTPUT_LEN,
+            "digest output shorter than 32 bytes"
+        );
+        HashFunction {
+            name,
+            output_len,
+            digest: digest_parts::<D>,
+            hkdf: hkdf_expand::<D>,
+            to_field: wide_field::<D>,
+        }
+    }
+
+    /// Built-in function called `name`
+    pub fn by_name(name: &str) -> Result<Self, LaiCryptoError> {
+        let builtin = Self::builtin();
+        builtin
+            .iter()
+            .copied()
+            .find(|h| h.name == name)
+            .ok_or_else(|| LaiCryptoError::InvalidParameter {
+                param: "hash".to_string(),
+                value: name.to_string(),
+                reason: "Not a built-in hash function".to_string(),
+                valid_range: builtin
+                    .iter()
+                    .map(|h| h.name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Digest of the concatenated `parts`
+    pub fn digest(&self, parts: &[&[u8]]) -> Vec<u8> {
+        (self.digest)(parts)
+    }
+
+    /// HKDF extract-and-expand into `out`; false if `out` is too long
+    pub fn hkdf(&self, salt: &[u8], ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> bool {
+        (self.hkdf)(salt, ikm, info, out)
+    }
+
+    /// Hash of the concatenated `parts` as a near-uniform element below `p`
+    pub fn to_field(&self, parts: &[&[u8]], p: u128) -> u128 {
+        (self.to_field)(parts, p)
+    }
+
+    /// Wall time to digest `rounds` messages of `len` bytes
+    pub fn time_digest(&self, len: usize, rounds: u32) -> Duration {
+        let message = vec![0x5au8; len];
+        let start = Instant::now();
+        for round in 0..rounds {
+            std::hint::black_box(self.digest(&[&round.to_be_bytes(), &message]));
+        }
+        start.elapsed()
+    }
+}
+
+impl Default for HashFunction {
+    fn default() -> Self {
+        HashFunction::SHA512
+    }
+}
+
+impl PartialEq for HashFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.output_len == other.output_len
+    }
+}
+
+impl Eq for HashFunction {}
+
+impl fmt::Debug for HashFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HashFunction({})", self.name)
+    }
+}
+
+fn digest_parts<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Whole digest, at least 256 bits, reduced modulo p
+fn wide_field<D: Digest>(parts: &[&[u8]], p: u128) -> u128 {
+    crate::reduce_wide(&digest_parts::<D>(parts), p)
+}
+
+fn hkdf_expand<D: Digest + BlockSizeUser + Clone>(
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[&[u8]],
+    out: &mut [u8],
+) -> bool {
+    SimpleHkdf::<D>::new(Some(salt), ikm)
+        .expand_multi_info(info, out)
+        .is_ok()
+}
+
+#[cfg(feature = "shake")]
+mod shake {
+    use sha3::digest::{ExtendableOutput, Update, XofReader};
+    use sha3::Shake256;
+
+    /// Bytes squeezed by [`digest`]
+    pub(super) const OUTPUT_LEN: usize = 64;
+
+    fn absorb(parts: &[&[u8]]) -> Shake256 {
+        let mut hasher = Shake256::default();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher
+    }
+
+    pub(super) fn digest(parts: &[&[u8]]) -> Vec<u8> {
+        let mut out = vec![0u8; OUTPUT_LEN];
+        absorb(parts).finalize_xof_into(&mut out);
+        out
+    }
+
+    /// Squeeze length-prefixed salt and key material, then the info parts
+    pub(super) fn kdf(salt: &[u8], ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> bool {
+        let mut hasher = absorb(&[
+            b"LAI-shake-kdf",
+            &(salt.len() as u32).to_be_bytes(),
+            salt,
+            &(ikm.len() as u32).to_be_bytes(),
+            ikm,
+        ]);
+        for part in info {
+            hasher.update(part);
+        }
+        hasher.finalize_xof_into(out);
+        true
+    }
+
+    pub(super) fn to_field(parts: &[&[u8]], p: u128) -> u128 {
+        let mut reader = absorb(parts).finalize_xof();
+        crate::sample_below(p, |buf| reader.read(buf))
+    }
+}
+
+#[cfg(feature = "blake3")]
+mod blake3_suite {
+    use blake3::Hasher;
+
+    pub(super) const OUTPUT_LEN: usize = blake3::OUT_LEN;
+
+    fn absorb(parts: &[&[u8]]) -> Hasher {
+        let mut hasher = Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher
+    }
+
+    pub(super) fn digest(parts: &[&[u8]]) -> Vec<u8> {
+        absorb(parts).finalize().as_bytes().to_vec()
+    }
+
+    /// BLAKE3 key derivation over length-prefixed salt and key material, then the info parts
+    pub(super) fn kdf(salt: &[u8], ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> bool {
+        let mut hasher = Hasher::new_derive_key("LAI blake3 KDF v1");
+        hasher.update(&(salt.len() as u32).to_be_bytes());
+        hasher.update(salt);
+        hasher.update(&(ikm.len() as u32).to_be_bytes());
+        hasher.update(ikm);
+        for part in info {
+            hasher.update(part);
+        }
+        hasher.finalize_xof().fill(out);
+        true
+    }
+
+    pub(super) fn to_field(parts: &[&[u8]], p: u128) -> u128 {
+        let mut reader = absorb(parts).finalize_xof();
+        crate::sample_below(p, |buf| reader.fill(buf))
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for HashFunction {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.name.encode(encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for HashFunction {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let name = String::decode(decoder)?;
+        HashFunction::by_name(&name)
+            .map_err(|e| bincode::error::DecodeError::OtherString(e.to_string()))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(HashFunction);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CiphertextVersion, LaiCryptoEngine};
+
+    #[test]
+    fn test_selectable_hash() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        assert_eq!(engine.hash, HashFunction::SHA512);
+        let p0 = engine.p0;
+        let k = 7;
+        let q = engine.pow_t(p0, k).unwrap();
+        let default_h = engine.h(4, 5, 6);
+        let ct = engine
+            .encrypt_versioned(42, q, k, CiphertextVersion::V2)
+            .unwrap();
+
+        engine.hash = HashFunction::of::<sha2::Sha384>("SHA-384");
+        assert_ne!(engine.h(4, 5, 6), default_h);
+        // The T-chain changes with the hash, so a SHA-512 ciphertext no longer opens
+        assert_ne!(engine.decrypt(&ct, k).ok(), Some(42));
+        let q = engine.pow_t(p0, k).unwrap();
+        let ct = engine
+            .encrypt_versioned(42, q, k, CiphertextVersion::V2)
+            .unwrap();
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+
+        assert_eq!(
+            HashFunction::by_name("SHA-256").unwrap(),
+            HashFunction::SHA256
+        );
+        assert!(HashFunction::by_name("SHA-384").is_err());
+        assert!(HashFunction::SHA512.time_digest(64, 4) > Duration::ZERO);
+    }
+
+    #[cfg(feature = "shake")]
+    #[test]
+    fn test_shake256_suite() {
+        use crate::envelope::{Envelope, Suite};
+        use crate::Ciphertext;
+
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.hash = HashFunction::SHAKE256;
+        assert!((0..64).all(|s| engine.h(4, 5, s) < 1009));
+        let p0 = engine.p0;
+        let k = 7;
+        let q = engine.pow_t(p0, k).unwrap();
+        let ct = engine
+            .encrypt_versioned(42, q, k, CiphertextVersion::V2)
+            .unwrap();
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+
+        let bytes = ct.to_envelope(&engine);
+        assert_eq!(Envelope::parse(&bytes).unwrap().suite, Suite::LaiShake256);
+        assert_eq!(Ciphertext::from_envelope(&engine, &bytes).unwrap(), ct);
+        let sha2 = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        assert!(Ciphertext::from_envelope(&sha2, &bytes).is_err());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_suite() {
+        use crate::envelope::{Envelope, Suite};
+
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.hash = HashFunction::by_name("BLAKE3").unwrap();
+        assert!((0..64).all(|s| engine.h(4, 5, s) < 1009));
+        let p0 = engine.p0;
+        let k = 7;
+        let q = engine.pow_t(p0, k).unwrap();
+        let ct = engine
+            .encrypt_versioned(42, q, k, CiphertextVersion::V2)
+            .unwrap();
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+        let bytes = ct.to_envelope(&engine);
+        assert_eq!(Envelope::parse(&bytes).unwrap().suite, Suite::LaiBlake3);
+    }
+}
@@ -0,0 +1,143 @@
+//! Additively homomorphic ciphertexts — malleable by design
+//!
+//! Because the masked coordinate is `m + f^r(Q).x mod p`, sums of masked
+//! values are sums of messages plus a sum of masks. A ciphertext here keeps
+//! every ephemeral point that contributed a mask, so decrypting a sum of n
+//! ciphertexts costs n T-chain walks.
+//!
+//! These ciphertexts carry no tag: anyone can add to them. Use them only for
+//! aggregation prototypes, never where integrity matters. The ephemeral
+//! points stay visible, so [`rerandomize`] changes the masked value but does
+//! not hide which inputs went into a sum.
+
+use crate::{add_mod, CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point, PublicKey};
+
+/// Malleable ciphertext supporting addition of ciphertexts and scalars
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ciphertext {
+    ephemerals: Vec<Point>,
+    masked: u128,
+    modulus: u128,
+}
+
+impl Ciphertext {
+    /// Ephemeral points f^r(P0), one per contributing encryption
+    pub fn ephemerals(&self) -> &[Point] {
+        &self.ephemerals
+    }
+
+    /// Sum of messages plus sum of masks, mod p
+    pub fn masked(&self) -> u128 {
+        self.masked
+    }
+
+    /// Ciphertext of the sum of both plaintexts mod p
+    pub fn add(&self, other: &Ciphertext) -> Result<Ciphertext, LaiCryptoError> {
+        if self.modulus != other.modulus {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "other".to_string(),
+                value: format!("modulus {}", other.modulus),
+                reason: "Ciphertexts under different moduli cannot be added".to_string(),
+                valid_range: format!("modulus {}", self.modulus),
+            });
+        }
+        let mut ephemerals = self.ephemerals.clone();
+        ephemerals.extend_from_slice(&other.ephemerals);
+        Ok(Ciphertext {
+            ephemerals,
+            masked: add_mod(self.masked, other.masked, self.modulus),
+            modulus: self.modulus,
+        })
+    }
+
+    /// Ciphertext of the plaintext plus `m` mod p
+    pub fn add_scalar(&self, m: u128) -> Ciphertext {
+        Ciphertext {
+            ephemerals: self.ephemerals.clone(),
+            masked: add_mod(self.masked, m % self.modulus, self.modulus),
+            modulus: self.modulus,
+        }
+    }
+}
+
+/// Encrypt `m` to public point `q`
+pub fn encrypt(engine: &mut LaiCryptoEngine, m: u128, q: Point) -> Result<Ciphertext, LaiCryptoError> {
+    engine.retry_ephemeral(|engine, r| encrypt_with_ephemeral(engine, m, q, r))
+}
+
+fn encrypt_with_ephemeral(
+    engine: &mut LaiCryptoEngine,
+    m: u128,
+    q: Point,
+    r: u128,
+) -> Result<Ciphertext, LaiCryptoError> {
+    let ct = engine.encrypt_versioned(m, q, r, CiphertextVersion::V1)?;
+    Ok(Ciphertext {
+        ephemerals: vec![ct.c1],
+        masked: ct.c2.x(),
+        modulus: engine.p,
+    })
+}
+
+/// Refresh `ct` in place without knowing its plaintext
+///
+/// Folds a fresh encryption of zero under `pk` into the ciphertext. Its mask
+/// f^r(Q).x equals f^k(f^r(P0)).x, so [`decrypt`] strips it along with the
+/// others and the plaintext is unchanged while the masked value moves. Tagged
+/// [`crate::Ciphertext`]s cannot be re-randomized, since their tag binds
+/// the original shared point.
+pub fn rerandomize(
+    engine: &mut LaiCryptoEngine,
+    ct: &mut Ciphertext,
+    pk: &PublicKey,
+) -> Result<(), LaiCryptoError> {
+    *ct = ct.add(&encrypt(engine, 0, pk.point())?)?;
+    Ok(())
+}
+
+/// Recover the plaintext sum with private key `k`
+pub fn decrypt(engine: &mut LaiCryptoEngine, ct: &Ciphertext, k: u128) -> Result<u128, LaiCryptoError> {
+    let p = engine.p;
+    ct.ephemerals.iter().try_fold(ct.masked % p, |acc, &c1| {
+        let mask = engine.pow_t_secret(c1, k)?.x();
+        Ok(add_mod(acc, p - mask, p))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_homomorphic_sum() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 8;
+        let q = engine.pow_t(p0, k).unwrap();
+
+        let a = encrypt(&mut engine, 500, q).unwrap();
+        let b = encrypt(&mut engine, 600, q).unwrap();
+        let sum = a.add(&b).unwrap().add_scalar(10);
+        assert_eq!(sum.ephemerals().len(), 2);
+        assert_eq!(decrypt(&mut engine, &sum, k).unwrap(), (500 + 600 + 10) % 1009);
+
+        let zero = encrypt(&mut engine, 0, q).unwrap();
+        let fresh = sum.add(&zero).unwrap();
+        assert_ne!(fresh.masked(), sum.masked());
+        assert_eq!(decrypt(&mut engine, &fresh, k).unwrap(), 101);
+
+        let mut rerandomized = sum.clone();
+        rerandomize(&mut engine, &mut rerandomized, &PublicKey::new(q)).unwrap();
+        assert_eq!(rerandomized.ephemerals().len(), 3);
+        assert_eq!(&rerandomized.ephemerals()[..2], sum.ephemerals());
+        assert_ne!(rerandomized.masked(), sum.masked());
+        assert_eq!(decrypt(&mut engine, &rerandomized, k).unwrap(), 101);
+
+        let foreign = Ciphertext {
+            ephemerals: Vec::new(),
+            masked: 0,
+            modulus: 10007,
+        };
+        assert!(sum.add(&foreign).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Byte-oriented hybrid encryption with encrypt-then-MAC
+//!
+//! A random content scalar is encrypted to the recipient's public point as
+//! a [`CiphertextVersion::V2`] ciphertext without its tag; the shared point
+//! and content scalar then key a SHA-512 keystream and an HMAC-SHA-512 tag.
+//! Wire format:
+//!
+//! ```text
+//! magic "LAIE" | version | mode | c1 (32) | c2 (32) | [commitment (32)] | body | HMAC-SHA-512 (64)
+//! ```
+//!
+//! The tag covers everything before it and is checked in constant time
+//! before the body is decrypted. In [`HybridMode::Committing`] the header
+//! also carries a hash of the derived keys, so a ciphertext opens under
+//! exactly one key even when several candidate keys are tried.
+
+use crate::{CiphertextVersion, LaiCryptoEngine, LaiCryptoError, Point};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, Rng};
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Magic bytes opening every hybrid ciphertext
+pub const MAGIC: [u8; 4] = *b"LAIE";
+/// Current wire format version; version 1 masked the content scalar as V1
+pub const VERSION: u8 = 2;
+/// Bytes before the body in standard mode: magic, version, mode, c1 and c2
+pub const HEADER_LEN: usize = MAGIC.len() + 2 + 2 * Point::ENCODED_LEN;
+/// Key commitment length in committing mode
+pub const COMMITMENT_LEN: usize = 32;
+/// HMAC-SHA-512 tag length
+pub const MAC_LEN: usize = 64;
+
+/// Hybrid ciphertext mode, recorded in the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HybridMode {
+    /// Encrypt-then-MAC
+    #[default]
+    Standard,
+    /// Encrypt-then-MAC plus a commitment to the derived keys
+    Committing,
+}
+
+impl HybridMode {
+    fn to_byte(self) -> u8 {
+        match self {
+            HybridMode::Standard => 0,
+            HybridMode::Committing => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(HybridMode::Standard),
+            1 => Some(HybridMode::Committing),
+            _ => None,
+        }
+    }
+
+    /// Header length including the commitment, if any
+    pub fn header_len(self) -> usize {
+        match self {
+            HybridMode::Standard => HEADER_LEN,
+            HybridMode::Committing => HEADER_LEN + COMMITMENT_LEN,
+        }
+    }
+}
+
+/// Encryption and MAC keys derived from the shared point
+pub(crate) struct SessionKeys {
+    enc: [u8; 32],
+    mac: [u8; 32],
+}
+
+impl SessionKeys {
+    fn derive(shared: Point, content: u128) -> Self {
+        let mut hasher = Sha512::new();
+        hasher.update(b"LAI-etm");
+        hasher.update(shared.to_bytes());
+        hasher.update(content.to_be_bytes());
+        Self::from_digest(&hasher.finalize())
+    }
+
+    /// Split a 64-byte KDF output into encryption and MAC keys
+    pub(crate) fn from_digest(digest: &[u8]) -> Self {
+        let (mut enc, mut mac) = ([0u8; 32], [0u8; 32]);
+        enc.copy_from_slice(&digest[..32]);
+        mac.copy_from_slice(&digest[32..]);
+        SessionKeys { enc, mac }
+    }
+
+    /// XOR `data` with the SHA-512 counter-mode keystream
+    pub(crate) fn apply_keystream(&self, data: &mut [u8]) {
+        for (counter, chunk) in data.chunks_mut(64).enumerate() {
+            let block = Sha512::new()
+                .chain_update(self.enc)
+                .chain_update((counter as u64).to_be_bytes())
+                .finalize();
+            for (byte, key) in chunk.iter_mut().zip(block.iter()) {
+                *byte ^= key;
+            }
+        }
+    }
+
+    pub(crate) fn mac(&self) -> HmacSha512 {
+        HmacSha512::new_from_slice(&self.mac).expect("HMAC accepts any key length")
+    }
+
+    /// Collision-resistant commitment to both keys
+    fn commitment(&self) -> [u8; COMMITMENT_LEN] {
+        let digest = Sha512::new()
+            .chain_update(b"LAI-commit")
+            .chain_update(self.enc)
+            .chain_update(self.mac)
+            .finalize();
+        let mut out = [0u8; COMMITMENT_LEN];
+        out.copy_from_slice(&digest[..COMMITMENT_LEN]);
+        out
+    }
+}
+
+fn malformed(reason: &str, len: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "ciphertext".to_string(),
+        value: format!("{} bytes", len),
+        reason: reason.to_string(),
+        valid_range: format!("at least {} bytes starting with \"LAIE\" v{}", HEADER_LEN + MAC_LEN, VERSION),
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Encrypt arbitrary bytes to public point `q` in standard mode
+    pub fn encrypt_bytes(&mut self, plaintext: &[u8], q: Point) -> Result<Vec<u8>, LaiCryptoError> {
+        self.encrypt_bytes_with(plaintext, q, HybridMode::Standard)
+    }
+
+    /// Encrypt arbitrary bytes to public point `q` in the given mode
+    ///
+    /// A fresh ephemeral scalar is drawn whenever its T chain fails.
+    pub fn encrypt_bytes_with(
+        &mut self,
+        plaintext: &[u8],
+        q: Point,
+        mode: HybridMode,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        self.retry_ephemeral(|engine, r| {
+            let content = OsRng.gen_range(0..engine.p);
+            engine.seal(plaintext, q, r, content, mode)
+        })
+    }
+
+    /// Encrypt with caller-chosen ephemeral and content scalars
+    pub(crate) fn seal(
+        &mut self,
+        plaintext: &[u8],
+        q: Point,
+        r: u128,
+        content: u128,
+        mode: HybridMode,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        let ct = self.encrypt_versioned(content, q, r, CiphertextVersion::V2)?;
+        let shared = self.pow_t(q, r)?;
+        let keys = SessionKeys::derive(shared, content);
+
+        let mut out = Vec::with_capacity(mode.header_len() + plaintext.len() + MAC_LEN);
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(mode.to_byte());
+        out.extend_from_slice(&ct.c1.to_bytes());
+        out.extend_from_slice(&ct.c2.to_bytes());
+        if mode == HybridMode::Committing {
+            out.extend_from_slice(&keys.commitment());
+        }
+        let body_start = out.len();
+        out.extend_from_slice(plaintext);
+        keys.apply_keystream(&mut out[body_start..]);
+
+        let mut mac = keys.mac();
+        mac.update(&out);
+        out.extend_from_slice(&mac.finalize().into_bytes());
+        Ok(out)
+    }
+
+    /// Verify and decrypt a ciphertext produced by [`LaiCryptoEngine::encrypt_bytes_with`]
+    ///
+    /// The mode is read from the header. Returns `AuthenticationFailed` for
+    /// a wrong key, a key commitment mismatch or any modified byte; the body
+    /// is only decrypted after all checks pass.
+    pub fn decrypt_bytes(&mut self, ciphertext: &[u8], k: u128) -> Result<Vec<u8>, LaiCryptoError> {
+        let len = ciphertext.len();
+        if len < HEADER_LEN + MAC_LEN {
+            return Err(malformed("Ciphertext too short", len));
+        }
+        if ciphertext[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LAIE magic", len));
+        }
+        if ciphertext[MAGIC.len()] != VERSION {
+            return Err(malformed("Unsupported version", len));
+        }
+        let mode = HybridMode::from_byte(ciphertext[MAGIC.len() + 1])
+            .ok_or_else(|| malformed("Unknown mode", len))?;
+        let header_len = mode.header_len();
+        if len < header_len + MAC_LEN {
+            return Err(malformed("Ciphertext too short", len));
+        }
+
+        let points = &ciphertext[MAGIC.len() + 2..HEADER_LEN];
+        let (c1, c2) = points.split_at(Point::ENCODED_LEN);
+        let (c1, c2) = match (raw_point(c1, self.p), raw_point(c2, self.p)) {
+            (Some(c1), Some(c2)) => (c1, c2),
+            _ => return Err(malformed("Coordinate not reduced modulo p", len)),
+        };
+
+        let (content, shared) = self.unmask(c1, c2, k, CiphertextVersion::V2)?;
+        let keys = SessionKeys::derive(shared, content);
+        let auth_failed = || LaiCryptoError::AuthenticationFailed {
+            operation: "decrypt_bytes".to_string(),
+        };
+        if mode == HybridMode::Committing
+            && !bool::from(keys.commitment()[..].ct_eq(&ciphertext[HEADER_LEN..header_len]))
+        {
+            return Err(auth_failed());
+        }
+        let (authenticated, tag) = ciphertext.split_at(len - MAC_LEN);
+        let mut mac = keys.mac();
+        mac.update(authenticated);
+        mac.verify_slice(tag).map_err(|_| auth_failed())?;
+
+        let mut body = authenticated[header_len..].to_vec();
+        keys.apply_keystream(&mut body);
+        Ok(body)
+    }
+}
+
+/// Decode a reduced point without the curve check; T outputs are generally off-curve
+pub(crate) fn raw_point(bytes: &[u8], p: u128) -> Option<Point> {
+    let (x, y) = bytes.split_at(Point::ENCODED_LEN / 2);
+    let coord = |b: &[u8]| u128::from_be_bytes(b.try_into().expect("16-byte coordinate"));
+    let (x, y) = (coord(x), coord(y));
+    (x < p && y < p).then(|| Point::new_unchecked(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_then_mac() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 8;
+        let q = engine.pow_t(p0, k).unwrap();
+
+        let sealed = engine.encrypt_bytes(b"attack at dawn", q).unwrap();
+        assert_eq!(sealed.len(), HEADER_LEN + 14 + MAC_LEN);
+        assert_eq!(&sealed[..4], b"LAIE");
+        // c2 = (content + mask, 0)
+        assert_eq!(sealed[HEADER_LEN - 16..HEADER_LEN], [0; 16]);
+        assert_eq!(engine.decrypt_bytes(&sealed, k).unwrap(), b"attack at dawn");
+
+        for i in [0, 5, HEADER_LEN, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(engine.decrypt_bytes(&tampered, k).is_err());
+        }
+        let mut body = sealed.clone();
+        body[HEADER_LEN] ^= 1;
+        assert!(matches!(
+            engine.decrypt_bytes(&body, k),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+        assert!(engine.decrypt_bytes(&sealed[..HEADER_LEN], k).is_err());
+        assert!(engine.encrypt_bytes(b"", q).unwrap().len() == HEADER_LEN + MAC_LEN);
+    }
+
+    #[test]
+    fn test_committing_mode() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 8;
+        let q = engine.pow_t(p0, k).unwrap();
+
+        let sealed = engine.encrypt_bytes_with(b"ledger", q, HybridMode::Committing).unwrap();
+        assert_eq!(sealed.len(), HybridMode::Committing.header_len() + 6 + MAC_LEN);
+        assert_eq!(engine.decrypt_bytes(&sealed, k).unwrap(), b"ledger");
+
+        // A ciphertext opens under exactly one candidate key
+        let opened = (1..20).filter(|&cand| engine.decrypt_bytes(&sealed, cand).is_ok()).count();
+        assert_eq!(opened, 1);
+
+        let mut forged = sealed.clone();
+        forged[HEADER_LEN] ^= 0x80;
+        assert!(matches!(
+            engine.decrypt_bytes(&forged, k),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+
+        let mut unknown = sealed;
+        unknown[MAGIC.len() + 1] = 9;
+        assert_eq!(engine.decrypt_bytes(&unknown, k).unwrap_err().kind(), crate::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,662 @@
+//! Standalone fixed-width integers with modular arithmetic
+//!
+//! [`FieldInt`] is implemented for `u64`, `u128` and the fixed-width
+//! [`U256`] and [`U384`]. Each type defines its own conversions and a
+//! fixed-length big-endian encoding of [`FieldInt::BYTES`] bytes.
+//!
+//! This is not a backend for the engine: [`crate::LaiCryptoEngine`],
+//! [`crate::Point`] and the wire formats are fixed to `u128`, and porting
+//! them is on hold for the reasons in `docs/design-notes.md`. The types
+//! here serve parameter research, such as testing primality or square
+//! roots at sizes the engine cannot use.
+//!
+//! The provided methods ([`FieldInt::pow_mod`],
+//! [`FieldInt::is_probable_prime`], [`FieldInt::sqrt_mod`]) are written
+//! once against the trait and behave identically for every type.
+//!
+//! ```
+//! use laicrypto::int::{FieldInt, U256};
+//!
+//! // 2^255 - 19
+//! let p = U256::from_limbs([u64::MAX - 18, u64::MAX, u64::MAX, u64::MAX >> 1]);
+//! assert!(p.is_probable_prime());
+//! let x = U256::from(12_345u64);
+//! let root = x.mul_mod(x, p).sqrt_mod(p).unwrap();
+//! assert!(root == x || root == p.wrapping_sub(x));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+
+/// Unsigned integer usable as the element and modulus type of a prime field
+///
+/// Modular methods expect operands already reduced below `m`, except
+/// [`FieldInt::rem`], which performs that reduction.
+pub trait FieldInt:
+    Copy + Eq + Ord + Hash + Default + fmt::Debug + fmt::Display + Send + Sync + 'static
+{
+    /// Width of the type in bits
+    const BITS: u32;
+    /// Length of the big-endian encoding
+    const BYTES: usize;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_u64(v: u64) -> Self;
+    /// `None` if `v` does not fit
+    fn try_from_u128(v: u128) -> Option<Self>;
+    /// `None` if `self` does not fit
+    fn to_u128(self) -> Option<u128>;
+
+    /// Position of the highest set bit plus one; 0 for zero
+    fn bit_len(self) -> u32;
+    fn bit(self, i: u32) -> bool;
+    fn shr1(self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// `self mod m`, for any `self`
+    fn rem(self, m: Self) -> Self;
+    fn add_mod(self, rhs: Self, m: Self) -> Self;
+    fn mul_mod(self, rhs: Self, m: Self) -> Self;
+
+    /// Write the big-endian encoding to `out`, which must be `BYTES` long
+    fn write_be(self, out: &mut [u8]);
+    /// Parse a big-endian encoding of exactly `BYTES` bytes
+    fn read_be(bytes: &[u8]) -> Option<Self>;
+
+    fn is_zero(self) -> bool {
+        self == Self::ZERO
+    }
+
+    fn is_odd(self) -> bool {
+        self.bit(0)
+    }
+
+    fn to_be_bytes_vec(self) -> Vec<u8> {
+        let mut out = vec![0; Self::BYTES];
+        self.write_be(&mut out);
+        out
+    }
+
+    fn sub_mod(self, rhs: Self, m: Self) -> Self {
+        if self >= rhs {
+            self.wrapping_sub(rhs)
+        } else {
+            self.add_mod(m.wrapping_sub(rhs), m)
+        }
+    }
+
+    /// Left-to-right square and multiply
+    fn pow_mod(self, exp: Self, m: Self) -> Self {
+        if m == Self::ONE {
+            return Self::ZERO;
+        }
+        let base = self.rem(m);
+        let mut result = Self::ONE;
+        for i in (0..exp.bit_len()).rev() {
+            result = result.mul_mod(result, m);
+            if exp.bit(i) {
+                result = result.mul_mod(base, m);
+            }
+        }
+        result
+    }
+
+    /// Miller-Rabin over the first twelve prime bases
+    ///
+    /// Deterministic below 2^78; beyond that a strong probable-prime test.
+    fn is_probable_prime(self) -> bool {
+        const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+        let n = self;
+        if n < Self::from_u64(2) {
+            return false;
+        }
+        for b in BASES {
+            let b = Self::from_u64(b);
+            if n == b {
+                return true;
+            }
+            if n.rem(b).is_zero() {
+                return false;
+            }
+        }
+
+        let n1 = n.wrapping_sub(Self::ONE);
+        let (d, s) = odd_part(n1);
+        'bases: for b in BASES {
+            let mut x = Self::from_u64(b).pow_mod(d, n);
+            if x == Self::ONE || x == n1 {
+                continue;
+            }
+            for _ in 1..s {
+                x = x.mul_mod(x, n);
+                if x == n1 {
+                    continue 'bases;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// Euler's criterion for an odd prime `p`
+    fn is_square_mod(self, p: Self) -> bool {
+        let a = self.rem(p);
+        a.is_zero() || a.pow_mod(p.wrapping_sub(Self::ONE).shr1(), p) == Self::ONE
+    }
+
+    /// Square root modulo an odd prime `p` by Tonelli-Shanks
+    fn sqrt_mod(self, p: Self) -> Option<Self> {
+        let a = self.rem(p);
+        if a.is_zero() {
+            return Some(a);
+        }
+        if !a.is_square_mod(p) {
+            return None;
+        }
+        let p1 = p.wrapping_sub(Self::ONE);
+        let (q, s) = odd_part(p1);
+        // (q + 1) / 2 without overflowing q + 1
+        let half = q.shr1().add_mod(Self::ONE, p);
+        if s == 1 {
+            return Some(a.pow_mod(half, p));
+        }
+
+        let mut z = Self::from_u64(2);
+        while z.is_square_mod(p) {
+            z = z.add_mod(Self::ONE, p);
+        }
+        let mut m = s;
+        let mut c = z.pow_mod(q, p);
+        let mut t = a.pow_mod(q, p);
+        let mut r = a.pow_mod(half, p);
+        while t != Self::ONE {
+            let mut i = 1;
+            let mut t2i = t.mul_mod(t, p);
+            while t2i != Self::ONE {
+                t2i = t2i.mul_mod(t2i, p);
+                i += 1;
+            }
+            let mut b = c;
+            for _ in 0..m - i - 1 {
+                b = b.mul_mod(b, p);
+            }
+            m = i;
+            c = b.mul_mod(b, p);
+            t = t.mul_mod(c, p);
+            r = r.mul_mod(b, p);
+        }
+        Some(r)
+    }
+}
+
+/// `(d, s)` with `n = d · 2^s` and `d` odd, for nonzero `n`
+fn odd_part<T: FieldInt>(mut n: T) -> (T, u32) {
+    let mut s = 0;
+    while !n.is_odd() {
+        n = n.shr1();
+        s += 1;
+    }
+    (n, s)
+}
+
+impl FieldInt for u64 {
+    const BITS: u32 = u64::BITS;
+    const BYTES: usize = 8;
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn from_u64(v: u64) -> Self {
+        v
+    }
+
+    fn try_from_u128(v: u128) -> Option<Self> {
+        u64::try_from(v).ok()
+    }
+
+    fn to_u128(self) -> Option<u128> {
+        Some(self.into())
+    }
+
+    fn bit_len(self) -> u32 {
+        u64::BITS - self.leading_zeros()
+    }
+
+    fn bit(self, i: u32) -> bool {
+        (self >> i) & 1 == 1
+    }
+
+    fn shr1(self) -> Self {
+        self >> 1
+    }
+
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        u64::wrapping_sub(self, rhs)
+    }
+
+    fn rem(self, m: Self) -> Self {
+        self % m
+    }
+
+    fn add_mod(self, rhs: Self, m: Self) -> Self {
+        ((u128::from(self) + u128::from(rhs)) % u128::from(m)) as u64
+    }
+
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        ((u128::from(self) * u128::from(rhs)) % u128::from(m)) as u64
+    }
+
+    fn write_be(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+
+    fn read_be(bytes: &[u8]) -> Option<Self> {
+        Some(u64::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl FieldInt for u128 {
+    const BITS: u32 = u128::BITS;
+    const BYTES: usize = 16;
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn from_u64(v: u64) -> Self {
+        v.into()
+    }
+
+    fn try_from_u128(v: u128) -> Option<Self> {
+        Some(v)
+    }
+
+    fn to_u128(self) -> Option<u128> {
+        Some(self)
+    }
+
+    fn bit_len(self) -> u32 {
+        u128::BITS - self.leading_zeros()
+    }
+
+    fn bit(self, i: u32) -> bool {
+        (self >> i) & 1 == 1
+    }
+
+    fn shr1(self) -> Self {
+        self >> 1
+    }
+
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        u128::wrapping_sub(self, rhs)
+    }
+
+    fn rem(self, m: Self) -> Self {
+        self % m
+    }
+
+    fn add_mod(self, rhs: Self, m: Self) -> Self {
+        crate::add_mod(self, rhs, m)
+    }
+
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        crate::mul_mod(self, rhs, m)
+    }
+
+    fn write_be(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+
+    fn read_be(bytes: &[u8]) -> Option<Self> {
+        Some(u128::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
+
+/// Fixed-width unsigned integer of `LIMBS` 64-bit words, least significant
+/// first
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uint<const LIMBS: usize>([u64; LIMBS]);
+
+/// 256-bit unsigned integer
+pub type U256 = Uint<4>;
+/// 384-bit unsigned integer
+pub type U384 = Uint<6>;
+
+impl<const LIMBS: usize> Uint<LIMBS> {
+    pub const ZERO: Self = Self([0; LIMBS]);
+
+    /// Value from little-endian limbs
+    pub const fn from_limbs(limbs: [u64; LIMBS]) -> Self {
+        Self(limbs)
+    }
+
+    pub const fn limbs(&self) -> &[u64; LIMBS] {
+        &self.0
+    }
+
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let mut out = [0; LIMBS];
+        let mut carry = false;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (s1, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (s2, c2) = s1.overflowing_add(carry as u64);
+            *limb = s2;
+            carry = c1 || c2;
+        }
+        (Self(out), carry)
+    }
+
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let mut out = [0; LIMBS];
+        let mut borrow = false;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (d1, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (d2, b2) = d1.overflowing_sub(borrow as u64);
+            *limb = d2;
+            borrow = b1 || b2;
+        }
+        (Self(out), borrow)
+    }
+
+    /// Shift left by one, returning the bit shifted out
+    fn shl1(self) -> (Self, bool) {
+        let mut out = [0; LIMBS];
+        let mut carry = 0;
+        for (i, limb) in out.iter_mut().enumerate() {
+            *limb = (self.0[i] << 1) | carry;
+            carry = self.0[i] >> 63;
+        }
+        (Self(out), carry == 1)
+    }
+
+    /// Quotient and remainder of division by a nonzero `d`
+    fn div_rem_u64(self, d: u64) -> (Self, u64) {
+        let mut out = [0; LIMBS];
+        let mut rem = 0u128;
+        for i in (0..LIMBS).rev() {
+            let cur = (rem << 64) | u128::from(self.0[i]);
+            out[i] = (cur / u128::from(d)) as u64;
+            rem = cur % u128::from(d);
+        }
+        (Self(out), rem as u64)
+    }
+}
+
+impl<const LIMBS: usize> Default for Uint<LIMBS> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<const LIMBS: usize> Ord for Uint<LIMBS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> PartialOrd for Uint<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> From<u64> for Uint<LIMBS> {
+    fn from(v: u64) -> Self {
+        let mut limbs = [0; LIMBS];
+        limbs[0] = v;
+        Self(limbs)
+    }
+}
+
+impl<const LIMBS: usize> From<u128> for Uint<LIMBS> {
+    fn from(v: u128) -> Self {
+        let mut limbs = [0; LIMBS];
+        limbs[0] = v as u64;
+        limbs[1] = (v >> 64) as u64;
+        Self(limbs)
+    }
+}
+
+impl<const LIMBS: usize> TryFrom<Uint<LIMBS>> for u128 {
+    type Error = crate::LaiCryptoError;
+
+    fn try_from(v: Uint<LIMBS>) -> Result<Self, Self::Error> {
+        v.to_u128()
+            .ok_or_else(|| crate::LaiCryptoError::InvalidParameter {
+                param: "value".to_string(),
+                value: v.to_string(),
+                reason: "Does not fit in 128 bits".to_string(),
+                valid_range: "0 ≤ value < 2^128".to_string(),
+            })
+    }
+}
+
+impl<const LIMBS: usize> fmt::Display for Uint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+        // Peel off 19 decimal digits at a time
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut chunks = Vec::new();
+        let mut n = *self;
+        while !n.is_zero() {
+            let (q, r) = n.div_rem_u64(CHUNK);
+            chunks.push(r);
+            n = q;
+        }
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().expect("nonzero"))?;
+        for chunk in chunks {
+            write!(f, "{:019}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const LIMBS: usize> fmt::Debug for Uint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for limb in self.0.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const LIMBS: usize> FieldInt for Uint<LIMBS> {
+    const BITS: u32 = 64 * LIMBS as u32;
+    const BYTES: usize = 8 * LIMBS;
+    const ZERO: Self = Self([0; LIMBS]);
+    const ONE: Self = {
+        let mut limbs = [0; LIMBS];
+        limbs[0] = 1;
+        Self(limbs)
+    };
+
+    fn from_u64(v: u64) -> Self {
+        v.into()
+    }
+
+    fn try_from_u128(v: u128) -> Option<Self> {
+        Some(v.into())
+    }
+
+    fn to_u128(self) -> Option<u128> {
+        if self.0[2..].iter().any(|&limb| limb != 0) {
+            return None;
+        }
+        Some(u128::from(self.0[1]) << 64 | u128::from(self.0[0]))
+    }
+
+    fn bit_len(self) -> u32 {
+        match self.0.iter().rposition(|&limb| limb != 0) {
+            Some(i) => 64 * i as u32 + (64 - self.0[i].leading_zeros()),
+            None => 0,
+        }
+    }
+
+    fn bit(self, i: u32) -> bool {
+        (self.0[(i / 64) as usize] >> (i % 64)) & 1 == 1
+    }
+
+    fn shr1(self) -> Self {
+        let mut out = [0; LIMBS];
+        for (i, limb) in out.iter_mut().enumerate() {
+            let high = if i + 1 < LIMBS {
+                self.0[i + 1] << 63
+            } else {
+                0
+            };
+            *limb = (self.0[i] >> 1) | high;
+        }
+        Self(out)
+    }
+
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Binary long division, one bit of `self` at a time
+    fn rem(self, m: Self) -> Self {
+        if self < m {
+            return self;
+        }
+        let mut r = Self::ZERO;
+        for i in (0..self.bit_len()).rev() {
+            let (shifted, out) = r.shl1();
+            r = shifted;
+            r.0[0] |= self.bit(i) as u64;
+            if out || r >= m {
+                r = r.wrapping_sub(m);
+            }
+        }
+        r
+    }
+
+    fn add_mod(self, rhs: Self, m: Self) -> Self {
+        let (sum, carry) = self.overflowing_add(rhs);
+        if carry || sum >= m {
+            sum.wrapping_sub(m)
+        } else {
+            sum
+        }
+    }
+
+    /// Double-and-add, so no intermediate exceeds `m`
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        let mut result = Self::ZERO;
+        for i in (0..rhs.bit_len()).rev() {
+            result = result.add_mod(result, m);
+            if rhs.bit(i) {
+                result = result.add_mod(self, m);
+            }
+        }
+        result
+    }
+
+    fn write_be(self, out: &mut [u8]) {
+        for (chunk, limb) in out.chunks_exact_mut(8).zip(self.0.iter().rev()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+    }
+
+    fn read_be(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES {
+            return None;
+        }
+        let mut limbs = [0; LIMBS];
+        for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().expect("8-byte chunk"));
+        }
+        Some(Self(limbs))
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<const LIMBS: usize> bincode::Encode for Uint<LIMBS> {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.0.encode(encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C, const LIMBS: usize> bincode::Decode<C> for Uint<LIMBS> {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self(<[u64; LIMBS]>::decode(decoder)?))
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<'de, C, const LIMBS: usize> bincode::BorrowDecode<'de, C> for Uint<LIMBS> {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = C>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        bincode::Decode::decode(decoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2^384 - 2^128 - 2^96 + 2^32 - 1
+    const P384: U384 = Uint::from_limbs([
+        0x0000_0000_ffff_ffff,
+        0xffff_ffff_0000_0000,
+        0xffff_ffff_ffff_fffe,
+        u64::MAX,
+        u64::MAX,
+        u64::MAX,
+    ]);
+
+    fn check_type<T: FieldInt>() {
+        let p = T::from_u64(1009);
+        assert!(p.is_probable_prime());
+        assert!(!T::from_u64(1011).is_probable_prime());
+        for a in 0..1009u64 {
+            let a = T::from_u64(a);
+            assert_eq!(
+                a.is_square_mod(p),
+                crate::has_sqrt(a.to_u128().unwrap(), 1009)
+            );
+            if let Some(r) = a.sqrt_mod(p) {
+                assert_eq!(r.mul_mod(r, p), a);
+            }
+        }
+        let x = T::from_u64(123_456_789);
+        assert_eq!(T::read_be(&x.to_be_bytes_vec()), Some(x));
+        assert_eq!(x.to_be_bytes_vec().len(), T::BYTES);
+    }
+
+    #[test]
+    fn test_types_agree() {
+        check_type::<u64>();
+        check_type::<u128>();
+        check_type::<U256>();
+        check_type::<U384>();
+
+        let (a, b, m) = (u64::MAX - 7, u64::MAX - 11, u64::MAX - 58);
+        let wide = U256::from(a).mul_mod(U256::from(b), U256::from(m));
+        assert_eq!(wide.to_u128(), Some(u128::from(a.mul_mod(b, m))));
+        assert_eq!(U256::from(u128::MAX).to_string(), u128::MAX.to_string());
+    }
+
+    #[test]
+    fn test_wide_moduli() {
+        assert!(P384.is_probable_prime());
+        // (2^127 - 1)(2^89 - 1) fits well below P384, so this is the exact product
+        let m127 = U384::from((1u128 << 127) - 1);
+        let m89 = U384::from((1u128 << 89) - 1);
+        assert!(m127.is_probable_prime() && m89.is_probable_prime());
+        assert!(!m127.mul_mod(m89, P384).is_probable_prime());
+        let x = P384.shr1();
+        let root = x.mul_mod(x, P384).sqrt_mod(P384).unwrap();
+        assert!(root == x || root == P384.wrapping_sub(x));
+        assert!(u128::try_from(P384).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Collection of recipients' public keys indexed by fingerprint
+//!
+//! A [`PublicKeyDirectory`] is the sender's view: other people's public keys,
+//! looked up by fingerprint before encrypting to them. The recipient's own
+//! generations of secret and public key live in a
+//! [`crate::rotation::KeyRing`], whose current public key is what senders
+//! add here.
+//!
+//! A fingerprint is SHA-256 over the engine's parameter hash and the
+//! encoded point, so the same point under different parameters gets a
+//! different fingerprint. Exported directories list each key as a
+//! [`crate::envelope`] public key:
+//!
+//! ```text
+//! magic "LKRG" | version | count (4) | count × (length (4) | envelope)
+//! ```
+
+use crate::envelope::{hex, point_at, wrong_length, Envelope, PayloadKind, Suite, PARAMS_HASH_LEN};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point, PublicKey};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Magic bytes opening an exported directory
+pub const MAGIC: [u8; 4] = *b"LKRG";
+/// Current export format version
+pub const VERSION: u8 = 1;
+/// Fingerprint length in bytes
+pub const FINGERPRINT_LEN: usize = 32;
+
+/// Stable identifier of a public key under one parameter set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct Fingerprint(pub [u8; FINGERPRINT_LEN]);
+
+impl Fingerprint {
+    pub fn as_bytes(&self) -> &[u8; FINGERPRINT_LEN] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex(&self.0))
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = LaiCryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || LaiCryptoError::InvalidParameter {
+            param: "fingerprint".to_string(),
+            value: s.to_string(),
+            reason: "Not a hex fingerprint".to_string(),
+            valid_range: format!("{} hex digits", 2 * FINGERPRINT_LEN),
+        };
+        if s.len() != 2 * FINGERPRINT_LEN || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let mut out = [0u8; FINGERPRINT_LEN];
+        for (byte, pair) in out.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Fingerprint(out))
+    }
+}
+
+impl PublicKey {
+    /// Fingerprint of this key under `engine`'s parameters
+    pub fn fingerprint(&self, engine: &LaiCryptoEngine) -> Fingerprint {
+        fingerprint(&engine.params_hash(), self.point())
+    }
+}
+
+fn fingerprint(params_hash: &[u8; PARAMS_HASH_LEN], point: Point) -> Fingerprint {
+    let digest = Sha256::new()
+        .chain_update(b"LAI-fingerprint")
+        .chain_update(params_hash)
+        .chain_update(point.to_bytes())
+        .finalize();
+    Fingerprint(digest[..].try_into().expect("SHA-256 output is 32 bytes"))
+}
+
+/// Public keys for one parameter set, keyed by fingerprint
+#[derive(Debug, Clone)]
+pub struct PublicKeyDirectory {
+    params_hash: [u8; PARAMS_HASH_LEN],
+    suite: Suite,
+    p: u128,
+    keys: BTreeMap<Fingerprint, PublicKey>,
+}
+
+impl PublicKeyDirectory {
+    /// Empty directory for keys under `engine`'s parameters
+    pub fn new(engine: &LaiCryptoEngine) -> Self {
+        PublicKeyDirectory {
+            params_hash: engine.params_hash(),
+            suite: engine.suite(),
+            p: engine.p,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Add a key, returning its fingerprint; adding a key twice keeps one copy
+    pub fn add(&mut self, key: PublicKey) -> Fingerprint {
+        let fingerprint = fingerprint(&self.params_hash, key.point());
+        self.keys.insert(fingerprint, key);
+        fingerprint
+    }
+
+    pub fn find(&self, fingerprint: &Fingerprint) -> Option<&PublicKey> {
+        self.keys.get(fingerprint)
+    }
+
+    /// Keys whose fingerprint starts with `prefix`, as typed by a user
+    pub fn find_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a Fingerprint, &'a PublicKey)> + 'a {
+        let prefix = prefix.to_ascii_lowercase();
+        self.keys
+            .iter()
+            .filter(move |(f, _)| f.to_string().starts_with(&prefix))
+    }
+
+    pub fn remove(&mut self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.keys.remove(fingerprint)
+    }
+
+    pub fn contains(&self, fingerprint: &Fingerprint) -> bool {
+        self.keys.contains_key(fingerprint)
+    }
+
+    /// Keys with their fingerprints, in fingerprint order
+    pub fn iter(&self) -> impl Iterator<Item = (&Fingerprint, &PublicKey)> {
+        self.keys.iter()
+    }
+
+    pub fn public_keys(&self) -> impl Iterator<Item = &PublicKey> {
+        self.keys.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn export(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        out.extend_from_slice(&(self.keys.len() as u32).to_be_bytes());
+        for key in self.keys.values() {
+            let envelope = Envelope {
+                suite: self.suite,
+                kind: PayloadKind::PublicKey,
+                params_hash: self.params_hash,
+                payload: key.point().to_bytes().to_vec(),
+            }
+            .to_bytes();
+            out.extend_from_slice(&(envelope.len() as u32).to_be_bytes());
+            out.extend_from_slice(&envelope);
+        }
+        out
+    }
+
+    /// Add every key from an export, returning how many were new
+    ///
+    /// Nothing is added unless the whole export parses and every key
+    /// belongs to this directory's parameters.
+    pub fn import(&mut self, bytes: &[u8]) -> Result<usize, LaiCryptoError> {
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "directory".to_string(),
+            value: format!("{} bytes", bytes.len()),
+            reason: reason.to_string(),
+            valid_range: format!("\"LKRG\" v{} export", VERSION),
+        };
+        if bytes.len() < MAGIC.len() + 5 || bytes[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LKRG magic"));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(malformed("Unsupported version"));
+        }
+        let count = u32::from_be_bytes(bytes[5..9].try_into().expect("length checked"));
+        let mut rest = &bytes[9..];
+        let mut keys = Vec::new();
+        for _ in 0..count {
+            if rest.len() < 4 {
+                return Err(malformed("Truncated key entry"));
+            }
+            let (len, tail) = rest.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().expect("4 bytes")) as usize;
+            if tail.len() < len {
+                return Err(malformed("Truncated key entry"));
+            }
+            let (envelope, tail) = tail.split_at(len);
+            keys.push(self.decode_key(envelope)?);
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(malformed("Trailing bytes after last key"));
+        }
+        let before = self.keys.len();
+        for key in keys {
+            self.add(key);
+        }
+        Ok(self.keys.len() - before)
+    }
+
+    /// Decode one exported envelope, as [`PublicKey::from_envelope`] does
+    fn decode_key(&self, bytes: &[u8]) -> Result<PublicKey, LaiCryptoError> {
+        let envelope = Envelope::parse(bytes)?;
+        if envelope.kind != PayloadKind::PublicKey
+            || envelope.suite != self.suite
+            || envelope.params_hash != self.params_hash
+        {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "directory import".to_string(),
+                expected: format!(
+                    "PublicKey payload under {:?} with parameters {}",
+                    self.suite,
+                    hex(&self.params_hash)
+                ),
+                actual: format!(
+                    "{:?} payload under {:?} with parameters {}",
+                    envelope.kind,
+                    envelope.suite,
+                    hex(&envelope.params_hash)
+                ),
+            });
+        }
+        if envelope.payload.len() != Point::ENCODED_LEN {
+            return Err(wrong_length(
+                "public key",
+                envelope.payload.len(),
+                Point::ENCODED_LEN,
+            ));
+        }
+        Ok(PublicKey::new(point_at(
+            &envelope.payload,
+            self.p,
+            "public key",
+        )?))
+    }
+
+    /// Write the export to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LaiCryptoError> {
+        let path = path.as_ref();
+        fs::write(path, self.export())
+            .map_err(|e| LaiCryptoError::io(format!("writing {}", path.display()), e))
+    }
+
+    /// Read a directory saved by [`PublicKeyDirectory::save`]
+    pub fn load(engine: &LaiCryptoEngine, path: impl AsRef<Path>) -> Result<Self, LaiCryptoError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .map_err(|e| LaiCryptoError::io(format!("reading {}", path.display()), e))?;
+        let mut directory = PublicKeyDirectory::new(engine);
+        directory.import(&bytes)?;
+        Ok(directory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_key_directory() {
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let mut directory = PublicKeyDirectory::new(&engine);
+        let keys: Vec<_> = (1..=3)
+            .map(|i| PublicKey::new(Point::new_unchecked(i, 2 * i)))
+            .collect();
+        let fingerprints: Vec<_> = keys.iter().map(|&k| directory.add(k)).collect();
+        assert_eq!(directory.add(keys[0]), fingerprints[0]);
+        assert_eq!(directory.len(), 3);
+        assert_eq!(directory.find(&fingerprints[1]), Some(&keys[1]));
+        assert_eq!(
+            fingerprints[2].to_string().parse::<Fingerprint>().unwrap(),
+            fingerprints[2]
+        );
+        let prefix = fingerprints[2].to_string()[..8].to_uppercase();
+        assert_eq!(
+            directory.find_prefix(&prefix).next().map(|(_, k)| *k),
+            Some(keys[2])
+        );
+
+        let other = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        assert_ne!(keys[0].fingerprint(&other), fingerprints[0]);
+
+        let path =
+            std::env::temp_dir().join(format!("laicrypto-directory-{}.bin", std::process::id()));
+        directory.save(&path).unwrap();
+        let loaded = PublicKeyDirectory::load(&engine, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.public_keys().collect::<Vec<_>>(),
+            directory.public_keys().collect::<Vec<_>>()
+        );
+
+        let mut foreign = PublicKeyDirectory::new(&other);
+        assert!(foreign.import(&directory.export()).is_err());
+        assert!(foreign.is_empty());
+        assert_eq!(directory.remove(&fingerprints[0]), Some(keys[0]));
+        assert_eq!(directory.import(&loaded.export()).unwrap(), 1);
+    }
+}
@@ -0,0 +1,348 @@
+//! Recipient key types
+//!
+//! Both key types have a fixed-length raw encoding for interop with
+//! systems that take plain byte strings:
+//!
+//! ```text
+//! PublicKey  x (16, big-endian) | y (16, big-endian)   32 bytes
+//! SecretKey  k (16, big-endian)                        16 bytes
+//! ```
+//!
+//! Raw bytes carry no parameters or version; decoding checks them against
+//! the [`CurveParams`] or modulus the caller supplies, and nothing else.
+
+use crate::audit::{Finding, FindingKind, Severity};
+use crate::{redact, CurveParams, LaiCryptoEngine, LaiCryptoError, Point, CHAIN_SEED};
+use secrecy::{ExposeSecret, SecretBox};
+use std::fmt;
+use std::time::Duration;
+use subtle::{Choice, ConstantTimeEq};
+
+/// Recipient private scalar k
+///
+/// Held in a [`SecretBox`] that is zeroed on drop; the scalar is only
+/// reachable through [`ExposeSecret::expose_secret`]. `Debug` prints it as
+/// [`redact::REDACTED`] unless the `unsafe-debug` feature is enabled, and
+/// the type is deliberately not serializable. Equality runs in constant
+/// time.
+#[derive(Clone)]
+pub struct SecretKey(SecretBox<u128>);
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretKey")
+            .field(&redact::Redacted(self.expose_secret()))
+            .finish()
+    }
+}
+
+impl SecretKey {
+    /// Length of [`SecretKey::to_bytes`]
+    pub const ENCODED_LEN: usize = 16;
+
+    pub fn new(k: u128) -> Self {
+        SecretKey(SecretBox::new(Box::new(k)))
+    }
+
+    /// k as 16 big-endian bytes, zeroed on drop like the key itself
+    pub fn to_bytes(&self) -> SecretBox<[u8; Self::ENCODED_LEN]> {
+        SecretBox::new(Box::new(self.expose_secret().to_be_bytes()))
+    }
+
+    /// Decode a [`SecretKey::to_bytes`] encoding, requiring 1 ≤ k < p
+    pub fn from_bytes(bytes: &[u8], p: u128) -> Result<Self, LaiCryptoError> {
+        let bytes: &[u8; Self::ENCODED_LEN] =
+            bytes
+                .try_into()
+                .map_err(|_| LaiCryptoError::InvalidParameter {
+                    param: "secret_key".to_string(),
+                    value: format!("{} bytes", bytes.len()),
+                    reason: "Wrong encoded key length".to_string(),
+                    valid_range: format!("exactly {} bytes", Self::ENCODED_LEN),
+                })?;
+        let key = SecretKey::new(u128::from_be_bytes(*bytes));
+        let k = *key.expose_secret();
+        if k == 0 || k >= p {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "secret_key".to_string(),
+                value: redact::REDACTED.to_string(),
+                reason: "Scalar out of range".to_string(),
+                valid_range: format!("1..{}", p),
+            });
+        }
+        Ok(key)
+    }
+}
+
+impl ExposeSecret<u128> for SecretKey {
+    fn expose_secret(&self) -> &u128 {
+        self.0.expose_secret()
+    }
+}
+
+impl ConstantTimeEq for SecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.expose_secret().ct_eq(other.expose_secret())
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl From<u128> for SecretKey {
+    fn from(k: u128) -> Self {
+        SecretKey::new(k)
+    }
+}
+
+/// Recipient public key Q = f^k(P0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey(Point);
+
+impl PublicKey {
+    /// Length of [`PublicKey::to_bytes`]
+    pub const ENCODED_LEN: usize = Point::ENCODED_LEN;
+
+    pub fn new(point: Point) -> Self {
+        PublicKey(point)
+    }
+
+    pub fn point(&self) -> Point {
+        self.0
+    }
+
+    /// Check a received key against the parameters, most severe findings first
+    ///
+    /// Unreduced coordinates are `Critical`; the identity and other points
+    /// with y = 0 are `High`. The curve equation is not checked, since
+    /// f^k(P0) does not satisfy it. An empty list means the key passed.
+    /// [`LaiCryptoEngine::validate_public_key`] adds the checks that need the
+    /// base point.
+    pub fn validate(&self, params: &CurveParams) -> Vec<Finding> {
+        let (x, y) = self.0.into();
+        let mut findings = Vec::new();
+        if x >= params.p || y >= params.p {
+            findings.push(Finding::new(
+                FindingKind::KeyOutOfRange,
+                Severity::Critical,
+                format!("Public key {} has a coordinate ≥ p = {}", self.0, params.p),
+                "Reject the key; coordinates must be reduced modulo p",
+            ));
+        } else if y == 0 {
+            findings.push(Finding::new(
+                FindingKind::WeakKeyPoint,
+                Severity::High,
+                if self.0.is_identity() {
+                    format!("Public key is the identity {}", self.0)
+                } else {
+                    format!("Public key {} has y = 0", self.0)
+                },
+                "Reject the key and ask the peer to generate a new one",
+            ));
+        }
+        findings
+    }
+
+    /// Big-endian x followed by big-endian y, as [`Point::to_bytes`]
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        self.0.to_bytes()
+    }
+
+    /// Decode a [`PublicKey::to_bytes`] encoding
+    ///
+    /// Fails on a wrong length, an unreduced coordinate or a degenerate
+    /// point with y = 0, the checks [`PublicKey::validate`] makes; the most
+    /// severe finding gives the error's reason.
+    pub fn from_bytes(bytes: &[u8], params: &CurveParams) -> Result<Self, LaiCryptoError> {
+        let bytes: &[u8; Self::ENCODED_LEN] =
+            bytes
+                .try_into()
+                .map_err(|_| LaiCryptoError::InvalidParameter {
+                    param: "public_key".to_string(),
+                    value: format!("{} bytes", bytes.len()),
+                    reason: "Wrong encoded key length".to_string(),
+                    valid_range: format!("exactly {} bytes", Self::ENCODED_LEN),
+                })?;
+        let mut x = [0u8; 16];
+        let mut y = [0u8; 16];
+        x.copy_from_slice(&bytes[..16]);
+        y.copy_from_slice(&bytes[16..]);
+        let key = PublicKey(Point::new_unchecked(
+            u128::from_be_bytes(x),
+            u128::from_be_bytes(y),
+        ));
+        match key.validate(params).into_iter().next() {
+            None => Ok(key),
+            Some(finding) => Err(LaiCryptoError::InvalidParameter {
+                param: "public_key".to_string(),
+                value: key.0.to_string(),
+                reason: finding.message,
+                valid_range: "a reduced point with y ≠ 0".to_string(),
+            }),
+        }
+    }
+}
+
+impl From<Point> for PublicKey {
+    fn from(point: Point) -> Self {
+        PublicKey(point)
+    }
+}
+
+/// Transforms of the base point's chain [`LaiCryptoEngine::validate_public_key`] searches
+pub const SMALL_KEY_STEPS: u128 = 256;
+
+impl LaiCryptoEngine {
+    /// [`PublicKey::validate`] plus checks against this engine's base point
+    ///
+    /// A key equal to `p0`, or to any of the first [`SMALL_KEY_STEPS`]
+    /// points of its chain, has a secret small enough to find by walking
+    /// the chain and is reported as `Critical`. Engine tracing is suspended
+    /// during the walk.
+    pub fn validate_public_key(&mut self, key: &PublicKey) -> Vec<Finding> {
+        let mut findings = key.validate(&self.curve());
+        if findings.iter().all(|f| f.severity < Severity::Critical) {
+            let tracing = std::mem::replace(&mut self.tracing, false);
+            let p0 = self.p0;
+            let steps = std::iter::successors(Some(p0), |&point| self.t(point, CHAIN_SEED).ok())
+                .take(SMALL_KEY_STEPS as usize + 1)
+                .position(|point| point == key.point());
+            self.tracing = tracing;
+            if let Some(k) = steps {
+                findings.push(Finding::new(
+                    FindingKind::WeakKeyPoint,
+                    Severity::Critical,
+                    format!("Public key {} is f^{}(P0)", key.point(), k),
+                    "Reject the key; its secret is recoverable",
+                ));
+            }
+        }
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    }
+}
+
+/// How [`LaiCryptoEngine::keygen`] arrived at a key pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeygenReport {
+    /// Scalars drawn, the successful one included; every earlier one failed
+    pub attempts: u32,
+    /// T-transforms evaluated across all attempts
+    pub t_steps: u32,
+    pub duration: Duration,
+}
+
+/// Output of [`LaiCryptoEngine::keygen`]
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    pub public: PublicKey,
+    pub secret: SecretKey,
+    pub report: KeygenReport,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_key_validation() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let curve = engine.curve();
+        let kinds = |findings: Vec<Finding>| findings.iter().map(|f| f.kind).collect::<Vec<_>>();
+
+        let unreduced = PublicKey::new(Point::new_unchecked(1009, 1));
+        assert_eq!(
+            kinds(unreduced.validate(&curve)),
+            [FindingKind::KeyOutOfRange]
+        );
+        let identity = PublicKey::new(Point::IDENTITY);
+        assert_eq!(
+            kinds(identity.validate(&curve)),
+            [FindingKind::WeakKeyPoint]
+        );
+
+        // y² = x³ + 3x has a square root for some x with y ≠ 0
+        let (x, y) = (1..1009)
+            .find_map(|x| {
+                engine
+                    .sqrt_mod(curve.rhs(x))
+                    .filter(|&y| y != 0)
+                    .map(|y| (x, y))
+            })
+            .unwrap();
+        let good = PublicKey::new(Point::new(x, y, &curve).unwrap());
+        assert!(good.validate(&curve).is_empty());
+        let extreme = PublicKey::new(Point::new_unchecked(u128::MAX, u128::MAX));
+        assert_eq!(
+            kinds(extreme.validate(&CurveParams {
+                p: u128::MAX - 158,
+                a: 3
+            })),
+            [FindingKind::KeyOutOfRange]
+        );
+
+        let findings = engine.validate_public_key(&identity);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(engine.trace.is_empty());
+
+        // Real keys are off the curve; only a short chain gives them away
+        let p0 = engine.p0;
+        let small = PublicKey::new(engine.pow_t(p0, 5).unwrap());
+        assert!(!small.point().is_on_curve(&curve));
+        assert!(small.validate(&curve).is_empty());
+        let findings = engine.validate_public_key(&small);
+        assert_eq!(kinds(findings.clone()), [FindingKind::WeakKeyPoint]);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(findings[0].message.ends_with("is f^5(P0)"));
+        let large = PublicKey::new(engine.pow_t(p0, 400).unwrap());
+        assert!(engine.validate_public_key(&large).is_empty());
+    }
+
+    #[test]
+    fn test_raw_key_bytes() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let curve = engine.curve();
+        let p0 = engine.p0;
+        let public = PublicKey::new(engine.pow_t(p0, 8).unwrap());
+        let bytes = public.to_bytes();
+        assert_eq!(bytes[..16], public.point().x().to_be_bytes());
+        assert_eq!(PublicKey::from_bytes(&bytes, &curve).unwrap(), public);
+        // Every engine key except the y = 0 point at step 100 round-trips
+        let keys: Vec<_> = (1..200).map(|k| PublicKey::new(engine.pow_t(p0, k).unwrap())).collect();
+        let decoded = keys
+            .iter()
+            .filter(|key| PublicKey::from_bytes(&key.to_bytes(), &curve).as_ref() == Ok(*key))
+            .count();
+        assert_eq!(decoded, keys.len() - 1);
+        assert!(PublicKey::from_bytes(&bytes[1..], &curve).is_err());
+        for bad in [
+            Point::IDENTITY,
+            Point::new_unchecked(1, 0),
+            Point::new_unchecked(1009, 1),
+        ] {
+            let err = PublicKey::from_bytes(&bad.to_bytes(), &curve).unwrap_err();
+            assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+        }
+
+        let secret = SecretKey::new(1008);
+        let bytes = secret.to_bytes();
+        assert_eq!(bytes.expose_secret()[14..], [0x03, 0xf0]);
+        assert_eq!(
+            SecretKey::from_bytes(bytes.expose_secret(), 1009).unwrap(),
+            secret
+        );
+        assert!(SecretKey::from_bytes(bytes.expose_secret(), 1008).is_err());
+        assert!(SecretKey::from_bytes(&[0; 16], 1009).is_err());
+        let err = SecretKey::from_bytes(&[1; 17], 1009).unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+        assert!(matches!(
+            SecretKey::from_bytes(&1009u128.to_be_bytes(), 1009),
+            Err(LaiCryptoError::InvalidParameter { value, .. }) if value == redact::REDACTED
+        ));
+    }
+}
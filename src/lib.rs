@@ -12,798 +12,695 @@
 //! - Custom graphing module for cryptographic metrics
 //! - Prime validation and parameter verification
 //! - Complete operational history tracking
+//!
+//! Graphing, history/metrics recording and tracing sit behind the default
+//! `graphs`, `metrics` and `trace` features; build with
+//! `default-features = false` to leave them out.
+//!
+//! # Layout
+//! The core types live in private modules and are re-exported here: field
+//! arithmetic (`field`), [`CurveParams`] and [`Point`] (`curve`), the key
+//! and [`Ciphertext`] types, [`LaiCryptoEngine`] itself (`engine`),
+//! [`TraceStep`] (`trace`), [`PerfMetrics`] (`metrics`) and, with
+//! `graphs`, [`CryptoGraph`] and its renderers (`graph`).
+
+pub mod analysis;
+pub mod armor;
+#[cfg(feature = "der")]
+pub mod asn1;
+pub mod attack;
+pub mod audit;
+pub mod batch;
+pub mod blinding;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod chunked;
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "hybrid")]
+pub mod composite;
+pub mod conformance;
+pub mod detect;
+pub mod domain;
+pub mod envelope;
+pub mod exchange;
+pub mod field;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod hash;
+pub mod homomorphic;
+pub mod keyring;
+pub mod metadata;
+pub mod multiexp;
+pub mod hybrid;
+pub mod int;
+pub mod noise;
+pub mod operation;
+pub mod padding;
+pub mod pake;
+pub mod precompute;
+pub mod preset;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod ratchet;
+pub mod redact;
+#[cfg(feature = "metrics")]
+pub mod registry;
+#[cfg(feature = "report")]
+pub mod report;
+pub mod reuse;
+mod rng;
+pub mod rotation;
+pub mod security;
+pub mod session;
+pub mod signcrypt;
+pub mod sidechannel;
+pub mod simd;
+pub mod siv;
+pub mod sqrt;
+pub mod subkey;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+#[cfg(feature = "bincode")]
+pub mod snapshot;
+pub mod threshold;
+#[cfg(feature = "rustls")]
+pub mod tls;
+
+mod ciphertext;
+mod curve;
+mod engine;
+mod error;
+#[cfg(feature = "graphs")]
+mod graph;
+mod keys;
+mod metrics;
+mod trace;
+
+/// Accessor for [`SecretKey`] and [`exchange::SharedSecret`] contents
+pub use secrecy::ExposeSecret;
+
+pub use ciphertext::{Ciphertext, CiphertextVersion, TAG_LEN};
+pub use curve::{CurveParams, Point};
+pub use engine::{LaiCryptoEngine, Orbit, CHAIN_SEED, T_SEED_RETRIES};
+pub use error::{ErrorKind, ErrorSource, LaiCryptoError};
+pub use field::random_scalar;
+#[cfg(feature = "graphs")]
+pub use graph::*;
+pub use keys::{KeyPair, KeygenReport, PublicKey, SecretKey, SMALL_KEY_STEPS};
+pub use metrics::PerfMetrics;
+pub use trace::TraceStep;
+
+pub(crate) use ciphertext::{confirmation_tag_v2, kdf_mask};
+pub(crate) use error::{json_object, json_string};
+pub(crate) use field::{add_mod, has_sqrt, is_prime, jacobi, mul_mod, pow_mod, reduce_wide};
+#[cfg(any(feature = "shake", feature = "blake3"))]
+pub(crate) use field::sample_below;
 
-use rand::{rngs::OsRng, RngCore};
-use sha2::{Digest, Sha512};
-use std::{
-    collections::HashMap,
-    fmt,
-    time::{Duration, Instant},
-};
-
-/// Comprehensive error types with solution guidance
-#[derive(Debug, Clone, PartialEq)]
-pub enum LaiCryptoError {
-    /// Modular square root failure (Tonelli-Shanks)
-    SqrtFailure {
-        input: u128,
-        modulus: u128,
-        attempts: u32,
-        advice: String,
-    },
-    /// T-transform failure with context
-    TransformFailure {
-        point: (u128, u128),
-        s: u128,
-        steps: Vec<TraceStep>,
-        advice: String,
-    },
-    /// Key generation failure
-    KeygenFailed {
-        attempts: u32,
-        modulus: u128,
-        base_point: (u128, u128),
-        advice: String,
-    },
-    /// Parameter validation failure
-    InvalidParameter {
-        param: String,
-        value: String,
-        reason: String,
-        valid_range: String,
-    },
-    /// Operation timeout
-    Timeout {
-        operation: String,
-        duration: Duration,
-        max_duration: Duration,
-    },
-    /// Cryptographic validation failure
-    ValidationError {
-        operation: String,
-        expected: String,
-        actual: String,
-    },
-    /// Graph rendering error
-    GraphError {
-        context: String,
-        cause: String,
-    },
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    #[cfg(feature = "graphs")]
+    use std::collections::HashMap;
+    use std::{io, time::Duration};
 
-impl fmt::Display for LaiCryptoError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::SqrtFailure { input, modulus, attempts, advice } => write!(
-                f,
-                "Square root failure for {} mod {} after {} attempts. {}",
-                input, modulus, attempts, advice
-            ),
-            Self::TransformFailure { point, s, steps, advice } => write!(
-                f,
-                "T-transform failed at point ({}, {}) with s={} after {} steps. {}",
-                point.0, point.1, s, steps.len(), advice
-            ),
-            Self::KeygenFailed { attempts, modulus, base_point, advice } => write!(
-                f,
-                "Key generation failed after {} attempts (modulus={}, base_point=({}, {})). {}",
-                attempts, modulus, base_point.0, base_point.1, advice
-            ),
-            Self::InvalidParameter { param, value, reason, valid_range } => write!(
-                f,
-                "Invalid parameter {}: {} (value={}). Valid range: {}",
-                param, reason, value, valid_range
-            ),
-            Self::Timeout { operation, duration, max_duration } => write!(
-                f,
-                "Operation '{}' timed out after {:?} (max allowed: {:?})",
-                operation, duration, max_duration
-            ),
-            Self::ValidationError { operation, expected, actual } => write!(
-                f,
-                "Validation failed for {}: expected {}, got {}",
-                operation, expected, actual
-            ),
-            Self::GraphError { context, cause } => {
-                write!(f, "Graph error in {}: {}", context, cause)
-            }
-        }
+    fn test_prime() -> u128 {
+        // 128-bit prime: 2^128 - 159
+        340_282_366_920_938_463_463_374_607_431_768_211_297
     }
-}
-
-/// Detailed transformation step recording
-#[derive(Debug, Clone, PartialEq)]
-pub struct TraceStep {
-    pub step: u32,
-    pub input: (u128, u128),
-    pub s: u128,
-    pub h: u128,
-    pub x1: u128,
-    pub y2: u128,
-    pub y1: Option<u128>,
-    pub output: Option<(u128, u128)>,
-    pub duration: Duration,
-}
 
-/// Performance metrics for operations
-#[derive(Debug, Clone)]
-pub struct PerfMetrics {
-    pub keygen_time: Duration,
-    pub encrypt_time: Duration,
-    pub decrypt_time: Duration,
-    pub t_transform_count: u32,
-    pub sqrt_attempts: u32,
-    pub operation_history: Vec<(String, Duration)>,
-}
+    #[test]
+    fn test_engine_creation() {
+        let prime = test_prime();
+        // x³ + ax is 11 at x = 1, a square mod this prime; at x = 5 it is not
+        let engine = LaiCryptoEngine::new(prime, 10, (1, 0));
+        assert!(engine.is_ok());
+    }
 
-/// Graphing module for cryptographic visualization
-#[derive(Debug, Clone)]
-pub struct CryptoGraph {
-    pub title: String,
-    pub data: Vec<(f64, f64)>,
-    pub labels: HashMap<String, String>,
-    pub style: GraphStyle,
-}
+    // A random k needs k steps of the fixed-seed chain, which a 128-bit
+    // modulus cannot afford, so key generation runs on the toy prime
+    #[test]
+    fn test_key_gen() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let key = engine.keygen();
+        assert!(key.is_ok());
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum GraphStyle {
-    Line,
-    Scatter,
-    Histogram,
-}
+    #[test]
+    fn test_encryption() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let keys = engine.keygen().unwrap();
+        let message = 12345;
+        let enc_result = engine.encrypt(message, keys.public.point());
+        assert!(enc_result.is_ok());
+    }
 
-impl CryptoGraph {
-    /// Renders graph to ASCII art
-    pub fn render_ascii(&self, width: usize, height: usize) -> Result<String, LaiCryptoError> {
-        if self.data.is_empty() {
-            return Err(LaiCryptoError::GraphError {
-                context: "render_ascii".to_string(),
-                cause: "No data to plot".to_string(),
-            });
-        }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_ascii_graph() {
+        let graph = CryptoGraph {
+            title: "Test Graph".to_string(),
+            data: vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0), (3.0, 4.0)],
+            labels: [
+                ("x".to_string(), "X Axis".to_string()),
+                ("y".to_string(), "Y Axis".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        let mut min_x = f64::MAX;
-        let mut max_x = f64::MIN;
-        let mut min_y = f64::MAX;
-        let mut max_y = f64::MIN;
+        let ascii = graph.render_ascii(60, 20);
+        assert!(ascii.is_ok());
+        println!("{}", ascii.unwrap());
 
-        for &(x, y) in &self.data {
-            min_x = min_x.min(x);
-            max_x = max_x.max(x);
-            min_y = min_y.min(y);
-            max_y = max_y.max(y);
+        assert!(graph.render_ascii(3, 3).is_ok());
+        for (width, height) in [(2, 20), (60, 2), (0, 0)] {
+            assert!(matches!(
+                graph.render_ascii(width, height),
+                Err(LaiCryptoError::GraphError { .. })
+            ));
         }
+    }
 
-        let x_range = max_x - min_x;
-        let y_range = max_y - min_y;
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_braille_graph() {
+        let graph = CryptoGraph {
+            title: "Braille".to_string(),
+            data: vec![(0.0, 0.0), (1.0, 1.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Braille,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        if x_range <= 0.0 || y_range <= 0.0 {
-            return Err(LaiCryptoError::GraphError {
-                context: "render_ascii".to_string(),
-                cause: "Invalid data range".to_string(),
-            });
-        }
+        let braille = graph.render_ascii(4, 3).unwrap();
+        // Single plot cell: bottom-left and top-right sub-pixels
+        assert_eq!(braille.lines().nth(1), Some("|\u{2840}\u{2808}|"));
+        assert!(graph.render_braille(2, 2).is_err());
+    }
 
-        let mut grid = vec![vec![' '; width]; height];
-        let mut result = String::new();
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_graph_color_mode() {
+        let mut graph = CryptoGraph::new("", vec![(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)], GraphStyle::Scatter);
+        assert_eq!(graph.color, ColorMode::Auto);
+        assert!(graph.labels.is_empty() && graph.series.is_empty() && !graph.strict);
+        graph.color = ColorMode::Never;
+        assert!(!graph.render_ascii(10, 6).unwrap().contains('\x1b'));
+
+        graph.color = ColorMode::Always;
+        let colored = graph.render_ascii(10, 6).unwrap();
+        assert!(colored.contains(MIN_COLOR));
+        assert!(colored.contains(MAX_COLOR));
+        assert!(colored.contains(SERIES_COLORS[0]));
+    }
 
-        // Add border
-        for row in 0..height {
-            grid[row][0] = '|';
-            grid[row][width - 1] = '|';
-        }
-        for col in 0..width {
-            grid[0][col] = '-';
-            grid[height - 1][col] = '-';
-        }
-        grid[0][0] = '+';
-        grid[0][width - 1] = '+';
-        grid[height - 1][0] = '+';
-        grid[height - 1][width - 1] = '+';
-
-        // Plot data points
-        for &(x, y) in &self.data {
-            let col = ((x - min_x) / x_range * (width - 2) as f64) as usize + 1;
-            let row = height - 1 - ((y - min_y) / y_range * (height - 2) as f64) as usize;
-
-            if row < height && col < width {
-                grid[row][col] = match self.style {
-                    GraphStyle::Scatter => '●',
-                    GraphStyle::Line => '•',
-                    GraphStyle::Histogram => '█',
-                };
-            }
-        }
+    #[cfg(feature = "plot-svg")]
+    #[test]
+    fn test_svg_export() {
+        let graph = CryptoGraph {
+            title: "Latency <ms>".to_string(),
+            data: vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0)],
+            labels: [("x".to_string(), "Op".to_string())].iter().cloned().collect(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        // Add title
-        if !self.title.is_empty() {
-            let title_pos = (width.saturating_sub(self.title.len())) / 2;
-            for (i, c) in self.title.chars().enumerate() {
-                if title_pos + i < width {
-                    grid[0][title_pos + i] = c;
-                }
-            }
-        }
+        let svg = graph.render_svg(400, 300).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("Latency &lt;ms&gt;"));
+        assert!(svg.contains("<polyline"));
+        assert!(graph.render_svg(10, 10).is_err());
+    }
 
-        // Add axis labels
-        if let Some(x_label) = self.labels.get("x") {
-            let label_pos = (width.saturating_sub(x_label.len())) / 2;
-            for (i, c) in x_label.chars().enumerate() {
-                if label_pos + i < width {
-                    grid[height - 1][label_pos + i] = c;
-                }
-            }
-        }
+    #[cfg(feature = "plot-png")]
+    #[test]
+    fn test_png_export() {
+        let graph = CryptoGraph {
+            title: "Timeline".to_string(),
+            data: vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Histogram,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        if let Some(y_label) = self.labels.get("y") {
-            let label_pos = height / 2;
-            for (i, c) in y_label.chars().enumerate() {
-                if i < height && label_pos < width {
-                    grid[label_pos][0] = c;
-                }
-            }
-        }
+        let png = graph.render_png(320, 240, &GraphTheme::dark()).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes([png[16], png[17], png[18], png[19]]), 320);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
 
-        // Build output string
-        for row in grid {
-            result.extend(row);
-            result.push('\n');
-        }
+    #[cfg(feature = "plot-sixel")]
+    #[test]
+    fn test_sixel_export() {
+        let graph = CryptoGraph {
+            title: "Sixel".to_string(),
+            data: vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Scatter,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        Ok(result)
+        let sixel = graph.render_sixel(240, 160, &GraphTheme::light()).unwrap();
+        assert!(sixel.starts_with("\x1bPq\"1;1;240;160"));
+        assert!(sixel.ends_with("\x1b\\"));
+        // 160 pixel rows are drawn as ceil(160 / 6) sixel bands
+        assert_eq!(sixel.matches('-').count(), 27);
     }
-}
 
-/// LAI cryptographic engine with enhanced capabilities
-pub struct LaiCryptoEngine {
-    pub p: u128,
-    pub a: u128,
-    pub p0: (u128, u128),
-    pub trace: Vec<TraceStep>,
-    pub metrics: PerfMetrics,
-    pub max_attempts: u32,
-    pub max_duration: Duration,
-}
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_sparkline() {
+        let graph = CryptoGraph {
+            title: String::new(),
+            data: (0..8).map(|i| (i as f64, i as f64)).collect(),
+            labels: HashMap::new(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-impl LaiCryptoEngine {
-    /// Create new engine with parameter validation
-    pub fn new(p: u128, a: u128, p0: (u128, u128)) -> Result<Self, LaiCryptoError> {
-        // Validate parameters
-        if p < 100 {
-            return Err(LaiCryptoError::InvalidParameter {
-                param: "p".to_string(),
-                value: p.to_string(),
-                reason: "Modulus too small (min 100)".to_string(),
-                valid_range: "100 ≤ p ≤ 2^128-1".to_string(),
-            });
-        }
+        assert_eq!(graph.render_sparkline(8).unwrap(), "▁▂▃▄▅▆▇█");
+        assert_eq!(graph.render_sparkline(2).unwrap(), "▁█");
+        assert_eq!(graph.render_sparkline(20).unwrap().chars().count(), 8);
+    }
 
-        if !is_prime(p) {
-            return Err(LaiCryptoError::InvalidParameter {
-                param: "p".to_string(),
-                value: p.to_string(),
-                reason: "Modulus must be prime".to_string(),
-                valid_range: "Prime numbers only".to_string(),
-            });
-        }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_heatmap_graph() {
+        let graph = CryptoGraph {
+            title: String::new(),
+            data: vec![(0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (1.0, 1.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Heatmap,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        if a >= p {
-            return Err(LaiCryptoError::InvalidParameter {
-                param: "a".to_string(),
-                value: a.to_string(),
-                reason: "Parameter a must be less than modulus".to_string(),
-                valid_range: format!("0 ≤ a < {}", p),
-            });
-        }
+        let heatmap = graph.render_ascii(4, 4).unwrap();
+        let rows: Vec<&str> = heatmap.lines().collect();
+        assert_eq!(rows[1], "| ░|");
+        assert_eq!(rows[2], "|█ |");
+    }
 
-        // Verify base point
-        let y_sq = (p0.0 * p0.0 * p0.0 + a * p0.0) % p;
-        if !has_sqrt(y_sq, p) {
-            return Err(LaiCryptoError::InvalidParameter {
-                param: "p0".to_string(),
-                value: format!("({}, {})", p0.0, p0.1),
-                reason: "Base point not on curve".to_string(),
-                valid_range: "Valid curve points".to_string(),
-            });
-        }
+    #[cfg(feature = "plot-html")]
+    #[test]
+    fn test_html_export() {
+        let graph = CryptoGraph {
+            title: "History <1>".to_string(),
+            data: vec![(0.0, 1.5), (1.0, f64::NAN), (2.0, 2.0)],
+            labels: [("y".to_string(), "</script>".to_string())].iter().cloned().collect(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-        Ok(Self {
-            p,
-            a,
-            p0,
-            trace: Vec::new(),
-            metrics: PerfMetrics {
-                keygen_time: Duration::default(),
-                encrypt_time: Duration::default(),
-                decrypt_time: Duration::default(),
-                t_transform_count: 0,
-                sqrt_attempts: 0,
-                operation_history: Vec::new(),
-            },
-            max_attempts: 100,
-            max_duration: Duration::from_secs(5),
-        })
+        let html = graph.render_html().unwrap();
+        assert!(html.contains("<title>History &lt;1&gt;</title>"));
+        assert!(html.contains("const SERIES = [{ name: \"data\", data: [[0,1.5],[2,2]] }];"));
+        assert_eq!(html.matches("</script>").count(), 1);
+        assert!(!html.contains("http"));
     }
 
-    /// Record operation metrics
-    fn record_operation(&mut self, op: &str, duration: Duration) {
-        self.metrics.operation_history.push((op.to_string(), duration));
-    }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_label_fitting() {
+        let graph = CryptoGraph {
+            title: "A very long graph title".to_string(),
+            data: vec![(0.0, 0.0), (1.0, 1.0)],
+            labels: [("y".to_string(), "Time".to_string())].iter().cloned().collect(),
+            style: GraphStyle::Scatter,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-    /// Modular exponentiation (optimized)
-    pub fn mod_pow(&self, mut base: u128, mut exp: u128) -> u128 {
-        let m = self.p;
-        let mut result = 1u128;
-        base %= m;
-        while exp > 0 {
-            if exp & 1 == 1 {
-                result = (result * base) % m;
-            }
-            base = (base * base) % m;
-            exp >>= 1;
-        }
-        result
+        let ascii = graph.render_ascii(10, 8).unwrap();
+        let rows: Vec<Vec<char>> = ascii.lines().map(|l| l.chars().collect()).collect();
+        assert_eq!(rows[0].iter().collect::<String>(), "+A very …+");
+        let y_label: String = rows.iter().map(|r| r[0]).collect();
+        assert_eq!(y_label, "+|Time|+");
+        assert!(graph.render_ascii_auto().is_ok());
     }
 
-    /// Modular square root with detailed error handling
-    pub fn sqrt_mod(&mut self, a: u128) -> Option<u128> {
-        let a = a % self.p;
-        if a == 0 {
-            return Some(0);
-        }
-        if self.mod_pow(a, (self.p - 1) / 2) == self.p - 1 {
-            return None;
-        }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_graph_smoothing_and_downsampling() {
+        let mut graph = CryptoGraph {
+            title: String::new(),
+            data: vec![(0.0, 0.0), (1.0, 4.0), (2.0, 0.0), (3.0, 4.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
+        graph.smooth(Smoothing::MovingAverage(2)).unwrap();
+        assert_eq!(graph.data, vec![(0.0, 0.0), (1.0, 2.0), (2.0, 2.0), (3.0, 2.0)]);
+        assert!(graph.smooth(Smoothing::Ema(0.0)).is_err());
+
+        graph.data = (0..100).map(|i| (i as f64, (i % 7) as f64)).collect();
+        graph.downsample(Downsampling::Lttb(10));
+        assert_eq!(graph.data.len(), 10);
+        assert_eq!(graph.data[0], (0.0, 0.0));
+        assert_eq!(graph.data[9], (99.0, 1.0));
+
+        graph.data = (0..100).map(|i| (i as f64, (i % 7) as f64)).collect();
+        graph.downsample(Downsampling::MinMax(5));
+        assert_eq!(graph.data.len(), 10);
+        assert!(graph.data.windows(2).all(|w| w[0].0 < w[1].0));
+    }
 
-        let mut attempts = 0;
-        let result = match self.p % 4 {
-            3 => Some(self.mod_pow(a, (self.p + 1) / 4)),
-            _ => {
-                let mut q = self.p - 1;
-                let mut s = 0;
-                while q % 2 == 0 {
-                    q /= 2;
-                    s += 1;
-                }
-
-                let mut z = 2;
-                while self.mod_pow(z, (self.p - 1) / 2) != self.p - 1 {
-                    z += 1;
-                    attempts += 1;
-                }
-
-                let mut m = s;
-                let mut c = self.mod_pow(z, q);
-                let mut t = self.mod_pow(a, q);
-                let mut r = self.mod_pow(a, (q + 1) / 2);
-
-                while t != 1 {
-                    let mut i = 1;
-                    let mut t2i = self.mod_pow(t, 2);
-                    while t2i != 1 && i < m {
-                        t2i = self.mod_pow(t2i, 2);
-                        i += 1;
-                        attempts += 1;
-                    }
-                    if i == m {
-                        return None;
-                    }
-
-                    let b = self.mod_pow(c, 1 << (m - i - 1));
-                    m = i;
-                    c = (b * b) % self.p;
-                    t = (t * c) % self.p;
-                    r = (r * b) % self.p;
-                }
-                Some(r)
-            }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_constant_data_rendering() {
+        let mut graph = CryptoGraph {
+            title: String::new(),
+            data: vec![(0.0, 2.0), (1.0, 2.0), (2.0, 2.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Line,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
         };
 
-        self.metrics.sqrt_attempts += attempts;
-        result
-    }
+        let ascii = graph.render_ascii(12, 7).unwrap();
+        let rows: Vec<&str> = ascii.lines().collect();
+        assert_eq!(rows[3].matches('•').count(), 3);
 
-    /// Enhanced hash function for T-transform
-    pub fn h(&self, x: u128, y: u128, s: u128) -> u128 {
-        let mut hasher = Sha512::new();
-        hasher.update(x.to_be_bytes());
-        hasher.update(y.to_be_bytes());
-        hasher.update(s.to_be_bytes());
-        hasher.update(self.p.to_be_bytes());
-        let digest = hasher.finalize();
-        let mut res = 0u128;
-        for &b in digest.iter().take(16) {
-            res = (res << 8) | b as u128;
-        }
-        res % self.p
+        graph.strict = true;
+        assert!(graph.render_ascii(12, 7).is_err());
     }
 
-    /// Single T-transform with detailed tracing
-    pub fn t(&mut self, point: (u128, u128), s: u128) -> Result<(u128, u128), LaiCryptoError> {
-        let start = Instant::now();
-        let (x, y) = point;
-        let inv2 = self.mod_pow(2, self.p - 2);
-        let mut s_cur = s;
-        let mut steps = Vec::new();
-
-        for i in 0..10 {
-            let step_start = Instant::now();
-            let hh = self.h(x, y, s_cur);
-            let x1 = ((x + self.a + hh) * inv2) % self.p;
-            let y2 = (x * y + hh) % self.p;
-            let y1 = self.sqrt_mod(y2);
-            let step_duration = step_start.elapsed();
-
-            let output = y1.map(|y| (x1, y));
-            let step = TraceStep {
-                step: i,
-                input: (x, y),
-                s: s_cur,
-                h: hh,
-                x1,
-                y2,
-                y1,
-                output,
-                duration: step_duration,
-            };
-            steps.push(step.clone());
-            self.trace.push(step);
-            self.metrics.t_transform_count += 1;
-
-            if let Some(y_val) = y1 {
-                let duration = start.elapsed();
-                self.record_operation("t", duration);
-                return Ok((x1, y_val));
-            }
-            s_cur += 1;
-        }
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_graph_renderer_trait() {
+        struct CsvRenderer;
+
+        impl GraphRenderer for CsvRenderer {
+            type Output = String;
 
-        let duration = start.elapsed();
-        if duration > self.max_duration {
-            return Err(LaiCryptoError::Timeout {
-                operation: "t".to_string(),
-                duration,
-                max_duration: self.max_duration,
-            });
+            fn render(&self, graph: &CryptoGraph) -> Result<String, LaiCryptoError> {
+                Ok(graph.data.iter().map(|(x, y)| format!("{},{}\n", x, y)).collect())
+            }
         }
 
-        self.record_operation("t", duration);
-        Err(LaiCryptoError::TransformFailure {
-            point,
-            s,
-            steps,
-            advice: format!(
-                "Possible solutions:\n1. Increase modulus (current: {})\n2. Try different starting s (current: {})\n3. Verify curve parameters (a={})",
-                self.p, s, self.a
-            ),
-        })
-    }
+        let graph = CryptoGraph {
+            title: String::new(),
+            data: vec![(0.0, 1.0), (1.0, 2.0)],
+            labels: HashMap::new(),
+            style: GraphStyle::Scatter,
+            color: ColorMode::Never,
+            strict: false,
+            series: Vec::new(),
+        };
 
-    /// Apply T-transform multiple times
-    pub fn pow_t_range(
-        &mut self,
-        mut point: (u128, u128),
-        start_s: u128,
-        exp: u128,
-    ) -> Result<(u128, u128), LaiCryptoError> {
-        let start = Instant::now();
-        let mut s = start_s;
-        for _ in 0..exp {
-            point = self.t(point, s)?;
-            s += 1;
+        let backends: Vec<Box<dyn GraphRenderer<Output = String>>> = vec![
+            Box::new(AsciiRenderer { width: 10, height: 5 }),
+            Box::new(BrailleRenderer { width: 10, height: 5 }),
+            Box::new(CsvRenderer),
+        ];
+        for backend in &backends {
+            assert!(graph.render_with(backend.as_ref()).is_ok());
         }
-        let duration = start.elapsed();
-        self.record_operation("pow_t_range", duration);
-        Ok(point)
+        assert_eq!(graph.render_with(&CsvRenderer).unwrap(), "0,1\n1,2\n");
+        assert_eq!(
+            graph.render_with(&AsciiRenderer { width: 10, height: 5 }),
+            graph.render_ascii(10, 5)
+        );
     }
 
-    /// Key generation with validation
-    pub fn keygen(&mut self) -> Result<(u128, (u128, u128)), LaiCryptoError> {
-        let start = Instant::now();
-        for attempt in 0..self.max_attempts {
-            let mut buf = [0u8; 16];
-            OsRng.fill_bytes(&mut buf);
-            let k = u128::from_be_bytes(buf) % (self.p - 1) + 1;
-            match self.pow_t_range(self.p0, 1, k) {
-                Ok(q) => {
-                    // Validate generated key
-                    if q.0 >= self.p || q.1 >= self.p {
-                        continue;
-                    }
-
-                    let y_sq = (q.0 * q.0 * q.0 + self.a * q.0) % self.p;
-                    let y_actual = (q.1 * q.1) % self.p;
-                    
-                    if y_sq != y_actual {
-                        return Err(LaiCryptoError::ValidationError {
-                            operation: "keygen".to_string(),
-                            expected: format!("y² = {}", y_sq),
-                            actual: format!("{}", y_actual),
-                        });
-                    }
-
-                    let duration = start.elapsed();
-                    self.metrics.keygen_time = duration;
-                    self.record_operation("keygen", duration);
-                    return Ok((k, q));
-                }
-                Err(_e) => {
-                    if attempt == self.max_attempts - 1 {
-                        let _duration = start.elapsed();
-                        return Err(LaiCryptoError::KeygenFailed {
-                            attempts: self.max_attempts,
-                            modulus: self.p,
-                            base_point: self.p0,
-                            advice: format!("Key generation failed after {} attempts. Consider:\n1. Using larger modulus (current: {})\n2. Changing curve parameter (a={})\n3. Verifying base point ({}, {})",
-                                self.max_attempts, self.p, self.a, self.p0.0, self.p0.1),
-                        });
-                    }
-                }
-            }
-        }
-        unreachable!()
+    #[cfg(feature = "graphs")]
+    #[test]
+    fn test_compare_engines() {
+        let mut small = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let large = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        small.metrics.t_transform_count = 5;
+
+        let graph = CryptoGraph::compare(&[&small, &large], GraphMetric::TransformCount);
+        assert_eq!(graph.series.len(), 2);
+        assert_eq!(graph.series[0].name, "#1 p=1009 a=3");
+        assert_eq!(graph.series[0].data, vec![(0.0, 5.0)]);
+        assert_eq!(graph.series[1].data, vec![(1.0, 0.0)]);
+
+        let ascii = graph.render_ascii(20, 8).unwrap();
+        assert!(ascii.contains(SERIES_MARKERS[0]));
+        assert!(ascii.contains(SERIES_MARKERS[1]));
     }
 
-    /// Encryption with integrity checks
-    pub fn encrypt(
-        &mut self,
-        m: u128,
-        q: (u128, u128),
-    ) -> Result<((u128, u128), (u128, u128), u128), LaiCryptoError> {
-        let start = Instant::now();
-        let mut buf = [0u8; 16];
-        OsRng.fill_bytes(&mut buf);
-        let r = u128::from_be_bytes(buf) % (self.p - 1) + 1;
-
-        let c1 = self.pow_t_range(self.p0, 1, r)?;
-        let sr = self.pow_t_range(q, 1, r)?;
-        let c2 = ((m + sr.0) % self.p, sr.1);
-
-        let duration = start.elapsed();
-        self.metrics.encrypt_time = duration;
-        self.record_operation("encrypt", duration);
-        Ok((c1, c2, r))
+    #[test]
+    fn test_point_validation() {
+        let params = CurveParams { p: test_prime(), a: 10 };
+        // 1³ + 10·1 = 11 is not a square, but x = 0 always gives y = 0
+        assert!(Point::new(0, 0, &params).unwrap().is_identity());
+        assert!(Point::new(1, 1, &params).is_err());
+        assert!(Point::new(test_prime(), 0, &params).is_err());
+
+        let small = CurveParams { p: 1009, a: 3 };
+        let x = (0..1009).find(|&x| has_sqrt(small.rhs(x), 1009) && x > 0).unwrap();
+        let y = (0..1009).find(|&y| y * y % 1009 == small.rhs(x)).unwrap();
+        let point = Point::new(x, y, &small).unwrap();
+        assert_eq!(Point::from_bytes(&point.to_bytes(), &small), Ok(point));
+        assert!(Point::from_bytes(&point.to_bytes()[1..], &small).is_err());
+
+        // Full-width operands must not overflow
+        assert_eq!(mul_mod(test_prime() - 1, test_prime() - 1, test_prime()), 1);
     }
 
-    /// Decryption with validation
-    pub fn decrypt(
-        &mut self,
-        c1: (u128, u128),
-        c2: (u128, u128),
-        k: u128,
-    ) -> Result<u128, LaiCryptoError> {
-        let start = Instant::now();
-        let s_val = self.pow_t_range(c1, 1, k)?;
-        let m = (c2.0 + self.p - s_val.0) % self.p;
-
-        // Verify decryption integrity
-        if m >= self.p {
-            return Err(LaiCryptoError::ValidationError {
-                operation: "decrypt".to_string(),
-                expected: format!("message < {}", self.p),
-                actual: m.to_string(),
-            });
-        }
-
-        let duration = start.elapsed();
-        self.metrics.decrypt_time = duration;
-        self.record_operation("decrypt", duration);
-        Ok(m)
+    #[test]
+    fn test_orbit_matches_pow_t_range() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let expected = engine.pow_t_range(p0, 1, 5);
+
+        let mut orbit = engine.orbit(p0, 1);
+        let fifth = orbit.by_ref().nth(4).unwrap();
+        assert_eq!(fifth, expected);
+        assert_eq!(orbit.next_s(), 6);
     }
 
-    /// Generate performance graphs
-    pub fn generate_perf_graph(&self, style: GraphStyle) -> CryptoGraph {
-        let mut data = Vec::new();
-        for (i, (_, duration)) in self.metrics.operation_history.iter().enumerate() {
-            data.push((i as f64, duration.as_secs_f64() * 1000.0)); // ms
-        }
+    #[test]
+    fn test_error_source_chain() {
+        use std::error::Error;
 
-        CryptoGraph {
-            title: "Operation Timeline".to_string(),
-            data,
-            labels: [
-                ("x".to_string(), "Operation Sequence".to_string()),
-                ("y".to_string(), "Time (ms)".to_string()),
-            ]
-            .iter()
-            .cloned()
-            .collect(),
-            style,
-        }
-    }
+        let missing = io::Error::new(io::ErrorKind::NotFound, "no such file");
+        let err = LaiCryptoError::io("reading keys.json", missing);
+        assert_eq!(err.source().unwrap().to_string(), "no such file");
+        assert_eq!(err.to_string(), "I/O error while reading keys.json: no such file");
+        assert_eq!(err.clone(), err);
 
-    /// Generate complexity graph
-    pub fn generate_complexity_graph(&self) -> CryptoGraph {
-        let mut data = Vec::new();
-        for (i, step) in self.trace.iter().enumerate() {
-            data.push((i as f64, step.duration.as_secs_f64() * 1_000_000.0)); // µs
-        }
+        let parsed: Result<u128, LaiCryptoError> = "12x".parse::<u128>().map_err(Into::into);
+        assert!(matches!(parsed, Err(LaiCryptoError::Serialization { .. })));
 
-        CryptoGraph {
-            title: "T-transform Step Complexity".to_string(),
-            data,
-            labels: [
-                ("x".to_string(), "Step Index".to_string()),
-                ("y".to_string(), "Duration (µs)".to_string()),
-            ]
-            .iter()
-            .cloned()
-            .collect(),
-            style: GraphStyle::Line,
-        }
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(LaiCryptoError::Timeout {
+            operation: "t".to_string(),
+            duration: Duration::from_secs(6),
+            max_duration: Duration::from_secs(5),
+        });
+        assert!(boxed.source().is_none());
     }
 
-    /// Print detailed trace with diagnostics
-    pub fn print_trace(&self) {
-        println!("=== LAI Cryptographic Trace ===");
-        println!("Modulus: {}, Parameter a: {}", self.p, self.a);
-        println!("Base Point: ({}, {})", self.p0.0, self.p0.1);
-        println!("Operations: {}", self.metrics.operation_history.len());
-        println!("T-transforms: {}", self.metrics.t_transform_count);
-        println!("Sqrt attempts: {}", self.metrics.sqrt_attempts);
-        println!("\nDetailed Trace:");
-
-        for step in &self.trace {
-            println!(
-                "[Step {}] s={} | Input: ({}, {})",
-                step.step, step.s, step.input.0, step.input.1
-            );
-            println!("  Hash h={} | x'={}, y²={}", step.h, step.x1, step.y2);
-            print!("  Status: ");
-            match step.y1 {
-                Some(y) => println!("Success -> Output: ({}, {})", step.x1, y),
-                None => println!("Failure: No modular square root found"),
-            }
-            println!("  Duration: {:.3}µs", step.duration.as_secs_f64() * 1_000_000.0);
-            println!("{}", "-".repeat(60));
-        }
+    #[test]
+    fn test_error_kind() {
+        let interrupted = LaiCryptoError::io("writing", io::Error::from(io::ErrorKind::Interrupted));
+        assert_eq!(interrupted.kind(), ErrorKind::Io);
+        assert!(interrupted.is_retryable());
+        assert!(!LaiCryptoError::io("reading", io::Error::from(io::ErrorKind::NotFound)).is_retryable());
+
+        let err = LaiCryptoEngine::new(1001, 3, (0, 0)).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(!err.is_retryable());
+    }
 
-        println!("\nPerformance Metrics:");
-        println!("Key Generation: {:.3}ms", self.metrics.keygen_time.as_secs_f64() * 1000.0);
-        println!("Encryption: {:.3}ms", self.metrics.encrypt_time.as_secs_f64() * 1000.0);
-        println!("Decryption: {:.3}ms", self.metrics.decrypt_time.as_secs_f64() * 1000.0);
+    #[test]
+    fn test_error_json() {
+        let err = LaiCryptoError::ValidationError {
+            operation: "decrypt".to_string(),
+            expected: "tag \"ok\"".to_string(),
+            actual: "mismatch".to_string(),
+        };
+        assert_eq!(err.code(), 4001);
+        assert_eq!(err.code_name(), "LAI_VALIDATION_ERROR");
+        let json = err.to_json();
+        assert!(json.starts_with(r#"{"code":4001,"name":"LAI_VALIDATION_ERROR","kind":"Validation","retryable":false,"#));
+        assert!(json.contains(r#""context":{"operation":"decrypt","expected":"tag \"ok\"","actual":"mismatch"}"#));
+
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let keygen = LaiCryptoError::KeygenFailed {
+            attempts: 3,
+            modulus: engine.p,
+            base_point: engine.p0,
+            findings: engine.curve().audit(),
+            failures: Vec::new(),
+        };
+        let json = keygen.to_json();
+        assert!(json.contains(r#""base_point":["0","0"]"#));
+        assert!(json.contains(r#""kind":"TinyModulus""#));
     }
-}
 
-/// Miller-Rabin primality test for u128
-fn is_prime(n: u128) -> bool {
-     // Handle small primes
-     match n {
-         2 | 3 => return true,
-         _ if n <= 1 || n % 2 == 0 => return false,
-         _ => {}
-     }
-
-     let mut d = n - 1;
-     let mut s = 0;
-     while d % 2 == 0 {
-         d /= 2;
-         s += 1;
-     }
-
-     // Extended bases for 128-bit numbers
-     let bases: &[u128] = if n < 2_047 {
-         &[2]
-     } else if n < 1_373_653 {
-         &[2, 3]
-     } else if n < 9_080_191 {
-         &[31, 73]
-     } else if n < 25_326_001 {
-         &[2, 3, 5]
-     } else if n < 3_215_031_751 {
-         &[2, 3, 5, 7]
-     } else if n < 4_759_123_141 {
-         &[2, 7, 61]
-     } else if n < 1_122_004_669_633 {
-         &[2, 13, 23, 1_662_803]
-     } else if n < 2_152_302_898_747 {
-         &[2, 3, 5, 7, 11]
-     } else if n < 3_474_749_660_383 {
-         &[2, 3, 5, 7, 11, 13]
-     } else if n < 341_550_071_728_321 {
-         &[2, 3, 5, 7, 11, 13, 17]
-     } else {
-         // Deterministic for 2^64 and larger
-         &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]
-     };
-
-     'base_loop: for &a in bases {
-         if a >= n {
-             continue;
-         }
-         
-         let mut x = mod_exp(a, d, n);
-         if x == 1 || x == n - 1 {
-             continue;
-         }
-         
-         for _ in 1..s {
-             x = mod_exp(x, 2, n);
-             if x == n - 1 {
-                 continue 'base_loop;
-             }
-         }
-         return false;
-     }
-     true
-}
+    #[test]
+    fn test_engine_base_point_validation() {
+        let engine = LaiCryptoEngine::new(1009, 3, (1, 2)).unwrap();
+        assert!(LaiCryptoEngine::new(1009, 3, engine.p0).is_ok());
 
-/// Modular exponentiation helper
-fn mod_exp(mut base: u128, mut exp: u128, modulus: u128) -> u128 {
-    if modulus == 1 {
-        return 0;
-    }
-    let mut result = 1;
-    base %= modulus;
-    while exp > 0 {
-        if exp & 1 == 1 {
-            result = (result * base) % modulus;
+        for p0 in [(1009, 0), (1, 1009)] {
+            let err = LaiCryptoEngine::new(1009, 3, p0).unwrap_err();
+            assert!(matches!(err, LaiCryptoError::InvalidParameter { ref param, .. } if param == "p0"));
         }
-        exp >>= 1;
-        base = (base * base) % modulus;
     }
-    result
-}
 
-/// Check if a has square root modulo p
-fn has_sqrt(a: u128, p: u128) -> bool {
-    if a == 0 {
-        return true;
-    }
-    mod_exp(a, (p - 1) / 2, p) == 1
-}
+    #[test]
+    fn test_keygen_collects_failures() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.max_attempts = 0;
+        match engine.keygen() {
+            Err(LaiCryptoError::KeygenFailed { attempts: 0, failures, .. }) => assert!(failures.is_empty()),
+            other => panic!("expected KeygenFailed, got {:?}", other),
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        engine.max_attempts = 5;
+        let keys = engine.keygen().unwrap();
+        let report = keys.report;
+        assert!((1..=5).contains(&report.attempts));
+        assert!(report.t_steps > 0);
+        let k = *keys.secret.expose_secret();
+        assert_eq!(engine.pow_t(engine.p0, k).unwrap(), keys.public.point());
+        // f^100(P0) has y = 0, which validation would flag
+        assert_eq!(engine.keygen_attempt(100).unwrap_err().kind(), ErrorKind::Validation);
+    }
 
-    fn test_prime() -> u128 {
-        // 128-bit prime: 2^128 - 159
-        340_282_366_920_938_463_463_374_607_431_768_211_297
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 9;
+        let q = engine.pow_t(p0, k).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, 6).unwrap();
+
+        let err = engine.decrypt(&ct, k + 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Authentication);
+
+        let mut tampered = ct;
+        tampered.c2 = Point::new_unchecked((ct.c2.x() + 1) % 1009, ct.c2.y());
+        assert!(matches!(
+            engine.decrypt(&tampered, k),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+
+        // Malformed ciphertexts are rejected before any transform runs
+        let count = engine.metrics.t_transform_count;
+        tampered.c1 = Point::new_unchecked(1009, ct.c1.y());
+        assert_eq!(engine.decrypt(&tampered, k).unwrap_err().kind(), ErrorKind::InvalidInput);
+        let v2 = Ciphertext { version: CiphertextVersion::V2, c2: Point::new_unchecked(ct.c2.x(), 1), ..ct };
+        assert!(v2.validate(&engine.curve()).is_err());
+        assert!(ct.validate(&engine.curve()).is_ok());
+        assert_eq!(engine.metrics.t_transform_count, count);
+
+        // V1 carries no tag, so a wrong key gives a wrong message instead
+        let v1 = engine.encrypt_versioned(42, q, 6, CiphertextVersion::V1).unwrap();
+        assert_eq!(v1.tag, [0; TAG_LEN]);
+        assert_eq!(engine.decrypt(&v1, k).unwrap(), 42);
+        assert_ne!(engine.decrypt(&v1, k + 1).ok(), Some(42));
     }
 
     #[test]
-    fn test_engine_creation() {
-        let prime = test_prime();
-        let engine = LaiCryptoEngine::new(prime, 10, (5, 10));
-        assert!(engine.is_ok());
+    fn test_encrypt_decrypt_round_trip() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 9;
+        let q = engine.pow_t(p0, k).unwrap();
+        for m in [0, 42, 1008] {
+            let (ct, r) = engine.encrypt(m, q).unwrap();
+            assert_eq!(ct.c1, engine.pow_t(p0, r).unwrap());
+            assert_eq!(engine.decrypt(&ct, k).unwrap(), m);
+        }
     }
 
     #[test]
-    fn test_key_gen() {
-        let prime = test_prime();
-        let mut engine = LaiCryptoEngine::new(prime, 10, (5, 10)).unwrap();
-        let key = engine.keygen();
-        assert!(key.is_ok());
+    fn test_kdf_mask_version() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 9;
+        let q = engine.pow_t(p0, k).unwrap();
+
+        let ct = engine.encrypt_with_ephemeral(42, q, 5).unwrap();
+        assert_eq!(ct.version, CiphertextVersion::V2);
+        assert_eq!(ct.c2.y(), 0);
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+
+        let v1 = engine.encrypt_versioned(42, q, 5, CiphertextVersion::V1).unwrap();
+        assert_eq!(engine.decrypt(&v1, k).unwrap(), 42);
+        assert_eq!(v1.c1, ct.c1);
+        assert_ne!(v1.c2.x(), ct.c2.x());
+
+        // Reading a V2 ciphertext as V1 skips the tag and unmasks garbage
+        let relabeled = Ciphertext { version: CiphertextVersion::V1, ..ct };
+        assert_ne!(engine.decrypt(&relabeled, k).unwrap(), 42);
     }
 
     #[test]
-    fn test_encryption() {
-        let prime = test_prime();
-        let mut engine = LaiCryptoEngine::new(prime, 10, (5, 10)).unwrap();
-        let (priv_key, pub_key) = engine.keygen().unwrap();
-        let message = 12345;
-        let enc_result = engine.encrypt(message, pub_key);
-        assert!(enc_result.is_ok());
+    fn test_reduce_wide() {
+        let mut bytes = [0u8; 32];
+        bytes[15] = 1;
+        bytes[31] = 5;
+        // 2^128 + 5 ≡ 2^128 mod p + 5
+        let p = 1_000_000_007u128;
+        assert_eq!(reduce_wide(&bytes, p), (mul_mod(u128::MAX % p, 1, p) + 1 + 5) % p);
+        assert_eq!(reduce_wide(&[255], 101), 255 % 101);
+
+        let engine = LaiCryptoEngine::new(101, 3, (0, 0)).unwrap();
+        let mut counts = [0u32; 101];
+        for s in 0..20_200 {
+            counts[engine.h(1, 2, s) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| (100..300).contains(&c)));
     }
 
     #[test]
-    fn test_ascii_graph() {
-        let mut graph = CryptoGraph {
-            title: "Test Graph".to_string(),
-            data: vec![(0.0, 1.0), (1.0, 3.0), (2.0, 2.0), (3.0, 4.0)],
-            labels: [
-                ("x".to_string(), "X Axis".to_string()),
-                ("y".to_string(), "Y Axis".to_string()),
-            ]
-            .iter()
-            .cloned()
-            .collect(),
-            style: GraphStyle::Line,
-        };
+    fn test_mod_pow_fixed_window() {
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        for base in [0, 1, 2, 11, 1008, 1009, 5000] {
+            for exp in [0, 1, 15, 16, 17, 255, 1007, 1008, 123_456_789, u128::MAX] {
+                assert_eq!(engine.mod_pow(base, exp), pow_mod(base, exp, 1009));
+            }
+        }
 
-        let ascii = graph.render_ascii(60, 20);
-        assert!(ascii.is_ok());
-        println!("{}", ascii.unwrap());
+        // Products of residues above 2^64 must not overflow
+        let p = (1 << 127) - 1;
+        let engine = LaiCryptoEngine::new_unchecked(p, 3, Point::IDENTITY);
+        for base in [2, u64::MAX as u128 + 1, p - 1] {
+            assert_eq!(engine.mod_pow(base, p - 1), 1);
+            assert_eq!(engine.mod_pow(base, 12_345), pow_mod(base, 12_345, p));
+        }
+    }
+
+    #[test]
+    fn test_random_scalar() {
+        let mut counts = [0u32; 101];
+        for _ in 0..20_000 {
+            counts[random_scalar(&mut OsRng, 101) as usize] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert!(counts[1..].iter().all(|&c| (100..300).contains(&c)));
+        assert!((0..1000).all(|_| (1..u128::MAX).contains(&random_scalar(&mut OsRng, u128::MAX))));
     }
 }
@@ -0,0 +1,278 @@
+//! Key lifecycle metadata: creation time, expiry and permitted operations
+//!
+//! Metadata travels with a serialized public key in a
+//! [`PayloadKind::PublicKeyWithMetadata`] envelope:
+//!
+//! ```text
+//! point (32) | created (8) | not-after (8, 0 = never) | usage bits (1)
+//! ```
+//!
+//! Times are Unix seconds. The `*_managed` engine methods check the
+//! metadata before touching the key and return `KeyExpired` or
+//! `UsageViolation` instead of performing the operation.
+
+use crate::envelope::{point_at, wrong_length, Envelope, PayloadKind};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point, PublicKey};
+use std::fmt;
+use std::ops::BitOr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::redact::Redacted;
+
+/// Encoded metadata length following the point
+pub const METADATA_LEN: usize = 8 + 8 + 1;
+
+/// Set of operations a key may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyUsage(u8);
+
+impl KeyUsage {
+    pub const NONE: KeyUsage = KeyUsage(0);
+    pub const ENCRYPT: KeyUsage = KeyUsage(1);
+    pub const DECRYPT: KeyUsage = KeyUsage(2);
+    pub const AGREE: KeyUsage = KeyUsage(4);
+    pub const ALL: KeyUsage = KeyUsage(7);
+
+    const NAMES: [(KeyUsage, &'static str); 3] = [
+        (KeyUsage::ENCRYPT, "encrypt"),
+        (KeyUsage::DECRYPT, "decrypt"),
+        (KeyUsage::AGREE, "agree"),
+    ];
+
+    pub fn contains(self, other: KeyUsage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Parse usage bits, rejecting any unknown bit
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        (bits & !Self::ALL.0 == 0).then_some(KeyUsage(bits))
+    }
+}
+
+impl BitOr for KeyUsage {
+    type Output = KeyUsage;
+
+    fn bitor(self, rhs: KeyUsage) -> KeyUsage {
+        KeyUsage(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for KeyUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = Self::NAMES
+            .iter()
+            .filter(|(u, _)| self.contains(*u))
+            .map(|(_, n)| *n)
+            .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join("|"))
+        }
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Creation time, optional expiry and permitted operations of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyMetadata {
+    pub created: u64,
+    pub not_after: Option<u64>,
+    pub usage: KeyUsage,
+}
+
+impl KeyMetadata {
+    /// Metadata created now, never expiring
+    pub fn new(usage: KeyUsage) -> Self {
+        KeyMetadata {
+            created: unix_now(),
+            not_after: None,
+            usage,
+        }
+    }
+
+    /// Expire `lifetime` after creation
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.not_after = Some(self.created.saturating_add(lifetime.as_secs()));
+        self
+    }
+
+    /// Check that the key is unexpired and permits `operation`
+    pub fn check(&self, operation: KeyUsage) -> Result<(), LaiCryptoError> {
+        let now = unix_now();
+        if let Some(not_after) = self.not_after.filter(|&t| now >= t) {
+            return Err(LaiCryptoError::KeyExpired { not_after, now });
+        }
+        if !self.usage.contains(operation) {
+            return Err(LaiCryptoError::UsageViolation {
+                operation: operation.to_string(),
+                allowed: self.usage.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn to_bytes(self) -> [u8; METADATA_LEN] {
+        let mut out = [0u8; METADATA_LEN];
+        out[..8].copy_from_slice(&self.created.to_be_bytes());
+        out[8..16].copy_from_slice(&self.not_after.unwrap_or(0).to_be_bytes());
+        out[16] = self.usage.bits();
+        out
+    }
+
+    fn from_bytes(bytes: &[u8; METADATA_LEN]) -> Result<Self, LaiCryptoError> {
+        let usage =
+            KeyUsage::from_bits(bytes[16]).ok_or_else(|| LaiCryptoError::InvalidParameter {
+                param: "key usage".to_string(),
+                value: format!("{:#04x}", bytes[16]),
+                reason: "Unknown usage bit".to_string(),
+                valid_range: format!("subset of {:#04x}", KeyUsage::ALL.bits()),
+            })?;
+        let not_after = u64::from_be_bytes(bytes[8..16].try_into().expect("8 bytes"));
+        Ok(KeyMetadata {
+            created: u64::from_be_bytes(bytes[..8].try_into().expect("8 bytes")),
+            not_after: (not_after != 0).then_some(not_after),
+            usage,
+        })
+    }
+}
+
+/// Public key carrying its lifecycle metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ManagedPublicKey {
+    pub key: PublicKey,
+    pub metadata: KeyMetadata,
+}
+
+impl ManagedPublicKey {
+    pub fn to_envelope(&self, engine: &LaiCryptoEngine) -> Vec<u8> {
+        let mut payload = self.key.point().to_bytes().to_vec();
+        payload.extend_from_slice(&self.metadata.to_bytes());
+        Envelope::new(
+            engine,
+            engine.suite(),
+            PayloadKind::PublicKeyWithMetadata,
+            payload,
+        )
+        .to_bytes()
+    }
+
+    pub fn from_envelope(engine: &LaiCryptoEngine, bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let payload = Envelope::open(engine, bytes, PayloadKind::PublicKeyWithMetadata)?.payload;
+        if payload.len() != Point::ENCODED_LEN + METADATA_LEN {
+            return Err(wrong_length(
+                "managed public key",
+                payload.len(),
+                Point::ENCODED_LEN + METADATA_LEN,
+            ));
+        }
+        let (point, metadata) = payload.split_at(Point::ENCODED_LEN);
+        Ok(ManagedPublicKey {
+            key: PublicKey::new(point_at(point, engine.p, "public key")?),
+            metadata: KeyMetadata::from_bytes(metadata.try_into().expect("length checked"))?,
+        })
+    }
+}
+
+/// Private scalar carrying its lifecycle metadata
+#[derive(Clone)]
+pub struct ManagedSecretKey {
+    secret: u128,
+    pub metadata: KeyMetadata,
+}
+
+impl fmt::Debug for ManagedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManagedSecretKey")
+            .field("secret", &Redacted(&self.secret))
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+impl ManagedSecretKey {
+    pub fn new(secret: u128, metadata: KeyMetadata) -> Self {
+        ManagedSecretKey { secret, metadata }
+    }
+}
+
+impl LaiCryptoEngine {
+    /// [`LaiCryptoEngine::encrypt_bytes`] to a key whose metadata permits encryption now
+    pub fn encrypt_bytes_managed(
+        &mut self,
+        plaintext: &[u8],
+        key: &ManagedPublicKey,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        key.metadata.check(KeyUsage::ENCRYPT)?;
+        self.encrypt_bytes(plaintext, key.key.point())
+    }
+
+    /// [`LaiCryptoEngine::decrypt_bytes`] with a key whose metadata permits decryption now
+    pub fn decrypt_bytes_managed(
+        &mut self,
+        ciphertext: &[u8],
+        key: &ManagedSecretKey,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        key.metadata.check(KeyUsage::DECRYPT)?;
+        self.decrypt_bytes(ciphertext, key.secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_enforced() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 8;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        let metadata = KeyMetadata::new(KeyUsage::ENCRYPT | KeyUsage::DECRYPT)
+            .with_lifetime(Duration::from_secs(3600));
+        let public = ManagedPublicKey {
+            key: PublicKey::new(q),
+            metadata,
+        };
+        let decoded =
+            ManagedPublicKey::from_envelope(&engine, &public.to_envelope(&engine)).unwrap();
+        assert_eq!(decoded, public);
+
+        let sealed = engine.encrypt_bytes_managed(b"policy", &public).unwrap();
+        let secret = ManagedSecretKey::new(k, metadata);
+        assert_eq!(
+            engine.decrypt_bytes_managed(&sealed, &secret).unwrap(),
+            b"policy"
+        );
+
+        let encrypt_only = ManagedSecretKey::new(k, KeyMetadata::new(KeyUsage::ENCRYPT));
+        match engine.decrypt_bytes_managed(&sealed, &encrypt_only) {
+            Err(LaiCryptoError::UsageViolation { operation, allowed }) => {
+                assert_eq!(
+                    (operation.as_str(), allowed.as_str()),
+                    ("decrypt", "encrypt")
+                );
+            }
+            other => panic!("expected UsageViolation, got {:?}", other),
+        }
+
+        let expired = ManagedPublicKey {
+            metadata: KeyMetadata {
+                not_after: Some(1),
+                ..metadata
+            },
+            ..public
+        };
+        assert!(matches!(
+            engine.encrypt_bytes_managed(b"x", &expired),
+            Err(LaiCryptoError::KeyExpired { not_after: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+//! Per-engine performance counters
+
+use crate::operation;
+use crate::sqrt::SqrtMethod;
+use std::time::Duration;
+
+/// Performance metrics for operations
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct PerfMetrics {
+    pub keygen_time: Duration,
+    pub encrypt_time: Duration,
+    pub decrypt_time: Duration,
+    pub t_transform_count: u32,
+    pub sqrt_attempts: u32,
+    /// Method behind the most recent square root, fixed by p
+    pub sqrt_method: Option<SqrtMethod>,
+    pub operation_history: Vec<(String, Duration)>,
+    /// Operation ID of each `operation_history` entry, index for index
+    pub operation_ids: Vec<Option<operation::OperationId>>,
+}
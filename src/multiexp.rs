@@ -0,0 +1,158 @@
+//! Products of several modular powers
+//!
+//! [`LaiCryptoEngine::multi_exp`] computes `∏ bases[i]^exps[i] mod p` with
+//! one shared chain of squarings instead of a full ladder per term. Short
+//! products use Straus's interleaved windows; from
+//! [`PIPPENGER_THRESHOLD`] terms on, Pippenger's bucket method amortises
+//! the per-window multiplications across all bases.
+//!
+//! Neither algorithm is constant time: they suit public exponents, as in
+//! signature and batch verification, not secret scalars.
+//!
+//! ```
+//! use laicrypto::LaiCryptoEngine;
+//!
+//! let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+//! let product = engine.multi_exp(&[2, 3], &[10, 5]).unwrap();
+//! assert_eq!(product, engine.mod_pow(2, 10) * engine.mod_pow(3, 5) % 1009);
+//! ```
+
+use crate::{mul_mod, LaiCryptoEngine, LaiCryptoError};
+
+/// Terms from which [`LaiCryptoEngine::multi_exp`] uses Pippenger over Straus
+pub const PIPPENGER_THRESHOLD: usize = 32;
+
+/// Exponent bits per window in Straus's method
+const STRAUS_WINDOW: u32 = 4;
+
+impl LaiCryptoEngine {
+    /// `∏ bases[i]^exps[i] mod p`, which is 1 for an empty product
+    pub fn multi_exp(&self, bases: &[u128], exps: &[u128]) -> Result<u128, LaiCryptoError> {
+        if bases.len() != exps.len() {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "exps".to_string(),
+                value: format!("{} exponents", exps.len()),
+                reason: "Each base needs exactly one exponent".to_string(),
+                valid_range: format!("{} exponents", bases.len()),
+            });
+        }
+        let bits = u128::BITS - exps.iter().fold(0, |acc, &e| acc | e).leading_zeros();
+        let bases: Vec<u128> = bases.iter().map(|&b| b % self.p).collect();
+        Ok(if bases.len() < PIPPENGER_THRESHOLD {
+            straus(&bases, exps, bits, self.p)
+        } else {
+            pippenger(&bases, exps, bits, self.p)
+        })
+    }
+}
+
+/// `c`-bit digit of `e` in window `w`, counting from the least significant
+fn digit(e: u128, w: u32, c: u32) -> usize {
+    ((e >> (w * c)) & ((1 << c) - 1)) as usize
+}
+
+/// Interleaved fixed windows over a 16-entry table per base
+fn straus(bases: &[u128], exps: &[u128], bits: u32, p: u128) -> u128 {
+    let tables: Vec<[u128; 1 << STRAUS_WINDOW]> = bases
+        .iter()
+        .map(|&b| {
+            let mut table = [1; 1 << STRAUS_WINDOW];
+            for d in 1..table.len() {
+                table[d] = mul_mod(table[d - 1], b, p);
+            }
+            table
+        })
+        .collect();
+
+    let mut result = 1;
+    for w in (0..bits.div_ceil(STRAUS_WINDOW)).rev() {
+        for _ in 0..STRAUS_WINDOW {
+            result = mul_mod(result, result, p);
+        }
+        for (table, &e) in tables.iter().zip(exps) {
+            let d = digit(e, w, STRAUS_WINDOW);
+            if d != 0 {
+                result = mul_mod(result, table[d], p);
+            }
+        }
+    }
+    result
+}
+
+/// Bucket method: each window sorts bases into buckets by digit, then
+/// folds `∏ bucket[d]^d` with two running products
+fn pippenger(bases: &[u128], exps: &[u128], bits: u32, p: u128) -> u128 {
+    // About log2(N) - 2 bits balances the N bucket insertions per window
+    // against the 2^c multiplications needed to fold the buckets
+    let c = (usize::BITS - bases.len().leading_zeros())
+        .saturating_sub(2)
+        .clamp(2, 16);
+
+    let mut result = 1;
+    let mut buckets = vec![1; 1 << c];
+    for w in (0..bits.div_ceil(c)).rev() {
+        for _ in 0..c {
+            result = mul_mod(result, result, p);
+        }
+        buckets.fill(1);
+        for (&b, &e) in bases.iter().zip(exps) {
+            let d = digit(e, w, c);
+            if d != 0 {
+                buckets[d] = mul_mod(buckets[d], b, p);
+            }
+        }
+        let (mut running, mut window) = (1, 1);
+        for &bucket in buckets[1..].iter().rev() {
+            running = mul_mod(running, bucket, p);
+            window = mul_mod(window, running, p);
+        }
+        result = mul_mod(result, window, p);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::{Lai127, LaiEngine};
+
+    #[test]
+    fn test_multi_exp_matches_independent_powers() {
+        let engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        for n in [
+            0,
+            1,
+            2,
+            7,
+            PIPPENGER_THRESHOLD - 1,
+            PIPPENGER_THRESHOLD,
+            200,
+        ] {
+            let bases: Vec<u128> = (0..n as u128).map(|i| i * 37 + 2).collect();
+            let exps: Vec<u128> = (0..n as u128)
+                .map(|i| match i % 4 {
+                    0 => 0,
+                    1 => i * 7919,
+                    2 => u128::MAX - i,
+                    _ => 1008,
+                })
+                .collect();
+            let expected = bases
+                .iter()
+                .zip(&exps)
+                .fold(1, |acc, (&b, &e)| acc * engine.mod_pow(b, e) % 1009);
+            assert_eq!(
+                engine.multi_exp(&bases, &exps).unwrap(),
+                expected,
+                "n = {}",
+                n
+            );
+        }
+
+        let wide = LaiEngine::<Lai127>::new();
+        let x = (1 << 126) + 12345;
+        let product = wide.multi_exp(&[x, x], &[3, wide.p - 4]).unwrap();
+        assert_eq!(product, 1);
+        assert!(engine.multi_exp(&[1, 2], &[3]).is_err());
+    }
+}
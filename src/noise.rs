@@ -0,0 +1,463 @@
+//! Noise-style handshake patterns over the fixed-seed key exchange
+//!
+//! Implements the NN, NK and XX patterns of the Noise framework with
+//! [`crate::exchange`] standing in for DH. Every token and payload is
+//! absorbed into a SHA-512 transcript hash, each agreement is mixed into
+//! a chaining key with HKDF-SHA-512, and once a key is established static
+//! keys and payloads are encrypted with the hash as associated data:
+//!
+//! ```text
+//! NN:  -> e            NK:  <- s            XX:  -> e
+//!      <- e, ee             ...                  <- e, ee, s, es
+//!                           -> e, es             -> s, se
+//!                           <- e, ee
+//! ```
+//!
+//! The cipher is the SHA-512 keystream with HMAC-SHA-512 of
+//! [`crate::hybrid`], keyed per message from the cipher key and nonce.
+
+pub use crate::exchange::Role;
+
+use crate::exchange::ExchangeKeyPair;
+use crate::hybrid::{raw_point, SessionKeys, MAC_LEN};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use hmac::Mac;
+use sha2::{Digest, Sha512};
+use crate::redact::Redacted;
+use std::fmt;
+
+/// Transcript hash length
+pub const HASH_LEN: usize = 64;
+
+/// Supported handshake patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HandshakePattern {
+    /// No static keys; unauthenticated but confidential
+    NN,
+    /// Responder's static key known to the initiator in advance
+    NK,
+    /// Static keys exchanged and authenticated during the handshake
+    XX,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+}
+
+impl HandshakePattern {
+    fn name(self) -> &'static str {
+        match self {
+            HandshakePattern::NN => "Noise_NN_LAI_HMACSHA512_SHA512",
+            HandshakePattern::NK => "Noise_NK_LAI_HMACSHA512_SHA512",
+            HandshakePattern::XX => "Noise_XX_LAI_HMACSHA512_SHA512",
+        }
+    }
+
+    /// Message token lists, alternating initiator then responder
+    fn messages(self) -> &'static [&'static [Token]] {
+        use Token::*;
+        match self {
+            HandshakePattern::NN => &[&[E], &[E, EE]],
+            HandshakePattern::NK => &[&[E, ES], &[E, EE]],
+            HandshakePattern::XX => &[&[E], &[E, EE, S, ES], &[S, SE]],
+        }
+    }
+
+    /// Whether each role needs its own static key, and the peer's in advance
+    fn requirements(self, role: Role) -> (bool, bool) {
+        match (self, role) {
+            (HandshakePattern::NN, _) => (false, false),
+            (HandshakePattern::NK, Role::Initiator) => (false, true),
+            (HandshakePattern::NK, Role::Responder) => (true, false),
+            (HandshakePattern::XX, _) => (true, false),
+        }
+    }
+}
+
+/// Keyed stream cipher with HMAC, one key per nonce
+pub(crate) fn seal(key: &[u8; 32], nonce: u64, ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let keys = aead_keys(key, nonce);
+    let mut out = plaintext.to_vec();
+    keys.apply_keystream(&mut out);
+    let mut mac = keys.mac();
+    mac.update(ad);
+    mac.update(&out);
+    out.extend_from_slice(&mac.finalize().into_bytes());
+    out
+}
+
+/// Inverse of [`seal`]; `None` if the tag does not verify
+pub(crate) fn open(key: &[u8; 32], nonce: u64, ad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    let body_len = sealed.len().checked_sub(MAC_LEN)?;
+    let (body, tag) = sealed.split_at(body_len);
+    let keys = aead_keys(key, nonce);
+    let mut mac = keys.mac();
+    mac.update(ad);
+    mac.update(body);
+    mac.verify_slice(tag).ok()?;
+    let mut out = body.to_vec();
+    keys.apply_keystream(&mut out);
+    Some(out)
+}
+
+fn aead_keys(key: &[u8; 32], nonce: u64) -> SessionKeys {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha512>::new(None, key)
+        .expand_multi_info(&[b"LAI-noise-aead", &nonce.to_be_bytes()], &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA-512 length");
+    SessionKeys::from_digest(&okm)
+}
+
+/// Keys and transcript hash exported once a handshake completes
+#[derive(Clone, PartialEq, Eq)]
+pub struct TransportKeys {
+    /// Key for messages this party sends
+    pub send: [u8; 32],
+    /// Key for messages this party receives
+    pub recv: [u8; 32],
+    /// Final transcript hash, identical on both sides; usable for channel binding
+    pub handshake_hash: [u8; HASH_LEN],
+}
+
+impl fmt::Debug for TransportKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TransportKeys")
+            .field("send", &Redacted(&self.send))
+            .field("recv", &Redacted(&self.recv))
+            .field("handshake_hash", &self.handshake_hash)
+            .finish()
+    }
+}
+
+/// One side of a handshake in progress
+pub struct HandshakeState {
+    pattern: HandshakePattern,
+    role: Role,
+    chaining_key: [u8; HASH_LEN],
+    hash: [u8; HASH_LEN],
+    key: Option<[u8; 32]>,
+    nonce: u64,
+    s: Option<ExchangeKeyPair>,
+    e: Option<ExchangeKeyPair>,
+    rs: Option<Point>,
+    re: Option<Point>,
+    message: usize,
+}
+
+impl fmt::Debug for HandshakeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HandshakeState")
+            .field("pattern", &self.pattern)
+            .field("role", &self.role)
+            .field("chaining_key", &Redacted(&self.chaining_key))
+            .field("hash", &self.hash)
+            .field("key", &Redacted(&self.key))
+            .field("nonce", &self.nonce)
+            .field("s", &self.s)
+            .field("e", &self.e)
+            .field("rs", &self.rs)
+            .field("re", &self.re)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl HandshakeState {
+    /// Set up a handshake
+    ///
+    /// `s` is this party's static key and `rs` the peer's static point when
+    /// known in advance; [`HandshakePattern::NK`] initiators need `rs` and
+    /// responders `s`, while XX needs `s` on both sides. Both parties must
+    /// use the same `prologue`.
+    pub fn new(
+        pattern: HandshakePattern,
+        role: Role,
+        prologue: &[u8],
+        s: Option<ExchangeKeyPair>,
+        rs: Option<Point>,
+    ) -> Result<Self, LaiCryptoError> {
+        let (needs_s, needs_rs) = pattern.requirements(role);
+        if needs_s && s.is_none() || needs_rs && rs.is_none() {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "handshake keys".to_string(),
+                value: format!("{:?} {:?}", pattern, role),
+                reason: "Pattern needs a key that was not supplied".to_string(),
+                valid_range: format!(
+                    "own static key {}, peer static point {}",
+                    if needs_s { "required" } else { "optional" },
+                    if needs_rs { "required" } else { "optional" }
+                ),
+            });
+        }
+        let hash: [u8; HASH_LEN] = Sha512::digest(pattern.name()).into();
+        let mut state = HandshakeState {
+            pattern,
+            role,
+            chaining_key: hash,
+            hash,
+            key: None,
+            nonce: 0,
+            s,
+            e: None,
+            rs,
+            re: None,
+            message: 0,
+        };
+        state.mix_hash(prologue);
+        if pattern == HandshakePattern::NK {
+            let responder_static = match role {
+                Role::Initiator => state.rs,
+                Role::Responder => state.s.as_ref().map(ExchangeKeyPair::public),
+            };
+            state.mix_hash(&responder_static.expect("checked above").to_bytes());
+        }
+        Ok(state)
+    }
+
+    /// Whether all handshake messages have been processed
+    pub fn is_finished(&self) -> bool {
+        self.message == self.pattern.messages().len()
+    }
+
+    /// Whether this party sends the next handshake message
+    pub fn is_my_turn(&self) -> bool {
+        let initiator_turn = self.message.is_multiple_of(2);
+        !self.is_finished() && initiator_turn == (self.role == Role::Initiator)
+    }
+
+    /// Peer's static point, once received or if known in advance
+    pub fn remote_static(&self) -> Option<Point> {
+        self.rs
+    }
+
+    /// Current transcript hash
+    pub fn handshake_hash(&self) -> [u8; HASH_LEN] {
+        self.hash
+    }
+
+    /// Produce the next handshake message carrying `payload`
+    pub fn write_message(&mut self, engine: &mut LaiCryptoEngine, payload: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        self.check_turn(true)?;
+        let mut out = Vec::new();
+        for &token in self.pattern.messages()[self.message] {
+            match token {
+                Token::E => {
+                    let e = ExchangeKeyPair::generate(engine)?;
+                    let bytes = e.public().to_bytes();
+                    self.mix_hash(&bytes);
+                    out.extend_from_slice(&bytes);
+                    self.e = Some(e);
+                }
+                Token::S => {
+                    let bytes = self.s.as_ref().expect("checked in new").public().to_bytes();
+                    out.extend(self.encrypt_and_hash(&bytes));
+                }
+                dh => self.mix_agreement(engine, dh)?,
+            }
+        }
+        out.extend(self.encrypt_and_hash(payload));
+        self.message += 1;
+        Ok(out)
+    }
+
+    /// Process the peer's next handshake message and return its payload
+    pub fn read_message(&mut self, engine: &mut LaiCryptoEngine, message: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        self.check_turn(false)?;
+        let mut rest = message;
+        let malformed = || LaiCryptoError::InvalidParameter {
+            param: "handshake_message".to_string(),
+            value: format!("{} bytes", message.len()),
+            reason: "Truncated or unreduced point".to_string(),
+            valid_range: "points reduced modulo p".to_string(),
+        };
+        for &token in self.pattern.messages()[self.message] {
+            match token {
+                Token::E => {
+                    if rest.len() < Point::ENCODED_LEN {
+                        return Err(malformed());
+                    }
+                    let (bytes, tail) = rest.split_at(Point::ENCODED_LEN);
+                    self.re = Some(raw_point(bytes, engine.p).ok_or_else(malformed)?);
+                    self.mix_hash(bytes);
+                    rest = tail;
+                }
+                Token::S => {
+                    let len = Point::ENCODED_LEN + if self.key.is_some() { MAC_LEN } else { 0 };
+                    if rest.len() < len {
+                        return Err(malformed());
+                    }
+                    let (sealed, tail) = rest.split_at(len);
+                    let bytes = self.decrypt_and_hash(sealed)?;
+                    self.rs = Some(raw_point(&bytes, engine.p).ok_or_else(malformed)?);
+                    rest = tail;
+                }
+                dh => self.mix_agreement(engine, dh)?,
+            }
+        }
+        let payload = self.decrypt_and_hash(rest)?;
+        self.message += 1;
+        Ok(payload)
+    }
+
+    /// Export the transport keys once the handshake is finished
+    pub fn split(&self) -> Result<TransportKeys, LaiCryptoError> {
+        if !self.is_finished() {
+            return Err(self.out_of_order("a finished handshake"));
+        }
+        let mut okm = [0u8; 64];
+        Hkdf::<Sha512>::new(Some(&self.chaining_key), &[])
+            .expand(b"LAI-noise-split", &mut okm)
+            .expect("64 bytes is a valid HKDF-SHA-512 length");
+        let (mut initiator, mut responder) = ([0u8; 32], [0u8; 32]);
+        initiator.copy_from_slice(&okm[..32]);
+        responder.copy_from_slice(&okm[32..]);
+        let (send, recv) = match self.role {
+            Role::Initiator => (initiator, responder),
+            Role::Responder => (responder, initiator),
+        };
+        Ok(TransportKeys {
+            send,
+            recv,
+            handshake_hash: self.hash,
+        })
+    }
+
+    fn check_turn(&self, writing: bool) -> Result<(), LaiCryptoError> {
+        if self.is_finished() || self.is_my_turn() != writing {
+            let expected = if writing { "this party's turn to write" } else { "this party's turn to read" };
+            return Err(self.out_of_order(expected));
+        }
+        Ok(())
+    }
+
+    fn out_of_order(&self, expected: &str) -> LaiCryptoError {
+        LaiCryptoError::ValidationError {
+            operation: "noise_handshake".to_string(),
+            expected: expected.to_string(),
+            actual: format!("message {} of {}", self.message, self.pattern.messages().len()),
+        }
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        self.hash = Sha512::new().chain_update(self.hash).chain_update(data).finalize().into();
+    }
+
+    fn mix_agreement(&mut self, engine: &mut LaiCryptoEngine, token: Token) -> Result<(), LaiCryptoError> {
+        // es and se name (initiator key, responder key); flip for the responder
+        let (mine, theirs) = match (token, self.role) {
+            (Token::EE, _) => (&self.e, self.re),
+            (Token::ES, Role::Initiator) | (Token::SE, Role::Responder) => (&self.e, self.rs),
+            (Token::ES, Role::Responder) | (Token::SE, Role::Initiator) => (&self.s, self.re),
+            _ => unreachable!("only agreement tokens reach mix_agreement"),
+        };
+        let pair = mine.as_ref().ok_or_else(|| self.out_of_order("own key for agreement"))?;
+        let peer = theirs.ok_or_else(|| self.out_of_order("peer key for agreement"))?;
+        let agreed = pair.agree(engine, peer)?;
+
+        let mut okm = [0u8; HASH_LEN + 32];
+        Hkdf::<Sha512>::new(Some(&self.chaining_key), &agreed.to_bytes())
+            .expand(b"LAI-noise-mix", &mut okm)
+            .expect("96 bytes is a valid HKDF-SHA-512 length");
+        self.chaining_key.copy_from_slice(&okm[..HASH_LEN]);
+        self.key = Some(okm[HASH_LEN..].try_into().expect("32-byte key"));
+        self.nonce = 0;
+        Ok(())
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let out = match self.key {
+            Some(key) => {
+                let sealed = seal(&key, self.nonce, &self.hash, plaintext);
+                self.nonce += 1;
+                sealed
+            }
+            None => plaintext.to_vec(),
+        };
+        self.mix_hash(&out);
+        out
+    }
+
+    fn decrypt_and_hash(&mut self, data: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        let out = match self.key {
+            Some(key) => {
+                let opened = open(&key, self.nonce, &self.hash, data).ok_or_else(|| {
+                    LaiCryptoError::AuthenticationFailed {
+                        operation: "noise_handshake".to_string(),
+                    }
+                })?;
+                self.nonce += 1;
+                opened
+            }
+            None => data.to_vec(),
+        };
+        self.mix_hash(data);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a full handshake; None when an agreement hit a failing T
+    fn handshake(
+        engine: &mut LaiCryptoEngine,
+        pattern: HandshakePattern,
+        keys: (Option<ExchangeKeyPair>, Option<Point>, Option<ExchangeKeyPair>),
+    ) -> Option<(HandshakeState, HandshakeState)> {
+        let (s_i, rs_i, s_r) = keys;
+        let mut initiator = HandshakeState::new(pattern, Role::Initiator, b"demo", s_i, rs_i).unwrap();
+        let mut responder = HandshakeState::new(pattern, Role::Responder, b"demo", s_r, None).unwrap();
+        let mut turn = 0;
+        while !initiator.is_finished() {
+            let (writer, reader) = if turn % 2 == 0 {
+                (&mut initiator, &mut responder)
+            } else {
+                (&mut responder, &mut initiator)
+            };
+            let message = writer.write_message(engine, b"hello").ok()?;
+            assert_eq!(reader.read_message(engine, &message).unwrap(), b"hello");
+            turn += 1;
+        }
+        Some((initiator, responder))
+    }
+
+    #[test]
+    fn test_handshake_patterns() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let bob = ExchangeKeyPair::generate(&mut engine).unwrap();
+
+        for pattern in [HandshakePattern::NN, HandshakePattern::NK, HandshakePattern::XX] {
+            let (s_i, rs_i) = match pattern {
+                HandshakePattern::NN => (None, None),
+                HandshakePattern::NK => (None, Some(bob.public())),
+                HandshakePattern::XX => (Some(alice.clone()), None),
+            };
+            let s_r = (pattern != HandshakePattern::NN).then(|| bob.clone());
+            let Some((initiator, responder)) =
+                (0..20).find_map(|_| handshake(&mut engine, pattern, (s_i.clone(), rs_i, s_r.clone())))
+            else {
+                continue;
+            };
+            assert!(responder.is_finished());
+            let (i, r) = (initiator.split().unwrap(), responder.split().unwrap());
+            assert!(i.send == r.recv && i.recv == r.send && i.handshake_hash == r.handshake_hash);
+            if pattern == HandshakePattern::XX {
+                assert_eq!(responder.remote_static(), Some(alice.public()));
+                assert_eq!(initiator.remote_static(), Some(bob.public()));
+            }
+        }
+
+        assert!(HandshakeState::new(HandshakePattern::NK, Role::Initiator, b"", None, None).is_err());
+        let mut early = HandshakeState::new(HandshakePattern::NN, Role::Responder, b"", None, None).unwrap();
+        assert!(early.write_message(&mut engine, b"").is_err());
+        assert!(early.split().is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Operation IDs correlating trace steps and history entries
+//!
+//! Every call to [`LaiCryptoEngine::keygen`], [`LaiCryptoEngine::encrypt`],
+//! [`LaiCryptoEngine::encrypt_with_ephemeral`] or
+//! [`LaiCryptoEngine::decrypt`] gets the engine's next [`OperationId`].
+//! Each [`TraceStep`] and `operation_history` entry recorded while it runs
+//! carries that ID, including those of the transforms it calls, so one
+//! failed decryption can be picked out of a busy engine's trace with
+//! [`LaiCryptoEngine::last_operation`] and [`LaiCryptoEngine::trace_of`].
+//! Work outside these calls, such as a direct `pow_t`, has no ID.
+
+use crate::{LaiCryptoEngine, TraceStep};
+use std::fmt;
+use std::time::Duration;
+
+/// Per-engine sequence number of a top-level operation, from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct OperationId(pub u64);
+
+impl fmt::Display for OperationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "op-{}", self.0)
+    }
+}
+
+/// ID counter and the operation in progress
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub(crate) struct Operations {
+    last: Option<OperationId>,
+    current: Option<OperationId>,
+}
+
+impl Operations {
+    pub(crate) fn current(&self) -> Option<OperationId> {
+        self.current
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Run `f` under a fresh operation ID unless one is already in progress
+    pub(crate) fn in_operation<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.operations.current.is_some() {
+            return f(self);
+        }
+        let id = OperationId(self.operations.last.map_or(1, |id| id.0 + 1));
+        self.operations.last = Some(id);
+        self.operations.current = Some(id);
+        let result = f(self);
+        self.operations.current = None;
+        result
+    }
+
+    /// ID of the most recently started operation, finished or not
+    pub fn last_operation(&self) -> Option<OperationId> {
+        self.operations.last
+    }
+
+    /// Trace steps recorded during operation `id`
+    pub fn trace_of(&self, id: OperationId) -> impl Iterator<Item = &TraceStep> + '_ {
+        self.trace
+            .iter()
+            .filter(move |step| step.operation == Some(id))
+    }
+
+    /// `operation_history` entries recorded during operation `id`
+    pub fn history_of(&self, id: OperationId) -> impl Iterator<Item = (&str, Duration)> + '_ {
+        self.metrics
+            .operation_history
+            .iter()
+            .zip(&self.metrics.operation_ids)
+            .filter(move |(_, op)| **op == Some(id))
+            .map(|((name, duration), _)| (name.as_str(), *duration))
+    }
+}
+
+#[cfg(all(test, feature = "trace", feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_ids_correlate_trace() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 7;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        assert_eq!(engine.last_operation(), None);
+        assert!(engine.trace.iter().all(|step| step.operation.is_none()));
+
+        let ct = engine.encrypt_with_ephemeral(42, q, k).unwrap();
+        let encrypt = engine.last_operation().unwrap();
+        assert!(engine.decrypt(&ct, k + 1).is_err());
+        let failed = engine.last_operation().unwrap();
+        assert_ne!(encrypt, failed);
+
+        assert!(engine.trace_of(failed).count() > 0);
+        assert!(engine
+            .trace_of(failed)
+            .all(|step| step.operation == Some(failed)));
+        assert!(engine.trace_of(encrypt).count() as u128 >= 2 * k);
+        let history: Vec<_> = engine.history_of(encrypt).map(|(op, _)| op).collect();
+        assert_eq!(history.iter().filter(|&&op| op == "pow_t").count(), 2);
+        assert_eq!(failed.to_string(), "op-2");
+    }
+}
@@ -0,0 +1,152 @@
+//! Block padding for byte messages mapped into field elements
+//!
+//! A message split into [`block_len`]-byte blocks fits one field element
+//! per block; the final block is filled by one of the [`Padding`] schemes.
+//! Unpadding inspects every byte of the padding window whatever its
+//! contents and reports all failures with the same error, so timing and
+//! error values do not reveal where a padding check failed.
+
+use crate::LaiCryptoError;
+use rand::{rngs::OsRng, Rng};
+
+/// Padding scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Padding {
+    /// PKCS#7: n bytes of value n, 1 ≤ n ≤ block length
+    Pkcs7,
+    /// ISO/IEC 7816-4: a 0x80 marker then zeros up to the block boundary
+    Iso7816,
+    /// ISO/IEC 7816-4 padding extended by up to `max_extra_blocks` random
+    /// whole blocks, hiding the exact message length
+    Random { max_extra_blocks: usize },
+}
+
+/// Largest block length, in bytes, whose values are all below `p`
+pub fn block_len(p: u128) -> usize {
+    (p.ilog2() / 8) as usize
+}
+
+/// Pad `data` to a multiple of `block_len` bytes
+///
+/// At least one byte is always added, so padding is unambiguous.
+pub fn pad(data: &[u8], block_len: usize, padding: Padding) -> Result<Vec<u8>, LaiCryptoError> {
+    check_block_len(block_len, padding)?;
+    let fill = block_len - data.len() % block_len;
+    let mut out = Vec::with_capacity(data.len() + fill);
+    out.extend_from_slice(data);
+    match padding {
+        Padding::Pkcs7 => out.resize(data.len() + fill, fill as u8),
+        Padding::Iso7816 | Padding::Random { .. } => {
+            let extra = match padding {
+                Padding::Random { max_extra_blocks } => OsRng.gen_range(0..=max_extra_blocks) * block_len,
+                _ => 0,
+            };
+            out.push(0x80);
+            out.resize(data.len() + fill + extra, 0);
+        }
+    }
+    Ok(out)
+}
+
+/// Strip padding added by [`pad`] with the same scheme and block length
+pub fn unpad(data: &[u8], block_len: usize, padding: Padding) -> Result<Vec<u8>, LaiCryptoError> {
+    check_block_len(block_len, padding)?;
+    let invalid = || LaiCryptoError::ValidationError {
+        operation: "unpad".to_string(),
+        expected: "valid padding".to_string(),
+        actual: "invalid padding".to_string(),
+    };
+    if data.is_empty() || !data.len().is_multiple_of(block_len) {
+        return Err(invalid());
+    }
+    let (strip, bad) = match padding {
+        Padding::Pkcs7 => pkcs7_len(data, block_len),
+        Padding::Iso7816 => iso7816_len(&data[data.len() - block_len..]),
+        Padding::Random { .. } => iso7816_len(data),
+    };
+    if bad != 0 {
+        return Err(invalid());
+    }
+    Ok(data[..data.len() - strip].to_vec())
+}
+
+fn check_block_len(block_len: usize, padding: Padding) -> Result<(), LaiCryptoError> {
+    let max = if padding == Padding::Pkcs7 { 255 } else { usize::MAX };
+    if block_len == 0 || block_len > max {
+        return Err(LaiCryptoError::InvalidParameter {
+            param: "block_len".to_string(),
+            value: block_len.to_string(),
+            reason: "Block length outside the scheme's range".to_string(),
+            valid_range: format!("1 ≤ block_len ≤ {}", max),
+        });
+    }
+    Ok(())
+}
+
+/// All-ones when `a == b`, else zero, without branching on the values
+fn ct_eq_mask(a: usize, b: usize) -> usize {
+    let diff = a ^ b;
+    ((diff | diff.wrapping_neg()) >> (usize::BITS - 1)).wrapping_sub(1)
+}
+
+/// All-ones when `a < b`, else zero; both must be below 2^(BITS-1)
+fn ct_lt_mask(a: usize, b: usize) -> usize {
+    (a.wrapping_sub(b) >> (usize::BITS - 1)).wrapping_neg()
+}
+
+/// (padding length, nonzero if malformed) reading the whole last block
+fn pkcs7_len(data: &[u8], block_len: usize) -> (usize, usize) {
+    let n = data[data.len() - 1] as usize;
+    let mut bad = ct_eq_mask(n, 0) | !ct_lt_mask(n, block_len + 1);
+    for (i, &byte) in data[data.len() - block_len..].iter().rev().enumerate() {
+        // Byte i from the end is padding when i < n and must then equal n
+        bad |= ct_lt_mask(i, n) & !ct_eq_mask(byte as usize, n);
+    }
+    (n & !bad, bad)
+}
+
+/// (padding length, nonzero if malformed) for a 0x80 marker then zeros in `window`
+fn iso7816_len(window: &[u8]) -> (usize, usize) {
+    let (mut found, mut strip, mut bad) = (0usize, 0usize, 0usize);
+    for (i, &byte) in window.iter().rev().enumerate() {
+        let nonzero = !ct_eq_mask(byte as usize, 0);
+        let first = nonzero & !found;
+        strip |= first & (i + 1);
+        bad |= first & !ct_eq_mask(byte as usize, 0x80);
+        found |= nonzero;
+    }
+    (strip, bad | !found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padding_schemes() {
+        assert_eq!(block_len(1009), 1);
+        assert_eq!(block_len((1u128 << 127) - 1), 15);
+
+        for padding in [Padding::Pkcs7, Padding::Iso7816, Padding::Random { max_extra_blocks: 3 }] {
+            for len in [0, 1, 7, 8, 9, 20] {
+                let data: Vec<u8> = (1..=len as u8).collect();
+                let padded = pad(&data, 8, padding).unwrap();
+                assert!(padded.len() > data.len() && padded.len().is_multiple_of(8));
+                assert_eq!(unpad(&padded, 8, padding).unwrap(), data);
+            }
+        }
+        assert_eq!(pad(b"abc", 4, Padding::Pkcs7).unwrap(), b"abc\x01");
+        assert_eq!(pad(b"abcd", 4, Padding::Iso7816).unwrap(), b"abcd\x80\0\0\0");
+
+        for bad in [&b"abc\x00"[..], b"ab\x01\x02", b"abc\x05", b"abc"] {
+            assert!(unpad(bad, 4, Padding::Pkcs7).is_err());
+        }
+        for bad in [&b"abc\x00"[..], b"ab\x81\x00", b"\0\0\0\0"] {
+            assert!(unpad(bad, 4, Padding::Iso7816).is_err());
+        }
+        // Random padding may span blocks; ISO unpadding only reads the last one
+        assert!(unpad(b"a\x80\0\0\0\0\0\0", 4, Padding::Iso7816).is_err());
+        assert_eq!(unpad(b"a\x80\0\0\0\0\0\0", 4, Padding::Random { max_extra_blocks: 1 }).unwrap(), b"a");
+        assert!(pad(b"", 256, Padding::Pkcs7).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! Password-authenticated key exchange with mutual key confirmation
+//!
+//! SPAKE2 blinds each share by adding a password multiple of a fixed group
+//! element, but T points have no group law to add or remove such a mask
+//! (see docs/design-notes.md). This is instead an EKE-style exchange over
+//! the commutative map in [`crate::exchange`]: each party sends its public
+//! point f^a(P0) masked by field offsets derived from the password, with
+//! the sign of y flipped at random. Decrypting a transcript under a wrong
+//! password guess yields an arbitrary pair in F_p², which canonicalizes to
+//! a point of the same shape, so offline guesses cannot be discarded.
+//!
+//! ```text
+//! initiator                                  responder
+//!   A* = mask_I(f^a(P0))  ───────────────▶
+//!                         ◀───────────────  B* = mask_R(f^b(P0))
+//!   K = f^a(B)                               K = f^b(A)
+//!   confirm_I             ───────────────▶   verify
+//!   verify                ◀───────────────   confirm_R
+//! ```
+//!
+//! Both confirmations are HMACs over the full transcript, so an active
+//! attacker learns at most one password guess per failed run.
+
+pub use crate::exchange::Role;
+
+use crate::exchange::{canonicalize, ExchangeKeyPair};
+use crate::{add_mod, reduce_wide, LaiCryptoEngine, LaiCryptoError, Point};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, Rng};
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+use crate::redact::Redacted;
+use std::fmt;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Length of the masked point each party sends
+pub const MESSAGE_LEN: usize = Point::ENCODED_LEN;
+/// Length of a key-confirmation message
+pub const CONFIRMATION_LEN: usize = 32;
+
+/// One party's state between sending its share and receiving the peer's
+pub struct Pake {
+    role: Role,
+    keys: ExchangeKeyPair,
+    password_hash: [u8; 64],
+    message: [u8; MESSAGE_LEN],
+}
+
+impl fmt::Debug for Pake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pake")
+            .field("role", &self.role)
+            .field("keys", &self.keys)
+            .field("password_hash", &Redacted(&self.password_hash))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys and confirmation messages once both shares are exchanged
+pub struct PakeOutput {
+    session_key: [u8; 32],
+    confirmation: [u8; CONFIRMATION_LEN],
+    expected: [u8; CONFIRMATION_LEN],
+}
+
+impl fmt::Debug for PakeOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PakeOutput")
+            .field("session_key", &Redacted(&self.session_key))
+            .field("confirmation", &self.confirmation)
+            .field("expected", &Redacted(&self.expected))
+            .finish()
+    }
+}
+
+/// Field offsets (dx, dy) masking the share sent by `role`
+fn mask(password_hash: &[u8; 64], role: Role, p: u128) -> (u128, u128) {
+    let digest = Sha512::new()
+        .chain_update(b"LAI-PAKE-mask")
+        .chain_update(role.label())
+        .chain_update(password_hash)
+        .finalize();
+    (reduce_wide(&digest[..32], p), reduce_wide(&digest[32..], p))
+}
+
+fn sub_mod(a: u128, b: u128, p: u128) -> u128 {
+    add_mod(a, (p - b) % p, p)
+}
+
+impl Pake {
+    /// Start an exchange, returning the state and the message to send
+    ///
+    /// `context` binds the run to the two identities or a session label;
+    /// both parties must pass the same bytes.
+    pub fn start(
+        engine: &mut LaiCryptoEngine,
+        role: Role,
+        password: &[u8],
+        context: &[u8],
+    ) -> Result<(Self, [u8; MESSAGE_LEN]), LaiCryptoError> {
+        let password_hash: [u8; 64] = Sha512::new()
+            .chain_update(b"LAI-PAKE-password")
+            .chain_update((context.len() as u64).to_be_bytes())
+            .chain_update(context)
+            .chain_update(password)
+            .finalize()
+            .into();
+        let keys = ExchangeKeyPair::generate(engine)?;
+
+        let p = engine.p;
+        let public = keys.public();
+        let (dx, dy) = mask(&password_hash, role, p);
+        let y = if OsRng.gen::<bool>() { public.y() } else { (p - public.y()) % p };
+        let masked = Point::new_unchecked(add_mod(public.x(), dx, p), add_mod(y, dy, p));
+        let message = masked.to_bytes();
+        Ok((
+            Pake {
+                role,
+                keys,
+                password_hash,
+                message,
+            },
+            message,
+        ))
+    }
+
+    /// Unmask the peer's share and derive the session key
+    ///
+    /// A wrong password is not detected here; it surfaces as a failed
+    /// [`PakeOutput::verify`].
+    pub fn finish(
+        self,
+        engine: &mut LaiCryptoEngine,
+        peer_message: &[u8],
+    ) -> Result<PakeOutput, LaiCryptoError> {
+        let p = engine.p;
+        let masked = decode(peer_message, p)?;
+        let (dx, dy) = mask(&self.password_hash, self.role.peer(), p);
+        let peer = canonicalize(
+            engine,
+            Point::new_unchecked(sub_mod(masked.x(), dx, p), sub_mod(masked.y(), dy, p)),
+        );
+        let shared = self.keys.agree(engine, peer)?;
+
+        let (initiator, responder) = match self.role {
+            Role::Initiator => (&self.message[..], peer_message),
+            Role::Responder => (peer_message, &self.message[..]),
+        };
+        let digest = Sha512::new()
+            .chain_update(b"LAI-PAKE")
+            .chain_update(self.password_hash)
+            .chain_update(initiator)
+            .chain_update(responder)
+            .chain_update(shared.to_bytes())
+            .finalize();
+        let (mut session_key, mut confirm_key) = ([0u8; 32], [0u8; 32]);
+        session_key.copy_from_slice(&digest[..32]);
+        confirm_key.copy_from_slice(&digest[32..]);
+
+        let confirm = |role: Role| {
+            let mut mac = HmacSha512::new_from_slice(&confirm_key).expect("HMAC accepts any key length");
+            mac.update(role.label());
+            let mut out = [0u8; CONFIRMATION_LEN];
+            out.copy_from_slice(&mac.finalize().into_bytes()[..CONFIRMATION_LEN]);
+            out
+        };
+        Ok(PakeOutput {
+            session_key,
+            confirmation: confirm(self.role),
+            expected: confirm(self.role.peer()),
+        })
+    }
+}
+
+impl PakeOutput {
+    /// Key-confirmation message to send to the peer
+    pub fn confirmation(&self) -> [u8; CONFIRMATION_LEN] {
+        self.confirmation
+    }
+
+    /// Check the peer's confirmation and release the session key
+    ///
+    /// Returns `AuthenticationFailed` if the passwords, contexts or
+    /// transcripts differ.
+    pub fn verify(self, peer_confirmation: &[u8]) -> Result<[u8; 32], LaiCryptoError> {
+        if !bool::from(self.expected[..].ct_eq(peer_confirmation)) {
+            return Err(LaiCryptoError::AuthenticationFailed {
+                operation: "pake_confirm".to_string(),
+            });
+        }
+        Ok(self.session_key)
+    }
+}
+
+fn decode(bytes: &[u8], p: u128) -> Result<Point, LaiCryptoError> {
+    let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+        param: "pake_message".to_string(),
+        value: format!("{} bytes", bytes.len()),
+        reason: reason.to_string(),
+        valid_range: format!("{} bytes, coordinates below p", MESSAGE_LEN),
+    };
+    let bytes: &[u8; MESSAGE_LEN] = bytes.try_into().map_err(|_| malformed("Wrong message length"))?;
+    let (x, y) = bytes.split_at(MESSAGE_LEN / 2);
+    let coord = |b: &[u8]| u128::from_be_bytes(b.try_into().expect("16-byte coordinate"));
+    let (x, y) = (coord(x), coord(y));
+    if x >= p || y >= p {
+        return Err(malformed("Coordinate not reduced modulo p"));
+    }
+    Ok(Point::new_unchecked(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Verified = Result<[u8; 32], LaiCryptoError>;
+
+    /// Run both sides; None when a walk hit a failing T and the run must be retried
+    fn run(password_i: &[u8], password_r: &[u8]) -> Option<(Verified, Verified)> {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let (alice, a_msg) = Pake::start(&mut engine, Role::Initiator, password_i, b"alice|bob").unwrap();
+        let (bob, b_msg) = Pake::start(&mut engine, Role::Responder, password_r, b"alice|bob").unwrap();
+        let alice = alice.finish(&mut engine, &b_msg).ok()?;
+        let bob = bob.finish(&mut engine, &a_msg).ok()?;
+        let (a_confirm, b_confirm) = (alice.confirmation(), bob.confirmation());
+        Some((alice.verify(&b_confirm), bob.verify(&a_confirm)))
+    }
+
+    #[test]
+    fn test_pake_mutual_confirmation() {
+        let (alice, bob) = (0..20).find_map(|_| run(b"hunter2", b"hunter2")).unwrap();
+        assert_eq!(alice.unwrap(), bob.unwrap());
+
+        let (alice, bob) = (0..20).find_map(|_| run(b"hunter2", b"hunter3")).unwrap();
+        assert!(matches!(alice, Err(LaiCryptoError::AuthenticationFailed { .. })));
+        assert!(bob.is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! Warming an engine before it serves requests
+//!
+//! A fresh engine pays for everything on its first operation: `2⁻¹ mod p`
+//! for every T-transform, the Tonelli-Shanks non-residue search on
+//! p ≢ 3 (mod 4), CPU feature detection for [`crate::simd`], and cold
+//! code and data caches. [`LaiCryptoEngine::precompute`] does that work up
+//! front and keeps the results on the engine, so the first key generation
+//! in a latency-sensitive service costs the same as the hundredth.
+//!
+//! From [`PrecomputeProfile::Standard`] on it also stores every
+//! [`STRIDE`]-th point of the base point's chain. [`LaiCryptoEngine::pow_t`]
+//! from `p0`, as in key generation and encryption, then resumes from the
+//! nearest stored point instead of walking the whole chain. That
+//! skips recorded steps, so the table is only consulted while `tracing` is
+//! off and blinding is disabled.
+//!
+//! Everything cached is tied to the parameters and hash it was computed
+//! for and is ignored once those change. Snapshots do not carry it.
+//!
+//! ```
+//! use laicrypto::precompute::PrecomputeProfile;
+//! use laicrypto::LaiCryptoEngine;
+//!
+//! let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+//! let report = engine.precompute(PrecomputeProfile::Standard);
+//! assert_eq!(report.inv2, 505);
+//! assert_eq!(engine.precomputed(), Some(PrecomputeProfile::Standard));
+//! ```
+
+use crate::hash::HashFunction;
+use crate::preset::BuiltinParams;
+use crate::simd::{self, Backend};
+use crate::{confirmation_tag_v2, jacobi, kdf_mask, LaiCryptoEngine, Point, CHAIN_SEED};
+use std::time::Duration;
+
+/// Distance in transforms between stored points of the base point's chain
+pub const STRIDE: u128 = 64;
+
+/// How much [`LaiCryptoEngine::precompute`] prepares
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PrecomputeProfile {
+    /// Field constants and SIMD backend detection only
+    Field,
+    /// Field constants and the first 2^12 transforms of the base point's chain
+    #[default]
+    Standard,
+    /// The first 2^16 transforms, and one pass over every hot path
+    Full,
+}
+
+impl PrecomputeProfile {
+    /// Transforms of the base point's chain the table covers, at most
+    pub fn chain_steps(self) -> u128 {
+        match self {
+            PrecomputeProfile::Field => 0,
+            PrecomputeProfile::Standard => 1 << 12,
+            PrecomputeProfile::Full => 1 << 16,
+        }
+    }
+}
+
+/// What a [`LaiCryptoEngine::precompute`] call built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecomputeReport {
+    pub profile: PrecomputeProfile,
+    /// `2⁻¹ mod p`
+    pub inv2: u128,
+    /// Smallest quadratic non-residue, when p ≢ 3 (mod 4)
+    pub non_residue: Option<u128>,
+    /// Batch arithmetic backend for p on this CPU
+    pub backend: Backend,
+    /// Points stored from the base point's chain, [`STRIDE`] transforms apart
+    pub chain_points: usize,
+    /// Transforms of p0 under the fixed-seed map the table covers; a chain
+    /// that fails earlier stops it at the failing step
+    pub chain_reach: u128,
+    pub duration: Duration,
+}
+
+/// Tonelli-Shanks constants for p - 1 = q · 2^s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SqrtConstants {
+    pub(crate) q: u128,
+    pub(crate) s: u32,
+    pub(crate) z: u128,
+    /// z^q, a generator of the 2^s-torsion
+    pub(crate) c: u128,
+}
+
+/// Cached values of an engine, valid only for the inputs they came from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tables {
+    profile: Option<PrecomputeProfile>,
+    p: u128,
+    a: u128,
+    p0: Option<Point>,
+    hash: Option<HashFunction>,
+    domain_separation: bool,
+    inv2: u128,
+    sqrt: Option<SqrtConstants>,
+    /// `chain[j]` is f^(j · STRIDE)(p0)
+    chain: Vec<Point>,
+}
+
+impl Tables {
+    fn field_valid(&self, p: u128) -> bool {
+        self.profile.is_some() && self.p == p
+    }
+
+    fn chain_valid(&self, engine: &LaiCryptoEngine) -> bool {
+        self.field_valid(engine.p)
+            && self.a == engine.a
+            && self.p0 == Some(engine.p0)
+            && self.hash == Some(engine.hash)
+            && self.domain_separation == engine.domain_separation
+    }
+
+    /// Field constants of a built-in preset, for an engine over its parameters
+    pub(crate) fn builtin(params: &BuiltinParams, engine: &LaiCryptoEngine) -> Self {
+        Tables {
+            profile: Some(PrecomputeProfile::Field),
+            p: params.p,
+            a: params.a,
+            p0: Some(params.p0),
+            hash: Some(engine.hash),
+            domain_separation: engine.domain_separation,
+            inv2: params.inv2,
+            sqrt: (params.p % 4 != 3).then_some(SqrtConstants {
+                q: params.odd_part,
+                s: params.two_adicity,
+                z: params.non_residue,
+                c: params.root_of_unity,
+            }),
+            chain: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for Tables {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C> bincode::Decode<C> for Tables {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Tables);
+
+impl LaiCryptoEngine {
+    /// Compute and keep the constants and tables of `profile`
+    ///
+    /// Replaces anything cached before. The transforms walked here leave
+    /// `trace` and the transform and square root counters in `metrics`
+    /// untouched; the call itself is recorded as a `precompute` operation.
+    pub fn precompute(&mut self, profile: PrecomputeProfile) -> PrecomputeReport {
+        let start = self.clock.now();
+        self.precomputed = Tables::default();
+        let (t_count, sqrt_attempts) = (self.metrics.t_transform_count, self.metrics.sqrt_attempts);
+        let tracing = std::mem::replace(&mut self.tracing, false);
+
+        let inv2 = self.mod_pow(2, self.p - 2);
+        let sqrt = (self.p % 4 != 3).then(|| {
+            let s = (self.p - 1).trailing_zeros();
+            let q = (self.p - 1) >> s;
+            let mut z = 2;
+            while jacobi(z, self.p) != -1 {
+                z += 1;
+            }
+            SqrtConstants {
+                q,
+                s,
+                z,
+                c: self.mod_pow(z, q),
+            }
+        });
+        let backend = simd::backend(self.p);
+        self.precomputed = Tables {
+            profile: Some(profile),
+            p: self.p,
+            a: self.a,
+            p0: Some(self.p0),
+            hash: Some(self.hash),
+            domain_separation: self.domain_separation,
+            inv2,
+            sqrt,
+            chain: Vec::new(),
+        };
+
+        let steps = profile.chain_steps().min(self.p);
+        let mut chain = Vec::new();
+        let mut reach = 0;
+        if steps > 0 {
+            chain.push(self.p0);
+            let mut point = self.p0;
+            for n in 1..=steps {
+                match self.t(point, CHAIN_SEED) {
+                    Ok(next) => point = next,
+                    Err(_) => break,
+                }
+                reach = n;
+                if n % STRIDE == 0 {
+                    chain.push(point);
+                }
+            }
+        }
+        let chain_points = chain.len();
+        self.precomputed.chain = chain;
+
+        if profile == PrecomputeProfile::Full {
+            self.warm_up();
+        }
+
+        self.tracing = tracing;
+        self.metrics.t_transform_count = t_count;
+        self.metrics.sqrt_attempts = sqrt_attempts;
+        let duration = self.clock.since(start);
+        self.record_operation("precompute", duration);
+        PrecomputeReport {
+            profile,
+            inv2,
+            non_residue: sqrt.map(|c| c.z),
+            backend,
+            chain_points,
+            chain_reach: reach,
+            duration,
+        }
+    }
+
+    /// Profile of the tables in effect for the current parameters
+    pub fn precomputed(&self) -> Option<PrecomputeProfile> {
+        if self.precomputed.chain_valid(self) {
+            self.precomputed.profile
+        } else {
+            None
+        }
+    }
+
+    /// Drop everything [`LaiCryptoEngine::precompute`] kept
+    pub fn clear_precomputed(&mut self) {
+        self.precomputed = Tables::default();
+    }
+
+    /// Run each hot path once on throwaway inputs
+    fn warm_up(&mut self) {
+        let p = self.p;
+        let values: Vec<u128> = (1..=64u128).map(|v| v % p).collect();
+        let mut out = vec![0; values.len()];
+        simd::pow_mod_batch(&values, (p - 1) / 2, p, &mut out);
+        simd::mul_mod_batch(&values, &out, p, &mut out.clone());
+        for &v in &values {
+            std::hint::black_box(self.sqrt_mod(v));
+            std::hint::black_box(self.h(v, v, v));
+        }
+        let (mask, tag_key) = kdf_mask(&self.hash, self.p0, self.p0, &self.params_hash(), p);
+        std::hint::black_box(confirmation_tag_v2(&tag_key, mask));
+    }
+
+    /// `2⁻¹ mod p`, from the tables when they match
+    pub(crate) fn inv2(&self) -> u128 {
+        if self.precomputed.field_valid(self.p) {
+            self.precomputed.inv2
+        } else {
+            self.mod_pow(2, self.p - 2)
+        }
+    }
+
+    /// Tonelli-Shanks constants from the tables when they match
+    pub(crate) fn cached_sqrt_constants(&self) -> Option<SqrtConstants> {
+        self.precomputed
+            .field_valid(self.p)
+            .then_some(self.precomputed.sqrt)
+            .flatten()
+    }
+
+    /// Furthest stored point of p0's chain at or before `exp` transforms,
+    /// with the number of transforms it stands for
+    pub(crate) fn chain_start(&self, point: Point, exp: u128) -> Option<(Point, u128)> {
+        let chain = &self.precomputed.chain;
+        if self.tracing
+            || self.blinding > 0
+            || point != self.p0
+            || chain.is_empty()
+            || !self.precomputed.chain_valid(self)
+        {
+            return None;
+        }
+        let j = (exp / STRIDE).min(chain.len() as u128 - 1);
+        Some((chain[j as usize], j * STRIDE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::{Lai10007, LaiEngine};
+
+    #[test]
+    fn test_precompute_preserves_results() {
+        let mut cold = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let mut warm = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        cold.tracing = false;
+        warm.tracing = false;
+        let report = warm.precompute(PrecomputeProfile::Full);
+        assert_eq!(report.non_residue, Some(11));
+        assert_eq!(report.chain_points as u128, report.chain_reach / STRIDE + 1);
+        assert_eq!(warm.metrics.t_transform_count, 0);
+        assert_eq!(warm.precomputed(), Some(PrecomputeProfile::Full));
+
+        for k in [1, 63, 64, 65, 200, 700] {
+            let expected = cold.pow_t(cold.p0, k).map_err(|e| e.kind());
+            let p0 = warm.p0;
+            assert_eq!(warm.pow_t(p0, k).map_err(|e| e.kind()), expected);
+        }
+        if report.chain_reach >= STRIDE {
+            assert!(warm.metrics.t_transform_count < cold.metrics.t_transform_count);
+        }
+        for a in 0..1009 {
+            assert_eq!(warm.sqrt_mod(a), cold.sqrt_mod(a));
+        }
+
+        warm.a = 5;
+        assert_eq!(warm.precomputed(), None);
+        warm.clear_precomputed();
+        assert_eq!(warm.inv2(), 505);
+
+        let mut preset = LaiEngine::<Lai10007>::new();
+        let report = preset.precompute(PrecomputeProfile::Field);
+        assert_eq!((report.non_residue, report.chain_points), (None, 0));
+    }
+}
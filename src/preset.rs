@@ -0,0 +1,427 @@
+//! Named parameter sets checked at compile time
+//!
+//! A [`Preset`] fixes `p`, `a` and `p0` as associated constants, and
+//! everything derived from the modulus (bit length, the Tonelli-Shanks
+//! decomposition `p - 1 = q · 2^s`, a non-residue and its root of unity) is
+//! evaluated by the compiler. [`LaiEngine`] is a [`LaiCryptoEngine`] typed
+//! by its preset: constructing one cannot fail and skips the runtime
+//! primality and base point checks, which run once during compilation
+//! instead.
+//!
+//! For a preset chosen at runtime, [`builtins`] lists every set with its
+//! derived constants, `2⁻¹ mod p` and Montgomery constant, built once on
+//! first use. [`LaiCryptoEngine::with_preset`] looks one up by name and
+//! starts the engine with those constants already cached, as if
+//! [`LaiCryptoEngine::precompute`] had run with
+//! [`crate::precompute::PrecomputeProfile::Field`].
+//!
+//! ```
+//! use laicrypto::preset::{Lai10007, LaiEngine, Preset};
+//! use laicrypto::LaiCryptoEngine;
+//!
+//! let engine = LaiEngine::<Lai10007>::new();
+//! assert_eq!(engine.p, Lai10007::P);
+//! assert_eq!(Lai10007::sqrt(4), Some(2));
+//!
+//! let named = LaiCryptoEngine::with_preset("Lai10007").unwrap();
+//! assert_eq!(named.params_hash(), engine.params_hash());
+//! ```
+
+use crate::precompute::{PrecomputeProfile, PrecomputeReport, Tables};
+use crate::{
+    add_mod, has_sqrt, is_prime, jacobi, mul_mod, pow_mod, simd, Ciphertext, CurveParams, KeyPair,
+    LaiCryptoEngine, LaiCryptoError, Point,
+};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Compile-time LAI parameter set
+///
+/// Implemented only by the presets in this module. The provided constants
+/// are derived from `P` and must not be overridden.
+pub trait Preset: sealed::Sealed + fmt::Debug + Default + Copy + 'static {
+    const NAME: &'static str;
+    const P: u128;
+    const A: u128;
+    const P0: Point;
+
+    /// Bit length of `P`
+    const BITS: u32 = 128 - Self::P.leading_zeros();
+    /// `s` in `p - 1 = q · 2^s`
+    const TWO_ADICITY: u32 = (Self::P - 1).trailing_zeros();
+    /// `q` in `p - 1 = q · 2^s`
+    const ODD_PART: u128 = (Self::P - 1) >> Self::TWO_ADICITY;
+    /// Smallest quadratic non-residue
+    const NON_RESIDUE: u128 = non_residue(Self::P);
+    /// Generator of the 2^s-torsion, `NON_RESIDUE^q`
+    const ROOT_OF_UNITY: u128 = pow_mod(Self::NON_RESIDUE, Self::ODD_PART, Self::P);
+    /// Fails compilation of [`LaiEngine::new`] for an invalid parameter set
+    const VALID: () = assert!(
+        Self::P >= 100
+            && is_prime(Self::P)
+            && Self::A < Self::P
+            && has_sqrt(
+                CurveParams {
+                    p: Self::P,
+                    a: Self::A
+                }
+                .rhs(Self::P0.x()),
+                Self::P
+            ),
+        "invalid LAI preset"
+    );
+
+    /// This preset's entry in [`builtins`]
+    fn builtin() -> &'static BuiltinParams {
+        builtin(Self::NAME).expect("every preset is listed in builtins")
+    }
+
+    fn curve() -> CurveParams {
+        CurveParams {
+            p: Self::P,
+            a: Self::A,
+        }
+    }
+
+    #[inline]
+    fn add(a: u128, b: u128) -> u128 {
+        add_mod(a % Self::P, b % Self::P, Self::P)
+    }
+
+    #[inline]
+    fn mul(a: u128, b: u128) -> u128 {
+        mul_mod(a, b, Self::P)
+    }
+
+    #[inline]
+    fn pow(a: u128, e: u128) -> u128 {
+        pow_mod(a, e, Self::P)
+    }
+
+    /// Euler's criterion
+    #[inline]
+    fn is_square(a: u128) -> bool {
+        has_sqrt(a % Self::P, Self::P)
+    }
+
+    /// Square root by Tonelli-Shanks over the precomputed decomposition
+    fn sqrt(a: u128) -> Option<u128> {
+        let a = a % Self::P;
+        if a == 0 {
+            return Some(0);
+        }
+        if !Self::is_square(a) {
+            return None;
+        }
+        if Self::TWO_ADICITY == 1 {
+            return Some(Self::pow(a, (Self::P + 1) / 4));
+        }
+
+        let mut m = Self::TWO_ADICITY;
+        let mut c = Self::ROOT_OF_UNITY;
+        let mut t = Self::pow(a, Self::ODD_PART);
+        let mut r = Self::pow(a, Self::ODD_PART.div_ceil(2));
+        while t != 1 {
+            // a is a square, so t has order 2^i for some i < m
+            let mut i = 1;
+            let mut t2i = Self::mul(t, t);
+            while t2i != 1 {
+                t2i = Self::mul(t2i, t2i);
+                i += 1;
+            }
+            let b = Self::pow(c, 1 << (m - i - 1));
+            m = i;
+            c = Self::mul(b, b);
+            t = Self::mul(t, c);
+            r = Self::mul(r, b);
+        }
+        Some(r)
+    }
+}
+
+const fn non_residue(p: u128) -> u128 {
+    let mut z = 2;
+    while jacobi(z, p) != -1 {
+        z += 1;
+    }
+    z
+}
+
+macro_rules! preset {
+    ($(#[$doc:meta])* $name:ident, $p:expr, $a:expr, $p0:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+        pub struct $name;
+
+        impl sealed::Sealed for $name {}
+
+        impl Preset for $name {
+            const NAME: &'static str = stringify!($name);
+            const P: u128 = $p;
+            const A: u128 = $a;
+            const P0: Point = Point::new_unchecked($p0.0, $p0.1);
+        }
+    };
+}
+
+preset!(
+    /// Toy modulus for tests and examples; offers no security
+    Lai1009,
+    1009,
+    3,
+    (0, 0)
+);
+preset!(
+    /// Small modulus with p ≡ 3 (mod 4), for tests and demonstrations
+    Lai10007,
+    10_007,
+    3,
+    (0, 0)
+);
+preset!(
+    /// Mersenne prime 2^127 - 1
+    Lai127,
+    (1 << 127) - 1,
+    3,
+    (0, 0)
+);
+
+/// A preset's parameters and derived constants as runtime values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuiltinParams {
+    pub name: &'static str,
+    pub p: u128,
+    pub a: u128,
+    pub p0: Point,
+    pub bits: u32,
+    pub two_adicity: u32,
+    pub odd_part: u128,
+    pub non_residue: u128,
+    pub root_of_unity: u128,
+    /// `2⁻¹ mod p`
+    pub inv2: u128,
+    /// `-p⁻¹ mod 2^32`, when p is small enough for [`crate::simd`]'s lanes
+    pub montgomery: Option<u32>,
+}
+
+impl BuiltinParams {
+    fn of<P: Preset>() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = P::VALID;
+        BuiltinParams {
+            name: P::NAME,
+            p: P::P,
+            a: P::A,
+            p0: P::P0,
+            bits: P::BITS,
+            two_adicity: P::TWO_ADICITY,
+            odd_part: P::ODD_PART,
+            non_residue: P::NON_RESIDUE,
+            root_of_unity: P::ROOT_OF_UNITY,
+            inv2: P::P.div_ceil(2),
+            montgomery: simd::montgomery_constant(P::P),
+        }
+    }
+}
+
+/// Every preset in this module, built on first use
+pub fn builtins() -> &'static [BuiltinParams] {
+    static BUILTINS: OnceLock<Vec<BuiltinParams>> = OnceLock::new();
+    BUILTINS.get_or_init(|| {
+        vec![
+            BuiltinParams::of::<Lai1009>(),
+            BuiltinParams::of::<Lai10007>(),
+            BuiltinParams::of::<Lai127>(),
+        ]
+    })
+}
+
+/// The preset whose [`Preset::NAME`] is `name`
+pub fn builtin(name: &str) -> Option<&'static BuiltinParams> {
+    builtins().iter().find(|params| params.name == name)
+}
+
+impl LaiCryptoEngine {
+    /// Engine over the built-in preset called `name`, e.g. `"Lai10007"`
+    ///
+    /// The parameters are known to be valid, so nothing is re-checked, and
+    /// the field constants come from [`builtins`] instead of being computed.
+    pub fn with_preset(name: &str) -> Result<Self, LaiCryptoError> {
+        let params = builtin(name).ok_or_else(|| LaiCryptoError::InvalidParameter {
+            param: "preset".to_string(),
+            value: name.to_string(),
+            reason: "Unknown preset".to_string(),
+            valid_range: builtins()
+                .iter()
+                .map(|params| params.name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        })?;
+        Ok(Self::from_builtin(params))
+    }
+
+    fn from_builtin(params: &BuiltinParams) -> Self {
+        let mut engine = Self::new_unchecked(params.p, params.a, params.p0);
+        engine.precomputed = Tables::builtin(params, &engine);
+        engine
+    }
+}
+
+/// [`LaiCryptoEngine`] over the parameters of preset `P`
+///
+/// Dereferences to the underlying engine for read-only access. Operations
+/// that need the engine mutably are forwarded below, so the public `p`, `a`
+/// and `p0` fields cannot be reassigned behind the preset's back; call
+/// [`LaiEngine::into_inner`] for anything else.
+pub struct LaiEngine<P: Preset> {
+    engine: LaiCryptoEngine,
+    preset: PhantomData<P>,
+}
+
+impl<P: Preset> LaiEngine<P> {
+    pub fn new() -> Self {
+        Self {
+            engine: LaiCryptoEngine::from_builtin(P::builtin()),
+            preset: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> LaiCryptoEngine {
+        self.engine
+    }
+
+    /// See [`LaiCryptoEngine::pow_t`]
+    pub fn pow_t(&mut self, point: Point, n: u128) -> Result<Point, LaiCryptoError> {
+        self.engine.pow_t(point, n)
+    }
+
+    /// See [`LaiCryptoEngine::keygen`]
+    pub fn keygen(&mut self) -> Result<KeyPair, LaiCryptoError> {
+        self.engine.keygen()
+    }
+
+    /// See [`LaiCryptoEngine::encrypt`]
+    pub fn encrypt(&mut self, m: u128, q: Point) -> Result<(Ciphertext, u128), LaiCryptoError> {
+        self.engine.encrypt(m, q)
+    }
+
+    /// See [`LaiCryptoEngine::decrypt`]
+    pub fn decrypt(&mut self, ct: &Ciphertext, k: u128) -> Result<u128, LaiCryptoError> {
+        self.engine.decrypt(ct, k)
+    }
+
+    /// See [`LaiCryptoEngine::encrypt_bytes`]
+    pub fn encrypt_bytes(&mut self, plaintext: &[u8], q: Point) -> Result<Vec<u8>, LaiCryptoError> {
+        self.engine.encrypt_bytes(plaintext, q)
+    }
+
+    /// See [`LaiCryptoEngine::decrypt_bytes`]
+    pub fn decrypt_bytes(&mut self, ciphertext: &[u8], k: u128) -> Result<Vec<u8>, LaiCryptoError> {
+        self.engine.decrypt_bytes(ciphertext, k)
+    }
+
+    /// See [`LaiCryptoEngine::precompute`]
+    pub fn precompute(&mut self, profile: PrecomputeProfile) -> PrecomputeReport {
+        self.engine.precompute(profile)
+    }
+}
+
+impl<P: Preset> Default for LaiEngine<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Preset> Deref for LaiEngine<P> {
+    type Target = LaiCryptoEngine;
+
+    fn deref(&self) -> &LaiCryptoEngine {
+        &self.engine
+    }
+}
+
+impl<P: Preset> From<LaiEngine<P>> for LaiCryptoEngine {
+    fn from(engine: LaiEngine<P>) -> Self {
+        engine.engine
+    }
+}
+
+impl<P: Preset> fmt::Debug for LaiEngine<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple(&format!("LaiEngine<{}>", P::NAME))
+            .field(&self.engine)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExposeSecret;
+
+    #[test]
+    fn test_preset_engine_matches_runtime_engine() {
+        let mut engine = LaiEngine::<Lai1009>::new();
+        let runtime = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        assert_eq!(engine.params_hash(), runtime.params_hash());
+
+        let k = 7;
+        let q = engine.pow_t(Lai1009::P0, k).unwrap();
+        let (ct, _) = engine.encrypt(42, q).unwrap();
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+        let sealed = engine.encrypt_bytes(b"preset", q).unwrap();
+        assert_eq!(engine.decrypt_bytes(&sealed, k).unwrap(), b"preset");
+
+        let keys = engine.keygen().unwrap();
+        let k = *keys.secret.expose_secret();
+        let (ct, _) = engine.encrypt(42, keys.public.point()).unwrap();
+        assert_eq!(engine.decrypt(&ct, k).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_builtin_tables() {
+        assert_eq!(builtins().len(), 3);
+        for params in builtins() {
+            let engine = LaiCryptoEngine::with_preset(params.name).unwrap();
+            assert_eq!(engine.precomputed(), Some(PrecomputeProfile::Field));
+            assert_eq!(params.inv2, pow_mod(2, params.p - 2, params.p));
+            assert_eq!(mul_mod(params.inv2, 2, params.p), 1);
+        }
+        assert_eq!(
+            Lai1009::builtin().montgomery.map(|m| m.wrapping_mul(1009)),
+            Some(u32::MAX)
+        );
+        assert_eq!(Lai127::builtin().montgomery, None);
+        assert!(std::ptr::eq(
+            Lai10007::builtin(),
+            builtin("Lai10007").unwrap()
+        ));
+        assert!(LaiCryptoEngine::with_preset("Lai31").is_err());
+    }
+
+    #[test]
+    fn test_preset_constants() {
+        assert_eq!((Lai1009::TWO_ADICITY, Lai1009::ODD_PART), (4, 63));
+        assert_eq!(Lai1009::NON_RESIDUE, 11);
+        assert_eq!(Lai127::BITS, 127);
+        assert_eq!(Lai127::TWO_ADICITY, 1);
+
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        for a in 0..1009 {
+            let root = Lai1009::sqrt(a);
+            assert_eq!(root.is_some(), engine.sqrt_mod(a).is_some());
+            if let Some(r) = root {
+                assert_eq!(Lai1009::mul(r, r), a);
+            }
+        }
+        let x = Lai127::P - 5;
+        let r = Lai127::sqrt(Lai127::mul(x, x)).unwrap();
+        assert!(r == x || r == Lai127::P - x);
+    }
+}
@@ -0,0 +1,255 @@
+//! Protobuf messages mirroring `proto/laicrypto.proto`
+//!
+//! The structs are written with prost derives rather than generated, so no
+//! `protoc` is needed at build time; field numbers match the schema file.
+//! Conversions to and from the crate's types validate field widths and,
+//! for points, reduction modulo p.
+
+use crate::{Ciphertext, CiphertextVersion, LaiCryptoError, PerfMetrics, Point, PublicKey, TAG_LEN};
+use std::time::Duration;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoPoint {
+    #[prost(bytes = "vec", tag = "1")]
+    pub x: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub y: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoParams {
+    #[prost(bytes = "vec", tag = "1")]
+    pub p: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub a: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub p0: Option<ProtoPoint>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoPublicKey {
+    #[prost(message, optional, tag = "1")]
+    pub params: Option<ProtoParams>,
+    #[prost(message, optional, tag = "2")]
+    pub q: Option<ProtoPoint>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoCiphertext {
+    #[prost(message, optional, tag = "1")]
+    pub c1: Option<ProtoPoint>,
+    #[prost(message, optional, tag = "2")]
+    pub c2: Option<ProtoPoint>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub tag: Vec<u8>,
+    /// Ciphertext format; 0 reads as version 1 for messages predating the field
+    #[prost(uint32, tag = "4")]
+    pub version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoKatBlock {
+    #[prost(bytes = "vec", tag = "1")]
+    pub m: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub r: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub c1: Option<ProtoPoint>,
+    #[prost(message, optional, tag = "4")]
+    pub c2: Option<ProtoPoint>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoKatVector {
+    #[prost(message, optional, tag = "1")]
+    pub params: Option<ProtoParams>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub k: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub q: Option<ProtoPoint>,
+    #[prost(message, repeated, tag = "4")]
+    pub blocks: Vec<ProtoKatBlock>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoOperationSample {
+    #[prost(string, tag = "1")]
+    pub operation: String,
+    #[prost(uint64, tag = "2")]
+    pub duration_ns: u64,
+    #[prost(uint64, optional, tag = "3")]
+    pub operation_id: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoBenchmarkResult {
+    #[prost(uint64, tag = "1")]
+    pub keygen_ns: u64,
+    #[prost(uint64, tag = "2")]
+    pub encrypt_ns: u64,
+    #[prost(uint64, tag = "3")]
+    pub decrypt_ns: u64,
+    #[prost(uint32, tag = "4")]
+    pub t_transform_count: u32,
+    #[prost(uint32, tag = "5")]
+    pub sqrt_attempts: u32,
+    #[prost(message, repeated, tag = "6")]
+    pub history: Vec<ProtoOperationSample>,
+}
+
+fn invalid(field: &str, reason: &str) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: field.to_string(),
+        value: String::new(),
+        reason: reason.to_string(),
+        valid_range: "present, at most 16 big-endian bytes, below p".to_string(),
+    }
+}
+
+/// Field element as 16 big-endian bytes
+pub fn scalar_to_bytes(value: u128) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+/// Inverse of [`scalar_to_bytes`], accepting shorter left-trimmed encodings
+pub fn scalar_from_bytes(bytes: &[u8], field: &str) -> Result<u128, LaiCryptoError> {
+    if bytes.len() > 16 {
+        return Err(invalid(field, "Integer wider than 128 bits"));
+    }
+    Ok(bytes.iter().fold(0u128, |acc, &b| acc << 8 | u128::from(b)))
+}
+
+impl From<Point> for ProtoPoint {
+    fn from(point: Point) -> Self {
+        ProtoPoint {
+            x: scalar_to_bytes(point.x()),
+            y: scalar_to_bytes(point.y()),
+        }
+    }
+}
+
+impl ProtoPoint {
+    /// Decode, requiring both coordinates below `p`
+    pub fn to_point(&self, p: u128, field: &str) -> Result<Point, LaiCryptoError> {
+        let (x, y) = (scalar_from_bytes(&self.x, field)?, scalar_from_bytes(&self.y, field)?);
+        if x >= p || y >= p {
+            return Err(invalid(field, "Coordinate not reduced modulo p"));
+        }
+        Ok(Point::new_unchecked(x, y))
+    }
+}
+
+fn required<'a, T>(value: &'a Option<T>, field: &str) -> Result<&'a T, LaiCryptoError> {
+    value.as_ref().ok_or_else(|| invalid(field, "Missing field"))
+}
+
+impl From<&Ciphertext> for ProtoCiphertext {
+    fn from(ct: &Ciphertext) -> Self {
+        ProtoCiphertext {
+            c1: Some(ct.c1.into()),
+            c2: Some(ct.c2.into()),
+            tag: ct.tag.to_vec(),
+            version: u32::from(ct.version.to_byte()),
+        }
+    }
+}
+
+impl ProtoCiphertext {
+    pub fn to_ciphertext(&self, p: u128) -> Result<Ciphertext, LaiCryptoError> {
+        Ok(Ciphertext {
+            c1: required(&self.c1, "c1")?.to_point(p, "c1")?,
+            c2: required(&self.c2, "c2")?.to_point(p, "c2")?,
+            tag: self
+                .tag
+                .as_slice()
+                .try_into()
+                .map_err(|_| invalid("tag", &format!("Tag must be {} bytes", TAG_LEN)))?,
+            version: match self.version {
+                0 => CiphertextVersion::V1,
+                v => u8::try_from(v)
+                    .ok()
+                    .and_then(CiphertextVersion::from_byte)
+                    .ok_or_else(|| invalid("version", "Unknown ciphertext version"))?,
+            },
+        })
+    }
+}
+
+impl ProtoPublicKey {
+    /// Public key message carrying the parameters it belongs to
+    pub fn new(key: &PublicKey, p: u128, a: u128, p0: Point) -> Self {
+        ProtoPublicKey {
+            params: Some(ProtoParams {
+                p: scalar_to_bytes(p),
+                a: scalar_to_bytes(a),
+                p0: Some(p0.into()),
+            }),
+            q: Some(key.point().into()),
+        }
+    }
+
+    /// Decode the key, checking its parameters match `(p, a)`
+    pub fn to_public_key(&self, p: u128, a: u128) -> Result<PublicKey, LaiCryptoError> {
+        let params = required(&self.params, "params")?;
+        if scalar_from_bytes(&params.p, "params.p")? != p || scalar_from_bytes(&params.a, "params.a")? != a {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "proto public key".to_string(),
+                expected: format!("p={}, a={}", p, a),
+                actual: "different parameters".to_string(),
+            });
+        }
+        Ok(PublicKey::new(required(&self.q, "q")?.to_point(p, "q")?))
+    }
+}
+
+impl From<&PerfMetrics> for ProtoBenchmarkResult {
+    fn from(metrics: &PerfMetrics) -> Self {
+        let ns = |d: &Duration| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX);
+        ProtoBenchmarkResult {
+            keygen_ns: ns(&metrics.keygen_time),
+            encrypt_ns: ns(&metrics.encrypt_time),
+            decrypt_ns: ns(&metrics.decrypt_time),
+            t_transform_count: metrics.t_transform_count,
+            sqrt_attempts: metrics.sqrt_attempts,
+            history: metrics
+                .operation_history
+                .iter()
+                .zip(metrics.operation_ids.iter().chain(std::iter::repeat(&None)))
+                .map(|((operation, d), id)| ProtoOperationSample {
+                    operation: operation.clone(),
+                    duration_ns: ns(d),
+                    operation_id: id.map(|id| id.0),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LaiCryptoEngine;
+    use prost::Message;
+
+    #[test]
+    fn test_proto_roundtrip() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, 8).unwrap();
+
+        let bytes = ProtoCiphertext::from(&ct).encode_to_vec();
+        let decoded = ProtoCiphertext::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.to_ciphertext(1009).unwrap(), ct);
+        assert!(decoded.to_ciphertext(ct.c1.x().max(ct.c2.x())).is_err());
+
+        let key = ProtoPublicKey::new(&PublicKey::new(q), 1009, 3, p0);
+        let key = ProtoPublicKey::decode(key.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(key.to_public_key(1009, 3).unwrap().point(), q);
+        assert!(key.to_public_key(1009, 5).is_err());
+
+        let bench = ProtoBenchmarkResult::from(&engine.metrics);
+        assert_eq!(ProtoBenchmarkResult::decode(bench.encode_to_vec().as_slice()).unwrap(), bench);
+        assert!(ProtoCiphertext::default().to_ciphertext(1009).is_err());
+    }
+}
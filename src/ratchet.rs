@@ -0,0 +1,438 @@
+//! Forward-secret double-ratchet sessions
+//!
+//! Follows the Signal double ratchet with the fixed-seed agreement of
+//! [`crate::exchange`] as the asymmetric step: whenever the peer presents a
+//! new ratchet point, both sides mix a fresh agreement into the root key
+//! with HKDF-SHA-512. Between steps each message key comes from an HMAC
+//! chain that only moves forward, so keys held now do not decrypt earlier
+//! messages. Message wire format:
+//!
+//! ```text
+//! magic "LAIR" | version | ratchet point (32) | previous chain length (4) | index (4) | body | HMAC-SHA-512 (64)
+//! ```
+
+use crate::exchange::ExchangeKeyPair;
+use crate::hybrid::{raw_point, SessionKeys, MAC_LEN};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::collections::HashMap;
+use std::io;
+use crate::redact::Redacted;
+use std::fmt;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Magic bytes opening every ratchet message
+pub const MAGIC: [u8; 4] = *b"LAIR";
+/// Magic bytes opening a serialized session
+pub const STATE_MAGIC: [u8; 4] = *b"LRAT";
+/// Current message and state format version
+pub const VERSION: u8 = 1;
+/// Bytes before the body: magic, version, ratchet point and two counters
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + Point::ENCODED_LEN + 8;
+/// Most message keys kept or derived ahead for out-of-order delivery
+pub const MAX_SKIP: u32 = 1000;
+
+/// One side of a double-ratchet session
+///
+/// The serialized form from [`RatchetSession::to_bytes`] contains the
+/// current secrets and must be stored as carefully as a private key.
+#[derive(Clone)]
+pub struct RatchetSession {
+    root_key: [u8; 32],
+    dh_self: ExchangeKeyPair,
+    dh_remote: Option<Point>,
+    send_chain: Option<[u8; 32]>,
+    recv_chain: Option<[u8; 32]>,
+    send_n: u32,
+    recv_n: u32,
+    prev_send_n: u32,
+    skipped: HashMap<([u8; Point::ENCODED_LEN], u32), [u8; 32]>,
+}
+
+impl fmt::Debug for RatchetSession {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RatchetSession")
+            .field("root_key", &Redacted(&self.root_key))
+            .field("dh_self", &self.dh_self)
+            .field("dh_remote", &self.dh_remote)
+            .field("send_chain", &Redacted(&self.send_chain))
+            .field("recv_chain", &Redacted(&self.recv_chain))
+            .field("send_n", &self.send_n)
+            .field("recv_n", &self.recv_n)
+            .field("prev_send_n", &self.prev_send_n)
+            .field("skipped", &self.skipped.len())
+            .finish()
+    }
+}
+
+/// Mix an agreement into the root key, yielding (root key, chain key)
+fn kdf_root(root_key: &[u8; 32], agreed: Point) -> ([u8; 32], [u8; 32]) {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha512>::new(Some(root_key), &agreed.to_bytes())
+        .expand(b"LAI-ratchet-root", &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA-512 length");
+    split(&okm)
+}
+
+/// Advance a chain key, yielding (next chain key, message key)
+fn kdf_chain(chain_key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let step = |byte: u8| {
+        let mut mac = HmacSha512::new_from_slice(chain_key).expect("HMAC accepts any key length");
+        mac.update(&[byte]);
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&mac.finalize().into_bytes()[..32]);
+        out
+    };
+    (step(2), step(1))
+}
+
+fn message_keys(message_key: &[u8; 32]) -> SessionKeys {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha512>::new(None, message_key)
+        .expand(b"LAI-ratchet-message", &mut okm)
+        .expect("64 bytes is a valid HKDF-SHA-512 length");
+    SessionKeys::from_digest(&okm)
+}
+
+fn split(okm: &[u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+    a.copy_from_slice(&okm[..32]);
+    b.copy_from_slice(&okm[32..]);
+    (a, b)
+}
+
+/// New ratchet key pair that agrees with `remote` and differs from `avoid`
+///
+/// Orbits at small moduli are short enough that a fresh secret can land on
+/// the previous public point, which the peer would not see as a new step.
+fn fresh_pair(
+    engine: &mut LaiCryptoEngine,
+    remote: Point,
+    avoid: Option<Point>,
+) -> Result<(ExchangeKeyPair, Point), LaiCryptoError> {
+    for _ in 0..engine.max_attempts.max(1) {
+        let (pair, agreed) = engine.retry_ephemeral(|engine, secret| {
+            let pair = ExchangeKeyPair::from_secret(engine, secret)?;
+            let agreed = pair.agree(engine, remote)?;
+            Ok((pair, agreed))
+        })?;
+        if Some(pair.public()) != avoid {
+            return Ok((pair, agreed));
+        }
+    }
+    Err(LaiCryptoError::ValidationError {
+        operation: "ratchet_step".to_string(),
+        expected: "a ratchet point different from the previous one".to_string(),
+        actual: "every drawn secret repeated the previous point".to_string(),
+    })
+}
+
+fn invalid_state(reason: &str) -> LaiCryptoError {
+    LaiCryptoError::serialization("ratchet state", io::Error::new(io::ErrorKind::InvalidData, reason.to_string()))
+}
+
+fn malformed(reason: &str, len: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "ratchet_message".to_string(),
+        value: format!("{} bytes", len),
+        reason: reason.to_string(),
+        valid_range: format!("at least {} bytes starting with \"LAIR\" v{}", HEADER_LEN + MAC_LEN, VERSION),
+    }
+}
+
+impl RatchetSession {
+    /// Start as the party that sends first
+    ///
+    /// `shared_secret` comes from a prior authenticated handshake such as
+    /// [`crate::pake`]; `remote` is the responder's ratchet public point.
+    pub fn initiate(
+        engine: &mut LaiCryptoEngine,
+        shared_secret: [u8; 32],
+        remote: Point,
+    ) -> Result<Self, LaiCryptoError> {
+        let (dh_self, agreed) = fresh_pair(engine, remote, None)?;
+        let (root_key, send_chain) = kdf_root(&shared_secret, agreed);
+        Ok(RatchetSession {
+            root_key,
+            dh_self,
+            dh_remote: Some(remote),
+            send_chain: Some(send_chain),
+            recv_chain: None,
+            send_n: 0,
+            recv_n: 0,
+            prev_send_n: 0,
+            skipped: HashMap::new(),
+        })
+    }
+
+    /// Start as the responder holding the key pair the initiator used
+    ///
+    /// The responder can send once the first message has been received.
+    pub fn respond(shared_secret: [u8; 32], dh_self: ExchangeKeyPair) -> Self {
+        RatchetSession {
+            root_key: shared_secret,
+            dh_self,
+            dh_remote: None,
+            send_chain: None,
+            recv_chain: None,
+            send_n: 0,
+            recv_n: 0,
+            prev_send_n: 0,
+            skipped: HashMap::new(),
+        }
+    }
+
+    /// Current ratchet public point
+    pub fn ratchet_point(&self) -> Point {
+        self.dh_self.public()
+    }
+
+    /// Encrypt the next message on the sending chain
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        let chain = self.send_chain.ok_or_else(|| LaiCryptoError::ValidationError {
+            operation: "ratchet_encrypt".to_string(),
+            expected: "a sending chain".to_string(),
+            actual: "none until the first message is received".to_string(),
+        })?;
+        let (next, message_key) = kdf_chain(&chain);
+        self.send_chain = Some(next);
+
+        let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + MAC_LEN);
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.dh_self.public().to_bytes());
+        out.extend_from_slice(&self.prev_send_n.to_be_bytes());
+        out.extend_from_slice(&self.send_n.to_be_bytes());
+        self.send_n += 1;
+
+        let keys = message_keys(&message_key);
+        out.extend_from_slice(plaintext);
+        keys.apply_keystream(&mut out[HEADER_LEN..]);
+        let mut mac = keys.mac();
+        mac.update(&out);
+        out.extend_from_slice(&mac.finalize().into_bytes());
+        Ok(out)
+    }
+
+    /// Verify and decrypt a message, stepping the ratchet if it carries a new point
+    ///
+    /// Messages may arrive out of order within [`MAX_SKIP`]. The session is
+    /// left unchanged when a message fails to verify.
+    pub fn decrypt(&mut self, engine: &mut LaiCryptoEngine, message: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        let len = message.len();
+        if len < HEADER_LEN + MAC_LEN {
+            return Err(malformed("Message too short", len));
+        }
+        if message[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LAIR magic", len));
+        }
+        if message[MAGIC.len()] != VERSION {
+            return Err(malformed("Unsupported version", len));
+        }
+        let point_start = MAGIC.len() + 1;
+        let point_bytes: [u8; Point::ENCODED_LEN] = message[point_start..point_start + Point::ENCODED_LEN]
+            .try_into()
+            .expect("header length checked");
+        let remote = raw_point(&point_bytes, engine.p).ok_or_else(|| malformed("Coordinate not reduced modulo p", len))?;
+        let counter = |at: usize| u32::from_be_bytes(message[at..at + 4].try_into().expect("header length checked"));
+        let (prev_n, n) = (counter(HEADER_LEN - 8), counter(HEADER_LEN - 4));
+
+        let mut next = self.clone();
+        let message_key = match next.skipped.remove(&(point_bytes, n)) {
+            Some(key) => key,
+            None => {
+                if next.dh_remote != Some(remote) {
+                    next.skip_until(prev_n)?;
+                    next.step(engine, remote)?;
+                }
+                next.skip_until(n)?;
+                let (chain, key) = kdf_chain(&next.recv_chain.expect("receiving chain set by step"));
+                next.recv_chain = Some(chain);
+                next.recv_n += 1;
+                key
+            }
+        };
+
+        let keys = message_keys(&message_key);
+        let (authenticated, tag) = message.split_at(len - MAC_LEN);
+        let mut mac = keys.mac();
+        mac.update(authenticated);
+        mac.verify_slice(tag).map_err(|_| LaiCryptoError::AuthenticationFailed {
+            operation: "ratchet_decrypt".to_string(),
+        })?;
+        let mut body = authenticated[HEADER_LEN..].to_vec();
+        keys.apply_keystream(&mut body);
+        *self = next;
+        Ok(body)
+    }
+
+    /// Store message keys of the receiving chain up to index `until`
+    fn skip_until(&mut self, until: u32) -> Result<(), LaiCryptoError> {
+        let (Some(mut chain), Some(remote)) = (self.recv_chain, self.dh_remote) else {
+            return Ok(());
+        };
+        if until > self.recv_n.saturating_add(MAX_SKIP) || self.skipped.len() as u32 >= MAX_SKIP {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "ratchet_index".to_string(),
+                value: until.to_string(),
+                reason: "Too many skipped messages".to_string(),
+                valid_range: format!("at most {} ahead of {}", MAX_SKIP, self.recv_n),
+            });
+        }
+        while self.recv_n < until {
+            let (next, key) = kdf_chain(&chain);
+            self.skipped.insert((remote.to_bytes(), self.recv_n), key);
+            chain = next;
+            self.recv_n += 1;
+        }
+        self.recv_chain = Some(chain);
+        Ok(())
+    }
+
+    /// Asymmetric ratchet step on receiving a new remote point
+    fn step(&mut self, engine: &mut LaiCryptoEngine, remote: Point) -> Result<(), LaiCryptoError> {
+        self.prev_send_n = self.send_n;
+        self.send_n = 0;
+        self.recv_n = 0;
+        self.dh_remote = Some(remote);
+        let agreed = self.dh_self.agree(engine, remote)?;
+        let (root_key, recv_chain) = kdf_root(&self.root_key, agreed);
+        let (dh_self, agreed) = fresh_pair(engine, remote, Some(self.dh_self.public()))?;
+        let (root_key, send_chain) = kdf_root(&root_key, agreed);
+        self.root_key = root_key;
+        self.recv_chain = Some(recv_chain);
+        self.dh_self = dh_self;
+        self.send_chain = Some(send_chain);
+        Ok(())
+    }
+
+    /// Serialize the full session state, secrets included
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&STATE_MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.root_key);
+        out.extend_from_slice(&self.dh_self.secret().to_be_bytes());
+        out.extend_from_slice(&self.dh_self.public().to_bytes());
+        let flags = u8::from(self.dh_remote.is_some())
+            | u8::from(self.send_chain.is_some()) << 1
+            | u8::from(self.recv_chain.is_some()) << 2;
+        out.push(flags);
+        out.extend_from_slice(&self.dh_remote.map_or([0; Point::ENCODED_LEN], |p| p.to_bytes()));
+        out.extend_from_slice(&self.send_chain.unwrap_or_default());
+        out.extend_from_slice(&self.recv_chain.unwrap_or_default());
+        for counter in [self.send_n, self.recv_n, self.prev_send_n, self.skipped.len() as u32] {
+            out.extend_from_slice(&counter.to_be_bytes());
+        }
+        let mut skipped: Vec<_> = self.skipped.iter().collect();
+        skipped.sort();
+        for ((point, n), key) in skipped {
+            out.extend_from_slice(point);
+            out.extend_from_slice(&n.to_be_bytes());
+            out.extend_from_slice(key);
+        }
+        out
+    }
+
+    /// Restore a session serialized by [`RatchetSession::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let mut rest = bytes;
+        let mut take = |n: usize| -> Result<&[u8], LaiCryptoError> {
+            if rest.len() < n {
+                return Err(invalid_state("Truncated state"));
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        if take(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(invalid_state("Missing LRAT magic"));
+        }
+        if take(1)?[0] != VERSION {
+            return Err(invalid_state("Unsupported version"));
+        }
+        let key = |b: &[u8]| -> [u8; 32] { b.try_into().expect("32-byte key") };
+        let point = |b: &[u8]| raw_point(b, u128::MAX).ok_or_else(|| invalid_state("Bad point"));
+
+        let root_key = key(take(32)?);
+        let secret = u128::from_be_bytes(take(16)?.try_into().expect("16-byte scalar"));
+        let public = point(take(Point::ENCODED_LEN)?)?;
+        let flags = take(1)?[0];
+        if flags > 0b111 {
+            return Err(invalid_state("Unknown flags"));
+        }
+        let remote = point(take(Point::ENCODED_LEN)?)?;
+        let (send_chain, recv_chain) = (key(take(32)?), key(take(32)?));
+        let mut counter = || -> Result<u32, LaiCryptoError> {
+            Ok(u32::from_be_bytes(take(4)?.try_into().expect("4-byte counter")))
+        };
+        let (send_n, recv_n, prev_send_n, count) = (counter()?, counter()?, counter()?, counter()?);
+        if count > MAX_SKIP {
+            return Err(invalid_state("Too many skipped keys"));
+        }
+        let mut skipped = HashMap::new();
+        for _ in 0..count {
+            let point: [u8; Point::ENCODED_LEN] = take(Point::ENCODED_LEN)?.try_into().expect("encoded point");
+            let n = u32::from_be_bytes(take(4)?.try_into().expect("4-byte counter"));
+            skipped.insert((point, n), key(take(32)?));
+        }
+        if !rest.is_empty() {
+            return Err(invalid_state("Trailing bytes"));
+        }
+        Ok(RatchetSession {
+            root_key,
+            dh_self: ExchangeKeyPair::from_parts(secret, public),
+            dh_remote: (flags & 1 != 0).then_some(remote),
+            send_chain: (flags & 2 != 0).then_some(send_chain),
+            recv_chain: (flags & 4 != 0).then_some(recv_chain),
+            send_n,
+            recv_n,
+            prev_send_n,
+            skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ratchet_roundtrip() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let shared = [7u8; 32];
+        let bob_pair = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let mut alice = RatchetSession::initiate(&mut engine, shared, bob_pair.public()).unwrap();
+        let bob_point = bob_pair.public();
+        let mut bob = RatchetSession::respond(shared, bob_pair);
+        assert!(bob.encrypt(b"too early").is_err());
+
+        // Out-of-order delivery within one chain
+        let first = alice.encrypt(b"one").unwrap();
+        let second = alice.encrypt(b"two").unwrap();
+        assert_eq!(bob.decrypt(&mut engine, &second).unwrap(), b"two");
+
+        // A tampered message is rejected without disturbing the session
+        let mut tampered = first.clone();
+        tampered[HEADER_LEN] ^= 1;
+        assert!(matches!(
+            bob.decrypt(&mut engine, &tampered),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+        assert_eq!(bob.decrypt(&mut engine, &first).unwrap(), b"one");
+        assert!(bob.decrypt(&mut engine, &first).is_err());
+
+        // Replies step the ratchet in both directions
+        let mut bob = RatchetSession::from_bytes(&bob.to_bytes()).unwrap();
+        let reply = bob.encrypt(b"three").unwrap();
+        assert_ne!(bob.ratchet_point(), bob_point);
+        assert_eq!(alice.decrypt(&mut engine, &reply).unwrap(), b"three");
+        let again = alice.encrypt(b"four").unwrap();
+        assert_eq!(bob.decrypt(&mut engine, &again).unwrap(), b"four");
+
+        assert!(RatchetSession::from_bytes(&alice.to_bytes()[..40]).is_err());
+    }
+}
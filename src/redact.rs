@@ -0,0 +1,55 @@
+//! Redaction of secrets in `Debug` output
+//!
+//! Types holding secret scalars, shared secrets or symmetric keys
+//! implement `Debug` by hand: public points, parameters and counters are
+//! printed, and every secret field shows as `<redacted>`. Building with the
+//! `unsafe-debug` feature prints the secret values instead, for local
+//! debugging only.
+
+use std::fmt;
+
+/// Placeholder printed in place of a secret
+pub const REDACTED: &str = "<redacted>";
+
+/// `Debug` of a secret field: [`REDACTED`] unless `unsafe-debug` is enabled
+pub(crate) struct Redacted<'a, T: ?Sized>(pub(crate) &'a T);
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Redacted<'_, T> {
+    #[cfg(not(feature = "unsafe-debug"))]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+
+    #[cfg(feature = "unsafe-debug")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(all(test, not(feature = "unsafe-debug")))]
+mod tests {
+    use super::*;
+    use crate::exchange::ExchangeKeyPair;
+    use crate::{LaiCryptoEngine, SecretKey};
+
+    #[test]
+    fn test_secrets_redacted() {
+        let secret = 987_654_321u128;
+        assert_eq!(
+            format!("{:?}", SecretKey::new(secret)),
+            "SecretKey(<redacted>)"
+        );
+
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let pair = ExchangeKeyPair::from_secret(&mut engine, 31_337).unwrap();
+        let debug = format!("{:?}", pair);
+        assert!(debug.contains(REDACTED) && !debug.contains("31337"));
+        assert!(debug.contains(&format!("{:?}", pair.public())));
+
+        assert_eq!(
+            engine.to_string(),
+            "LAI(p = 1009, a = 3, P0 = (0, 0), SHA-512)"
+        );
+        assert!(format!("{:?}", engine).contains("params_hash"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Process-wide aggregation of engine metrics
+//!
+//! Each engine keeps its own [`crate::PerfMetrics`], which disappear with
+//! it. Servers that build a short-lived engine per request can instead
+//! register every engine with one [`MetricsRegistry`], usually
+//! [`MetricsRegistry::global`], and read counts and latencies per
+//! parameter set and operation from there.
+
+use crate::envelope::hex;
+use crate::LaiCryptoEngine;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Dimensions one series is keyed by
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Labels {
+    /// Hex [`LaiCryptoEngine::params_hash`] of the reporting engine
+    pub params: String,
+    pub operation: String,
+}
+
+/// Aggregated latencies of one series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub count: u64,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl OpStats {
+    fn record(&mut self, duration: Duration) {
+        self.min = if self.count == 0 {
+            duration
+        } else {
+            self.min.min(duration)
+        };
+        self.max = self.max.max(duration);
+        self.total += duration;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.count) {
+            Ok(0) => Duration::ZERO,
+            Ok(n) => self.total / n,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.count as f64),
+        }
+    }
+}
+
+/// Thread-safe store of [`OpStats`] by [`Labels`]
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    series: Mutex<BTreeMap<Labels, OpStats>>,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry shared by the whole process
+    pub fn global() -> Arc<MetricsRegistry> {
+        static GLOBAL: OnceLock<Arc<MetricsRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(Default::default).clone()
+    }
+
+    pub fn record(&self, labels: Labels, duration: Duration) {
+        self.lock().entry(labels).or_default().record(duration);
+    }
+
+    pub fn get(&self, params: &str, operation: &str) -> Option<OpStats> {
+        let labels = Labels {
+            params: params.to_string(),
+            operation: operation.to_string(),
+        };
+        self.lock().get(&labels).copied()
+    }
+
+    /// Every series, ordered by parameter set then operation
+    pub fn snapshot(&self) -> Vec<(Labels, OpStats)> {
+        self.lock().iter().map(|(l, s)| (l.clone(), *s)).collect()
+    }
+
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<Labels, OpStats>> {
+        // A panic mid-update leaves at worst one half-recorded sample
+        self.series.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Registry an engine reports to, if any
+///
+/// Not part of snapshots: it encodes to nothing and a restored engine is
+/// unregistered.
+#[derive(Clone, Default)]
+pub(crate) struct Registration(Option<(Arc<MetricsRegistry>, String)>);
+
+impl fmt::Debug for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.is_some())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for Registration {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C> bincode::Decode<C> for Registration {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Registration);
+
+impl Registration {
+    pub(crate) fn record(&self, operation: &str, duration: Duration) {
+        if let Some((registry, params)) = &self.0 {
+            registry.record(
+                Labels {
+                    params: params.clone(),
+                    operation: operation.to_string(),
+                },
+                duration,
+            );
+        }
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Also report every recorded operation to `registry`
+    ///
+    /// Operations are labeled with this engine's parameters hash as of
+    /// registration; re-register after changing `p`, `a` or `p0`.
+    pub fn register(&mut self, registry: Arc<MetricsRegistry>) {
+        let params = hex(&self.params_hash());
+        self.registry = Registration(Some((registry, params)));
+    }
+
+    /// [`LaiCryptoEngine::register`] with [`MetricsRegistry::global`]
+    pub fn register_global(&mut self) {
+        self.register(MetricsRegistry::global());
+    }
+
+    pub fn unregister(&mut self) {
+        self.registry = Registration::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_aggregates_engines() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let mut params = None;
+        for k in [3, 5] {
+            let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+            engine.register(registry.clone());
+            params = Some(hex(&engine.params_hash()));
+            engine.pow_t_range(engine.p0, 1, k).unwrap();
+        }
+        let mut other = LaiCryptoEngine::new(10007, 3, (0, 0)).unwrap();
+        other.register(registry.clone());
+        other.pow_t_range(other.p0, 1, 2).unwrap();
+        other.unregister();
+        other.pow_t_range(other.p0, 1, 2).unwrap();
+
+        let stats = registry.get(&params.unwrap(), "pow_t_range").unwrap();
+        assert_eq!(stats.count, 2);
+        assert!(stats.min <= stats.mean() && stats.mean() <= stats.max);
+        let other_params = hex(&other.params_hash());
+        assert_eq!(registry.get(&other_params, "pow_t_range").unwrap().count, 1);
+        assert_eq!(registry.get(&other_params, "t").unwrap().count, 2);
+
+        registry.reset();
+        assert!(registry.snapshot().is_empty());
+        assert!(Arc::ptr_eq(
+            &MetricsRegistry::global(),
+            &MetricsRegistry::global()
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+//! Signed telemetry bundles
+//!
+//! [`LaiCryptoEngine::telemetry_json`] collects the engine's parameters,
+//! metrics, trace and audit log into one JSON document. [`export`] signs
+//! that document with an Ed25519 [`ReportingKey`] so a benchmark or
+//! incident report passed between teams can be checked against the
+//! reporter's published key:
+//!
+//! ```text
+//! magic "LAIR" | version | signer key (32) | signature (64) | JSON payload
+//! ```
+//!
+//! The signature covers a domain label, the signer key and the payload.
+//! The T-transform has no signature scheme of its own (see
+//! [`crate::signcrypt`]), and a report must be verifiable by any number of
+//! readers, so this module relies on `ed25519-dalek` behind the `report`
+//! feature.
+
+use crate::audit::AuditRecord;
+use crate::envelope::hex;
+use crate::{json_object, json_string, LaiCryptoEngine, LaiCryptoError};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+
+/// Magic bytes opening every signed report
+pub const MAGIC: [u8; 4] = *b"LAIR";
+/// Current wire format version
+pub const VERSION: u8 = 1;
+/// Bytes before the payload: magic, version, signer key and signature
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 32 + 64;
+
+const DOMAIN: &[u8] = b"LAI-report v1";
+
+/// Ed25519 key a reporter signs bundles with
+pub struct ReportingKey(SigningKey);
+
+impl ReportingKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self(SigningKey::generate(rng))
+    }
+
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self(SigningKey::from_bytes(secret))
+    }
+
+    /// Public half to hand to readers of the reports
+    pub fn public(&self) -> [u8; 32] {
+        self.0.verifying_key().to_bytes()
+    }
+}
+
+impl fmt::Debug for ReportingKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReportingKey")
+            .field("public", &hex(&self.public()))
+            .field("secret", &crate::redact::Redacted(&self.0.to_bytes()))
+            .finish()
+    }
+}
+
+/// Telemetry payload with its signer and signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedReport {
+    pub signer: [u8; 32],
+    pub signature: [u8; 64],
+    pub payload: String,
+}
+
+fn signed_message(signer: &[u8; 32], payload: &str) -> Vec<u8> {
+    [DOMAIN, signer, payload.as_bytes()].concat()
+}
+
+/// Sign the engine's current telemetry with `key`
+pub fn export(engine: &LaiCryptoEngine, key: &ReportingKey) -> SignedReport {
+    sign(engine.telemetry_json(), key)
+}
+
+/// Sign an arbitrary JSON payload with `key`
+pub fn sign(payload: String, key: &ReportingKey) -> SignedReport {
+    let signer = key.public();
+    let signature = key.0.sign(&signed_message(&signer, &payload)).to_bytes();
+    SignedReport {
+        signer,
+        signature,
+        payload,
+    }
+}
+
+impl SignedReport {
+    /// Check the signature and that it was made by `trusted`
+    ///
+    /// Returns the payload on success and `AuthenticationFailed` for a
+    /// different signer or any modified byte.
+    pub fn verify(&self, trusted: &[u8; 32]) -> Result<&str, LaiCryptoError> {
+        let failed = || LaiCryptoError::AuthenticationFailed {
+            operation: "verify report".to_string(),
+        };
+        if &self.signer != trusted {
+            return Err(failed());
+        }
+        let key = VerifyingKey::from_bytes(trusted).map_err(|_| failed())?;
+        key.verify_strict(
+            &signed_message(&self.signer, &self.payload),
+            &Signature::from_bytes(&self.signature),
+        )
+        .map_err(|_| failed())?;
+        Ok(&self.payload)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.signer);
+        out.extend_from_slice(&self.signature);
+        out.extend_from_slice(self.payload.as_bytes());
+        out
+    }
+
+    /// Parse the layout written by [`SignedReport::to_bytes`]
+    ///
+    /// Only the framing is checked; call [`SignedReport::verify`] before
+    /// trusting the payload.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "report".to_string(),
+            value: format!("{} bytes", bytes.len()),
+            reason: reason.to_string(),
+            valid_range: format!(
+                "at least {} bytes starting with \"LAIR\" v{}",
+                HEADER_LEN, VERSION
+            ),
+        };
+        if bytes.len() < HEADER_LEN {
+            return Err(malformed("Report too short"));
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(malformed("Missing LAIR magic"));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(malformed("Unsupported version"));
+        }
+        let (signer, rest) = bytes[MAGIC.len() + 1..].split_at(32);
+        let (signature, payload) = rest.split_at(64);
+        let payload =
+            std::str::from_utf8(payload).map_err(|_| malformed("Payload is not UTF-8"))?;
+        Ok(Self {
+            signer: signer.try_into().expect("32 bytes"),
+            signature: signature.try_into().expect("64 bytes"),
+            payload: payload.to_string(),
+        })
+    }
+}
+
+fn audit_record_json(record: &AuditRecord) -> String {
+    json_object(&[
+        ("seq", record.seq.to_string()),
+        ("timestamp", record.timestamp.to_string()),
+        ("operation", json_string(&record.operation.to_string())),
+        (
+            "key",
+            record
+                .key
+                .map_or("null".to_string(), |k| json_string(&k.to_string())),
+        ),
+        ("params_hash", json_string(&hex(&record.params_hash))),
+        ("success", record.success.to_string()),
+        ("prev_hash", json_string(&hex(&record.prev_hash))),
+        ("hash", json_string(&hex(&record.hash))),
+    ])
+}
+
+impl LaiCryptoEngine {
+    /// Parameters, metrics, trace and audit log as one JSON document
+    ///
+    /// Integers beyond JSON's exact range are decimal strings and
+    /// durations are milliseconds, as in [`LaiCryptoError::to_json`].
+    pub fn telemetry_json(&self) -> String {
+        let num = |v: u128| json_string(&v.to_string());
+        let ms = |d: &std::time::Duration| format!("{}", d.as_secs_f64() * 1000.0);
+        let metrics = &self.metrics;
+        let history: Vec<String> = metrics
+            .operation_history
+            .iter()
+            .zip(metrics.operation_ids.iter().chain(std::iter::repeat(&None)))
+            .map(|((op, d), id)| {
+                json_object(&[
+                    ("operation", json_string(op)),
+                    ("duration_ms", ms(d)),
+                    (
+                        "operation_id",
+                        id.map_or("null".to_string(), |id| id.0.to_string()),
+                    ),
+                ])
+            })
+            .collect();
+        let trace: Vec<String> = self.trace.iter().map(|step| step.to_json()).collect();
+        let audit = self.audit_log.as_ref().map_or("null".to_string(), |log| {
+            let records: Vec<String> = log.records().iter().map(audit_record_json).collect();
+            format!("[{}]", records.join(","))
+        });
+        json_object(&[
+            (
+                "params",
+                json_object(&[
+                    ("p", num(self.p)),
+                    ("a", num(self.a)),
+                    ("p0", format!("[{},{}]", num(self.p0.x()), num(self.p0.y()))),
+                    ("hash", json_string(self.hash.name())),
+                    ("params_hash", json_string(&hex(&self.params_hash()))),
+                ]),
+            ),
+            (
+                "metrics",
+                json_object(&[
+                    ("keygen_ms", ms(&metrics.keygen_time)),
+                    ("encrypt_ms", ms(&metrics.encrypt_time)),
+                    ("decrypt_ms", ms(&metrics.decrypt_time)),
+                    ("t_transform_count", metrics.t_transform_count.to_string()),
+                    ("sqrt_attempts", metrics.sqrt_attempts.to_string()),
+                    (
+                        "sqrt_method",
+                        metrics
+                            .sqrt_method
+                            .map_or("null".to_string(), |m| json_string(m.name())),
+                    ),
+                    ("history", format!("[{}]", history.join(","))),
+                ]),
+            ),
+            ("trace", format!("[{}]", trace.join(","))),
+            ("audit", audit),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_signed_report() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.audit_log = Some(AuditLog::new());
+        let k = 7;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, k).unwrap();
+        engine.decrypt(&ct, k).unwrap();
+
+        let key = ReportingKey::generate(&mut OsRng);
+        let report = export(&engine, &key);
+        assert!(report.payload.contains("\"audit\":[{\"seq\":0"));
+        assert!(report.payload.contains("\"operation\":\"decrypt\""));
+
+        let parsed = SignedReport::from_bytes(&report.to_bytes()).unwrap();
+        assert_eq!(parsed, report);
+        assert_eq!(parsed.verify(&key.public()).unwrap(), report.payload);
+
+        let other = ReportingKey::generate(&mut OsRng);
+        assert!(report.verify(&other.public()).is_err());
+        let mut forged = report.clone();
+        forged.payload = forged.payload.replace("decrypt", "encrypt");
+        assert!(forged.verify(&key.public()).is_err());
+        let mut bytes = report.to_bytes();
+        bytes[0] = b'X';
+        assert!(SignedReport::from_bytes(&bytes).is_err());
+    }
+}
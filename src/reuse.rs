@@ -0,0 +1,96 @@
+//! Detection of repeated ephemeral commitments
+//!
+//! Two ciphertexts with the same c1 were made with the same ephemeral
+//! chain, so their masks coincide and subtracting the c2 values reveals
+//! the difference of the plaintexts. A working RNG practically never
+//! repeats one at real parameter sizes; a repeat signals broken or cloned
+//! randomness. The guard remembers the last `capacity` values of c1 and
+//! turns a repeat into [`LaiCryptoError::RandomnessReuse`] before the
+//! ciphertext is released.
+
+use crate::{LaiCryptoEngine, Point};
+use std::collections::{HashSet, VecDeque};
+
+/// Bounded memory of recently emitted c1 values, evicting the oldest first
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ReuseGuard {
+    capacity: usize,
+    seen: HashSet<Point>,
+    order: VecDeque<Point>,
+}
+
+impl ReuseGuard {
+    pub fn new(capacity: usize) -> Self {
+        ReuseGuard {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn contains(&self, c1: Point) -> bool {
+        self.seen.contains(&c1)
+    }
+
+    /// Remember `c1`, forgetting the oldest entry when full
+    pub fn insert(&mut self, c1: Point) {
+        if self.capacity == 0 || !self.seen.insert(c1) {
+            return;
+        }
+        self.order.push_back(c1);
+        if self.order.len() > self.capacity {
+            let oldest = self.order.pop_front().expect("over capacity");
+            self.seen.remove(&oldest);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Reject encryptions that repeat any of the last `capacity` c1 values
+    ///
+    /// Deterministic encryption in [`crate::siv`] repeats c1 by design for
+    /// equal inputs and bypasses the guard.
+    pub fn enable_reuse_detection(&mut self, capacity: usize) {
+        self.reuse_guard = Some(ReuseGuard::new(capacity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LaiCryptoError;
+
+    #[test]
+    fn test_reuse_detected() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+        engine.enable_reuse_detection(2);
+
+        let first = engine.encrypt_with_ephemeral(1, q, 5).unwrap();
+        let reused = engine.encrypt_with_ephemeral(2, q, 5).unwrap_err();
+        assert_eq!(reused, LaiCryptoError::RandomnessReuse { operation: "encrypt".to_string(), c1: first.c1 });
+        assert_eq!(reused.code(), 4003);
+
+        // Once evicted, the same c1 is accepted again
+        let mut guard = ReuseGuard::new(2);
+        for x in 0..3 {
+            guard.insert(Point::new_unchecked(x, 0));
+        }
+        assert_eq!(guard.len(), 2);
+        assert!(!guard.contains(Point::new_unchecked(0, 0)));
+        assert!(guard.contains(Point::new_unchecked(2, 0)));
+
+        engine.reuse_guard = None;
+        assert!(engine.encrypt_with_ephemeral(2, q, 5).is_ok());
+    }
+}
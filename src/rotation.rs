@@ -0,0 +1,268 @@
+//! Key rotation across generations of a keypair
+//!
+//! A [`KeyRing`] holds every generation of a recipient's keypair under a
+//! numeric key ID. It is the recipient's side; senders keep the current
+//! public keys of many recipients in a
+//! [`crate::keyring::PublicKeyDirectory`]. Encryption always uses the current generation and
+//! prefixes the [`crate::hybrid`] ciphertext with its ID, so decryption
+//! picks the matching secret without trial decryption:
+//!
+//! ```text
+//! magic "LAIK" | version | key ID (4) | hybrid ciphertext
+//! ```
+//!
+//! The ID is not covered by the inner MAC; changing it only selects a
+//! different secret, which then fails authentication.
+
+use crate::{ExposeSecret, LaiCryptoEngine, LaiCryptoError, Point};
+use crate::redact::Redacted;
+use std::fmt;
+
+/// Magic bytes opening every key-ID-framed ciphertext
+pub const MAGIC: [u8; 4] = *b"LAIK";
+/// Current framing version
+pub const VERSION: u8 = 1;
+/// Bytes before the hybrid ciphertext: magic, version and key ID
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// What a generation may still be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyStatus {
+    /// Current generation: encrypts and decrypts
+    Active,
+    /// Rotated out: only decrypts existing ciphertexts
+    DecryptOnly,
+}
+
+/// One generation of the keypair
+#[derive(Clone)]
+pub struct KeyGeneration {
+    id: u32,
+    secret: u128,
+    public: Point,
+    status: KeyStatus,
+}
+
+impl fmt::Debug for KeyGeneration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyGeneration")
+            .field("id", &self.id)
+            .field("secret", &Redacted(&self.secret))
+            .field("public", &self.public)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl KeyGeneration {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn public(&self) -> Point {
+        self.public
+    }
+
+    pub fn status(&self) -> KeyStatus {
+        self.status
+    }
+}
+
+/// All generations of a keypair, newest last
+#[derive(Debug, Clone)]
+pub struct KeyRing {
+    generations: Vec<KeyGeneration>,
+}
+
+impl KeyRing {
+    /// Start a ring with a freshly generated first key, ID 1
+    pub fn generate(engine: &mut LaiCryptoEngine) -> Result<Self, LaiCryptoError> {
+        let keys = engine.keygen()?;
+        Ok(Self::from_key(*keys.secret.expose_secret(), keys.public.point()))
+    }
+
+    /// Start a ring from an existing keypair, ID 1
+    pub fn from_key(secret: u128, public: Point) -> Self {
+        KeyRing {
+            generations: vec![KeyGeneration {
+                id: 1,
+                secret,
+                public,
+                status: KeyStatus::Active,
+            }],
+        }
+    }
+
+    /// Current generation, used by [`KeyRing::encrypt`]
+    pub fn current(&self) -> &KeyGeneration {
+        self.generations.last().expect("a key ring is never empty")
+    }
+
+    pub fn get(&self, id: u32) -> Option<&KeyGeneration> {
+        self.generations.iter().find(|g| g.id == id)
+    }
+
+    /// Every generation still held, oldest first
+    pub fn generations(&self) -> impl Iterator<Item = &KeyGeneration> {
+        self.generations.iter()
+    }
+
+    /// Generate a new current key and mark all earlier ones decrypt-only
+    ///
+    /// Returns the new key ID.
+    pub fn rotate(&mut self, engine: &mut LaiCryptoEngine) -> Result<u32, LaiCryptoError> {
+        let keys = engine.keygen()?;
+        Ok(self.rotate_to(*keys.secret.expose_secret(), keys.public.point()))
+    }
+
+    /// Install an existing keypair as the new current key
+    pub fn rotate_to(&mut self, secret: u128, public: Point) -> u32 {
+        let id = self.current().id + 1;
+        for generation in &mut self.generations {
+            generation.status = KeyStatus::DecryptOnly;
+        }
+        self.generations.push(KeyGeneration {
+            id,
+            secret,
+            public,
+            status: KeyStatus::Active,
+        });
+        id
+    }
+
+    /// Drop a decrypt-only generation; ciphertexts under it become unreadable
+    ///
+    /// The current key cannot be removed.
+    pub fn remove(&mut self, id: u32) -> Result<(), LaiCryptoError> {
+        if id == self.current().id {
+            return Err(LaiCryptoError::InvalidParameter {
+                param: "key id".to_string(),
+                value: id.to_string(),
+                reason: "Cannot remove the active key".to_string(),
+                valid_range: "IDs of decrypt-only keys".to_string(),
+            });
+        }
+        let before = self.generations.len();
+        self.generations.retain(|g| g.id != id);
+        if self.generations.len() == before {
+            return Err(unknown_id(id));
+        }
+        Ok(())
+    }
+
+    /// Encrypt to the current key, recording its ID in the header
+    pub fn encrypt(
+        &self,
+        engine: &mut LaiCryptoEngine,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        let current = self.current();
+        Ok(frame(
+            current.id,
+            &engine.encrypt_bytes(plaintext, current.public)?,
+        ))
+    }
+
+    /// Decrypt with whichever generation the header names
+    pub fn decrypt(
+        &self,
+        engine: &mut LaiCryptoEngine,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        let id = key_id(ciphertext)?;
+        let generation = self.get(id).ok_or_else(|| unknown_id(id))?;
+        engine.decrypt_bytes(&ciphertext[HEADER_LEN..], generation.secret)
+    }
+}
+
+fn unknown_id(id: u32) -> LaiCryptoError {
+    LaiCryptoError::ValidationError {
+        operation: "key ring".to_string(),
+        expected: "a key ID held by the ring".to_string(),
+        actual: format!("key ID {}", id),
+    }
+}
+
+fn frame(id: u32, ciphertext: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&id.to_be_bytes());
+    out.extend_from_slice(ciphertext);
+    out
+}
+
+/// Key ID a framed ciphertext was encrypted under
+pub fn key_id(ciphertext: &[u8]) -> Result<u32, LaiCryptoError> {
+    let len = ciphertext.len();
+    if len < HEADER_LEN || ciphertext[..MAGIC.len()] != MAGIC || ciphertext[MAGIC.len()] != VERSION
+    {
+        return Err(LaiCryptoError::InvalidParameter {
+            param: "ciphertext".to_string(),
+            value: format!("{} bytes", len),
+            reason: "Missing key ID header".to_string(),
+            valid_range: format!(
+                "at least {} bytes starting with \"LAIK\" v{}",
+                HEADER_LEN, VERSION
+            ),
+        });
+    }
+    Ok(u32::from_be_bytes(
+        ciphertext[MAGIC.len() + 1..HEADER_LEN]
+            .try_into()
+            .expect("length checked"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let (k1, k2) = (8, 11);
+        let (q1, q2) = (
+            engine.pow_t(p0, k1).unwrap(),
+            engine.pow_t(p0, k2).unwrap(),
+        );
+
+        let mut ring = KeyRing::from_key(k1, q1);
+        let old = ring.encrypt(&mut engine, b"before").unwrap();
+        assert_eq!(ring.rotate_to(k2, q2), 2);
+        assert_eq!(ring.current().public(), q2);
+        assert_eq!(ring.get(1).unwrap().status(), KeyStatus::DecryptOnly);
+        let new = ring.encrypt(&mut engine, b"after").unwrap();
+
+        assert_eq!(key_id(&old).unwrap(), 1);
+        assert_eq!(key_id(&new).unwrap(), 2);
+        assert_eq!(ring.decrypt(&mut engine, &old).unwrap(), b"before");
+        assert_eq!(ring.decrypt(&mut engine, &new).unwrap(), b"after");
+
+        let mut relabeled = new.clone();
+        relabeled[HEADER_LEN - 1] = 1;
+        assert!(ring.decrypt(&mut engine, &relabeled).is_err());
+
+        assert!(ring.remove(2).is_err());
+        ring.remove(1).unwrap();
+        assert!(matches!(
+            ring.decrypt(&mut engine, &old),
+            Err(LaiCryptoError::ValidationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_generate_and_rotate() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let mut ring = KeyRing::generate(&mut engine).unwrap();
+        let old = ring.encrypt(&mut engine, b"first generation").unwrap();
+
+        assert_eq!(ring.rotate(&mut engine).unwrap(), 2);
+        assert_eq!(ring.get(1).unwrap().status(), KeyStatus::DecryptOnly);
+        let new = ring.encrypt(&mut engine, b"second generation").unwrap();
+        assert_eq!(key_id(&new).unwrap(), 2);
+        assert_eq!(ring.decrypt(&mut engine, &old).unwrap(), b"first generation");
+        assert_eq!(ring.decrypt(&mut engine, &new).unwrap(), b"second generation");
+    }
+}
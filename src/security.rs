@@ -0,0 +1,136 @@
+//! Approximate security estimates for LAI parameter sets
+//!
+//! The figures are cost models for generic attacks, not proofs. They are
+//! meant to catch parameter choices that are obviously too small; use
+//! [`crate::attack`] to check the cheap end empirically.
+
+use crate::{is_prime, CurveParams};
+use std::fmt;
+
+/// Bit-security level below which an estimate carries a warning
+pub const MIN_RECOMMENDED_BITS: f64 = 80.0;
+
+/// Modelled cost of a single attack
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackCost {
+    pub name: &'static str,
+    /// log2 of T-transform evaluations
+    pub log2_time: f64,
+    /// log2 of stored points
+    pub log2_memory: f64,
+    /// Assumption the model rests on
+    pub note: &'static str,
+}
+
+/// Approximate bit security with the attack costs behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityEstimate {
+    pub params: CurveParams,
+    /// Cheapest modelled attack, in bits
+    pub bits: f64,
+    pub attacks: Vec<AttackCost>,
+    pub warnings: Vec<String>,
+}
+
+/// Estimate generic attack costs for `params`
+pub fn estimate(params: &CurveParams) -> SecurityEstimate {
+    let n = (params.p.max(2) - 1) as f64;
+    let key_bits = n.log2();
+    // Expected rho length of a random map on F_p² is sqrt(π p² / 2)
+    let orbit_bits = 0.5 * (std::f64::consts::PI / 2.0).log2() + (params.p.max(2) as f64).log2();
+
+    let attacks = vec![
+        AttackCost {
+            name: "key-chain walk",
+            log2_time: key_bits,
+            log2_memory: 0.0,
+            note: "walks f from P0 until Q appears (attack::chain_walk)",
+        },
+        AttackCost {
+            name: "generic rho",
+            log2_time: orbit_bits / 2.0,
+            log2_memory: 0.0,
+            note: "sqrt of the fixed-seed orbit size, if a collision ever yields k",
+        },
+        AttackCost {
+            name: "meet-in-the-middle",
+            log2_time: key_bits / 2.0,
+            log2_memory: key_bits / 2.0,
+            note: "assumes the key chain splits into independent halves",
+        },
+    ];
+    let bits = attacks
+        .iter()
+        .map(|a| a.log2_time)
+        .fold(f64::INFINITY, f64::min);
+
+    let mut warnings = Vec::new();
+    if bits < MIN_RECOMMENDED_BITS {
+        warnings.push(format!(
+            "Estimated {:.1} bits is below the recommended {} bits",
+            bits, MIN_RECOMMENDED_BITS
+        ));
+    }
+    if !is_prime(params.p) {
+        warnings.push(format!("Modulus {} is not prime", params.p));
+    }
+    if params.a.is_multiple_of(params.p) {
+        warnings.push("a = 0 gives the structurally special curve y² = x³".to_string());
+    }
+
+    SecurityEstimate {
+        params: *params,
+        bits,
+        attacks,
+        warnings,
+    }
+}
+
+impl fmt::Display for SecurityEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Security estimate for p={}, a={}", self.params.p, self.params.a)?;
+        writeln!(f, "  Estimated security: {:.1} bits", self.bits)?;
+        writeln!(f, "  {:<20} {:>10} {:>12}", "Attack", "log2(time)", "log2(memory)")?;
+        for attack in &self.attacks {
+            writeln!(
+                f,
+                "  {:<20} {:>10.1} {:>12.1}  ({})",
+                attack.name, attack.log2_time, attack.log2_memory, attack.note
+            )?;
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f, "  Warnings:")?;
+            for warning in &self.warnings {
+                writeln!(f, "  - {}", warning)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_scales_with_modulus() {
+        let small = estimate(&CurveParams { p: 1009, a: 3 });
+        let large = estimate(&CurveParams { p: (1 << 61) - 1, a: 3 });
+        assert!(small.bits < 10.0);
+        assert!((large.bits - 30.5).abs() < 0.5);
+        assert!(small.warnings.iter().any(|w| w.contains("below the recommended")));
+
+        let composite = estimate(&CurveParams { p: 1001, a: 0 });
+        assert!(composite.warnings.iter().any(|w| w.contains("not prime")));
+        assert!(composite.warnings.iter().any(|w| w.contains("a = 0")));
+
+        let mersenne = estimate(&CurveParams { p: (1 << 127) - 1, a: 3 });
+        assert!(!mersenne.warnings.iter().any(|w| w.contains("not prime")));
+        let composite = estimate(&CurveParams { p: (1 << 127) + 1, a: 3 });
+        assert!(composite.warnings.iter().any(|w| w.contains("not prime")));
+
+        let report = small.to_string();
+        assert!(report.contains("meet-in-the-middle"));
+        assert!(report.contains("Warnings:"));
+    }
+}
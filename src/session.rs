@@ -0,0 +1,255 @@
+//! Encrypted sessions over a Noise-style handshake
+//!
+//! A [`Session`] first runs a [`crate::noise`] handshake, then switches to
+//! transport mode with one key per direction. Application frames are
+//!
+//! ```text
+//! length (4) | nonce (8) | body | HMAC-SHA-512 (64)
+//! ```
+//!
+//! where the length counts everything after itself. Nonces must arrive in
+//! order, which rejects replays and reordering, and every
+//! `rekey_interval` frames both sides replace their key with a one-way
+//! function of it, so a key captured later cannot open earlier frames.
+
+pub use crate::noise::{HandshakePattern, Role};
+
+use crate::exchange::ExchangeKeyPair;
+use crate::hybrid::MAC_LEN;
+use crate::noise::{open, seal, HandshakeState, HASH_LEN};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use hkdf::Hkdf;
+use sha2::Sha512;
+use std::fmt;
+
+/// Frames between automatic rekeys unless configured otherwise
+pub const DEFAULT_REKEY_INTERVAL: u64 = 1 << 16;
+/// Bytes before the body: length and nonce
+pub const FRAME_HEADER_LEN: usize = 4 + 8;
+
+/// One direction of transport encryption
+struct CipherState {
+    key: [u8; 32],
+    nonce: u64,
+}
+
+impl CipherState {
+    /// Move past a frame, replacing the key every `interval` frames
+    fn advance(&mut self, interval: u64) {
+        self.nonce += 1;
+        if self.nonce.is_multiple_of(interval) {
+            let mut key = [0u8; 32];
+            Hkdf::<Sha512>::new(None, &self.key)
+                .expand(b"LAI-session-rekey", &mut key)
+                .expect("32 bytes is a valid HKDF-SHA-512 length");
+            self.key = key;
+        }
+    }
+}
+
+/// Handshake-then-transport session for one peer
+pub struct Session {
+    handshake: Option<HandshakeState>,
+    send: Option<CipherState>,
+    recv: Option<CipherState>,
+    handshake_hash: Option<[u8; HASH_LEN]>,
+    remote_static: Option<Point>,
+    rekey_interval: u64,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("handshake", &self.handshake)
+            .field("established", &self.send.is_some())
+            .field("remote_static", &self.remote_static)
+            .field("rekey_interval", &self.rekey_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    /// Session for the party that sends the first handshake message
+    pub fn initiator(
+        pattern: HandshakePattern,
+        prologue: &[u8],
+        s: Option<ExchangeKeyPair>,
+        rs: Option<Point>,
+    ) -> Result<Self, LaiCryptoError> {
+        Self::with_handshake(HandshakeState::new(pattern, Role::Initiator, prologue, s, rs)?)
+    }
+
+    /// Session for the party that answers the first handshake message
+    pub fn responder(
+        pattern: HandshakePattern,
+        prologue: &[u8],
+        s: Option<ExchangeKeyPair>,
+    ) -> Result<Self, LaiCryptoError> {
+        Self::with_handshake(HandshakeState::new(pattern, Role::Responder, prologue, s, None)?)
+    }
+
+    fn with_handshake(handshake: HandshakeState) -> Result<Self, LaiCryptoError> {
+        Ok(Session {
+            handshake: Some(handshake),
+            send: None,
+            recv: None,
+            handshake_hash: None,
+            remote_static: None,
+            rekey_interval: DEFAULT_REKEY_INTERVAL,
+        })
+    }
+
+    /// Set the number of frames between automatic rekeys; both sides must agree
+    pub fn with_rekey_interval(mut self, interval: u64) -> Self {
+        self.rekey_interval = interval.max(1);
+        self
+    }
+
+    /// Whether the handshake is complete and application frames can flow
+    pub fn is_established(&self) -> bool {
+        self.handshake.is_none()
+    }
+
+    /// Final handshake transcript hash, for channel binding
+    pub fn handshake_hash(&self) -> Option<[u8; HASH_LEN]> {
+        self.handshake_hash
+    }
+
+    /// Peer's static point, once authenticated by the handshake
+    pub fn remote_static(&self) -> Option<Point> {
+        self.remote_static.or_else(|| self.handshake.as_ref()?.remote_static())
+    }
+
+    /// Write the next handshake message or, once established, an application frame
+    pub fn write_message(&mut self, engine: &mut LaiCryptoEngine, payload: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        if let Some(handshake) = &mut self.handshake {
+            let message = handshake.write_message(engine, payload)?;
+            self.finish_handshake()?;
+            return Ok(message);
+        }
+        let interval = self.rekey_interval;
+        let send = self.send.as_mut().expect("transport keys set with handshake finished");
+        let nonce = send.nonce;
+        let sealed = seal(&send.key, nonce, &nonce.to_be_bytes(), payload);
+        send.advance(interval);
+
+        let len = u32::try_from(8 + sealed.len()).map_err(|_| LaiCryptoError::InvalidParameter {
+            param: "payload".to_string(),
+            value: format!("{} bytes", payload.len()),
+            reason: "Frame length does not fit the 4-byte prefix".to_string(),
+            valid_range: format!("at most {} bytes", u32::MAX as usize - 8 - MAC_LEN),
+        })?;
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + sealed.len());
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&nonce.to_be_bytes());
+        frame.extend(sealed);
+        Ok(frame)
+    }
+
+    /// Read the peer's next handshake message or application frame
+    pub fn read_message(&mut self, engine: &mut LaiCryptoEngine, message: &[u8]) -> Result<Vec<u8>, LaiCryptoError> {
+        if let Some(handshake) = &mut self.handshake {
+            let payload = handshake.read_message(engine, message)?;
+            self.finish_handshake()?;
+            return Ok(payload);
+        }
+        let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+            param: "frame".to_string(),
+            value: format!("{} bytes", message.len()),
+            reason: reason.to_string(),
+            valid_range: format!("at least {} bytes with a matching length prefix", FRAME_HEADER_LEN + MAC_LEN),
+        };
+        if message.len() < FRAME_HEADER_LEN + MAC_LEN {
+            return Err(malformed("Frame too short"));
+        }
+        let len = u32::from_be_bytes(message[..4].try_into().expect("length checked")) as usize;
+        if len != message.len() - 4 {
+            return Err(malformed("Length prefix does not match frame"));
+        }
+        let nonce = u64::from_be_bytes(message[4..FRAME_HEADER_LEN].try_into().expect("length checked"));
+
+        let interval = self.rekey_interval;
+        let recv = self.recv.as_mut().expect("transport keys set with handshake finished");
+        if nonce != recv.nonce {
+            return Err(LaiCryptoError::ValidationError {
+                operation: "session_read".to_string(),
+                expected: format!("nonce {}", recv.nonce),
+                actual: format!("nonce {}", nonce),
+            });
+        }
+        let payload = open(&recv.key, nonce, &nonce.to_be_bytes(), &message[FRAME_HEADER_LEN..]).ok_or_else(|| {
+            LaiCryptoError::AuthenticationFailed {
+                operation: "session_read".to_string(),
+            }
+        })?;
+        recv.advance(interval);
+        Ok(payload)
+    }
+
+    fn finish_handshake(&mut self) -> Result<(), LaiCryptoError> {
+        let Some(handshake) = self.handshake.take_if(|h| h.is_finished()) else {
+            return Ok(());
+        };
+        let keys = handshake.split()?;
+        self.send = Some(CipherState { key: keys.send, nonce: 0 });
+        self.recv = Some(CipherState { key: keys.recv, nonce: 0 });
+        self.handshake_hash = Some(keys.handshake_hash);
+        self.remote_static = handshake.remote_static();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect(engine: &mut LaiCryptoEngine, alice: &ExchangeKeyPair, bob: &ExchangeKeyPair) -> Option<(Session, Session)> {
+        let mut initiator = Session::initiator(HandshakePattern::XX, b"chat", Some(alice.clone()), None)
+            .unwrap()
+            .with_rekey_interval(2);
+        let mut responder = Session::responder(HandshakePattern::XX, b"chat", Some(bob.clone()))
+            .unwrap()
+            .with_rekey_interval(2);
+        let first = initiator.write_message(engine, b"").ok()?;
+        responder.read_message(engine, &first).ok()?;
+        let second = responder.write_message(engine, b"").ok()?;
+        initiator.read_message(engine, &second).ok()?;
+        let third = initiator.write_message(engine, b"").ok()?;
+        responder.read_message(engine, &third).ok()?;
+        Some((initiator, responder))
+    }
+
+    #[test]
+    fn test_session_frames_and_rekey() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let bob = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let Some((mut alice_session, mut bob_session)) = (0..20).find_map(|_| connect(&mut engine, &alice, &bob))
+        else {
+            return;
+        };
+        assert!(alice_session.is_established() && bob_session.is_established());
+        assert_eq!(alice_session.handshake_hash(), bob_session.handshake_hash());
+        assert_eq!(bob_session.remote_static(), Some(alice.public()));
+
+        // Five frames cross two rekeys in each direction
+        let mut frames = Vec::new();
+        for i in 0..5u8 {
+            let frame = alice_session.write_message(&mut engine, &[i; 3]).unwrap();
+            assert_eq!(bob_session.read_message(&mut engine, &frame).unwrap(), [i; 3]);
+            let reply = bob_session.write_message(&mut engine, &[i]).unwrap();
+            assert_eq!(alice_session.read_message(&mut engine, &reply).unwrap(), [i]);
+            frames.push(frame);
+        }
+        assert_ne!(frames[0][FRAME_HEADER_LEN..], frames[2][FRAME_HEADER_LEN..]);
+
+        // Replays and modified frames are rejected
+        assert!(bob_session.read_message(&mut engine, &frames[4]).is_err());
+        let mut frame = alice_session.write_message(&mut engine, b"last").unwrap();
+        frame[FRAME_HEADER_LEN] ^= 1;
+        assert!(matches!(
+            bob_session.read_message(&mut engine, &frame),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Timing-leak detection in the style of dudect
+//!
+//! An operation is timed on inputs from two classes, a fixed secret and
+//! fresh random secrets, interleaved in random order so drift in the
+//! machine's clock or load affects both alike. Welch's t-statistic then
+//! compares the two timing distributions: |t| above [`T_THRESHOLD`] is
+//! strong evidence that timing depends on the secret.
+//!
+//! Results depend on hardware, compiler flags and load, so the checks in
+//! this module's tests are `#[ignore]`d; run them in release mode with
+//! `cargo test --release sidechannel -- --ignored`.
+
+use rand::{rngs::OsRng, Rng};
+use std::time::Instant;
+
+/// |t| beyond which a leak is reported, as in dudect
+pub const T_THRESHOLD: f64 = 4.5;
+
+/// Input class of one measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Fixed,
+    Random,
+}
+
+/// Timing comparison of the two classes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingReport {
+    /// Measurements per class after cropping outliers
+    pub fixed_samples: usize,
+    pub random_samples: usize,
+    /// Welch's t-statistic of fixed against random timings
+    pub t: f64,
+}
+
+impl TimingReport {
+    /// Whether |t| exceeds [`T_THRESHOLD`]
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > T_THRESHOLD
+    }
+}
+
+/// Time `op` on `samples` randomly interleaved inputs
+///
+/// `prepare` builds every input for its class before timing starts; `op`
+/// is the operation under test. The slowest tenth of all measurements is
+/// dropped before comparing, which removes interrupts and page faults.
+pub fn measure<I>(
+    samples: usize,
+    mut prepare: impl FnMut(Class) -> I,
+    mut op: impl FnMut(&I),
+) -> TimingReport {
+    let inputs: Vec<(Class, I)> = (0..samples)
+        .map(|_| {
+            let class = if OsRng.gen::<bool>() {
+                Class::Fixed
+            } else {
+                Class::Random
+            };
+            (class, prepare(class))
+        })
+        .collect();
+    let timings: Vec<(Class, f64)> = inputs
+        .iter()
+        .map(|(class, input)| {
+            let start = Instant::now();
+            op(std::hint::black_box(input));
+            (*class, start.elapsed().as_nanos() as f64)
+        })
+        .collect();
+
+    let mut sorted: Vec<f64> = timings.iter().map(|&(_, t)| t).collect();
+    sorted.sort_by(f64::total_cmp);
+    let cutoff = sorted
+        .get(samples * 9 / 10)
+        .copied()
+        .unwrap_or(f64::INFINITY);
+    let class_timings = |class: Class| -> Vec<f64> {
+        timings
+            .iter()
+            .filter(|&&(c, t)| c == class && t <= cutoff)
+            .map(|&(_, t)| t)
+            .collect()
+    };
+    let (fixed, random) = (class_timings(Class::Fixed), class_timings(Class::Random));
+    TimingReport {
+        fixed_samples: fixed.len(),
+        random_samples: random.len(),
+        t: welch_t(&fixed, &random),
+    }
+}
+
+/// Welch's t-statistic; 0 when either side has fewer than two values
+pub fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let stats = |v: &[f64]| {
+        let n = v.len() as f64;
+        let mean = v.iter().sum::<f64>() / n;
+        let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        (mean, var / n)
+    };
+    let ((ma, va), (mb, vb)) = (stats(a), stats(b));
+    if va + vb == 0.0 {
+        return 0.0;
+    }
+    (ma - mb) / (va + vb).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LaiCryptoEngine, TAG_LEN};
+    use subtle::ConstantTimeEq;
+
+    #[test]
+    fn test_welch_t() {
+        assert_eq!(welch_t(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert!(welch_t(&[10.0, 11.0, 12.0], &[1.0, 2.0, 3.0]) > T_THRESHOLD);
+        assert_eq!(welch_t(&[1.0], &[2.0, 3.0]), 0.0);
+
+        let report = measure(200, |class| class, |_| {});
+        assert!(report.fixed_samples + report.random_samples <= 200);
+        assert!(report.t.is_finite());
+    }
+
+    #[test]
+    #[ignore]
+    fn test_tag_comparison_constant_time() {
+        let expected = [0x5au8; TAG_LEN];
+        let report = measure(
+            200_000,
+            |class| match class {
+                Class::Fixed => expected,
+                Class::Random => OsRng.gen(),
+            },
+            |tag| {
+                std::hint::black_box(expected.ct_eq(tag));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_decrypt_constant_time() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        engine.tracing = false;
+        let k = 9;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        let ct = engine.encrypt_with_ephemeral(42, q, k).unwrap();
+        let report = measure(
+            20_000,
+            |class| match class {
+                Class::Fixed => ct,
+                Class::Random => crate::Ciphertext {
+                    tag: OsRng.gen(),
+                    ..ct
+                },
+            },
+            |ct| {
+                let _ = std::hint::black_box(engine.decrypt(ct, k));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+}
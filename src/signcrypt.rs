@@ -0,0 +1,172 @@
+//! Signcryption: sender-authenticated encryption in one pass
+//!
+//! The crate has no signature primitive, so authentication comes from a
+//! static–static agreement over [`crate::exchange`] instead of a signature
+//! wrapped in a ciphertext. The sender combines an ephemeral agreement
+//! f^e(B) with its static agreement f^a(B) and keys the encrypt-then-MAC
+//! layer of [`crate::hybrid`] with both:
+//!
+//! ```text
+//! magic "LAIS" | version | E = f^e(P0) (32) | A = f^a(P0) (32) | body | HMAC-SHA-512 (64)
+//! ```
+//!
+//! Only someone holding a or b can produce a valid tag, so a successful
+//! [`unsigncrypt`] proves the message came from the returned sender key.
+//! Unlike a signature this is deniable: the recipient could have produced
+//! the same ciphertext and cannot convince a third party of its origin.
+
+use crate::exchange::ExchangeKeyPair;
+use crate::hybrid::{raw_point, SessionKeys, MAC_LEN};
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use hmac::Mac;
+use sha2::{Digest, Sha512};
+
+/// Magic bytes opening every signcrypted message
+pub const MAGIC: [u8; 4] = *b"LAIS";
+/// Current wire format version
+pub const VERSION: u8 = 1;
+/// Bytes before the body: magic, version, ephemeral and sender points
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + 2 * Point::ENCODED_LEN;
+
+/// Plaintext of a verified message together with its authenticated sender
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsigncrypted {
+    pub sender: Point,
+    pub plaintext: Vec<u8>,
+}
+
+fn derive_keys(ephemeral_shared: Point, static_shared: Point, header: &[u8], recipient: Point) -> SessionKeys {
+    let digest = Sha512::new()
+        .chain_update(b"LAI-signcrypt")
+        .chain_update(ephemeral_shared.to_bytes())
+        .chain_update(static_shared.to_bytes())
+        .chain_update(header)
+        .chain_update(recipient.to_bytes())
+        .finalize();
+    SessionKeys::from_digest(&digest)
+}
+
+/// Encrypt `plaintext` to `recipient`, authenticated as `sender`
+///
+/// A fresh ephemeral scalar is drawn whenever its T chain fails; a failure
+/// of the static agreement is returned, since retrying cannot change it.
+pub fn signcrypt(
+    engine: &mut LaiCryptoEngine,
+    plaintext: &[u8],
+    sender: &ExchangeKeyPair,
+    recipient: Point,
+) -> Result<Vec<u8>, LaiCryptoError> {
+    let static_shared = sender.agree(engine, recipient)?;
+    let (ephemeral, ephemeral_shared) = engine.retry_ephemeral(|engine, e| {
+        let ephemeral = ExchangeKeyPair::from_secret(engine, e)?;
+        let shared = ephemeral.agree(engine, recipient)?;
+        Ok((ephemeral.public(), shared))
+    })?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + MAC_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&ephemeral.to_bytes());
+    out.extend_from_slice(&sender.public().to_bytes());
+    let keys = derive_keys(ephemeral_shared, static_shared, &out, recipient);
+    out.extend_from_slice(plaintext);
+    keys.apply_keystream(&mut out[HEADER_LEN..]);
+
+    let mut mac = keys.mac();
+    mac.update(&out);
+    out.extend_from_slice(&mac.finalize().into_bytes());
+    Ok(out)
+}
+
+/// Verify and decrypt a message produced by [`signcrypt`]
+///
+/// On success the sender's static public point is returned alongside the
+/// plaintext; callers decide whether that key is one they trust. Returns
+/// `AuthenticationFailed` for any modified byte or a wrong recipient key.
+pub fn unsigncrypt(
+    engine: &mut LaiCryptoEngine,
+    message: &[u8],
+    recipient: &ExchangeKeyPair,
+) -> Result<Unsigncrypted, LaiCryptoError> {
+    let len = message.len();
+    let malformed = |reason: &str| LaiCryptoError::InvalidParameter {
+        param: "signcrypted".to_string(),
+        value: format!("{} bytes", len),
+        reason: reason.to_string(),
+        valid_range: format!("at least {} bytes starting with \"LAIS\" v{}", HEADER_LEN + MAC_LEN, VERSION),
+    };
+    if len < HEADER_LEN + MAC_LEN {
+        return Err(malformed("Message too short"));
+    }
+    if message[..MAGIC.len()] != MAGIC {
+        return Err(malformed("Missing LAIS magic"));
+    }
+    if message[MAGIC.len()] != VERSION {
+        return Err(malformed("Unsupported version"));
+    }
+    let points = &message[MAGIC.len() + 1..HEADER_LEN];
+    let (ephemeral, sender) = points.split_at(Point::ENCODED_LEN);
+    let (ephemeral, sender) = match (raw_point(ephemeral, engine.p), raw_point(sender, engine.p)) {
+        (Some(e), Some(a)) => (e, a),
+        _ => return Err(malformed("Coordinate not reduced modulo p")),
+    };
+
+    let ephemeral_shared = recipient.agree(engine, ephemeral)?;
+    let static_shared = recipient.agree(engine, sender)?;
+    let keys = derive_keys(ephemeral_shared, static_shared, &message[..HEADER_LEN], recipient.public());
+    let (authenticated, tag) = message.split_at(len - MAC_LEN);
+    let mut mac = keys.mac();
+    mac.update(authenticated);
+    mac.verify_slice(tag).map_err(|_| LaiCryptoError::AuthenticationFailed {
+        operation: "unsigncrypt".to_string(),
+    })?;
+
+    let mut plaintext = authenticated[HEADER_LEN..].to_vec();
+    keys.apply_keystream(&mut plaintext);
+    Ok(Unsigncrypted { sender, plaintext })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signcrypt_authenticates_sender() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let alice = ExchangeKeyPair::generate(&mut engine).unwrap();
+        let bob = ExchangeKeyPair::generate(&mut engine).unwrap();
+        // Orbits at this toy modulus are short, so redraw until Mallory's
+        // keys and agreements actually differ from Alice's and Bob's
+        let mallory = loop {
+            let m = ExchangeKeyPair::generate(&mut engine).unwrap();
+            if m.public() != alice.public()
+                && m.public() != bob.public()
+                && m.agree(&mut engine, bob.public()).ok() != alice.agree(&mut engine, bob.public()).ok()
+            {
+                break m;
+            }
+        };
+
+        // Static agreements can hit a failing T; only check pairs that work
+        let Ok(sealed) = signcrypt(&mut engine, b"wire 100", &alice, bob.public()) else {
+            return;
+        };
+        assert_eq!(sealed.len(), HEADER_LEN + 8 + MAC_LEN);
+        let opened = unsigncrypt(&mut engine, &sealed, &bob).unwrap();
+        assert_eq!(opened.plaintext, b"wire 100");
+        assert_eq!(opened.sender, alice.public());
+
+        // Claiming another sender key breaks the tag
+        let mut forged = sealed.clone();
+        forged[MAGIC.len() + 1 + Point::ENCODED_LEN..HEADER_LEN].copy_from_slice(&mallory.public().to_bytes());
+        assert!(unsigncrypt(&mut engine, &forged, &bob).is_err());
+        assert!(unsigncrypt(&mut engine, &sealed, &mallory).is_err());
+
+        let mut body = sealed;
+        body[HEADER_LEN] ^= 1;
+        assert!(matches!(
+            unsigncrypt(&mut engine, &body, &bob),
+            Err(LaiCryptoError::AuthenticationFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+//! Batch modular arithmetic over independent lanes
+//!
+//! [`mul_mod_batch`] and [`pow_mod_batch`] evaluate many independent
+//! products or powers under one modulus. For odd moduli below 2^31, the
+//! range of the toy and experimental parameter sets the analysis tools
+//! sweep, lanes run in 32-bit Montgomery form four at a time with AVX2 on
+//! x86_64 (detected at runtime) or two per register with NEON on aarch64.
+//! Other moduli and CPUs take the scalar path; every backend returns the
+//! same results as [`crate::mul_mod`]. [`crate::LaiCryptoEngine::audit`]
+//! runs its square-root failure estimate through both, and precomputation
+//! warms them up.
+//!
+//! ```
+//! use laicrypto::simd::pow_mod_batch;
+//!
+//! let mut out = [0; 3];
+//! pow_mod_batch(&[2, 3, 5], 1008, 1009, &mut out);
+//! assert_eq!(out, [1, 1, 1]);
+//! ```
+
+use crate::{mul_mod, pow_mod};
+
+/// Largest modulus the vector paths accept
+pub const LANE_MAX_P: u128 = (1 << 31) - 1;
+
+/// Implementation a batch call dispatches to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Avx2,
+    Neon,
+    Scalar,
+}
+
+/// Backend used for modulus `m` on this CPU
+pub fn backend(m: u128) -> Backend {
+    if m.is_multiple_of(2) || m > LANE_MAX_P {
+        return Backend::Scalar;
+    }
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        return Backend::Avx2;
+    }
+    #[cfg(target_arch = "aarch64")]
+    return Backend::Neon;
+    #[allow(unreachable_code)]
+    Backend::Scalar
+}
+
+/// `-m⁻¹ mod 2^32`, the Montgomery constant for moduli the vector paths accept
+pub(crate) fn montgomery_constant(m: u128) -> Option<u32> {
+    (!m.is_multiple_of(2) && m <= LANE_MAX_P).then(|| Montgomery::new(m as u32).m_neg_inv)
+}
+
+/// `out[i] = a[i] · b[i] mod m`
+///
+/// # Panics
+/// If the slices differ in length or `m` is zero.
+pub fn mul_mod_batch(a: &[u128], b: &[u128], m: u128, out: &mut [u128]) {
+    assert!(
+        a.len() == out.len() && b.len() == out.len(),
+        "batch length mismatch"
+    );
+    assert!(m != 0, "zero modulus");
+    match backend(m) {
+        Backend::Scalar => {
+            for ((o, &x), &y) in out.iter_mut().zip(a).zip(b) {
+                *o = mul_mod(x, y, m);
+            }
+        }
+        lanes => {
+            let mont = Montgomery::new(m as u32);
+            let mut x: Vec<u64> = a.iter().map(|&v| mont.encode(v)).collect();
+            let y: Vec<u64> = b.iter().map(|&v| mont.encode(v)).collect();
+            mont.mul_lanes(lanes, &mut x, &y);
+            for (o, v) in out.iter_mut().zip(x) {
+                *o = u128::from(mont.redc(v));
+            }
+        }
+    }
+}
+
+/// `out[i] = bases[i]^exp mod m`, one shared exponent for every lane
+///
+/// # Panics
+/// If the slices differ in length or `m` is zero.
+pub fn pow_mod_batch(bases: &[u128], exp: u128, m: u128, out: &mut [u128]) {
+    assert!(bases.len() == out.len(), "batch length mismatch");
+    assert!(m != 0, "zero modulus");
+    match backend(m) {
+        Backend::Scalar => {
+            for (o, &b) in out.iter_mut().zip(bases) {
+                *o = pow_mod(b, exp, m);
+            }
+        }
+        lanes => {
+            let mont = Montgomery::new(m as u32);
+            let base: Vec<u64> = bases.iter().map(|&v| mont.encode(v)).collect();
+            let mut acc = vec![mont.encode(1); bases.len()];
+            for i in (0..128 - exp.leading_zeros()).rev() {
+                let squared = acc.clone();
+                mont.mul_lanes(lanes, &mut acc, &squared);
+                if (exp >> i) & 1 == 1 {
+                    mont.mul_lanes(lanes, &mut acc, &base);
+                }
+            }
+            for (o, v) in out.iter_mut().zip(acc) {
+                *o = u128::from(mont.redc(v));
+            }
+        }
+    }
+}
+
+/// Montgomery arithmetic with R = 2^32 for an odd modulus below 2^31
+///
+/// Lanes hold values below m in u64 slots. The bound on m keeps
+/// `t + q·m < 2^64` in every reduction.
+#[derive(Debug, Clone, Copy)]
+struct Montgomery {
+    m: u32,
+    /// -m⁻¹ mod 2^32
+    m_neg_inv: u32,
+}
+
+impl Montgomery {
+    fn new(m: u32) -> Self {
+        // Newton's iteration doubles the correct low bits each step
+        let mut inv = 1u32;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u32.wrapping_sub(m.wrapping_mul(inv)));
+        }
+        Self {
+            m,
+            m_neg_inv: inv.wrapping_neg(),
+        }
+    }
+
+    fn encode(&self, v: u128) -> u64 {
+        ((v % u128::from(self.m)) << 32) as u64 % u64::from(self.m)
+    }
+
+    fn redc(&self, t: u64) -> u64 {
+        let q = (t as u32).wrapping_mul(self.m_neg_inv);
+        let u = (t + u64::from(q) * u64::from(self.m)) >> 32;
+        if u >= u64::from(self.m) {
+            u - u64::from(self.m)
+        } else {
+            u
+        }
+    }
+
+    /// `acc[i] = acc[i] · rhs[i] · R⁻¹ mod m`
+    fn mul_lanes(&self, backend: Backend, acc: &mut [u64], rhs: &[u64]) {
+        let done = match backend {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: Backend::Avx2 is only chosen after runtime detection
+            Backend::Avx2 => unsafe { self.mul_avx2(acc, rhs) },
+            #[cfg(target_arch = "aarch64")]
+            // SAFETY: NEON is part of the aarch64 baseline
+            Backend::Neon => unsafe { self.mul_neon(acc, rhs) },
+            _ => 0,
+        };
+        for (a, &b) in acc[done..].iter_mut().zip(&rhs[done..]) {
+            *a = self.redc(*a * b);
+        }
+    }
+
+    /// Multiply whole groups of four lanes, returning how many were done
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul_avx2(&self, acc: &mut [u64], rhs: &[u64]) -> usize {
+        use std::arch::x86_64::*;
+        let m = _mm256_set1_epi64x(i64::from(self.m));
+        let m_minus_1 = _mm256_set1_epi64x(i64::from(self.m) - 1);
+        let m_neg_inv = _mm256_set1_epi64x(i64::from(self.m_neg_inv));
+        let groups = acc.len() / 4;
+        for g in 0..groups {
+            let a = _mm256_loadu_si256(acc.as_ptr().add(4 * g) as *const __m256i);
+            let b = _mm256_loadu_si256(rhs.as_ptr().add(4 * g) as *const __m256i);
+            let t = _mm256_mul_epu32(a, b);
+            // Only the low 32 bits of each lane feed the next multiply
+            let q = _mm256_mul_epu32(t, m_neg_inv);
+            let u = _mm256_srli_epi64(_mm256_add_epi64(t, _mm256_mul_epu32(q, m)), 32);
+            let ge = _mm256_cmpgt_epi64(u, m_minus_1);
+            let r = _mm256_sub_epi64(u, _mm256_and_si256(ge, m));
+            _mm256_storeu_si256(acc.as_mut_ptr().add(4 * g) as *mut __m256i, r);
+        }
+        4 * groups
+    }
+
+    /// Multiply whole pairs of lanes, returning how many were done
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon")]
+    unsafe fn mul_neon(&self, acc: &mut [u64], rhs: &[u64]) -> usize {
+        use std::arch::aarch64::*;
+        let m32 = vdup_n_u32(self.m);
+        let m = vdupq_n_u64(u64::from(self.m));
+        let m_neg_inv = vdup_n_u32(self.m_neg_inv);
+        let pairs = acc.len() / 2;
+        for g in 0..pairs {
+            let a = vmovn_u64(vld1q_u64(acc.as_ptr().add(2 * g)));
+            let b = vmovn_u64(vld1q_u64(rhs.as_ptr().add(2 * g)));
+            let t = vmull_u32(a, b);
+            let q = vmul_u32(vmovn_u64(t), m_neg_inv);
+            let u = vshrq_n_u64::<32>(vaddq_u64(t, vmull_u32(q, m32)));
+            let ge = vcgeq_u64(u, m);
+            vst1q_u64(acc.as_mut_ptr().add(2 * g), vsubq_u64(u, vandq_u64(ge, m)));
+        }
+        2 * pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_matches_scalar() {
+        let values: Vec<u128> = (0..1000u128).map(|i| i * i * 7919 + i).collect();
+        for m in [1009u128, 65_537, LANE_MAX_P, 1 << 40, (1 << 127) - 1] {
+            let a: Vec<u128> = values.iter().map(|v| v % m).collect();
+            let b: Vec<u128> = a.iter().rev().copied().collect();
+            let mut out = vec![0; a.len()];
+
+            mul_mod_batch(&a, &b, m, &mut out);
+            for i in 0..a.len() {
+                assert_eq!(out[i], mul_mod(a[i], b[i], m), "m = {}", m);
+            }
+
+            let exp = m - 2;
+            pow_mod_batch(&a, exp, m, &mut out);
+            for i in 0..a.len() {
+                assert_eq!(out[i], pow_mod(a[i], exp, m), "m = {}", m);
+            }
+        }
+        assert_eq!(backend(1 << 40), Backend::Scalar);
+        assert_eq!(backend(1010), Backend::Scalar);
+    }
+}
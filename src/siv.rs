@@ -0,0 +1,102 @@
+//! Deterministic, misuse-resistant encryption (synthetic IV)
+//!
+//! The ephemeral scalar and content scalar are derived with
+//! HMAC-SHA-512 from a sender-held SIV key, the recipient's point and the
+//! message instead of the RNG. Equal inputs give equal ciphertexts, which
+//! reveals repeated messages and nothing else; a broken or repeating RNG
+//! can no longer reuse an ephemeral across different messages. Output
+//! formats are unchanged, so the usual decryption functions apply.
+
+use crate::hybrid::HybridMode;
+use crate::{reduce_wide, Ciphertext, ErrorKind, LaiCryptoEngine, LaiCryptoError, Point};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+type HmacSha512 = Hmac<Sha512>;
+
+impl LaiCryptoEngine {
+    /// (ephemeral, content) scalars for one attempt
+    fn siv_scalars(&self, siv_key: &[u8], q: Point, message: &[u8], attempt: u32) -> (u128, u128) {
+        let mut mac = HmacSha512::new_from_slice(siv_key).expect("HMAC accepts any key length");
+        mac.update(b"LAI-siv");
+        mac.update(&q.to_bytes());
+        mac.update(&attempt.to_be_bytes());
+        mac.update(message);
+        let digest = mac.finalize().into_bytes();
+        (
+            reduce_wide(&digest[..32], self.p - 1) + 1,
+            reduce_wide(&digest[32..], self.p),
+        )
+    }
+
+    /// Run `f` over the derived scalars, moving to the next attempt when a chain fails
+    fn with_siv<T>(
+        &mut self,
+        siv_key: &[u8],
+        q: Point,
+        message: &[u8],
+        mut f: impl FnMut(&mut Self, u128, u128) -> Result<T, LaiCryptoError>,
+    ) -> Result<T, LaiCryptoError> {
+        // Equal inputs repeat c1 by design, so the reuse guard does not apply
+        let guard = self.reuse_guard.take();
+        let mut result = None;
+        for attempt in 0..self.max_attempts.max(1) {
+            let (r, content) = self.siv_scalars(siv_key, q, message, attempt);
+            match f(self, r, content) {
+                Err(e) if e.kind() == ErrorKind::Transform => result = Some(Err(e)),
+                done => {
+                    result = Some(done);
+                    break;
+                }
+            }
+        }
+        self.reuse_guard = guard;
+        result.expect("at least one attempt")
+    }
+
+    /// Encrypt `m` to `q` with the ephemeral derived from `siv_key`, `q` and `m`
+    pub fn encrypt_siv(&mut self, m: u128, q: Point, siv_key: &[u8]) -> Result<Ciphertext, LaiCryptoError> {
+        let m = m % self.p;
+        self.with_siv(siv_key, q, &m.to_be_bytes(), |engine, r, _| {
+            engine.encrypt_with_ephemeral(m, q, r)
+        })
+    }
+
+    /// Deterministic counterpart of [`LaiCryptoEngine::encrypt_bytes_with`]
+    pub fn encrypt_bytes_siv(
+        &mut self,
+        plaintext: &[u8],
+        q: Point,
+        siv_key: &[u8],
+        mode: HybridMode,
+    ) -> Result<Vec<u8>, LaiCryptoError> {
+        self.with_siv(siv_key, q, plaintext, |engine, r, content| {
+            engine.seal(plaintext, q, r, content, mode)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_siv_is_deterministic() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+
+        let a = engine.encrypt_bytes_siv(b"backup", q, b"siv key", HybridMode::Standard).unwrap();
+        let b = engine.encrypt_bytes_siv(b"backup", q, b"siv key", HybridMode::Standard).unwrap();
+        assert_eq!(a, b);
+        let other_key = engine.encrypt_bytes_siv(b"backup", q, b"other", HybridMode::Standard).unwrap();
+        assert_ne!(a, other_key);
+
+        assert_eq!(engine.encrypt_siv(42, q, b"k").unwrap(), engine.encrypt_siv(42 + 1009, q, b"k").unwrap());
+        engine.enable_reuse_detection(8);
+        assert_eq!(engine.encrypt_siv(7, q, b"k").unwrap(), engine.encrypt_siv(7, q, b"k").unwrap());
+        let (r, content) = engine.siv_scalars(b"k", q, b"m", 0);
+        assert!((1..1009).contains(&r) && content < 1009);
+        assert_ne!(engine.siv_scalars(b"k", q, b"m", 1), (r, content));
+    }
+}
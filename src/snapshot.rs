@@ -0,0 +1,87 @@
+//! Bincode snapshots of engine state and traces
+//!
+//! For persisting long experiments between runs on the same machine. The
+//! format follows bincode's standard configuration and carries no
+//! version of its own, so snapshots are not meant for interchange; use
+//! [`crate::envelope`] or [`crate::cbor`] for that. Restored engines have
+//! their parameters validated as if built with [`LaiCryptoEngine::new`]
+//! and read the system clock again.
+
+use crate::{LaiCryptoEngine, LaiCryptoError, TraceStep};
+
+fn decode<T: bincode::Decode<()>>(bytes: &[u8]) -> Result<T, LaiCryptoError> {
+    let (value, read) = bincode::decode_from_slice(bytes, bincode::config::standard())
+        .map_err(|e| LaiCryptoError::serialization("bincode", e))?;
+    if read != bytes.len() {
+        return Err(LaiCryptoError::serialization(
+            "bincode",
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "trailing bytes after snapshot"),
+        ));
+    }
+    Ok(value)
+}
+
+fn encode<T: bincode::Encode>(value: &T) -> Vec<u8> {
+    bincode::encode_to_vec(value, bincode::config::standard()).expect("in-memory encoding cannot fail")
+}
+
+impl LaiCryptoEngine {
+    /// Parameters, configuration, trace, metrics and reuse guard as bytes
+    pub fn snapshot(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    /// Rebuild an engine from [`LaiCryptoEngine::snapshot`] output
+    pub fn restore(bytes: &[u8]) -> Result<Self, LaiCryptoError> {
+        let saved: LaiCryptoEngine = decode(bytes)?;
+        let mut engine = LaiCryptoEngine::new(saved.p, saved.a, saved.p0)?;
+        engine.trace = saved.trace;
+        engine.metrics = saved.metrics;
+        engine.max_attempts = saved.max_attempts;
+        engine.max_duration = saved.max_duration;
+        engine.tracing = saved.tracing;
+        engine.reuse_guard = saved.reuse_guard;
+        engine.domain_separation = saved.domain_separation;
+        engine.hash = saved.hash;
+        engine.blinding = saved.blinding;
+        engine.audit_log = saved.audit_log;
+        engine.operations = saved.operations;
+        Ok(engine)
+    }
+}
+
+pub fn trace_to_bincode(trace: &[TraceStep]) -> Vec<u8> {
+    encode(&trace)
+}
+
+pub fn trace_from_bincode(bytes: &[u8]) -> Result<Vec<TraceStep>, LaiCryptoError> {
+    decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let q = engine.pow_t(p0, 8).unwrap();
+        engine.enable_reuse_detection(4);
+        let ct = engine.encrypt_with_ephemeral(42, q, 8).unwrap();
+        engine.max_attempts = 7;
+
+        let mut restored = LaiCryptoEngine::restore(&engine.snapshot()).unwrap();
+        assert_eq!(restored.trace, engine.trace);
+        assert_eq!(restored.max_attempts, 7);
+        assert_eq!(restored.metrics.t_transform_count, engine.metrics.t_transform_count);
+        // The reuse guard survives, so the restored engine still refuses the old c1
+        assert!(restored.encrypt_with_ephemeral(1, q, 8).is_err());
+        assert_eq!(restored.decrypt(&ct, 8).unwrap(), 42);
+
+        assert_eq!(trace_from_bincode(&trace_to_bincode(&engine.trace)).unwrap(), engine.trace);
+        let mut bad = engine.snapshot();
+        bad.push(0);
+        assert!(LaiCryptoEngine::restore(&bad).is_err());
+    }
+}
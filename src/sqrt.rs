@@ -0,0 +1,229 @@
+//! Square roots modulo p, and of secret-derived values
+//!
+//! The method is fixed by p, see [`SqrtMethod`]: a single exponentiation
+//! for p ≡ 3 (mod 4), the p ≡ 5 (mod 8) shortcut, and Tonelli-Shanks for
+//! the rest. Each returns the root Tonelli-Shanks would, so T-transform
+//! outputs do not depend on which one ran.
+//!
+//! For the general case [`LaiCryptoEngine::sqrt_mod`] runs Tonelli-Shanks with an inner loop
+//! that stops as soon as it finds the order of `t`, so its running time
+//! follows the discrete logarithm of the input in the 2-power subgroup.
+//! [`LaiCryptoEngine::sqrt_mod_ct`] is the fixed-schedule variant of
+//! RFC 9380, Appendix I.4: every input takes s - 1 rounds of the same
+//! squarings and multiplications, with conditional moves in place of
+//! branches, and exponentiations go through the fixed-window
+//! [`LaiCryptoEngine::mod_pow`]. Only p, and so s, shapes the schedule.
+//!
+//! Both variants return the same root for every input, so a chain is the
+//! same whichever one evaluated it. Key generation and decryption run
+//! their chains with `sqrt_mod` dispatching to the fixed-schedule variant;
+//! whether a value has a root at all is still visible, as the
+//! T-transform's seed retries already reveal it.
+//!
+//! ```
+//! use laicrypto::LaiCryptoEngine;
+//!
+//! let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+//! for a in [0, 4, 13, 1008] {
+//!     assert_eq!(engine.sqrt_mod_ct(a), engine.sqrt_mod(a));
+//! }
+//! ```
+
+use crate::{mul_mod, LaiCryptoEngine, LaiCryptoError, Point};
+use std::fmt;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+/// How square roots modulo p are computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub enum SqrtMethod {
+    /// p ≡ 3 (mod 4): a^((p+1)/4)
+    ThreeMod4,
+    /// p ≡ 5 (mod 8): x = a^((p+3)/8), times √-1 = 2^((p-1)/4) when x² = -a
+    FiveMod8,
+    /// p ≡ 1 (mod 8): general Tonelli-Shanks
+    TonelliShanks,
+}
+
+impl SqrtMethod {
+    /// Method used for odd prime `p`
+    pub fn for_prime(p: u128) -> Self {
+        match p % 8 {
+            3 | 7 => SqrtMethod::ThreeMod4,
+            5 => SqrtMethod::FiveMod8,
+            _ => SqrtMethod::TonelliShanks,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SqrtMethod::ThreeMod4 => "3 mod 4",
+            SqrtMethod::FiveMod8 => "5 mod 8",
+            SqrtMethod::TonelliShanks => "tonelli-shanks",
+        }
+    }
+}
+
+impl fmt::Display for SqrtMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Whether values reaching `sqrt_mod` are derived from a secret scalar
+///
+/// Transient state of a running operation, never serialized.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SecretInput(bool);
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for SecretInput {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C> bincode::Decode<C> for SecretInput {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(SecretInput);
+
+impl LaiCryptoEngine {
+    /// Modular square root on a schedule independent of `a`
+    ///
+    /// Returns exactly what [`LaiCryptoEngine::sqrt_mod`] returns.
+    pub fn sqrt_mod_ct(&mut self, a: u128) -> Option<u128> {
+        let p = self.p;
+        let method = SqrtMethod::for_prime(p);
+        self.metrics.sqrt_method = Some(method);
+        let a = a % p;
+        let root = match method {
+            SqrtMethod::ThreeMod4 => self.mod_pow(a, (p + 1) / 4),
+            SqrtMethod::FiveMod8 => {
+                let (x, c) = self.sqrt_5_mod_8(a);
+                u128::conditional_select(&mul_mod(x, c, p), &x, mul_mod(x, x, p).ct_eq(&a))
+            }
+            SqrtMethod::TonelliShanks => {
+                let (q, s, c) = self.sqrt_constants();
+                let mut z = self.mod_pow(a, q.div_ceil(2));
+                let mut t = self.mod_pow(a, q);
+                let mut c = c;
+                for i in (2..=s).rev() {
+                    let mut b = t;
+                    for _ in 0..i - 2 {
+                        b = mul_mod(b, b, p);
+                    }
+                    let done = b.ct_eq(&1);
+                    z = u128::conditional_select(&mul_mod(z, c, p), &z, done);
+                    c = mul_mod(c, c, p);
+                    t = u128::conditional_select(&mul_mod(t, c, p), &t, done);
+                }
+                z
+            }
+        };
+        let is_root: Choice = mul_mod(root, root, p).ct_eq(&a);
+        bool::from(is_root).then_some(root)
+    }
+
+    /// Candidate root a^((p+3)/8) and √-1 for p ≡ 5 (mod 8)
+    ///
+    /// x² is a·a^((p-1)/4), so it is a when a^((p-1)/4) = 1 and -a when it
+    /// is -1; x·√-1 is then the root Tonelli-Shanks returns. Any other x²
+    /// means a has no root.
+    pub(crate) fn sqrt_5_mod_8(&mut self, a: u128) -> (u128, u128) {
+        let x = self.mod_pow(a, (self.p + 3) / 8);
+        let (_, _, c) = self.sqrt_constants();
+        (x, c)
+    }
+
+    /// [`LaiCryptoEngine::pow_t`] with k secret
+    ///
+    /// Square roots along the chain use [`LaiCryptoEngine::sqrt_mod_ct`].
+    pub(crate) fn pow_t_secret(&mut self, point: Point, k: u128) -> Result<Point, LaiCryptoError> {
+        let outer = std::mem::replace(&mut self.secret_input, SecretInput(true));
+        let result = self.pow_t(point, k);
+        self.secret_input = outer;
+        result
+    }
+
+    pub(crate) fn secret_input(&self) -> bool {
+        self.secret_input.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pow_mod;
+
+    /// Textbook Tonelli-Shanks, whatever p is
+    fn tonelli_shanks(a: u128, p: u128) -> Option<u128> {
+        if a == 0 {
+            return Some(0);
+        }
+        if pow_mod(a, (p - 1) / 2, p) != 1 {
+            return None;
+        }
+        let (s, q) = (
+            (p - 1).trailing_zeros(),
+            (p - 1) >> (p - 1).trailing_zeros(),
+        );
+        let z = (2..)
+            .find(|&z| pow_mod(z, (p - 1) / 2, p) == p - 1)
+            .unwrap();
+        let (mut m, mut c) = (s, pow_mod(z, q, p));
+        let (mut t, mut r) = (pow_mod(a, q, p), pow_mod(a, q.div_ceil(2), p));
+        while t != 1 {
+            let i = (1..m).find(|&i| pow_mod(t, 1 << i, p) == 1).unwrap();
+            let b = pow_mod(c, 1 << (m - i - 1), p);
+            (m, c) = (i, b * b % p);
+            (t, r) = (t * c % p, r * b % p);
+        }
+        Some(r)
+    }
+
+    #[test]
+    fn test_sqrt_mod_ct_matches_sqrt_mod() {
+        // s = 1 (p ≡ 3 mod 4), s = 2 (p ≡ 5 mod 8), s = 4 and s = 9
+        for p in [1019, 1013, 101, 1009, 7681] {
+            let mut engine = LaiCryptoEngine::new_unchecked(p, 3, Point::IDENTITY);
+            for a in 0..p {
+                let expected = tonelli_shanks(a, p);
+                assert_eq!(engine.sqrt_mod(a), expected, "{} mod {}", a, p);
+                assert_eq!(engine.sqrt_mod_ct(a), expected, "{} mod {}", a, p);
+            }
+            assert_eq!(engine.metrics.sqrt_method, Some(SqrtMethod::for_prime(p)));
+        }
+
+        assert_eq!(SqrtMethod::for_prime(1019), SqrtMethod::ThreeMod4);
+        assert_eq!(SqrtMethod::for_prime(1013), SqrtMethod::FiveMod8);
+
+        // Roots above 2^64 must not overflow: p ≡ 5 and p ≡ 1 (mod 8)
+        for p in [(1 << 100) + 277, (1 << 100) + 577] {
+            let mut engine = LaiCryptoEngine::new_unchecked(p, 3, Point::IDENTITY);
+            for x in [2, u64::MAX as u128 + 7, p - 3] {
+                let a = mul_mod(x, x, p);
+                let root = engine.sqrt_mod(a).unwrap();
+                assert!(root == x || root == p - x);
+                assert_eq!(engine.sqrt_mod_ct(a), Some(root));
+            }
+        }
+
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let k = 11;
+        let q = engine.pow_t(engine.p0, k).unwrap();
+        let p0 = engine.p0;
+        assert_eq!(engine.pow_t_secret(p0, k).unwrap(), q);
+        assert!(!engine.secret_input());
+    }
+}
@@ -0,0 +1,96 @@
+//! Context-labeled subkeys from a single master secret
+//!
+//! Both derivations run HKDF-SHA-512 with a fixed salt per source and an
+//! info string of the form
+//!
+//! ```text
+//! "LAI-subkey" | source tag (1) | label length (2) | label
+//! ```
+//!
+//! The length prefix keeps one label from being a prefix-extension of
+//! another, so every distinct label yields an independent key.
+
+use crate::exchange::SharedSecret;
+use crate::{LaiCryptoError, SecretKey};
+use hkdf::Hkdf;
+use secrecy::ExposeSecret;
+use sha2::Sha512;
+
+/// Length of keys from [`SecretKey::derive_subkey`]
+pub const SUBKEY_LEN: usize = 32;
+/// Longest HKDF-SHA-512 output
+pub const MAX_DERIVED_LEN: usize = 255 * 64;
+
+const SHARED_SECRET_TAG: u8 = 1;
+const SECRET_KEY_TAG: u8 = 2;
+
+fn expand(ikm: &[u8], tag: u8, label: &str, out: &mut [u8]) -> Result<(), LaiCryptoError> {
+    let label_len = u16::try_from(label.len()).map_err(|_| LaiCryptoError::InvalidParameter {
+        param: "label".to_string(),
+        value: format!("{} bytes", label.len()),
+        reason: "Label too long".to_string(),
+        valid_range: format!("at most {} bytes", u16::MAX),
+    })?;
+    let info = [
+        b"LAI-subkey".as_slice(),
+        &[tag],
+        &label_len.to_be_bytes(),
+        label.as_bytes(),
+    ]
+    .concat();
+    Hkdf::<Sha512>::new(Some(b"LAI-subkey-salt"), ikm)
+        .expand(&info, out)
+        .map_err(|_| LaiCryptoError::InvalidParameter {
+            param: "length".to_string(),
+            value: out.len().to_string(),
+            reason: "Exceeds the HKDF-SHA-512 output limit".to_string(),
+            valid_range: format!("1..={}", MAX_DERIVED_LEN),
+        })
+}
+
+impl SharedSecret {
+    /// Derive `length` bytes for the purpose named by `label`
+    pub fn derive(&self, label: &str, length: usize) -> Result<Vec<u8>, LaiCryptoError> {
+        let mut out = vec![0u8; length];
+        expand(self.expose_secret(), SHARED_SECRET_TAG, label, &mut out)?;
+        Ok(out)
+    }
+}
+
+impl SecretKey {
+    /// Derive a key for `context` that reveals nothing about the scalar
+    pub fn derive_subkey(&self, context: &str) -> Result<[u8; SUBKEY_LEN], LaiCryptoError> {
+        let mut out = [0u8; SUBKEY_LEN];
+        expand(
+            &self.expose_secret().to_be_bytes(),
+            SECRET_KEY_TAG,
+            context,
+            &mut out,
+        )?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labeled_subkeys() {
+        let secret = SharedSecret::new([7; 32]);
+        let enc = secret.derive("encryption", 32).unwrap();
+        assert_eq!(enc, secret.derive("encryption", 32).unwrap());
+        assert_ne!(enc, secret.derive("mac", 32).unwrap());
+        assert_eq!(secret.derive("encryption", 64).unwrap()[..32], enc[..]);
+        assert!(secret.derive("too long", MAX_DERIVED_LEN + 1).is_err());
+
+        let key = SecretKey::new(7);
+        let subkey = key.derive_subkey("encryption").unwrap();
+        assert_ne!(subkey, key.derive_subkey("application").unwrap());
+        assert_ne!(
+            subkey,
+            SecretKey::new(8).derive_subkey("encryption").unwrap()
+        );
+        assert_ne!(subkey[..], enc[..]);
+    }
+}
@@ -0,0 +1,324 @@
+// Batch T-transform orbits, one per invocation
+//
+// Mirrors LaiCryptoEngine::t for SHA-512 engines with p < 2^15, so every
+// product below fits in 32 bits. SHA-512 words are vec2<u32>(high, low).
+
+struct Params {
+    p: u32,
+    a: u32,
+    inv2: u32,
+    steps: u32,
+    start_s: u32,
+    domain_separation: u32,
+    count: u32,
+    retries: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> starts: array<vec2<u32>>;
+@group(0) @binding(2) var<storage, read_write> points: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read_write> lengths: array<u32>;
+
+const NO_ROOT: u32 = 0xffffffffu;
+
+var<private> K: array<vec2<u32>, 80> = array<vec2<u32>, 80>(
+    vec2<u32>(0x428a2f98u, 0xd728ae22u),
+    vec2<u32>(0x71374491u, 0x23ef65cdu),
+    vec2<u32>(0xb5c0fbcfu, 0xec4d3b2fu),
+    vec2<u32>(0xe9b5dba5u, 0x8189dbbcu),
+    vec2<u32>(0x3956c25bu, 0xf348b538u),
+    vec2<u32>(0x59f111f1u, 0xb605d019u),
+    vec2<u32>(0x923f82a4u, 0xaf194f9bu),
+    vec2<u32>(0xab1c5ed5u, 0xda6d8118u),
+    vec2<u32>(0xd807aa98u, 0xa3030242u),
+    vec2<u32>(0x12835b01u, 0x45706fbeu),
+    vec2<u32>(0x243185beu, 0x4ee4b28cu),
+    vec2<u32>(0x550c7dc3u, 0xd5ffb4e2u),
+    vec2<u32>(0x72be5d74u, 0xf27b896fu),
+    vec2<u32>(0x80deb1feu, 0x3b1696b1u),
+    vec2<u32>(0x9bdc06a7u, 0x25c71235u),
+    vec2<u32>(0xc19bf174u, 0xcf692694u),
+    vec2<u32>(0xe49b69c1u, 0x9ef14ad2u),
+    vec2<u32>(0xefbe4786u, 0x384f25e3u),
+    vec2<u32>(0x0fc19dc6u, 0x8b8cd5b5u),
+    vec2<u32>(0x240ca1ccu, 0x77ac9c65u),
+    vec2<u32>(0x2de92c6fu, 0x592b0275u),
+    vec2<u32>(0x4a7484aau, 0x6ea6e483u),
+    vec2<u32>(0x5cb0a9dcu, 0xbd41fbd4u),
+    vec2<u32>(0x76f988dau, 0x831153b5u),
+    vec2<u32>(0x983e5152u, 0xee66dfabu),
+    vec2<u32>(0xa831c66du, 0x2db43210u),
+    vec2<u32>(0xb00327c8u, 0x98fb213fu),
+    vec2<u32>(0xbf597fc7u, 0xbeef0ee4u),
+    vec2<u32>(0xc6e00bf3u, 0x3da88fc2u),
+    vec2<u32>(0xd5a79147u, 0x930aa725u),
+    vec2<u32>(0x06ca6351u, 0xe003826fu),
+    vec2<u32>(0x14292967u, 0x0a0e6e70u),
+    vec2<u32>(0x27b70a85u, 0x46d22ffcu),
+    vec2<u32>(0x2e1b2138u, 0x5c26c926u),
+    vec2<u32>(0x4d2c6dfcu, 0x5ac42aedu),
+    vec2<u32>(0x53380d13u, 0x9d95b3dfu),
+    vec2<u32>(0x650a7354u, 0x8baf63deu),
+    vec2<u32>(0x766a0abbu, 0x3c77b2a8u),
+    vec2<u32>(0x81c2c92eu, 0x47edaee6u),
+    vec2<u32>(0x92722c85u, 0x1482353bu),
+    vec2<u32>(0xa2bfe8a1u, 0x4cf10364u),
+    vec2<u32>(0xa81a664bu, 0xbc423001u),
+    vec2<u32>(0xc24b8b70u, 0xd0f89791u),
+    vec2<u32>(0xc76c51a3u, 0x0654be30u),
+    vec2<u32>(0xd192e819u, 0xd6ef5218u),
+    vec2<u32>(0xd6990624u, 0x5565a910u),
+    vec2<u32>(0xf40e3585u, 0x5771202au),
+    vec2<u32>(0x106aa070u, 0x32bbd1b8u),
+    vec2<u32>(0x19a4c116u, 0xb8d2d0c8u),
+    vec2<u32>(0x1e376c08u, 0x5141ab53u),
+    vec2<u32>(0x2748774cu, 0xdf8eeb99u),
+    vec2<u32>(0x34b0bcb5u, 0xe19b48a8u),
+    vec2<u32>(0x391c0cb3u, 0xc5c95a63u),
+    vec2<u32>(0x4ed8aa4au, 0xe3418acbu),
+    vec2<u32>(0x5b9cca4fu, 0x7763e373u),
+    vec2<u32>(0x682e6ff3u, 0xd6b2b8a3u),
+    vec2<u32>(0x748f82eeu, 0x5defb2fcu),
+    vec2<u32>(0x78a5636fu, 0x43172f60u),
+    vec2<u32>(0x84c87814u, 0xa1f0ab72u),
+    vec2<u32>(0x8cc70208u, 0x1a6439ecu),
+    vec2<u32>(0x90befffau, 0x23631e28u),
+    vec2<u32>(0xa4506cebu, 0xde82bde9u),
+    vec2<u32>(0xbef9a3f7u, 0xb2c67915u),
+    vec2<u32>(0xc67178f2u, 0xe372532bu),
+    vec2<u32>(0xca273eceu, 0xea26619cu),
+    vec2<u32>(0xd186b8c7u, 0x21c0c207u),
+    vec2<u32>(0xeada7dd6u, 0xcde0eb1eu),
+    vec2<u32>(0xf57d4f7fu, 0xee6ed178u),
+    vec2<u32>(0x06f067aau, 0x72176fbau),
+    vec2<u32>(0x0a637dc5u, 0xa2c898a6u),
+    vec2<u32>(0x113f9804u, 0xbef90daeu),
+    vec2<u32>(0x1b710b35u, 0x131c471bu),
+    vec2<u32>(0x28db77f5u, 0x23047d84u),
+    vec2<u32>(0x32caab7bu, 0x40c72493u),
+    vec2<u32>(0x3c9ebe0au, 0x15c9bebcu),
+    vec2<u32>(0x431d67c4u, 0x9c100d4cu),
+    vec2<u32>(0x4cc5d4beu, 0xcb3e42b6u),
+    vec2<u32>(0x597f299cu, 0xfc657e2au),
+    vec2<u32>(0x5fcb6fabu, 0x3ad6faecu),
+    vec2<u32>(0x6c44198cu, 0x4a475817u)
+);
+
+var<private> IV: array<vec2<u32>, 8> = array<vec2<u32>, 8>(
+    vec2<u32>(0x6a09e667u, 0xf3bcc908u),
+    vec2<u32>(0xbb67ae85u, 0x84caa73bu),
+    vec2<u32>(0x3c6ef372u, 0xfe94f82bu),
+    vec2<u32>(0xa54ff53au, 0x5f1d36f1u),
+    vec2<u32>(0x510e527fu, 0xade682d1u),
+    vec2<u32>(0x9b05688cu, 0x2b3e6c1fu),
+    vec2<u32>(0x1f83d9abu, 0xfb41bd6bu),
+    vec2<u32>(0x5be0cd19u, 0x137e2179u)
+);
+
+// "LAI-h/transform"
+var<private> LABEL: array<u32, 15> = array<u32, 15>(
+    76u, 65u, 73u, 45u, 104u, 47u, 116u, 114u, 97u, 110u, 115u, 102u, 111u, 114u, 109u
+);
+
+fn add64(a: vec2<u32>, b: vec2<u32>) -> vec2<u32> {
+    let lo = a.y + b.y;
+    return vec2<u32>(a.x + b.x + select(0u, 1u, lo < a.y), lo);
+}
+
+// 0 < n < 64, n != 32
+fn rotr64(v: vec2<u32>, n: u32) -> vec2<u32> {
+    var w = v;
+    var k = n;
+    if (k > 32u) {
+        w = v.yx;
+        k = k - 32u;
+    }
+    return vec2<u32>((w.x >> k) | (w.y << (32u - k)), (w.y >> k) | (w.x << (32u - k)));
+}
+
+// 0 < n < 32
+fn shr64(v: vec2<u32>, n: u32) -> vec2<u32> {
+    return vec2<u32>(v.x >> n, (v.y >> n) | (v.x << (32u - n)));
+}
+
+fn sha512_block(block: ptr<function, array<vec2<u32>, 16>>) -> array<vec2<u32>, 8> {
+    var w: array<vec2<u32>, 80>;
+    for (var i = 0u; i < 16u; i++) {
+        w[i] = (*block)[i];
+    }
+    for (var i = 16u; i < 80u; i++) {
+        let s0 = rotr64(w[i - 15u], 1u) ^ rotr64(w[i - 15u], 8u) ^ shr64(w[i - 15u], 7u);
+        let s1 = rotr64(w[i - 2u], 19u) ^ rotr64(w[i - 2u], 61u) ^ shr64(w[i - 2u], 6u);
+        w[i] = add64(add64(w[i - 16u], s0), add64(w[i - 7u], s1));
+    }
+
+    var v = IV;
+    for (var i = 0u; i < 80u; i++) {
+        let e = v[4];
+        let a = v[0];
+        let s1 = rotr64(e, 14u) ^ rotr64(e, 18u) ^ rotr64(e, 41u);
+        let ch = (e & v[5]) ^ (~e & v[6]);
+        let t1 = add64(add64(add64(v[7], s1), add64(ch, K[i])), w[i]);
+        let s0 = rotr64(a, 28u) ^ rotr64(a, 34u) ^ rotr64(a, 39u);
+        let maj = (a & v[1]) ^ (a & v[2]) ^ (v[1] & v[2]);
+        let t2 = add64(s0, maj);
+        v[7] = v[6];
+        v[6] = v[5];
+        v[5] = v[4];
+        v[4] = add64(v[3], t1);
+        v[3] = v[2];
+        v[2] = v[1];
+        v[1] = v[0];
+        v[0] = add64(t1, t2);
+    }
+    for (var i = 0u; i < 8u; i++) {
+        v[i] = add64(v[i], IV[i]);
+    }
+    return v;
+}
+
+// Last four bytes of a 16-byte big-endian field starting at `offset`
+fn put_u32(bytes: ptr<function, array<u32, 128>>, offset: u32, value: u32) {
+    for (var i = 0u; i < 4u; i++) {
+        (*bytes)[offset + 12u + i] = (value >> (24u - 8u * i)) & 0xffu;
+    }
+}
+
+fn be32(bytes: ptr<function, array<u32, 128>>, offset: u32) -> u32 {
+    return ((*bytes)[offset] << 24u) | ((*bytes)[offset + 1u] << 16u)
+        | ((*bytes)[offset + 2u] << 8u) | (*bytes)[offset + 3u];
+}
+
+// LaiCryptoEngine::h_in(Domain::Transform, ..) for SHA-512
+fn h(x: u32, y: u32, s: u32) -> u32 {
+    var bytes: array<u32, 128>;
+    var off = 0u;
+    if (params.domain_separation != 0u) {
+        bytes[0] = 15u;
+        for (var i = 0u; i < 15u; i++) {
+            bytes[1u + i] = LABEL[i];
+        }
+        off = 16u;
+    }
+    put_u32(&bytes, off, x);
+    put_u32(&bytes, off + 16u, y);
+    put_u32(&bytes, off + 32u, s);
+    put_u32(&bytes, off + 48u, params.p);
+    let len = off + 64u;
+    bytes[len] = 0x80u;
+    bytes[126] = (len * 8u) >> 8u;
+    bytes[127] = (len * 8u) & 0xffu;
+
+    var block: array<vec2<u32>, 16>;
+    for (var i = 0u; i < 16u; i++) {
+        block[i] = vec2<u32>(be32(&bytes, 8u * i), be32(&bytes, 8u * i + 4u));
+    }
+    var digest = sha512_block(&block);
+
+    // Whole digest with domain separation, else its first 128 bits
+    let n = select(16u, 64u, params.domain_separation != 0u);
+    var acc = 0u;
+    for (var i = 0u; i < n; i++) {
+        let word = digest[i / 8u];
+        let half = select(word.y, word.x, i % 8u < 4u);
+        let b = (half >> (24u - 8u * (i % 4u))) & 0xffu;
+        acc = (acc * 256u + b) % params.p;
+    }
+    return acc;
+}
+
+fn mul(a: u32, b: u32) -> u32 {
+    return (a * b) % params.p;
+}
+
+fn pow(base: u32, exp: u32) -> u32 {
+    var result = 1u;
+    var b = base % params.p;
+    var e = exp;
+    while (e > 0u) {
+        if ((e & 1u) == 1u) {
+            result = mul(result, b);
+        }
+        b = mul(b, b);
+        e = e >> 1u;
+    }
+    return result;
+}
+
+// Same branches as LaiCryptoEngine::sqrt_mod, so the same root is chosen
+fn sqrt(v: u32) -> u32 {
+    let p = params.p;
+    let a = v % p;
+    if (a == 0u) {
+        return 0u;
+    }
+    if (pow(a, (p - 1u) / 2u) == p - 1u) {
+        return NO_ROOT;
+    }
+    if (p % 4u == 3u) {
+        return pow(a, (p + 1u) / 4u);
+    }
+
+    var q = p - 1u;
+    var s = 0u;
+    while (q % 2u == 0u) {
+        q = q / 2u;
+        s = s + 1u;
+    }
+    var z = 2u;
+    while (pow(z, (p - 1u) / 2u) != p - 1u) {
+        z = z + 1u;
+    }
+    var m = s;
+    var c = pow(z, q);
+    var t = pow(a, q);
+    var r = pow(a, (q + 1u) / 2u);
+    while (t != 1u) {
+        var i = 1u;
+        var t2i = mul(t, t);
+        while (t2i != 1u && i < m) {
+            t2i = mul(t2i, t2i);
+            i = i + 1u;
+        }
+        if (i == m) {
+            return NO_ROOT;
+        }
+        let b = pow(c, 1u << (m - i - 1u));
+        m = i;
+        c = mul(b, b);
+        t = mul(t, c);
+        r = mul(r, b);
+    }
+    return r;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let orbit = id.x;
+    if (orbit >= params.count) {
+        return;
+    }
+    var x = starts[orbit].x;
+    var y = starts[orbit].y;
+    var done = 0u;
+    for (var k = 0u; k < params.steps; k++) {
+        var found = false;
+        for (var i = 0u; i < params.retries; i++) {
+            let hh = h(x, y, params.start_s + k + i);
+            let x1 = ((x + params.a + hh) * params.inv2) % params.p;
+            let y1 = sqrt((x * y + hh) % params.p);
+            if (y1 != NO_ROOT) {
+                x = x1;
+                y = y1;
+                found = true;
+                break;
+            }
+        }
+        if (!found) {
+            break;
+        }
+        points[orbit * params.steps + k] = vec2<u32>(x, y);
+        done = k + 1u;
+    }
+    lengths[orbit] = done;
+}
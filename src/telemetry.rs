@@ -0,0 +1,287 @@
+//! Newline-delimited JSON telemetry files with rotation
+//!
+//! An engine given a [`RotatingWriter`] through
+//! [`LaiCryptoEngine::with_telemetry`] appends one JSON object per line
+//! for every recorded operation and, while `tracing` is on, every trace
+//! step:
+//!
+//! ```text
+//! {"event":"operation","operation":"decrypt","duration_ms":0.41,"operation_id":12}
+//! {"event":"trace","step":0,...}
+//! ```
+//!
+//! Each line is written straight to the file, so everything up to a crash
+//! survives it. When the active file `<prefix>.ndjson` would exceed
+//! `max_bytes`, it becomes `<prefix>.1.ndjson`, older files shift up by
+//! one and those beyond `retain` are deleted.
+//!
+//! Write failures never fail the cryptographic operation; they are counted
+//! in [`RotatingWriter::errors`] and the last one is kept.
+
+#[cfg(feature = "trace")]
+use crate::TraceStep;
+use crate::{json_object, json_string, LaiCryptoEngine, LaiCryptoError};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Default size at which the active file is rotated
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Default number of rotated files kept
+pub const DEFAULT_RETAIN: usize = 5;
+
+/// Size-rotated NDJSON file writer
+#[derive(Debug)]
+pub struct RotatingWriter {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: u64,
+    retain: usize,
+    file: Option<File>,
+    written: u64,
+    errors: u64,
+    last_error: Option<LaiCryptoError>,
+}
+
+impl RotatingWriter {
+    /// Writer for `<dir>/<prefix>.ndjson`, appending to an existing file
+    pub fn new(dir: impl Into<PathBuf>, prefix: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            retain: DEFAULT_RETAIN,
+            file: None,
+            written: 0,
+            errors: 0,
+            last_error: None,
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Keep at most `retain` rotated files; 0 discards the old file on rotation
+    pub fn with_retain(mut self, retain: usize) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    /// `<dir>/<prefix>.ndjson` for generation 0, `<prefix>.<n>.ndjson` after
+    pub fn path(&self, generation: usize) -> PathBuf {
+        let name = match generation {
+            0 => format!("{}.ndjson", self.prefix),
+            n => format!("{}.{}.ndjson", self.prefix, n),
+        };
+        self.dir.join(name)
+    }
+
+    /// Failed writes so far
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    pub fn last_error(&self) -> Option<&LaiCryptoError> {
+        self.last_error.as_ref()
+    }
+
+    /// Append `json` as one line, rotating first if it would not fit
+    pub fn write_line(&mut self, json: &str) -> Result<(), LaiCryptoError> {
+        let len = json.len() as u64 + 1;
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        let mut line = Vec::with_capacity(json.len() + 1);
+        line.extend_from_slice(json.as_bytes());
+        line.push(b'\n');
+        let file = self.file.as_mut().expect("opened above");
+        file.write_all(&line)
+            .map_err(|e| LaiCryptoError::io(format!("writing {}", self.path(0).display()), e))?;
+        self.written += len;
+        Ok(())
+    }
+
+    /// [`RotatingWriter::write_line`], recording rather than returning errors
+    pub(crate) fn emit(&mut self, json: &str) {
+        if let Err(e) = self.write_line(json) {
+            self.errors += 1;
+            self.last_error = Some(e);
+        }
+    }
+
+    fn open(&mut self) -> Result<(), LaiCryptoError> {
+        let path = self.path(0);
+        let context = || format!("opening {}", path.display());
+        fs::create_dir_all(&self.dir).map_err(|e| LaiCryptoError::io(context(), e))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| LaiCryptoError::io(context(), e))?;
+        self.written = file
+            .metadata()
+            .map_err(|e| LaiCryptoError::io(context(), e))?
+            .len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), LaiCryptoError> {
+        self.file = None;
+        let rename = |from: &Path, to: &Path| {
+            fs::rename(from, to).map_err(|e| {
+                LaiCryptoError::io(
+                    format!("rotating {} to {}", from.display(), to.display()),
+                    e,
+                )
+            })
+        };
+        let oldest = self.path(self.retain.max(1));
+        if self.retain == 0 || oldest.exists() {
+            let doomed = if self.retain == 0 {
+                self.path(0)
+            } else {
+                oldest
+            };
+            fs::remove_file(&doomed)
+                .map_err(|e| LaiCryptoError::io(format!("removing {}", doomed.display()), e))?;
+        }
+        for generation in (0..self.retain).rev() {
+            let from = self.path(generation);
+            if from.exists() {
+                rename(&from, &self.path(generation + 1))?;
+            }
+        }
+        self.open()
+    }
+}
+
+/// Writer an engine emits telemetry to, if any
+///
+/// Not part of snapshots: it encodes to nothing and a restored engine
+/// writes no telemetry.
+#[derive(Default)]
+pub(crate) struct TelemetrySink(Option<RotatingWriter>);
+
+impl fmt::Debug for TelemetrySink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Some(writer) => write!(f, "{}", writer.path(0).display()),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for TelemetrySink {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<C> bincode::Decode<C> for TelemetrySink {
+    fn decode<D: bincode::de::Decoder<Context = C>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(TelemetrySink);
+
+impl TelemetrySink {
+    pub(crate) fn operation(
+        &mut self,
+        operation: &str,
+        duration: Duration,
+        id: Option<crate::operation::OperationId>,
+    ) {
+        if let Some(writer) = &mut self.0 {
+            writer.emit(&json_object(&[
+                ("event", json_string("operation")),
+                ("operation", json_string(operation)),
+                (
+                    "duration_ms",
+                    format!("{}", duration.as_secs_f64() * 1000.0),
+                ),
+                (
+                    "operation_id",
+                    id.map_or("null".to_string(), |id| id.0.to_string()),
+                ),
+            ]));
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    pub(crate) fn trace(&mut self, step: &TraceStep) {
+        if let Some(writer) = &mut self.0 {
+            // Splice the event tag into the step's own object
+            let step = step.to_json();
+            writer.emit(&format!("{{\"event\":\"trace\",{}", &step[1..]));
+        }
+    }
+}
+
+impl LaiCryptoEngine {
+    /// Append operations and trace steps to `writer` as they are recorded
+    pub fn with_telemetry(mut self, writer: RotatingWriter) -> Self {
+        self.telemetry = TelemetrySink(Some(writer));
+        self
+    }
+
+    pub fn telemetry(&self) -> Option<&RotatingWriter> {
+        self.telemetry.0.as_ref()
+    }
+
+    /// Stop writing telemetry, returning the writer
+    pub fn take_telemetry(&mut self) -> Option<RotatingWriter> {
+        self.telemetry.0.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_writer() {
+        let dir = std::env::temp_dir().join(format!("laicrypto-telemetry-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0))
+            .unwrap()
+            .with_telemetry(
+                RotatingWriter::new(&dir, "lai")
+                    .with_max_bytes(2048)
+                    .with_retain(2),
+            );
+        let q = engine.pow_t(engine.p0, 7).unwrap();
+        for _ in 0..4 {
+            engine.encrypt_with_ephemeral(42, q, 7).unwrap();
+        }
+
+        let writer = engine.take_telemetry().unwrap();
+        assert_eq!(writer.errors(), 0);
+        assert!(writer.path(1).exists() && writer.path(2).exists());
+        assert!(!writer.path(3).exists());
+        for generation in 0..=2 {
+            let text = fs::read_to_string(writer.path(generation)).unwrap();
+            assert!(text.len() <= 2048);
+            assert!(text.lines().all(|line| line.starts_with("{\"event\":")));
+        }
+        let active = fs::read_to_string(writer.path(0)).unwrap();
+        assert!(active.contains("\"operation_id\":4"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
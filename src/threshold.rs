@@ -0,0 +1,222 @@
+//! Threshold (t-of-n) decryption by replicated chain sharing
+//!
+//! Decryption applies the fixed-seed map f k times to c1, and
+//! f^k = f^(k_n) ∘ … ∘ f^(k_1) for any lengths ("segments") summing to k.
+//! Following replicated secret sharing, there is one segment per
+//! (t − 1)-subset of holders, given to every holder outside the subset:
+//! any t holders cover every segment, any t − 1 miss one.
+//!
+//! A share holds only the lengths of its own segments, so k stays hidden
+//! from any set of holders that misses a segment. Partials for a segment
+//! held by several participants are cross-checked, and the ciphertext tag
+//! verifies the combined result.
+
+use crate::redact::Redacted;
+use crate::{Ciphertext, LaiCryptoEngine, LaiCryptoError, Point};
+use rand::{rngs::OsRng, Rng};
+use std::fmt;
+
+/// Run of consecutive transforms of the private scalar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    index: usize,
+    len: u128,
+}
+
+/// One holder's share: the segments it can evaluate
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    pub holder: usize,
+    pub threshold: usize,
+    pub holders: usize,
+    segments: Vec<Segment>,
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("holder", &self.holder)
+            .field("threshold", &self.threshold)
+            .field("holders", &self.holders)
+            .field("segments", &Redacted(&self.segments))
+            .finish()
+    }
+}
+
+/// A holder's evaluation of one segment on an intermediate point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partial {
+    pub holder: usize,
+    pub segment: usize,
+    pub input: Point,
+    pub output: Point,
+}
+
+/// All (size)-subsets of 0..n in lexicographic order
+fn subsets(n: usize, size: usize) -> Vec<Vec<usize>> {
+    fn extend(from: usize, n: usize, size: usize, cur: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if cur.len() == size {
+            out.push(cur.clone());
+            return;
+        }
+        for i in from..n {
+            cur.push(i);
+            extend(i + 1, n, size, cur, out);
+            cur.pop();
+        }
+    }
+    let mut out = Vec::new();
+    extend(0, n, size, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Split private scalar `k` into `n` shares, any `t` of which can decrypt
+pub fn split(k: u128, t: usize, n: usize) -> Result<Vec<Share>, LaiCryptoError> {
+    if t == 0 || t > n {
+        return Err(LaiCryptoError::InvalidParameter {
+            param: "t".to_string(),
+            value: t.to_string(),
+            reason: "Threshold must be between 1 and the number of holders".to_string(),
+            valid_range: format!("1 ≤ t ≤ {}", n),
+        });
+    }
+
+    let excluded = subsets(n, t - 1);
+    let mut cuts: Vec<u128> = (1..excluded.len()).map(|_| OsRng.gen_range(0..=k)).collect();
+    cuts.push(0);
+    cuts.push(k);
+    cuts.sort_unstable();
+
+    let mut shares: Vec<Share> = (0..n)
+        .map(|holder| Share {
+            holder,
+            threshold: t,
+            holders: n,
+            segments: Vec::new(),
+        })
+        .collect();
+    for (index, subset) in excluded.iter().enumerate() {
+        let segment = Segment {
+            index,
+            len: cuts[index + 1] - cuts[index],
+        };
+        for share in shares.iter_mut().filter(|s| !subset.contains(&s.holder)) {
+            share.segments.push(segment);
+        }
+    }
+    Ok(shares)
+}
+
+impl Share {
+    /// Segment indices this share can evaluate
+    pub fn segments(&self) -> impl Iterator<Item = usize> + '_ {
+        self.segments.iter().map(|s| s.index)
+    }
+
+    /// Evaluate `segment` on the output of the previous segment
+    pub fn partial(
+        &self,
+        engine: &mut LaiCryptoEngine,
+        segment: usize,
+        input: Point,
+    ) -> Result<Partial, LaiCryptoError> {
+        let seg = self
+            .segments
+            .iter()
+            .find(|s| s.index == segment)
+            .ok_or_else(|| LaiCryptoError::InvalidParameter {
+                param: "segment".to_string(),
+                value: segment.to_string(),
+                reason: format!("Holder {} does not hold this segment", self.holder),
+                valid_range: format!("{:?}", self.segments().collect::<Vec<_>>()),
+            })?;
+        Ok(Partial {
+            holder: self.holder,
+            segment,
+            input,
+            output: engine.pow_t_secret(input, seg.len)?,
+        })
+    }
+}
+
+/// Decrypt `ct` with the participating shares
+///
+/// Segments are evaluated in order; when several participants hold a
+/// segment their partials must agree, and the final shared point must
+/// reproduce the ciphertext tag.
+pub fn decrypt(
+    engine: &mut LaiCryptoEngine,
+    ct: &Ciphertext,
+    participants: &[&Share],
+) -> Result<u128, LaiCryptoError> {
+    let Some(first) = participants.first() else {
+        return Err(not_enough(0, 1));
+    };
+    if participants.len() < first.threshold {
+        return Err(not_enough(participants.len(), first.threshold));
+    }
+
+    let segments = subsets(first.holders, first.threshold - 1).len();
+    let mut point = ct.c1;
+    for segment in 0..segments {
+        let mut agreed: Option<Partial> = None;
+        for share in participants.iter().filter(|s| s.segments().any(|i| i == segment)) {
+            let partial = share.partial(engine, segment, point)?;
+            match agreed {
+                Some(prev) if prev.output != partial.output => {
+                    return Err(LaiCryptoError::ValidationError {
+                        operation: format!("threshold segment {}", segment),
+                        expected: format!("holder {} output {}", prev.holder, prev.output),
+                        actual: format!("holder {} output {}", partial.holder, partial.output),
+                    });
+                }
+                _ => agreed = Some(partial),
+            }
+        }
+        point = agreed.ok_or_else(|| not_enough(participants.len(), first.threshold))?.output;
+    }
+
+    engine.open_with_shared(ct, point, "threshold decrypt")
+}
+
+fn not_enough(have: usize, need: usize) -> LaiCryptoError {
+    LaiCryptoError::InvalidParameter {
+        param: "participants".to_string(),
+        value: have.to_string(),
+        reason: "Participating shares do not cover every segment".to_string(),
+        valid_range: format!("at least {} distinct holders", need),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_of_three() {
+        let mut engine = LaiCryptoEngine::new(1009, 3, (0, 0)).unwrap();
+        let p0 = engine.p0;
+        let k = 8;
+        let q = engine.pow_t(p0, k).unwrap();
+        let (ct, _) = engine.encrypt(42, q).unwrap();
+
+        let shares = split(k, 2, 3).unwrap();
+        assert!(shares.iter().all(|s| s.segments().count() == 2));
+        for pair in [[0, 1], [0, 2], [1, 2]] {
+            let chosen = [&shares[pair[0]], &shares[pair[1]]];
+            assert_eq!(decrypt(&mut engine, &ct, &chosen).unwrap(), 42);
+        }
+        assert!(decrypt(&mut engine, &ct, &[&shares[0]]).is_err());
+
+        // Holders 0 and 1 both hold segment 2, so a corrupted copy is caught
+        let mut corrupt = shares[0].clone();
+        corrupt.segments.iter_mut().find(|s| s.index == 2).unwrap().len += 1;
+        let err = decrypt(&mut engine, &ct, &[&corrupt, &shares[1]]).unwrap_err();
+        assert!(matches!(
+            err,
+            LaiCryptoError::ValidationError { .. } | LaiCryptoError::TransformFailure { .. }
+        ));
+
+        assert!(split(k, 4, 3).is_err());
+    }
+}
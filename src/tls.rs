@@ -0,0 +1,166 @@
+//! rustls key-exchange groups backed by the fixed-seed exchange
+//!
+//! [`LaiKxGroup`] implements rustls's `SupportedKxGroup`, so an
+//! experimental TLS 1.3 handshake can carry LAI key shares. Groups are
+//! identified by private-use codepoints and must be `'static`, which the
+//! `const` constructors allow:
+//!
+//! ```ignore
+//! static GROUP: LaiKxGroup = LaiKxGroup::new(LAI_GROUP_ID, 10007, 3, Point::new_unchecked(0, 0));
+//! let provider = with_group(rustls::crypto::ring::default_provider(), &GROUP);
+//! ```
+//!
+//! With the `hybrid` feature, [`LaiKxGroup::hybrid`] shares an X25519 key
+//! alongside and combines both secrets as in [`crate::composite`].
+
+use crate::exchange::ExchangeKeyPair;
+use crate::hybrid::raw_point;
+use crate::{LaiCryptoEngine, LaiCryptoError, Point};
+use secrecy::ExposeSecret;
+use rustls::crypto::{ActiveKeyExchange, CryptoProvider, SharedSecret, SupportedKxGroup};
+use rustls::{Error, NamedGroup, PeerMisbehaved};
+
+#[cfg(feature = "hybrid")]
+use crate::composite::{HybridKeyPair, HybridPublicKey};
+
+/// Private-use codepoint for the plain LAI group
+pub const LAI_GROUP_ID: u16 = 0xFE4C;
+/// Private-use codepoint for the LAI + X25519 group
+pub const LAI_X25519_GROUP_ID: u16 = 0xFE4D;
+
+/// TLS key-exchange group over fixed LAI parameters
+#[derive(Debug)]
+pub struct LaiKxGroup {
+    id: u16,
+    p: u128,
+    a: u128,
+    p0: Point,
+    x25519: bool,
+}
+
+impl LaiKxGroup {
+    /// Group sharing LAI exchange points only
+    pub const fn new(id: u16, p: u128, a: u128, p0: Point) -> Self {
+        LaiKxGroup { id, p, a, p0, x25519: false }
+    }
+
+    /// Group sharing an LAI point and an X25519 key, combined
+    #[cfg(feature = "hybrid")]
+    pub const fn hybrid(id: u16, p: u128, a: u128, p0: Point) -> Self {
+        LaiKxGroup { id, p, a, p0, x25519: true }
+    }
+
+    fn engine(&self) -> Result<LaiCryptoEngine, Error> {
+        LaiCryptoEngine::new(self.p, self.a, self.p0).map_err(tls_error)
+    }
+}
+
+/// `provider` with `group` preferred over its own key-exchange groups
+pub fn with_group(mut provider: CryptoProvider, group: &'static LaiKxGroup) -> CryptoProvider {
+    provider.kx_groups.insert(0, group);
+    provider
+}
+
+fn tls_error(error: LaiCryptoError) -> Error {
+    Error::General(error.to_string())
+}
+
+enum KeyPair {
+    Lai(ExchangeKeyPair),
+    #[cfg(feature = "hybrid")]
+    Hybrid(HybridKeyPair),
+}
+
+struct LaiKeyExchange {
+    group: NamedGroup,
+    engine: LaiCryptoEngine,
+    keys: KeyPair,
+    public: Vec<u8>,
+}
+
+impl SupportedKxGroup for LaiKxGroup {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, Error> {
+        let mut engine = self.engine()?;
+        let (keys, public) = match self.x25519 {
+            #[cfg(feature = "hybrid")]
+            true => {
+                let pair = HybridKeyPair::generate(&mut engine).map_err(tls_error)?;
+                let public = pair.public().to_bytes().to_vec();
+                (KeyPair::Hybrid(pair), public)
+            }
+            _ => {
+                let pair = ExchangeKeyPair::generate(&mut engine).map_err(tls_error)?;
+                let public = pair.public().to_bytes().to_vec();
+                (KeyPair::Lai(pair), public)
+            }
+        };
+        Ok(Box::new(LaiKeyExchange {
+            group: self.name(),
+            engine,
+            keys,
+            public,
+        }))
+    }
+
+    fn name(&self) -> NamedGroup {
+        NamedGroup::from(self.id)
+    }
+}
+
+impl ActiveKeyExchange for LaiKeyExchange {
+    fn complete(mut self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, Error> {
+        let p = self.engine.p;
+        let secret = match &self.keys {
+            KeyPair::Lai(pair) => {
+                let peer = (peer_pub_key.len() == Point::ENCODED_LEN)
+                    .then(|| raw_point(peer_pub_key, p))
+                    .flatten()
+                    .ok_or(PeerMisbehaved::InvalidKeyShare)?;
+                pair.shared_secret(&mut self.engine, peer)
+            }
+            #[cfg(feature = "hybrid")]
+            KeyPair::Hybrid(pair) => {
+                let peer = HybridPublicKey::from_bytes(peer_pub_key, p).map_err(|_| PeerMisbehaved::InvalidKeyShare)?;
+                pair.shared_secret(&mut self.engine, &peer)
+            }
+        };
+        Ok(SharedSecret::from(&secret.map_err(tls_error)?.expose_secret()[..]))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    fn group(&self) -> NamedGroup {
+        self.group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static GROUP: LaiKxGroup = LaiKxGroup::new(LAI_GROUP_ID, 1009, 3, Point::new_unchecked(0, 0));
+    #[cfg(feature = "hybrid")]
+    static HYBRID: LaiKxGroup = LaiKxGroup::hybrid(LAI_X25519_GROUP_ID, 1009, 3, Point::new_unchecked(0, 0));
+
+    fn exchange(group: &LaiKxGroup) {
+        let client = group.start().unwrap();
+        let server = group.start().unwrap();
+        assert_eq!(client.group(), NamedGroup::from(group.id));
+        let (client_public, server_public) = (client.pub_key().to_vec(), server.pub_key().to_vec());
+        assert!(group.start().unwrap().complete(&server_public[1..]).is_err());
+
+        // Agreements can hit a failing T at this toy modulus; compare when both succeed
+        if let (Ok(c), Ok(s)) = (client.complete(&server_public), server.complete(&client_public)) {
+            assert_eq!(c.secret_bytes(), s.secret_bytes());
+        }
+    }
+
+    #[test]
+    fn test_kx_groups() {
+        exchange(&GROUP);
+        #[cfg(feature = "hybrid")]
+        exchange(&HYBRID);
+    }
+}
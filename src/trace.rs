@@ -0,0 +1,51 @@
+//! Recording of individual T-transform steps
+
+use crate::{json_object, json_string, operation, Point};
+use std::time::Duration;
+
+/// Detailed transformation step recording
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct TraceStep {
+    pub step: u32,
+    pub input: Point,
+    pub s: u128,
+    pub h: u128,
+    pub x1: u128,
+    pub y2: u128,
+    pub y1: Option<u128>,
+    pub output: Option<Point>,
+    pub duration: Duration,
+    /// Enclosing keygen, encrypt or decrypt call, see [`operation`]
+    pub operation: Option<operation::OperationId>,
+}
+
+impl TraceStep {
+    /// Serialize as JSON, with integers as decimal strings like [`crate::LaiCryptoError::to_json`]
+    pub fn to_json(&self) -> String {
+        let num = |v: u128| json_string(&v.to_string());
+        let point = |p: &Point| format!("[{},{}]", num(p.x()), num(p.y()));
+        json_object(&[
+            ("step", self.step.to_string()),
+            ("input", point(&self.input)),
+            ("s", num(self.s)),
+            ("h", num(self.h)),
+            ("x1", num(self.x1)),
+            ("y2", num(self.y2)),
+            ("y1", self.y1.map_or("null".to_string(), num)),
+            (
+                "output",
+                self.output.as_ref().map_or("null".to_string(), point),
+            ),
+            (
+                "duration_ms",
+                format!("{}", self.duration.as_secs_f64() * 1000.0),
+            ),
+            (
+                "operation",
+                self.operation
+                    .map_or("null".to_string(), |id| id.0.to_string()),
+            ),
+        ])
+    }
+}